@@ -0,0 +1,31 @@
+//! Detect whether the toolchain accepts `#![feature(...)]` (a nightly or dev build, or
+//! `RUSTC_BOOTSTRAP` set), so that the nightly-only crate features fail with guidance on stable
+//! (see `src/lib.rs`), rather than with rustc's generic E0554.
+
+use std::env;
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=RUSTC_BOOTSTRAP");
+    println!("cargo:rustc-check-cfg=cfg(lazysort_nightly)");
+
+    let rustc = env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+    let version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok());
+    let nightly = match version {
+        Some(version) => {
+            version.contains("-nightly")
+                || version.contains("-dev")
+                || env::var_os("RUSTC_BOOTSTRAP").is_some()
+        }
+        // Unknown: don't stand in the way. rustc itself rejects `#![feature]` where it must.
+        None => true,
+    };
+    if nightly {
+        println!("cargo:rustc-cfg=lazysort_nightly");
+    }
+}
@@ -0,0 +1,121 @@
+//! O(n) bucketing pre-pass for numeric keys.
+//!
+//! Items are distributed (in place) into buckets of equal key ranges, between the minimum and the
+//! maximum key. The buckets are then recorded in an [`Engine`] as already partitioned, so that lazy
+//! sorting starts within buckets. For roughly uniform keys, that saves the first several levels of
+//! partitioning (and their comparisons).
+
+use crate::engine::{Engine, Observer, DEFAULT_DEPTH};
+use crate::idx::Index;
+
+#[cfg(test)]
+mod bucket_tests;
+
+/// Maximum number of buckets: half of [`DEFAULT_DEPTH`], so that the [`Engine`] has room left for
+/// partitioning within buckets.
+pub const MAX_BUCKETS: usize = DEFAULT_DEPTH / 2;
+
+/// Keys that map to [`u128`] monotonically: `a <= b` implies `a.ordinal() <= b.ordinal()`.
+///
+/// Implemented for all primitive integers, including the 128-bit ones (IDs, IPv6 addresses...).
+pub trait NumericKey: Ord {
+    fn ordinal(&self) -> u128;
+}
+
+macro_rules! numeric_key_unsigned {
+    ($($t:ty),*) => {$(
+        impl NumericKey for $t {
+            #[inline]
+            fn ordinal(&self) -> u128 {
+                *self as u128
+            }
+        }
+    )*};
+}
+
+macro_rules! numeric_key_signed {
+    ($($t:ty),*) => {$(
+        impl NumericKey for $t {
+            /// Shifted by flipping the sign bit, so that negative values come first.
+            #[inline]
+            fn ordinal(&self) -> u128 {
+                (*self as i128 as u128) ^ (1 << 127)
+            }
+        }
+    )*};
+}
+
+numeric_key_unsigned!(u8, u16, u32, u64, u128, usize);
+numeric_key_signed!(i8, i16, i32, i64, i128, isize);
+
+/// Distribute `items` into (up to) `buckets` buckets by `key`, and record them in `engine` (which
+/// has to track exactly `items`, none consumed). Return the number of non-empty buckets.
+///
+/// `buckets` is capped at [`MAX_BUCKETS`]. Zero or one bucket does nothing.
+pub fn distribute<T, I, O, M, K, KEY, const DEPTH: usize>(
+    items: &mut [T],
+    buckets: usize,
+    key: KEY,
+    engine: &mut Engine<I, DEPTH, O, M>,
+) -> usize
+where
+    I: Index,
+    O: Observer<M>,
+    M: Copy,
+    K: NumericKey,
+    KEY: Fn(&T) -> &K,
+{
+    debug_assert!(engine.front() == 0 && engine.back() == items.len());
+    let buckets = buckets.min(MAX_BUCKETS).min(DEPTH / 2);
+    if buckets < 2 || items.len() < 2 {
+        return usize::from(!items.is_empty());
+    }
+    let (mut min, mut max) = (u128::MAX, u128::MIN);
+    for item in items.iter() {
+        let ordinal = key(item).ordinal();
+        min = min.min(ordinal);
+        max = max.max(ordinal);
+    }
+    let span = max - min;
+    // Bucket of an ordinal: proportional to its offset from `min`. For spans of up to 64 bits that
+    // is exact (and the product fits in `u128`). Wider spans are split into buckets of equal
+    // width (rounded up), so the last bucket may be narrower (or even unused).
+    let width = span / buckets as u128 + 1;
+    let bucket_of = |item: &T| {
+        let offset = key(item).ordinal() - min;
+        if span <= u128::from(u64::MAX) {
+            (offset * buckets as u128 / (span + 1)) as usize
+        } else {
+            (offset / width) as usize
+        }
+    };
+
+    let mut ends = [0usize; MAX_BUCKETS];
+    for item in items.iter() {
+        ends[bucket_of(item)] += 1;
+    }
+    let mut starts = [0usize; MAX_BUCKETS];
+    let (mut total, mut non_empty) = (0, 0);
+    for bucket in 0..buckets {
+        non_empty += usize::from(ends[bucket] > 0);
+        starts[bucket] = total;
+        total += ends[bucket];
+        ends[bucket] = total;
+    }
+    // In place ("American flag"): `starts[bucket]` is the next slot of `bucket` to fill. Each swap
+    // puts (at least) one item into its bucket for good.
+    for bucket in 0..buckets {
+        while starts[bucket] < ends[bucket] {
+            let target = bucket_of(&items[starts[bucket]]);
+            if target == bucket {
+                starts[bucket] += 1;
+            } else {
+                items.swap(starts[bucket], starts[target]);
+                starts[target] += 1;
+            }
+        }
+        let _recorded = engine.split_at(ends[bucket]);
+        debug_assert!(_recorded);
+    }
+    non_empty
+}
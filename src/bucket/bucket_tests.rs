@@ -0,0 +1,69 @@
+use crate::bucket::{distribute, NumericKey, MAX_BUCKETS};
+use crate::engine::Engine;
+
+#[test]
+fn ordinals_are_monotone() {
+    let signed = [i64::MIN, -5, -1, 0, 1, 7, i64::MAX];
+    assert!(signed.windows(2).all(|w| w[0].ordinal() < w[1].ordinal()));
+    assert!((-3i8).ordinal() < 2i8.ordinal());
+    assert!(3u8.ordinal() < 200u8.ordinal());
+    let wide = [i128::MIN, -1, 0, i128::MAX];
+    assert!(wide.windows(2).all(|w| w[0].ordinal() < w[1].ordinal()));
+    assert!(u128::MAX.ordinal() > u128::from(u64::MAX).ordinal());
+}
+
+#[test]
+fn buckets_are_partitioned() {
+    let mut items = [0i32; 300];
+    for (i, item) in items.iter_mut().enumerate() {
+        *item = (i as i32 * 7919) % 300 - 150;
+    }
+    let mut engine = Engine::<u16>::try_new(items.len()).unwrap();
+    assert_eq!(distribute(&mut items, 10, |item| item, &mut engine), 10);
+    for (nth, bucket) in items.chunks(30).enumerate() {
+        let lo = nth as i32 * 30 - 150;
+        assert!(bucket.iter().all(|item| (lo..lo + 30).contains(item)));
+    }
+    let mut cmp = |a: &i32, b: &i32| {
+        assert_eq!((a + 150) / 30, (b + 150) / 30, "compared across buckets");
+        a.cmp(b)
+    };
+    for position in [0, 299, 150, 42] {
+        engine.resolve_at(&mut items, position, &mut cmp);
+        assert_eq!(items[position], position as i32 - 150);
+    }
+}
+
+#[test]
+fn degenerate() {
+    let mut same = [7u64; 50];
+    let mut engine = Engine::<usize>::try_new(same.len()).unwrap();
+    assert_eq!(
+        distribute(&mut same, MAX_BUCKETS * 2, |item| item, &mut engine),
+        1
+    );
+
+    let mut extremes = [u64::MAX, 0, u64::MAX, 0];
+    let mut engine = Engine::<usize>::try_new(extremes.len()).unwrap();
+    assert_eq!(distribute(&mut extremes, 4, |item| item, &mut engine), 2);
+    assert_eq!(extremes, [0, 0, u64::MAX, u64::MAX]);
+}
+
+#[test]
+fn wide_keys() {
+    // IPv6-like: spread over the whole `u128` range.
+    let mut items = [0u128; 64];
+    for (i, item) in items.iter_mut().enumerate() {
+        *item = ((i as u128 * 37) % 64) << 122;
+    }
+    let mut engine = Engine::<usize>::try_new(items.len()).unwrap();
+    assert_eq!(distribute(&mut items, 8, |item| item, &mut engine), 8);
+    for (nth, bucket) in items.chunks(8).enumerate() {
+        assert!(bucket.iter().all(|item| (item >> 125) as usize == nth));
+    }
+    let mut extremes = [u128::MAX, 0, i128::MAX as u128, 1];
+    let mut engine = Engine::<usize>::try_new(extremes.len()).unwrap();
+    assert_eq!(distribute(&mut extremes, 2, |item| item, &mut engine), 2);
+    assert!(extremes[..3].iter().all(|&item| item <= i128::MAX as u128));
+    assert_eq!(extremes[3], u128::MAX);
+}
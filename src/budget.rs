@@ -0,0 +1,137 @@
+//! Hard memory budget, checked at compile time.
+//!
+//! Intended for embedded targets: if a chosen combination of the item type `T`, the index type `I`
+//! (see [`Index`]) and the maximum number of items `N` doesn't fit, the build fails - before the
+//! device is flashed. [`plan_for()`] sizes the buffers at compile time, too.
+
+use crate::idx::Index;
+use core::marker::PhantomData;
+use core::mem;
+
+#[cfg(test)]
+mod budget_tests;
+
+/// A hard memory budget of `BYTES` bytes.
+///
+/// The budget covers
+/// - the items themselves: `N * size_of::<T>()`, and
+/// - the index/metadata storage: `N * size_of::<I>()`.
+///
+/// It does NOT cover the (fixed-size) bookkeeping of the sorter itself, nor the stack.
+///
+/// Use [`Budget::assert_fits()`] to enforce the budget at compile time. Use [`Budget::fits()`]
+/// (a `const fn`) if you want to branch on it instead.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Budget<const BYTES: usize>;
+
+impl<const BYTES: usize> Budget<BYTES> {
+    pub const BYTES: usize = BYTES;
+
+    /// How many bytes `n` items of type `T`, indexed by `I`, need. [`None`] on overflow.
+    pub const fn required_bytes<T, I: Index>(n: usize) -> Option<usize> {
+        let Some(items) = n.checked_mul(mem::size_of::<T>()) else {
+            return None;
+        };
+        let Some(indices) = n.checked_mul(mem::size_of::<I>()) else {
+            return None;
+        };
+        items.checked_add(indices)
+    }
+
+    /// Whether `required` bytes (like [`Budget::required_bytes()`]) are within `BYTES`. [`None`]
+    /// (an overflow) never is.
+    pub const fn covers(required: Option<usize>) -> bool {
+        match required {
+            Some(required) => required <= BYTES,
+            None => false,
+        }
+    }
+
+    /// Whether `N` items of type `T`, indexed by `I`, fit in this budget. That requires both
+    /// - `N` to be indexable by `I` (see [`Index::MAX_INDEXABLE_LEN`]), and
+    /// - [`Budget::required_bytes()`] to be within `BYTES`.
+    pub const fn fits<T, I: Index, const N: usize>() -> bool {
+        Self::fits_len::<T, I>(N)
+    }
+
+    /// Like [`Budget::fits()`], but for `n` items given as a (`const` or runtime) value.
+    pub const fn fits_len<T, I: Index>(n: usize) -> bool {
+        n <= I::MAX_INDEXABLE_LEN && Self::covers(Self::required_bytes::<T, I>(n))
+    }
+
+    /// Fail the build (at monomorphization time) unless [`Budget::fits()`] holds for the given
+    /// `T`, `I` and `N`. Zero-cost at runtime.
+    #[inline(always)]
+    pub const fn assert_fits<T, I: Index, const N: usize>() {
+        #[allow(clippy::let_unit_value)]
+        let () = BudgetCheck::<T, I, N, BYTES>::FITS;
+    }
+}
+
+/// Sizes for lazily sorting (up to) `N` items of type `T` indexed by `I`, all known at compile
+/// time: see [`plan_for()`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StaticPlan {
+    /// Whether `I` can index `N` items (see [`Index::MAX_INDEXABLE_LEN`]). If not, the lengths
+    /// below are zero.
+    pub index_feasible: bool,
+    /// Length of the index scratch (`[I; index_len]`) for [`crate::index_sort::IndexSorter`] (and
+    /// [`crate::rows::RowSorter`]).
+    pub index_len: usize,
+    /// Length of the item scratch (`[MaybeUninit<T>; scratch_len]`) for out-of-place partitioning
+    /// (like [`crate::ping_pong`]). In-place sorters need none.
+    pub scratch_len: usize,
+    /// Bytes of both scratches, as per [`Budget::required_bytes()`]. [`None`] on overflow.
+    pub scratch_bytes: Option<usize>,
+    /// Bound of the pending segments: an [`crate::engine::Engine`] `DEPTH` that never runs out with
+    /// [`crate::engine::Schedule::SmallerSideFirst`] (2 log2(`N`) + 2, at least 4).
+    pub pending_bound: usize,
+}
+
+/// Plan the memory for (up to) `N` items of type `T` indexed by `I`, at compile time. So that
+/// embedded users can size static buffers from it:
+///
+/// ```
+/// use core::mem::MaybeUninit;
+/// use lazysort_no_alloc::budget::{plan_for, StaticPlan};
+///
+/// const PLAN: StaticPlan = plan_for::<u32, u8, 200>();
+/// static INDICES: [u8; PLAN.index_len] = [0; PLAN.index_len];
+/// let scratch = [MaybeUninit::<u32>::uninit(); PLAN.scratch_len];
+/// assert!(PLAN.index_feasible);
+/// assert_eq!((INDICES.len(), scratch.len(), PLAN.pending_bound), (200, 200, 18));
+/// ```
+pub const fn plan_for<T, I: Index, const N: usize>() -> StaticPlan {
+    if N > I::MAX_INDEXABLE_LEN {
+        return StaticPlan {
+            index_feasible: false,
+            index_len: 0,
+            scratch_len: 0,
+            scratch_bytes: Some(0),
+            pending_bound: 0,
+        };
+    }
+    let log2 = match N {
+        0 | 1 => 0,
+        _ => (usize::BITS - (N - 1).leading_zeros()) as usize,
+    };
+    let pending_bound = 2 * log2 + 2;
+    StaticPlan {
+        index_feasible: true,
+        index_len: N,
+        scratch_len: N,
+        scratch_bytes: Budget::<0>::required_bytes::<T, I>(N),
+        pending_bound: if pending_bound < 4 { 4 } else { pending_bound },
+    }
+}
+
+/// Host of the associated `const` that gets evaluated (and may fail the build) per each
+/// combination of the generic parameters.
+struct BudgetCheck<T, I, const N: usize, const BYTES: usize>(PhantomData<(T, I)>);
+
+impl<T, I: Index, const N: usize, const BYTES: usize> BudgetCheck<T, I, N, BYTES> {
+    const FITS: () = assert!(
+        Budget::<BYTES>::fits::<T, I, N>(),
+        "Memory budget exceeded, or the index type can't address that many items."
+    );
+}
@@ -0,0 +1,54 @@
+use crate::budget::Budget;
+use core::num::NonZeroU8;
+
+#[test]
+fn required_bytes() {
+    assert_eq!(Budget::<0>::required_bytes::<u32, u8>(10), Some(50));
+    assert_eq!(Budget::<0>::required_bytes::<(), u8>(10), Some(10));
+    assert_eq!(Budget::<0>::required_bytes::<u64, usize>(usize::MAX), None);
+}
+
+#[test]
+fn fits() {
+    assert!(Budget::<1280>::fits::<u32, u8, 256>());
+    assert!(!Budget::<1279>::fits::<u32, u8, 256>());
+    // 256 items can't be indexed by NonZeroU8 (only 1..=255).
+    assert!(!Budget::<{ usize::MAX }>::fits::<u32, NonZeroU8, 256>());
+    assert!(Budget::<{ usize::MAX }>::fits::<u32, NonZeroU8, 255>());
+    assert!(Budget::<1280>::fits_len::<u32, u8>(256));
+    assert!(!Budget::<1280>::fits_len::<u32, u8>(257));
+    assert!(Budget::<10>::covers(Some(10)));
+    assert!(!Budget::<{ usize::MAX }>::covers(None));
+}
+
+#[test]
+fn assert_fits_compiles() {
+    const _: () = Budget::<1280>::assert_fits::<u32, u8, 256>();
+    Budget::<64>::assert_fits::<u8, usize, 4>();
+}
+
+#[test]
+fn plan_for() {
+    use crate::budget::{plan_for, StaticPlan};
+    use crate::engine::{Engine, Schedule};
+
+    const PLAN: StaticPlan = plan_for::<u32, u8, 256>();
+    const _: () = assert!(PLAN.index_feasible);
+    assert_eq!((PLAN.index_len, PLAN.scratch_len), (256, 256));
+    assert_eq!(PLAN.scratch_bytes, Some(1280));
+    assert_eq!(PLAN.pending_bound, 18);
+    assert!(!plan_for::<u32, NonZeroU8, 256>().index_feasible);
+    assert_eq!(plan_for::<u32, NonZeroU8, 256>().index_len, 0);
+    assert_eq!(plan_for::<u32, u8, 1>().pending_bound, 4);
+
+    // The pending bound is enough for the worst pivots.
+    let mut items: [u32; PLAN.scratch_len] = core::array::from_fn(|i| i as u32);
+    let mut engine = Engine::<u8, { PLAN.pending_bound }>::try_new(items.len()).unwrap();
+    engine.set_schedule(Schedule::SmallerSideFirst);
+    engine.set_pivot(crate::engine::Pivot::TowardTarget);
+    while engine
+        .next_back(&mut items[..engine.back()], &mut u32::cmp)
+        .is_some()
+    {}
+    assert!(!engine.stats().degraded());
+}
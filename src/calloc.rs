@@ -28,7 +28,7 @@ pub use alloc::alloc::{Allocator, Global};
 pub trait Allocator {}
 
 #[cfg(not(feature = "_internal_use_allocator_api"))]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Default)]
 pub struct Global {}
 
 #[cfg(not(feature = "_internal_use_allocator_api"))]
@@ -3,8 +3,6 @@ use alloc::vec::Vec as StdVec;
 
 use crate::calloc::{Allocator, Global};
 #[cfg(not(feature = "_internal_use_allocator_api"))]
-use core::marker::PhantomData;
-#[cfg(not(feature = "_internal_use_allocator_api"))]
 use core::ops::{Deref, DerefMut};
 
 // Alternatively, we could apply
@@ -32,9 +30,44 @@ pub use from_std::{Vec, VecDeque};
 pub type Vec<T, A: Allocator = Global> = StdVec<T, A>;
 // --
 
+/// The allocator value is stored (and propagated by the conversions), even though [`StdVec`]
+/// doesn't use it. `repr(C)`, so that the layout depends on `T` only as much as [`StdVec`]'s does.
+#[cfg(not(feature = "_internal_use_allocator_api"))]
+#[repr(C)]
+pub struct Vec<T, A: Allocator = Global>(pub StdVec<T>, A);
+
+#[cfg(not(feature = "_internal_use_allocator_api"))]
+impl<T, A: Allocator> Vec<T, A> {
+    pub fn new_in(alloc: A) -> Self {
+        Self(StdVec::new(), alloc)
+    }
+
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        Self(StdVec::with_capacity(capacity), alloc)
+    }
+
+    pub fn allocator(&self) -> &A {
+        &self.1
+    }
+}
+
 #[cfg(not(feature = "_internal_use_allocator_api"))]
-#[repr(transparent)]
-pub struct Vec<T, A: Allocator = Global>(pub StdVec<T>, PhantomData<A>);
+impl<T, A: Allocator + Default> Vec<T, A> {
+    pub fn new() -> Self {
+        Self::new_in(A::default())
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_in(capacity, A::default())
+    }
+}
+
+#[cfg(not(feature = "_internal_use_allocator_api"))]
+impl<T, A: Allocator + Default> Default for Vec<T, A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 #[cfg(not(feature = "_internal_use_allocator_api"))]
 impl<T, A: Allocator> Deref for Vec<T, A> {
@@ -55,9 +88,36 @@ impl<T, A: Allocator> DerefMut for Vec<T, A> {
 #[cfg(not(feature = "_internal_use_allocator_api"))]
 impl<T, A: Allocator> From<VecDeque<T, A>> for Vec<T, A> {
     fn from(vec_deque: VecDeque<T, A>) -> Self {
-        Self(vec_deque.0.into(), PhantomData)
+        Self(vec_deque.0.into(), vec_deque.1)
     }
 }
+
+/// Like [`StdVec::from_raw_parts()`] (with the allocator of a custom-allocator [`Vec`]).
+///
+/// # Safety
+///
+/// As per [`StdVec::from_raw_parts()`]: `ptr` has to be allocated (with `alloc`, where that
+/// applies) for exactly `capacity` items, the first `length` of which are initialized.
+#[cfg(not(feature = "_internal_use_allocator_api"))]
+pub(crate) unsafe fn from_raw_parts_in<T, A: Allocator>(
+    ptr: *mut T,
+    length: usize,
+    capacity: usize,
+    alloc: A,
+) -> Vec<T, A> {
+    Vec(StdVec::from_raw_parts(ptr, length, capacity), alloc)
+}
+
+/// See the other (stable) variant.
+#[cfg(feature = "_internal_use_allocator_api")]
+pub(crate) unsafe fn from_raw_parts_in<T, A: Allocator>(
+    ptr: *mut T,
+    length: usize,
+    capacity: usize,
+    alloc: A,
+) -> Vec<T, A> {
+    StdVec::from_raw_parts_in(ptr, length, capacity, alloc)
+}
 //-------- end of: Vec
 
 //-------- VecDeque
@@ -67,10 +127,12 @@ impl<T, A: Allocator> From<VecDeque<T, A>> for Vec<T, A> {
 pub type VecDeque<T, A: Allocator = Global> = StdVecDeque<T, A>;
 // --
 
+/// Like [`Vec`], it stores the allocator value. `repr(C)`, so that (like with [`StdVecDeque`]) a
+/// `VecDeque<MaybeUninit<T>, A>` has the same layout as `VecDeque<T, A>`.
 #[cfg(not(feature = "_internal_use_allocator_api"))]
 #[derive(Debug)]
-#[repr(transparent)]
-pub struct VecDeque<T, A: Allocator = Global>(StdVecDeque<T>, PhantomData<A>);
+#[repr(C)]
+pub struct VecDeque<T, A: Allocator = Global>(StdVecDeque<T>, A);
 
 // TODO if never used in release, then enable it for debug? Or remove!
 //
@@ -79,18 +141,34 @@ pub struct VecDeque<T, A: Allocator = Global>(StdVecDeque<T>, PhantomData<A>);
 // and have with_capacity(...) only.
 #[cfg(not(feature = "_internal_use_allocator_api"))]
 impl<T, A: Allocator> VecDeque<T, A> {
-    pub fn new_in(_alloc: A) -> Self {
-        Self(StdVecDeque::new(), PhantomData)
+    pub fn new_in(alloc: A) -> Self {
+        Self(StdVecDeque::new(), alloc)
     }
-    pub fn new() -> Self {
-        Self(StdVecDeque::new(), PhantomData)
+
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        Self(StdVecDeque::with_capacity(capacity), alloc)
     }
 
-    pub fn with_capacity_in(capacity: usize, _alloc: A) -> Self {
-        Self(StdVecDeque::with_capacity(capacity), PhantomData)
+    pub fn allocator(&self) -> &A {
+        &self.1
     }
+}
+
+#[cfg(not(feature = "_internal_use_allocator_api"))]
+impl<T, A: Allocator + Default> VecDeque<T, A> {
+    pub fn new() -> Self {
+        Self::new_in(A::default())
+    }
+
     pub fn with_capacity(capacity: usize) -> Self {
-        Self(StdVecDeque::with_capacity(capacity), PhantomData)
+        Self::with_capacity_in(capacity, A::default())
+    }
+}
+
+#[cfg(not(feature = "_internal_use_allocator_api"))]
+impl<T, A: Allocator + Default> Default for VecDeque<T, A> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -113,7 +191,7 @@ impl<T, A: Allocator> DerefMut for VecDeque<T, A> {
 #[cfg(not(feature = "_internal_use_allocator_api"))]
 impl<T, A: Allocator> From<Vec<T, A>> for VecDeque<T, A> {
     fn from(v: Vec<T, A>) -> Self {
-        Self(v.0.into(), PhantomData)
+        Self(v.0.into(), v.1)
     }
 }
 //-------- end of: VecDeque
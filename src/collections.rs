@@ -0,0 +1,700 @@
+//! Bulk-loaded collections that sort lazily: each query partitions only as much as it needs, and
+//! that work is kept for the next queries. Hence the sorting cost is amortized across queries.
+
+use crate::bucket::{self, NumericKey};
+use crate::budget::Budget;
+use crate::engine::{Engine, Pivot, Schedule};
+use crate::error::Error;
+use crate::idx::Index;
+use crate::owned::OwnedLive;
+use crate::pairs::{self, Layout, SortedLazyPairs};
+use crate::ping_pong::PingPongSort;
+use crate::plan::Plan;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::{Bound, RangeBounds};
+
+#[cfg(test)]
+mod collections_tests;
+
+/// A set, bulk-loaded from unsorted items, and sorted lazily by the queries.
+///
+/// Duplicates are NOT removed (it behaves like a multi-set): they count towards
+/// [`LazySortedSet::len()`] and [`LazySortedSet::nth()`]. Deduplicate beforehand if that matters.
+///
+/// The queries take `&mut self`, since they may partition the items.
+#[derive(Clone, Debug)]
+pub struct LazySortedSet<T: Ord> {
+    items: Vec<T>,
+    engine: Engine,
+}
+
+impl<T: Ord> From<Vec<T>> for LazySortedSet<T> {
+    /// O(1): no sorting happens here.
+    fn from(items: Vec<T>) -> Self {
+        let engine = Engine::try_new(items.len()).unwrap_or_else(|_| unreachable!());
+        Self { items, engine }
+    }
+}
+
+impl<T: Ord> FromIterator<T> for LazySortedSet<T> {
+    fn from_iter<ITER: IntoIterator<Item = T>>(iter: ITER) -> Self {
+        iter.into_iter().collect::<Vec<_>>().into()
+    }
+}
+
+impl<T: Ord> LazySortedSet<T> {
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// The `n`-th smallest item (0-based).
+    pub fn nth(&mut self, n: usize) -> Option<&T> {
+        if n >= self.items.len() {
+            return None;
+        }
+        self.engine.resolve_at(&mut self.items, n, &mut T::cmp);
+        Some(&self.items[n])
+    }
+
+    /// The smallest item.
+    pub fn first(&mut self) -> Option<&T> {
+        self.nth(0)
+    }
+
+    /// The greatest item.
+    pub fn last(&mut self) -> Option<&T> {
+        self.nth(self.items.len().wrapping_sub(1))
+    }
+
+    /// Number of items less than `value`.
+    pub fn rank(&mut self, value: &T) -> usize {
+        partition_point(&mut self.items, &mut self.engine, &mut T::cmp, |item| {
+            item < value
+        })
+    }
+
+    pub fn contains(&mut self, value: &T) -> bool {
+        let rank = self.rank(value);
+        rank < self.items.len() && self.items[rank] == *value
+    }
+
+    /// Sorted items within `range`. Only those get sorted fully.
+    pub fn range<R: RangeBounds<T>>(&mut self, range: R) -> &[T] {
+        let (items, engine) = (&mut self.items, &mut self.engine);
+        let (lo, hi) = range_ranks(items, engine, &mut T::cmp, range, |item| item);
+        &self.items[lo..hi]
+    }
+
+    /// Distinct items in ascending order, each with its number of occurrences. Sorted lazily, as
+    /// consumed. Duplicates cost one comparison each (see [`Engine::next_run_front()`]).
+    pub fn sorted_lazy_counts(self) -> SortedLazyCounts<T> {
+        SortedLazyCounts(OwnedLive::new(self.items, self.engine))
+    }
+
+    /// Visit the items in ascending order, one by one, until `visit` returns `false`. Return the
+    /// number of items visited.
+    ///
+    /// `visit` gets a [`SortedCursor`]: the current item, and (since the cursor is kept apart from
+    /// the set) the set itself, for any other queries on the way. Those share the partitioning with
+    /// the traversal.
+    ///
+    /// ```
+    /// use lazysort_no_alloc::collections::LazySortedSet;
+    ///
+    /// let mut set: LazySortedSet<u32> = (0..100).map(|i| (i * 37) % 100).collect();
+    /// // Items whose double is in the set, too.
+    /// let mut found = Vec::new();
+    /// set.visit_sorted(|cursor| {
+    ///     let double = *cursor.item() * 2;
+    ///     if cursor.set().contains(&double) {
+    ///         found.push(double / 2);
+    ///     }
+    ///     double < 100
+    /// });
+    /// assert_eq!(found, (0..50).collect::<Vec<_>>());
+    /// ```
+    pub fn visit_sorted<VISIT>(&mut self, mut visit: VISIT) -> usize
+    where
+        VISIT: FnMut(&mut SortedCursor<'_, T>) -> bool,
+    {
+        let mut cursor = SortedCursor {
+            set: self,
+            position: 0,
+        };
+        while cursor.position < cursor.set.len() {
+            let resume = visit(&mut cursor);
+            cursor.position += 1;
+            if !resume {
+                break;
+            }
+        }
+        cursor.position
+    }
+
+    /// Resolve all `ranks` (for [`LazySortedSet::nth()`]) in one sweep: see [`Plan`].
+    pub fn plan(&mut self, ranks: &[usize]) {
+        Plan::new(ranks).execute(&mut self.engine, &mut self.items, &mut T::cmp);
+    }
+
+    /// The items, in no particular order (partitioned as much as the queries needed).
+    pub fn into_vec(self) -> Vec<T> {
+        self.items
+    }
+}
+
+/// Position of a [`LazySortedSet::visit_sorted()`] traversal, with access to the set.
+pub struct SortedCursor<'a, T: Ord> {
+    set: &'a mut LazySortedSet<T>,
+    position: usize,
+}
+
+impl<T: Ord> SortedCursor<'_, T> {
+    /// Rank of the current item (0-based).
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// The current item. (`&mut self`, since the queries in between may have partitioned it away.)
+    pub fn item(&mut self) -> &T {
+        self.set
+            .nth(self.position)
+            .unwrap_or_else(|| unreachable!())
+    }
+
+    /// The item that comes next, if any.
+    pub fn peek(&mut self) -> Option<&T> {
+        self.set.nth(self.position + 1)
+    }
+
+    /// The set being traversed: all its queries are available.
+    pub fn set(&mut self) -> &mut LazySortedSet<T> {
+        self.set
+    }
+}
+
+/// Builds [`LazySortedSet`]s and [`LazySortedMap`]s with optional pre-passes. (Plain
+/// [`From<Vec>`] conversions do none.)
+///
+/// ```
+/// use lazysort_no_alloc::collections::Builder;
+///
+/// let mut set = Builder::new().buckets(16).set((0..1000u32).rev().collect());
+/// assert_eq!(set.nth(990), Some(&990));
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Builder<P = NoPrePass> {
+    pre_pass: P,
+    schedule: Schedule,
+    pivot: Pivot,
+    seed: Option<u64>,
+    layout: Layout,
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<P> Builder<P> {
+    /// Distribute the items into (up to) `buckets` buckets by their numeric keys first (an O(n)
+    /// pre-pass, see [`crate::bucket`]). That cuts the work of the queries on roughly uniform keys.
+    /// Capped at [`bucket::MAX_BUCKETS`]. Default: none ([`NoPrePass`]).
+    ///
+    /// The keys then have to be [`NumericKey`]s: the items of [`Builder::set()`], or the keys of
+    /// [`Builder::map()`].
+    pub fn buckets(self, buckets: usize) -> Builder<Buckets> {
+        Builder {
+            pre_pass: Buckets(buckets),
+            schedule: self.schedule,
+            pivot: self.pivot,
+            seed: self.seed,
+            layout: self.layout,
+        }
+    }
+
+    /// Which partition pieces the queries keep pending (see [`Schedule`]). Default:
+    /// [`Schedule::TargetOnly`] (the cheapest first query).
+    pub fn schedule(mut self, schedule: Schedule) -> Self {
+        self.schedule = schedule;
+        self
+    }
+
+    /// How the queries choose pivots (see [`Pivot`]). Default: [`Pivot::MedianOfThree`].
+    pub fn pivot(mut self, pivot: Pivot) -> Self {
+        self.pivot = pivot;
+        self
+    }
+
+    /// Seed every random choice (of the [`Builder::pivot()`], see [`Pivot::with_seed()`]) with
+    /// `seed`, so that the same items and queries partition the same way across runs and platforms:
+    /// for deterministic replay (simulations, games...). The pre-passes and the other pivots are
+    /// deterministic already. Default: none (the [`Pivot`]'s own [`crate::util::rng::Rng`]).
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// How [`Builder::pairs()`] lays out the pairs for partitioning (see [`Layout`]). Default:
+    /// [`Layout::Auto`].
+    pub fn layout(mut self, layout: Layout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Cap the memory of the collections at `BYTES` bytes (see [`Budget`]), for up to `N` items of
+    /// type `T`, indexed by `I`. If those don't fit (as per [`Budget::assert_fits()`]), the build
+    /// fails. The returned builder then rejects (with [`Error::CapacityExceeded`]) more than `N`
+    /// items, or a buffer that would exceed `BYTES` anyway. Call it last.
+    ///
+    /// ```
+    /// use lazysort_no_alloc::collections::Builder;
+    /// use lazysort_no_alloc::error::Error;
+    ///
+    /// let builder = Builder::new().buckets(16).budget::<8192, u32, u16, 1024>();
+    /// assert!(builder.set((0..1000u32).collect()).is_ok());
+    /// let too_many = builder.set((0..1025u32).collect());
+    /// assert_eq!(too_many.err(), Some(Error::CapacityExceeded));
+    /// ```
+    ///
+    /// 1024 items of 4 bytes, with 2-byte indices, don't fit in 4096 bytes:
+    ///
+    /// ```compile_fail
+    /// use lazysort_no_alloc::collections::Builder;
+    ///
+    /// let builder = Builder::new().budget::<4096, u32, u16, 1024>();
+    /// ```
+    pub fn budget<const BYTES: usize, T, I: Index, const N: usize>(
+        self,
+    ) -> BudgetedBuilder<BYTES, T, I, N, P> {
+        Budget::<BYTES>::assert_fits::<T, I, N>();
+        BudgetedBuilder {
+            builder: self,
+            item: PhantomData,
+        }
+    }
+
+    fn engine(&self, len: usize) -> Engine {
+        let mut engine = Engine::try_new(len).unwrap_or_else(|_| unreachable!());
+        engine.set_schedule(self.schedule);
+        engine.set_pivot(match self.seed {
+            Some(seed) => self.pivot.with_seed(seed),
+            None => self.pivot,
+        });
+        engine
+    }
+
+    pub fn set<T: Ord>(&self, mut items: Vec<T>) -> LazySortedSet<T>
+    where
+        P: PrePass<T>,
+    {
+        let mut engine = self.engine(items.len());
+        self.pre_pass.run(&mut items, |item| item, &mut engine);
+        LazySortedSet { items, engine }
+    }
+
+    /// The alternative engine for [`Copy`] items: partitioning by copying between two buffers (see
+    /// [`crate::ping_pong`]). It holds twice the memory of the others, as reported by
+    /// [`PingPongSort::memory_bytes()`]. The pre-passes, the [`Schedule`] and the [`Pivot`] don't
+    /// apply.
+    pub fn ping_pong<T: Copy + Ord>(&self, items: Vec<T>) -> PingPongSort<T> {
+        PingPongSort::new(items)
+    }
+
+    /// (Key, value) pairs, yielded sorted by key: see [`crate::pairs`]. The bucket pre-pass
+    /// doesn't apply.
+    pub fn pairs<K: Ord, V>(&self, pairs: Vec<(K, V)>) -> SortedLazyPairs<K, V> {
+        let engine = self.engine(pairs.len());
+        pairs::with_engine(pairs, self.layout, engine)
+    }
+
+    pub fn map<K: Ord, V>(&self, mut entries: Vec<(K, V)>) -> LazySortedMap<K, V>
+    where
+        P: PrePass<K>,
+    {
+        let mut engine = self.engine(entries.len());
+        self.pre_pass
+            .run(&mut entries, |entry| &entry.0, &mut engine);
+        LazySortedMap { entries, engine }
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Pre-pass of a [`Builder`] over keys of type `K`: either [`NoPrePass`] or [`Buckets`]. Sealed.
+pub trait PrePass<K: Ord>: sealed::Sealed {
+    /// Pre-partition `items` by `key`, and record that in `engine` (which tracks exactly `items`).
+    #[doc(hidden)]
+    fn run<T, KEY: Fn(&T) -> &K>(&self, items: &mut [T], key: KEY, engine: &mut Engine);
+}
+
+/// No pre-pass: the default of a [`Builder`]. Any [`Ord`] keys.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NoPrePass;
+
+/// The bucket pre-pass of [`Builder::buckets()`], into (up to) this many buckets. [`NumericKey`]s
+/// only.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Buckets(pub usize);
+
+impl sealed::Sealed for NoPrePass {}
+impl sealed::Sealed for Buckets {}
+
+impl<K: Ord> PrePass<K> for NoPrePass {
+    fn run<T, KEY: Fn(&T) -> &K>(&self, _: &mut [T], _: KEY, _: &mut Engine) {}
+}
+
+impl<K: NumericKey> PrePass<K> for Buckets {
+    fn run<T, KEY: Fn(&T) -> &K>(&self, items: &mut [T], key: KEY, engine: &mut Engine) {
+        bucket::distribute(items, self.0, key, engine);
+    }
+}
+
+/// A [`Builder`] under a hard memory budget of `BYTES` bytes, for up to `N` items of type `T`
+/// indexed by `I`: see [`Builder::budget()`]. For [`BudgetedBuilder::map()`] and
+/// [`BudgetedBuilder::pairs()`], `T` is the (key, value) tuple.
+///
+/// The budget covers the buffers of the items as per [`Budget::required_bytes()`]: the given
+/// [`Vec`] (its whole capacity, with an `I` per item), plus any copies or side arrays (of
+/// [`BudgetedBuilder::ping_pong()`], or of [`BudgetedBuilder::pairs()`] with big values or
+/// [`Layout::Blocked`]), at their peak. It does NOT cover the (fixed-size) bookkeeping, nor the
+/// pending segments of [`PingPongSort`].
+pub struct BudgetedBuilder<const BYTES: usize, T, I: Index, const N: usize, P = NoPrePass> {
+    builder: Builder<P>,
+    item: PhantomData<fn() -> (T, I)>,
+}
+
+impl<const BYTES: usize, T, I: Index, const N: usize, P: Clone> Clone
+    for BudgetedBuilder<BYTES, T, I, N, P>
+{
+    fn clone(&self) -> Self {
+        Self {
+            builder: self.builder.clone(),
+            item: PhantomData,
+        }
+    }
+}
+
+impl<const BYTES: usize, T, I: Index, const N: usize, P: Copy> Copy
+    for BudgetedBuilder<BYTES, T, I, N, P>
+{
+}
+
+impl<const BYTES: usize, T, I: Index, const N: usize, P: fmt::Debug> fmt::Debug
+    for BudgetedBuilder<BYTES, T, I, N, P>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BudgetedBuilder")
+            .field("builder", &self.builder)
+            .field("bytes", &BYTES)
+            .field("max_len", &N)
+            .finish()
+    }
+}
+
+impl<const BYTES: usize, T, I: Index, const N: usize, P> BudgetedBuilder<BYTES, T, I, N, P> {
+    /// Twice the items (see [`Builder::ping_pong()`]) fit. Evaluated (and failing the build) only
+    /// if [`BudgetedBuilder::ping_pong()`] is used.
+    const PING_PONG_FITS: () = assert!(
+        N <= usize::MAX / 2 && Budget::<BYTES>::fits_len::<T, I>(2 * N),
+        "Memory budget exceeded by the two buffers of ping_pong()."
+    );
+
+    fn check(len: usize, required: Option<usize>) -> Result<(), Error> {
+        if len <= N && Budget::<BYTES>::covers(required) {
+            Ok(())
+        } else {
+            Err(Error::CapacityExceeded)
+        }
+    }
+
+    /// See [`Builder::set()`].
+    pub fn set(&self, items: Vec<T>) -> Result<LazySortedSet<T>, Error>
+    where
+        T: Ord,
+        P: PrePass<T>,
+    {
+        let required = Budget::<BYTES>::required_bytes::<T, I>(items.capacity());
+        Self::check(items.len(), required)?;
+        Ok(self.builder.set(items))
+    }
+
+    /// See [`Builder::ping_pong()`]. The build fails unless the budget holds `2 * N` items.
+    pub fn ping_pong(&self, items: Vec<T>) -> Result<PingPongSort<T>, Error>
+    where
+        T: Copy + Ord,
+    {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::PING_PONG_FITS;
+        let buffers = items.capacity().checked_add(items.len());
+        let required = buffers.and_then(Budget::<BYTES>::required_bytes::<T, I>);
+        Self::check(items.len(), required)?;
+        Ok(self.builder.ping_pong(items))
+    }
+}
+
+impl<const BYTES: usize, K: Ord, V, I: Index, const N: usize, P>
+    BudgetedBuilder<BYTES, (K, V), I, N, P>
+{
+    /// See [`Builder::pairs()`]. The build checks the pairs themselves: any side arrays (see
+    /// [`BudgetedBuilder`]) are checked at runtime only.
+    pub fn pairs(&self, pairs: Vec<(K, V)>) -> Result<SortedLazyPairs<K, V>, Error> {
+        let required =
+            pairs::peak_bytes::<K, V, I>(pairs.capacity(), pairs.len(), self.builder.layout);
+        Self::check(pairs.len(), required)?;
+        Ok(self.builder.pairs(pairs))
+    }
+
+    /// See [`Builder::map()`].
+    pub fn map(&self, entries: Vec<(K, V)>) -> Result<LazySortedMap<K, V>, Error>
+    where
+        P: PrePass<K>,
+    {
+        let required = Budget::<BYTES>::required_bytes::<(K, V), I>(entries.capacity());
+        Self::check(entries.len(), required)?;
+        Ok(self.builder.map(entries))
+    }
+}
+
+/// Order statistics: [`OrderStatistics::select()`] and [`OrderStatistics::rank()`] are inverse to
+/// each other (for distinct keys).
+///
+/// Both resolve only the ranks they need, and the partitioning they do stays (as memoized pivots):
+/// repeated or nearby calls get cheaper, instead of each call starting from scratch.
+pub trait OrderStatistics {
+    type Key: Ord;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The `k`-th smallest key (0-based).
+    fn select(&mut self, k: usize) -> Option<&Self::Key>;
+
+    /// Number of keys less than `key`.
+    fn rank(&mut self, key: &Self::Key) -> usize;
+}
+
+impl<T: Ord> OrderStatistics for LazySortedSet<T> {
+    type Key = T;
+
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    fn select(&mut self, k: usize) -> Option<&T> {
+        self.nth(k)
+    }
+
+    fn rank(&mut self, key: &T) -> usize {
+        LazySortedSet::rank(self, key)
+    }
+}
+
+/// Iterator returned by [`LazySortedSet::sorted_lazy_counts()`].
+pub struct SortedLazyCounts<T: Ord>(OwnedLive<T>);
+
+impl<T: Ord> Iterator for SortedLazyCounts<T> {
+    type Item = (T, usize);
+
+    fn next(&mut self) -> Option<(T, usize)> {
+        self.0.next_run_front(&mut T::cmp)
+    }
+}
+
+impl<T: Ord> SortedLazyCounts<T> {
+    /// The items not yielded yet (duplicates included), in no particular order, in the buffer of
+    /// the original [`LazySortedSet`].
+    pub fn into_inner(self) -> Vec<T> {
+        self.0.into_inner()
+    }
+}
+
+/// A map, bulk-loaded from unsorted entries, and sorted (by keys) lazily by the queries.
+///
+/// Duplicate keys are NOT removed: [`LazySortedMap::get()`] finds any one of them.
+///
+/// The queries take `&mut self`, since they may partition the entries.
+#[derive(Clone, Debug)]
+pub struct LazySortedMap<K: Ord, V> {
+    entries: Vec<(K, V)>,
+    engine: Engine,
+}
+
+impl<K: Ord, V> From<Vec<(K, V)>> for LazySortedMap<K, V> {
+    /// O(1): no sorting happens here.
+    fn from(entries: Vec<(K, V)>) -> Self {
+        let engine = Engine::try_new(entries.len()).unwrap_or_else(|_| unreachable!());
+        Self { entries, engine }
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for LazySortedMap<K, V> {
+    fn from_iter<ITER: IntoIterator<Item = (K, V)>>(iter: ITER) -> Self {
+        iter.into_iter().collect::<Vec<_>>().into()
+    }
+}
+
+impl<K: Ord, V> LazySortedMap<K, V> {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn cmp_keys(a: &(K, V), b: &(K, V)) -> Ordering {
+        a.0.cmp(&b.0)
+    }
+
+    /// The entry with the `n`-th smallest key (0-based).
+    pub fn nth(&mut self, n: usize) -> Option<(&K, &V)> {
+        if n >= self.entries.len() {
+            return None;
+        }
+        self.engine
+            .resolve_at(&mut self.entries, n, &mut Self::cmp_keys);
+        let (key, value) = &self.entries[n];
+        Some((key, value))
+    }
+
+    pub fn first_key_value(&mut self) -> Option<(&K, &V)> {
+        self.nth(0)
+    }
+
+    pub fn last_key_value(&mut self) -> Option<(&K, &V)> {
+        self.nth(self.entries.len().wrapping_sub(1))
+    }
+
+    /// Number of entries whose keys are less than `key`.
+    pub fn rank(&mut self, key: &K) -> usize {
+        partition_point(
+            &mut self.entries,
+            &mut self.engine,
+            &mut Self::cmp_keys,
+            |entry| entry.0 < *key,
+        )
+    }
+
+    /// Position of an entry with `key`, if any.
+    fn find(&mut self, key: &K) -> Option<usize> {
+        let rank = self.rank(key);
+        (rank < self.entries.len() && self.entries[rank].0 == *key).then_some(rank)
+    }
+
+    pub fn contains_key(&mut self, key: &K) -> bool {
+        self.find(key).is_some()
+    }
+
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let position = self.find(key)?;
+        Some(&self.entries[position].1)
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let position = self.find(key)?;
+        Some(&mut self.entries[position].1)
+    }
+
+    /// Entries (sorted by key) whose keys are within `range`. Only those get sorted fully.
+    pub fn range<R: RangeBounds<K>>(&mut self, range: R) -> &[(K, V)] {
+        let (entries, engine) = (&mut self.entries, &mut self.engine);
+        let (lo, hi) = range_ranks(entries, engine, &mut Self::cmp_keys, range, |entry| {
+            &entry.0
+        });
+        &self.entries[lo..hi]
+    }
+
+    /// Resolve all `ranks` (for [`LazySortedMap::nth()`]) in one sweep: see [`Plan`].
+    pub fn plan(&mut self, ranks: &[usize]) {
+        Plan::new(ranks).execute(&mut self.engine, &mut self.entries, &mut Self::cmp_keys);
+    }
+
+    /// The entries, in no particular order (partitioned as much as the queries needed).
+    pub fn into_vec(self) -> Vec<(K, V)> {
+        self.entries
+    }
+}
+
+impl<K: Ord, V> OrderStatistics for LazySortedMap<K, V> {
+    type Key = K;
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn select(&mut self, k: usize) -> Option<&K> {
+        self.nth(k).map(|(key, _)| key)
+    }
+
+    fn rank(&mut self, key: &K) -> usize {
+        LazySortedMap::rank(self, key)
+    }
+}
+
+/// Binary search (by rank) for the first item for which `pred` is `false`. Like
+/// [`slice::partition_point()`], but resolving (only) the probed ranks. `pred` has to be monotone
+/// with respect to `cmp`.
+fn partition_point<T, CMP, PRED>(
+    items: &mut [T],
+    engine: &mut Engine,
+    cmp: &mut CMP,
+    mut pred: PRED,
+) -> usize
+where
+    CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+    PRED: FnMut(&T) -> bool,
+{
+    let (mut lo, mut hi) = (0, items.len());
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        engine.resolve_at(items, mid, cmp);
+        if pred(&items[mid]) {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    lo
+}
+
+/// Ranks `lo..hi` of items whose keys are within `range`, with those items sorted.
+fn range_ranks<T, K, CMP, R, KEY>(
+    items: &mut [T],
+    engine: &mut Engine,
+    cmp: &mut CMP,
+    range: R,
+    key: KEY,
+) -> (usize, usize)
+where
+    K: Ord,
+    CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+    R: RangeBounds<K>,
+    KEY: Fn(&T) -> &K,
+{
+    let lo = match range.start_bound() {
+        Bound::Included(start) => partition_point(items, engine, cmp, |item| key(item) < start),
+        Bound::Excluded(start) => partition_point(items, engine, cmp, |item| key(item) <= start),
+        Bound::Unbounded => 0,
+    };
+    let hi = match range.end_bound() {
+        Bound::Included(end) => partition_point(items, engine, cmp, |item| key(item) <= end),
+        Bound::Excluded(end) => partition_point(items, engine, cmp, |item| key(item) < end),
+        Bound::Unbounded => items.len(),
+    };
+    let hi = hi.max(lo);
+    engine.sort_range(items, lo, hi, cmp);
+    (lo, hi)
+}
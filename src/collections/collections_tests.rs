@@ -0,0 +1,297 @@
+use crate::bucket::NumericKey;
+use crate::collections::{Builder, LazySortedMap, LazySortedSet, OrderStatistics};
+use crate::engine::Schedule;
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::cmp::Ordering;
+
+extern crate std;
+
+fn set() -> LazySortedSet<u32> {
+    (0..500u32).map(|i| (i * 7919 + 11) % 500).collect()
+}
+
+#[test]
+fn set_queries() {
+    let mut set = set();
+    assert_eq!(set.len(), 500);
+    assert_eq!(set.first(), Some(&0));
+    assert_eq!(set.last(), Some(&499));
+    assert_eq!(set.nth(250), Some(&250));
+    assert_eq!(set.nth(500), None);
+    assert_eq!(set.rank(&100), 100);
+    assert!(set.contains(&42));
+    assert!(!set.contains(&500));
+    assert_eq!(set.range(10..13), &[10, 11, 12]);
+    assert_eq!(set.range(..=2), &[0, 1, 2]);
+    assert_eq!(set.range(497..), &[497, 498, 499]);
+    let (lo, hi) = (20, 10);
+    assert!(set.range(lo..hi).is_empty());
+}
+
+#[test]
+fn set_empty() {
+    let mut set = LazySortedSet::<u8>::from(Vec::new());
+    assert!(set.is_empty());
+    assert_eq!(set.first(), None);
+    assert_eq!(set.last(), None);
+    assert!(!set.contains(&0));
+    assert!(set.range(..).is_empty());
+}
+
+#[test]
+fn map_queries() {
+    let mut map: LazySortedMap<u32, u32> = (0..300u32).map(|i| ((i * 101) % 300, i)).collect();
+    assert_eq!(map.first_key_value().map(|(k, _)| *k), Some(0));
+    assert_eq!(map.last_key_value().map(|(k, _)| *k), Some(299));
+    assert_eq!(map.get(&101), Some(&1));
+    *map.get_mut(&101).unwrap() = 1000;
+    assert_eq!(map.get(&101), Some(&1000));
+    assert!(!map.contains_key(&300));
+    let keys: Vec<u32> = map.range(5..8).iter().map(|(k, _)| *k).collect();
+    assert_eq!(keys, [5, 6, 7]);
+}
+
+std::thread_local!(static COMPARISONS: Cell<usize> = const { Cell::new(0) });
+
+#[derive(Debug, PartialEq, Eq)]
+struct Counted(u32);
+
+impl PartialOrd for Counted {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Counted {
+    fn cmp(&self, other: &Self) -> Ordering {
+        COMPARISONS.with(|c| c.set(c.get() + 1));
+        self.0.cmp(&other.0)
+    }
+}
+
+impl NumericKey for Counted {
+    fn ordinal(&self) -> u128 {
+        self.0.ordinal()
+    }
+}
+
+fn comparisons<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    COMPARISONS.with(|c| c.set(0));
+    let result = f();
+    (result, COMPARISONS.with(|c| c.get()))
+}
+
+#[test]
+fn order_statistics_inverse() {
+    fn check<S: OrderStatistics<Key = u32>>(stats: &mut S) {
+        for k in [0, 77, 150, 1, stats.len() - 1] {
+            let key = *stats.select(k).unwrap();
+            assert_eq!(stats.rank(&key), k);
+        }
+        assert_eq!(stats.select(stats.len()), None);
+    }
+    check(&mut set());
+    check(
+        &mut (0..300u32)
+            .map(|i| ((i * 101) % 300, i))
+            .collect::<LazySortedMap<_, _>>(),
+    );
+}
+
+#[test]
+fn order_statistics_reuse_partitioning() {
+    let mut set: LazySortedSet<Counted> =
+        (0..1000u32).map(|i| Counted((i * 7919) % 1000)).collect();
+    let (_, first) = comparisons(|| set.select(500).map(|c| c.0));
+    assert!(first > 1000);
+    // Resolved already: no comparisons.
+    let (value, again) = comparisons(|| set.select(500).map(|c| c.0));
+    assert_eq!((value, again), (Some(500), 0));
+    let (rank, ranking) = comparisons(|| set.rank(&Counted(500)));
+    assert_eq!(rank, 500);
+    assert!(ranking < first / 2, "{ranking} vs. {first}");
+    // Nearby: partitioned within what's left around rank 500 only.
+    let (_, nearby) = comparisons(|| set.select(510).map(|c| c.0));
+    assert!(nearby < first / 2, "{nearby} vs. {first}");
+}
+
+#[test]
+fn bucket_pre_pass() {
+    let items = || {
+        (0..10_000u32)
+            .map(|i| Counted((i * 7919) % 10_000))
+            .collect::<Vec<_>>()
+    };
+    let percentiles = |set: &mut LazySortedSet<Counted>| {
+        for n in [5000, 9000, 9900, 100] {
+            assert_eq!(set.nth(n).map(|c| c.0), Some(n as u32));
+        }
+    };
+    let (_, plain) = comparisons(|| percentiles(&mut items().into()));
+    let (_, bucketed) = comparisons(|| percentiles(&mut Builder::new().buckets(32).set(items())));
+    assert!(bucketed < plain / 2, "{bucketed} vs. {plain}");
+
+    let mut map = Builder::new()
+        .buckets(8)
+        .map((0..100i32).map(|i| (50 - i, i)).collect());
+    assert_eq!(map.get(&-20), Some(&70));
+    assert_eq!(map.first_key_value(), Some((&-49, &99)));
+}
+
+#[test]
+fn builder_schedule() {
+    let mut set = Builder::new()
+        .schedule(Schedule::SmallerSideFirst)
+        .set((0..1000u32).map(|i| (i * 7919) % 1000).collect());
+    assert_eq!(set.nth(3), Some(&3));
+    assert_eq!(set.nth(997), Some(&997));
+
+    // Any `Ord` items: only the bucket pre-pass needs `NumericKey`s.
+    let mut words = Builder::new().schedule(Schedule::SmallerSideFirst).map(
+        ["pear", "fig", "apple"]
+            .map(|word| (word, word.len()))
+            .to_vec(),
+    );
+    assert_eq!(words.first_key_value(), Some((&"apple", &5)));
+}
+
+#[test]
+fn visit_sorted() {
+    let mut set = set();
+    let mut seen = Vec::new();
+    let visited = set.visit_sorted(|cursor| {
+        let (position, item) = (cursor.position(), *cursor.item());
+        let next = cursor.peek().copied();
+        // Queries on the set itself, in between.
+        assert_eq!(cursor.set().rank(&item), position);
+        assert_eq!(cursor.set().last(), Some(&499));
+        assert_eq!(*cursor.item(), item);
+        seen.push((item, next));
+        item < 9
+    });
+    assert_eq!(visited, 10);
+    assert!(seen.iter().copied().eq((0..10).map(|i| (i, Some(i + 1)))));
+
+    assert_eq!(set.visit_sorted(|_| true), 500);
+    assert_eq!(
+        LazySortedSet::<u32>::from(Vec::new()).visit_sorted(|_| true),
+        0
+    );
+}
+
+#[test]
+fn builder_ping_pong() {
+    let sorted = Builder::new().ping_pong((0..100u32).rev().collect());
+    assert!(sorted.memory_bytes() >= 2 * 100 * 4);
+    assert!(sorted.eq(0..100));
+}
+
+#[test]
+fn builder_budget() {
+    use crate::error::Error;
+    use crate::pairs::Layout;
+
+    // 5 bytes per item: a u32, and a u8 index.
+    let builder = Builder::new().budget::<500, u32, u8, 100>();
+    let items = |len: u32| (0..len).rev().collect::<Vec<_>>();
+    assert_eq!(builder.set(items(100)).unwrap().nth(7), Some(&7));
+    assert_eq!(builder.set(items(101)).err(), Some(Error::CapacityExceeded));
+    // The whole capacity counts.
+    assert!(builder.set(Vec::with_capacity(101)).is_err());
+    // Twice the items.
+    let builder = Builder::new().budget::<500, u32, u8, 50>();
+    assert!(builder.ping_pong(items(50)).unwrap().eq(0..50));
+    assert!(builder.ping_pong(items(51)).is_err());
+
+    // 9 bytes per entry.
+    let builder = Builder::new().budget::<450, (u32, u32), u8, 50>();
+    let entries = |len: u32| (0..len).map(|i| (i, i)).collect::<Vec<_>>();
+    assert_eq!(builder.map(entries(50)).unwrap().get(&3), Some(&3));
+    assert!(builder.map(entries(51)).is_err());
+    assert_eq!(builder.pairs(entries(50)).unwrap().count(), 50);
+    // The pairs, plus keys, values and indices: 21 bytes per entry.
+    let blocked = Builder::new()
+        .layout(Layout::Blocked)
+        .budget::<450, (u32, u32), u8, 50>();
+    assert!(blocked.pairs(entries(21)).is_ok());
+    assert!(blocked.pairs(entries(22)).is_err());
+}
+
+#[test]
+fn planned_ranks() {
+    let mut set: LazySortedSet<Counted> = (0..500u32).map(|i| Counted((i * 7919) % 500)).collect();
+    let ranks = [0, 1, 2, 250, 499];
+    set.plan(&ranks);
+    for rank in ranks {
+        // Resolved already: no comparisons.
+        let (value, comparisons) = comparisons(|| set.nth(rank).map(|c| c.0));
+        assert_eq!((value, comparisons), (Some(rank as u32), 0));
+    }
+    let mut map: LazySortedMap<u32, ()> = (0..100u32).rev().map(|k| (k, ())).collect();
+    map.plan(&[10, 90]);
+    assert_eq!(map.nth(90).map(|(k, _)| *k), Some(90));
+}
+
+#[test]
+fn sorted_lazy_counts() {
+    let set: LazySortedSet<u32> = (0..100u32).map(|i| (i * 37) % 7).collect();
+    let counts: Vec<(u32, usize)> = set.sorted_lazy_counts().collect();
+    // `i * 37 % 7` is 0 or 2 for the remainders 0 and 1 of `i % 7` (which 100 has 15 of).
+    let expected = (0..7).map(|value| (value, if value % 2 == 0 && value < 4 { 15 } else { 14 }));
+    assert!(counts.into_iter().eq(expected));
+
+    // Dropping a partially consumed iterator drops the rest (checked by Miri, or by a leak
+    // checker).
+    let set: LazySortedSet<alloc::string::String> =
+        (0..50u32).map(|i| alloc::format!("{}", i % 5)).collect();
+    let mut counts = set.sorted_lazy_counts();
+    assert_eq!(counts.next(), Some(("0".into(), 10)));
+    let rest = counts.into_inner();
+    assert_eq!(rest.len(), 40);
+    assert!(rest.iter().all(|value| value.as_str() != "0"));
+}
+
+/// The same seed partitions the same way, also with random pivots.
+#[test]
+fn builder_seed() {
+    use crate::engine::{Adaptive, Pivot};
+    use crate::util::rng::Rng;
+
+    let items = || (0..1000u32).map(|i| (i * 7919) % 1000).collect::<Vec<_>>();
+    let queried = |builder: Builder| {
+        let mut set = builder.set(items());
+        assert_eq!(set.nth(500), Some(&500));
+        set.into_vec()
+    };
+    let random = Builder::new().pivot(Pivot::Random(Rng::new(1)));
+    assert_eq!(queried(random.seed(7)), queried(random.seed(7)));
+    assert_ne!(queried(random.seed(7)), queried(random.seed(8)));
+    assert_eq!(
+        queried(random.seed(7)),
+        queried(Builder::new().pivot(Pivot::Random(Rng::new(7))))
+    );
+    let adaptive = Builder::new().pivot(Pivot::Adaptive(Adaptive::default()));
+    assert_eq!(queried(adaptive.seed(3)), queried(adaptive.seed(3)));
+    // Deterministic pivots ignore it.
+    assert_eq!(Pivot::TowardTarget.with_seed(3), Pivot::TowardTarget);
+
+    // Non-numeric items (no bucket pre-pass) replay the same, too.
+    let tuples = || (0..1000u32).map(|i| ((i * 7919) % 1000, i % 3)).collect();
+    let replay = |seed| {
+        let mut set = random.seed(seed).set::<(u32, u32)>(tuples());
+        assert_eq!(set.nth(500).map(|tuple| tuple.0), Some(500));
+        set.into_vec()
+    };
+    assert_eq!(replay(7), replay(7));
+}
+
+#[test]
+fn builder_pairs_layout() {
+    use crate::pairs::Layout;
+
+    let pairs = (0..500u32).map(|i| ((i * 7919) % 500, [i; 8])).collect();
+    let mut sorted = Builder::new().layout(Layout::Blocked).pairs(pairs);
+    assert_eq!(sorted.next().map(|(key, _)| key), Some(0));
+    assert!(sorted.map(|(key, _)| key).eq(1..500));
+}
@@ -0,0 +1,1450 @@
+//! Lazy (delayed) partition engine, shared by the sorters. No allocation, non-recursive.
+//!
+//! The engine doesn't own the items. The caller (a sorter) owns them, and passes the "live" items
+//! (the ones NOT consumed yet) to each call as a slice. The engine only keeps its bookkeeping: a
+//! fixed-capacity ring of "segments" (ranges of positions), so that
+//! - every item in a segment is less than or equal to every item in any later segment, and
+//! - a segment is either known to be sorted, or not (yet).
+//!
+//! Partitioning happens only where needed (usually at the end - front or back - being consumed),
+//! and only as much as needed to determine the requested item. Adjacent sorted segments get merged.
+//! If the ring is full, we merge two segments at the end farther away. That loses some of the
+//! previous partitioning work, but it never loses correctness.
+
+use crate::heap::heapsort;
+use crate::idx::Index;
+use crate::util::rng::Rng;
+use crate::util::{cmp_at, swap_at};
+use core::cmp::Ordering;
+use core::ops::Range;
+
+#[cfg(test)]
+mod engine_tests;
+
+/// Default number of segments that [`Engine`] keeps track of.
+pub const DEFAULT_DEPTH: usize = 64;
+
+/// Segments this short get insertion-sorted (rather than partitioned further).
+const SMALL_SORT_LEN: usize = 16;
+
+/// Number of items that [`Pivot::FastFirst`] samples.
+const SAMPLES: usize = 32;
+
+/// Segments shorter than this get [`Pivot::TowardTarget`] pivots, even with [`Pivot::FastFirst`]:
+/// sampling wouldn't pay off.
+const SAMPLED_MIN_LEN: usize = 32 * SAMPLES;
+
+/// Leading bytes of an exported plan (see [`Engine::export_plan()`]): a tag and the format version.
+const PLAN_MAGIC: [u8; 4] = *b"LZP\x01";
+
+/// Bytes of an exported plan before its segments: the magic, `front` and `back` (as `u64`) and the
+/// number of segments (as `u32`).
+const PLAN_HEADER_LEN: usize = 4 + 8 + 8 + 4;
+
+/// Bytes per segment of an exported plan: its start (as `u64`) and whether it's sorted.
+const PLAN_SEGMENT_LEN: usize = 8 + 1;
+
+/// How [`Engine`] chooses the pivot of a partition. Except for [`Pivot::Random`] (and
+/// [`Pivot::Adaptive`], once switched), it's one of the first, the middle and the last item of the
+/// segment being partitioned.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Pivot {
+    /// The median of the three. Balanced partitions: best for sorting (many) items.
+    #[default]
+    MedianOfThree,
+    /// The one of the three closest (by rank) to the position being resolved. Unbalanced
+    /// partitions, which shrink the segment containing that position faster: best for a few items
+    /// at either end (the minimum or the maximum).
+    TowardTarget,
+    /// The median of three items at random positions. Guards against inputs crafted (or happening)
+    /// to defeat the fixed positions above. Reproducible, since the [`Rng`] (its seed) is explicit.
+    Random(Rng),
+    /// For interactive consumers of huge inputs: the first few items with minimal delay. Estimate
+    /// the rank of the position being resolved from a sample of items spread over the segment, and
+    /// take the sample item of that rank. For the minimum that's the smallest of 32 samples, so the
+    /// first partition leaves only ~1/32 of the items before it (where [`Pivot::TowardTarget`]
+    /// leaves ~1/4). Short segments get [`Pivot::TowardTarget`] pivots. Sorting everything this way
+    /// costs more than by [`Pivot::MedianOfThree`].
+    FastFirst,
+    /// Start with [`Pivot::MedianOfThree`], and watch the balance of the partitions (see
+    /// [`Adaptive`]): switch to [`Pivot::Random`]-like pivots once they go bad, and heapsort a
+    /// segment that keeps going bad even then. Robust without manual tuning. Counted in
+    /// [`Stats::adaptive_switches`].
+    Adaptive(Adaptive),
+}
+
+impl Pivot {
+    /// The same strategy, with its random choices (if any) seeded by `seed`: reproducible across
+    /// runs and platforms. The other strategies are deterministic already.
+    pub fn with_seed(self, seed: u64) -> Self {
+        match self {
+            Self::Random(_) => Self::Random(Rng::new(seed)),
+            Self::Adaptive(adaptive) => Self::Adaptive(Adaptive {
+                rng: Rng::new(seed),
+                ..adaptive
+            }),
+            deterministic => deterministic,
+        }
+    }
+}
+
+/// Consecutive unbalanced partitions after which [`Adaptive`] switches to random pivots.
+const ADAPTIVE_RANDOM_AFTER: u8 = 2;
+
+/// Consecutive unbalanced partitions (with random pivots) after which [`Adaptive`] heapsorts.
+const ADAPTIVE_HEAPSORT_AFTER: u8 = 4;
+
+/// State of [`Pivot::Adaptive`].
+///
+/// A partition is unbalanced if its greater piece of the less and the greater items keeps more than
+/// 7/8 of the segment. The items equal to the pivot count as progress: the (always) three-way
+/// partitioning resolves them all at once, so lots of duplicates never look unbalanced.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Adaptive {
+    rng: Rng,
+    mode: AdaptiveMode,
+    /// Consecutive unbalanced partitions (in the current mode).
+    unbalanced: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum AdaptiveMode {
+    MedianOfThree,
+    Random,
+    /// Heapsort the next segment (then back to [`AdaptiveMode::Random`]).
+    Heapsort,
+}
+
+impl Adaptive {
+    /// `seed` is for the random pivots (once switched to them). See [`Rng::new()`].
+    pub const fn new(seed: u64) -> Self {
+        Self {
+            rng: Rng::new(seed),
+            mode: AdaptiveMode::MedianOfThree,
+            unbalanced: 0,
+        }
+    }
+
+    /// Whether it has switched to random pivots (so far).
+    pub fn is_random(&self) -> bool {
+        self.mode != AdaptiveMode::MedianOfThree
+    }
+
+    /// Record a partition of `len` items into `lt` less and `len - gt` greater ones. Return whether
+    /// that switched the mode.
+    fn record(&mut self, len: usize, lt: usize, gt: usize) -> bool {
+        if lt.max(len - gt) <= len - len / 8 {
+            self.unbalanced = 0;
+            return false;
+        }
+        self.unbalanced += 1;
+        let mode = match self.mode {
+            AdaptiveMode::MedianOfThree if self.unbalanced >= ADAPTIVE_RANDOM_AFTER => {
+                AdaptiveMode::Random
+            }
+            AdaptiveMode::Random if self.unbalanced >= ADAPTIVE_HEAPSORT_AFTER => {
+                AdaptiveMode::Heapsort
+            }
+            _ => return false,
+        };
+        self.mode = mode;
+        self.unbalanced = 0;
+        true
+    }
+
+    /// Whether to heapsort the segment about to be partitioned. (Then it's back to random pivots.)
+    fn take_heapsort(&mut self) -> bool {
+        let heapsort = self.mode == AdaptiveMode::Heapsort;
+        if heapsort {
+            self.mode = AdaptiveMode::Random;
+        }
+        heapsort
+    }
+}
+
+impl Default for Adaptive {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+/// What [`Engine`] does when its segments (`DEPTH` of them) run out, yet a segment needs
+/// partitioning.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OnFull {
+    /// Merge segments at the end farther away. That discards some earlier partitioning (which may
+    /// have to be repeated later), but it keeps the cost of this step low. (With `DEPTH` of four,
+    /// merging may not make room without merging the segment itself: then it's heapsorted.)
+    #[default]
+    Merge,
+    /// Sort the segment fully, in place, by heapsort: O(m log m) for `m` items in the segment, in
+    /// the worst case, and no earlier partitioning is lost. Counted in
+    /// [`Stats::heapsorted_segments`].
+    Heapsort,
+}
+
+/// Which pieces of a partition [`Engine`] keeps pending (as unsorted segments), and which ones it
+/// sorts right away.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Schedule {
+    /// Keep all pieces pending, and only partition further the one containing the position being
+    /// resolved. The least work before the first item gets yielded, but the pending segments may
+    /// pile up (up to `DEPTH`, see [`OnFull`]) on unlucky pivots.
+    #[default]
+    TargetOnly,
+    /// Sort a piece right away if it's smaller than the piece containing the position being
+    /// resolved (like recursing into the smaller side first in a quicksort). Then every pending
+    /// segment is at least as long as all the (unsorted) ones closer to that position, so there are
+    /// at most log2(n) of them: a `DEPTH` of 2 log2(n) + 2 never runs out. The cost is a higher
+    /// latency of the first items. Counted in [`Stats::eagerly_sorted_segments`].
+    SmallerSideFirst,
+}
+
+/// Consumption position of an [`Engine`], to rewind to: see [`Engine::checkpoint()`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Checkpoint {
+    front: usize,
+    back: usize,
+}
+
+/// Counters of what [`Engine`] has done so far (see [`Engine::stats()`]).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Number of partitioning steps.
+    pub partitions: usize,
+    /// Number of segments merged into their neighbors, since the segments ran out (see
+    /// [`OnFull::Merge`]).
+    pub merged_segments: usize,
+    /// Number of segments sorted in place, since the segments ran out (see [`OnFull::Heapsort`]).
+    pub heapsorted_segments: usize,
+    /// Number of partition pieces sorted right away, per [`Schedule::SmallerSideFirst`].
+    pub eagerly_sorted_segments: usize,
+    /// Number of times [`Pivot::Adaptive`] switched its strategy (to random pivots, or to
+    /// heapsorting a segment).
+    pub adaptive_switches: usize,
+    /// Number of whole segments skipped (without partitioning them) by
+    /// [`Engine::skip_front_while()`].
+    pub skipped_segments: usize,
+    /// Comparisons made by each [`Engine::next_front()`] and [`Engine::next_back()`] call: the
+    /// amortization profile of the lazy sorting. With crate feature `instrument` only.
+    #[cfg(feature = "instrument")]
+    pub comparisons_per_next: WorkHistogram,
+}
+
+impl Stats {
+    /// Whether the engine has had to work around running out of segments (by either strategy).
+    pub fn degraded(&self) -> bool {
+        self.merged_segments > 0 || self.heapsorted_segments > 0
+    }
+}
+
+/// Number of [`WorkHistogram`] buckets.
+#[cfg(feature = "instrument")]
+pub const WORK_BUCKETS: usize = 16;
+
+/// Histogram of the work (comparisons) done per call, in power-of-two buckets: bucket 0 counts the
+/// calls with no work, bucket `b` the calls with `2^(b-1)..2^b` units of work. The last bucket
+/// also counts any calls with more work. Fixed size, no allocation.
+#[cfg(feature = "instrument")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WorkHistogram {
+    buckets: [usize; WORK_BUCKETS],
+}
+
+#[cfg(feature = "instrument")]
+impl WorkHistogram {
+    /// Number of calls per bucket.
+    pub fn buckets(&self) -> &[usize; WORK_BUCKETS] {
+        &self.buckets
+    }
+
+    /// Range of work counted by `bucket` (the last one is open-ended).
+    pub fn bucket_range(bucket: usize) -> Range<usize> {
+        match bucket {
+            0 => 0..1,
+            _ if bucket + 1 >= WORK_BUCKETS => 1 << (WORK_BUCKETS - 2)..usize::MAX,
+            _ => 1 << (bucket - 1)..1 << bucket,
+        }
+    }
+
+    /// Number of calls recorded.
+    pub fn calls(&self) -> usize {
+        self.buckets.iter().sum()
+    }
+
+    pub(crate) fn record(&mut self, work: usize) {
+        let bucket = (usize::BITS - work.leading_zeros()) as usize;
+        self.buckets[bucket.min(WORK_BUCKETS - 1)] += 1;
+    }
+}
+
+/// Hooks into [`Engine`], for visualization, debugging or tuning. All positions are absolute (as
+/// elsewhere in [`Engine`]). Every method does nothing by default, and `()` observes nothing (at no
+/// cost).
+///
+/// `M` is the type of the segment tags (see [`Engine::with_tag()`]).
+pub trait Observer<M: Copy = ()> {
+    /// Segment `lo..hi` is about to be partitioned.
+    fn on_partition_start(&mut self, _lo: usize, _hi: usize) {}
+
+    /// Segment `lo..hi`, about to be partitioned, carries `tag`. (All its pieces will, too.)
+    fn on_partition_tag(&mut self, _lo: usize, _hi: usize, _tag: M) {}
+
+    /// The pivot is the item at `position` (before partitioning moves it).
+    fn on_pivot_chosen(&mut self, _position: usize) {}
+
+    /// Segment `lo..hi` got partitioned: the items at `equal` are equal to the pivot, the ones
+    /// before are less, the ones after are greater.
+    fn on_partition_end(&mut self, _lo: usize, _hi: usize, _equal: Range<usize>) {}
+
+    /// The item at `position` got consumed (the caller takes it).
+    fn on_yield(&mut self, _position: usize) {}
+}
+
+impl<M: Copy> Observer<M> for () {}
+
+/// A type-erased comparator. Every method that takes a comparator (`cmp: &mut CMP`) accepts
+/// `CMP = DynCmp<T>`, too. Then all callers (with any closure types) share one compiled copy of the
+/// engine per item type - at the cost of an indirect call per comparison. That suits code size
+/// constrained (embedded, WASM...) applications.
+///
+/// ```
+/// use lazysort_no_alloc::engine::{DynCmp, Engine};
+///
+/// let mut items = [3, 1, 2];
+/// let mut engine = Engine::<u8>::try_new(items.len()).unwrap();
+/// let cmp: &mut DynCmp<u32> = &mut |a, b| b.cmp(a);
+/// assert_eq!(engine.next_front(&mut items, cmp), Some(0));
+/// assert_eq!(items[0], 3);
+/// ```
+pub type DynCmp<'a, T> = dyn FnMut(&T, &T) -> Ordering + 'a;
+
+/// Bookkeeping for lazy sorting of `len` items (see [`Engine::new()`]).
+///
+/// `I` is the type used to store segment boundaries. Use a narrow one (like [`u8`]) to minimize
+/// memory on micro controllers. `DEPTH` is the maximum number of segments kept track of (at least
+/// 4). `O` gets notified of partitioning and consumption (see [`Engine::with_observer()`]). `M` is
+/// a (small) tag carried by each segment, like a source or a bucket id (see
+/// [`Engine::with_tag()`]).
+///
+/// All positions accepted/returned by [`Engine`] are "absolute" (relative to the start of the
+/// original items), even though the `live` slices passed in cover only the items NOT consumed yet:
+/// `live[0]` is the item at position [`Engine::front()`].
+#[derive(Clone, Debug)]
+pub struct Engine<
+    I: Index = usize,
+    const DEPTH: usize = DEFAULT_DEPTH,
+    O: Observer<M> = (),
+    M: Copy = (),
+> {
+    /// Position of the smallest live item.
+    front: usize,
+    /// Position right after the greatest live item.
+    back: usize,
+    /// Items consumed before this position may have been consumed out of order (skipped, see
+    /// [`Engine::skip_front()`] and [`Engine::skip_front_while()`]). Never after `front`.
+    skipped_front: usize,
+    /// Like `skipped_front`, but for items consumed at or after this position (by
+    /// [`Engine::skip_back()`]). Only ever at or after `back`.
+    skipped_back: usize,
+    /// Ring buffer of segment starts (shifted by [`Index::min_index_usize()`], so that we can use
+    /// [`core::num::NonZeroU8`]...). The first segment always starts at `front`, the last one ends
+    /// at `back`.
+    starts: [I; DEPTH],
+    /// Whether the respective segment is known to be sorted.
+    sorted: [bool; DEPTH],
+    /// Tag of the respective segment. Pieces of a segment inherit its tag.
+    tags: [M; DEPTH],
+    /// Slot (in `starts` & `sorted`) of the first segment.
+    head: usize,
+    /// Number of segments. Zero if and only if there are no live items.
+    count: usize,
+    pivot: Pivot,
+    on_full: OnFull,
+    schedule: Schedule,
+    stats: Stats,
+    observer: O,
+}
+
+impl<I: Index, const DEPTH: usize> Engine<I, DEPTH> {
+    const DEPTH_AT_LEAST_FOUR: () = assert!(DEPTH >= 4, "Engine DEPTH has to be at least 4.");
+
+    /// Start tracking `len` (unsorted) items. Panic if `len` is not indexable by `I`.
+    ///
+    /// Not available with crate feature `no-panic`. Use [`Engine::try_new()`] instead.
+    #[cfg(not(feature = "no-panic"))]
+    pub fn new(len: usize) -> Self {
+        match Self::try_new(len) {
+            Ok(engine) => engine,
+            Err(_) => release_panic!("Too many items for the index type."),
+        }
+    }
+
+    /// Start tracking `len` (unsorted) items. Return [`crate::error::Error::CapacityExceeded`] if
+    /// `len` is not indexable by `I`.
+    pub fn try_new(len: usize) -> Result<Self, crate::error::Error> {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::DEPTH_AT_LEAST_FOUR;
+        if len > I::MAX_INDEXABLE_LEN {
+            return Err(crate::error::Error::CapacityExceeded);
+        }
+        let mut engine = Self {
+            front: 0,
+            back: len,
+            skipped_front: 0,
+            skipped_back: len,
+            starts: [I::min_index(); DEPTH],
+            sorted: [false; DEPTH],
+            tags: [(); DEPTH],
+            head: 0,
+            count: 0,
+            pivot: Pivot::default(),
+            on_full: OnFull::default(),
+            schedule: Schedule::default(),
+            stats: Stats::default(),
+            observer: (),
+        };
+        if len > 0 {
+            engine.push_back_segment(0, len == 1);
+        }
+        Ok(engine)
+    }
+
+    /// Resume from a plan exported by [`Engine::export_plan()`] (possibly by another process, with
+    /// a different index type or `DEPTH`): the same consumption position and segments. The pivot
+    /// strategy, [`OnFull`], [`Schedule`], [`Stats`] and tags are NOT part of a plan: they start
+    /// as by [`Engine::try_new()`].
+    ///
+    /// Return [`crate::error::Error::InvalidArgument`] if `plan` is not a (well formed) plan, or
+    /// [`crate::error::Error::CapacityExceeded`] if it has more than `DEPTH` segments, or positions
+    /// not indexable by `I`.
+    pub fn try_from_plan(plan: &[u8]) -> Result<Self, crate::error::Error> {
+        use crate::error::Error;
+        if plan.len() < PLAN_HEADER_LEN {
+            return Err(Error::InvalidArgument);
+        }
+        let (header, segments) = plan.split_at(PLAN_HEADER_LEN);
+        if header[..4] != PLAN_MAGIC {
+            return Err(Error::InvalidArgument);
+        }
+        let front = plan_usize(&header[4..12])?;
+        let back = plan_usize(&header[12..20])?;
+        let count = u32::from_le_bytes([header[20], header[21], header[22], header[23]]);
+        let count = usize::try_from(count).map_err(|_| Error::InvalidArgument)?;
+        if segments.len()
+            != count
+                .checked_mul(PLAN_SEGMENT_LEN)
+                .ok_or(Error::InvalidArgument)?
+            || front > back
+            || (count == 0) != (front == back)
+        {
+            return Err(Error::InvalidArgument);
+        }
+        if count > DEPTH || back > I::MAX_INDEXABLE_LEN {
+            return Err(Error::CapacityExceeded);
+        }
+        let mut engine = Self::try_new(0)?;
+        // How the other one consumed the items before `front` (and after `back`) is unknown.
+        (engine.front, engine.back) = (front, back);
+        (engine.skipped_front, engine.skipped_back) = (front, back);
+        let mut previous = None;
+        for segment in segments.chunks_exact(PLAN_SEGMENT_LEN) {
+            let (start, sorted) = (plan_usize(&segment[..8])?, segment[8]);
+            let in_order = match previous {
+                None => start == front,
+                Some(previous) => previous < start && start < back,
+            };
+            if !in_order || sorted > 1 {
+                return Err(Error::InvalidArgument);
+            }
+            engine.push_back_segment(start, sorted == 1);
+            previous = Some(start);
+        }
+        Ok(engine)
+    }
+}
+
+/// A `u64` position of an exported plan.
+fn plan_usize(bytes: &[u8]) -> Result<usize, crate::error::Error> {
+    let mut le = [0; 8];
+    le.copy_from_slice(bytes);
+    usize::try_from(u64::from_le_bytes(le)).map_err(|_| crate::error::Error::CapacityExceeded)
+}
+
+impl<I: Index, const DEPTH: usize, O: Observer> Engine<I, DEPTH, O> {
+    /// Tag all segments with `tag` from now on. Tag segments individually with
+    /// [`Engine::split_at_tagged()`].
+    pub fn with_tag<N: Copy>(self, tag: N) -> Engine<I, DEPTH, O, N>
+    where
+        O: Observer<N>,
+    {
+        Engine {
+            front: self.front,
+            back: self.back,
+            skipped_front: self.skipped_front,
+            skipped_back: self.skipped_back,
+            starts: self.starts,
+            sorted: self.sorted,
+            tags: [tag; DEPTH],
+            head: self.head,
+            count: self.count,
+            pivot: self.pivot,
+            on_full: self.on_full,
+            schedule: self.schedule,
+            stats: self.stats,
+            observer: self.observer,
+        }
+    }
+}
+
+impl<I: Index, const DEPTH: usize, O: Observer<M>, M: Copy> Engine<I, DEPTH, O, M> {
+    /// Notify `observer` from now on (replacing the current one).
+    pub fn with_observer<P: Observer<M>>(self, observer: P) -> Engine<I, DEPTH, P, M> {
+        Engine {
+            front: self.front,
+            back: self.back,
+            skipped_front: self.skipped_front,
+            skipped_back: self.skipped_back,
+            starts: self.starts,
+            sorted: self.sorted,
+            tags: self.tags,
+            head: self.head,
+            count: self.count,
+            pivot: self.pivot,
+            on_full: self.on_full,
+            schedule: self.schedule,
+            stats: self.stats,
+            observer,
+        }
+    }
+
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    pub fn observer_mut(&mut self) -> &mut O {
+        &mut self.observer
+    }
+
+    /// Number of live (not consumed yet) items.
+    pub fn len(&self) -> usize {
+        self.back - self.front
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.front == self.back
+    }
+
+    /// Position of the smallest live item. (The number of items consumed from the front so far.)
+    pub fn front(&self) -> usize {
+        self.front
+    }
+
+    /// Position right after the greatest live item.
+    pub fn back(&self) -> usize {
+        self.back
+    }
+
+    pub fn pivot(&self) -> Pivot {
+        self.pivot
+    }
+
+    /// Set how to choose pivots from now on.
+    pub fn set_pivot(&mut self, pivot: Pivot) {
+        self.pivot = pivot;
+    }
+
+    pub fn on_full(&self) -> OnFull {
+        self.on_full
+    }
+
+    /// Set what to do when the segments run out, from now on.
+    pub fn set_on_full(&mut self, on_full: OnFull) {
+        self.on_full = on_full;
+    }
+
+    pub fn schedule(&self) -> Schedule {
+        self.schedule
+    }
+
+    /// Set which partition pieces to keep pending, from now on.
+    pub fn set_schedule(&mut self, schedule: Schedule) {
+        self.schedule = schedule;
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    /// Add `additional` (unsorted) live items right after [`Engine::back()`]. This discards the
+    /// partitioning done so far (but not the consumption). Panic if the positions would not be
+    /// indexable by `I` anymore.
+    ///
+    /// Not available with crate feature `no-panic`. Use [`Engine::try_append()`] instead.
+    #[cfg(not(feature = "no-panic"))]
+    pub fn append(&mut self, additional: usize) {
+        if self.try_append(additional).is_err() {
+            release_panic!("Too many items for the index type.");
+        }
+    }
+
+    /// Like [`Engine::append()`], but return [`crate::error::Error::CapacityExceeded`] (and change
+    /// nothing) if the positions would not be indexable by `I` anymore.
+    pub fn try_append(&mut self, additional: usize) -> Result<(), crate::error::Error> {
+        if additional > I::MAX_INDEXABLE_LEN - self.back {
+            return Err(crate::error::Error::CapacityExceeded);
+        }
+        self.back += additional;
+        self.skipped_back = self.skipped_back.max(self.back);
+        // The one remaining segment keeps the tag of the (former) first one.
+        let tag = self.tags[self.slot(0)];
+        self.count = 0;
+        if !self.is_empty() {
+            let len = self.len();
+            self.push_back_segment(self.front, len == 1);
+            let first = self.slot(0);
+            self.tags[first] = tag;
+        }
+        Ok(())
+    }
+
+    /// The consumption position (at both ends), to [`Engine::rewind()`] to later. O(1), `Copy`.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            front: self.front,
+            back: self.back,
+        }
+    }
+
+    /// Number of bytes that [`Engine::export_plan()`] writes (currently).
+    pub fn plan_len(&self) -> usize {
+        PLAN_HEADER_LEN + self.count * PLAN_SEGMENT_LEN
+    }
+
+    /// Write the partitioning done so far (the consumption position, and the boundaries of the
+    /// segments and whether they're sorted - not the items) to `out`, in a compact, platform
+    /// independent (little endian) format. Return the number of bytes written (see
+    /// [`Engine::plan_len()`]), or [`crate::error::Error::CapacityExceeded`] if `out` is shorter.
+    ///
+    /// For a hand-off across processes that map the same items (in shared memory): the other one
+    /// resumes by [`Engine::try_from_plan()`], where this one left off, without repeating any
+    /// partitioning.
+    ///
+    /// ```
+    /// use lazysort_no_alloc::engine::Engine;
+    ///
+    /// let mut items = [50, 20, 40, 10, 30];
+    /// let mut engine = Engine::<u8>::try_new(items.len()).unwrap();
+    /// assert_eq!(engine.next_front(&mut items, &mut i32::cmp), Some(0));
+    /// let mut plan = [0; 64];
+    /// let len = engine.export_plan(&mut plan).unwrap();
+    ///
+    /// let mut resumed = Engine::<u16>::try_from_plan(&plan[..len]).unwrap();
+    /// assert_eq!(resumed.next_front(&mut items[1..], &mut i32::cmp), Some(1));
+    /// assert_eq!(items[1], 20);
+    /// ```
+    pub fn export_plan(&self, out: &mut [u8]) -> Result<usize, crate::error::Error> {
+        let len = self.plan_len();
+        let out = out
+            .get_mut(..len)
+            .ok_or(crate::error::Error::CapacityExceeded)?;
+        // (Never: `len` covers the header. But it spares a panic path, see feature `no-panic`.)
+        if out.len() < PLAN_HEADER_LEN {
+            return Err(crate::error::Error::CapacityExceeded);
+        }
+        let (header, segments) = out.split_at_mut(PLAN_HEADER_LEN);
+        header[..4].copy_from_slice(&PLAN_MAGIC);
+        header[4..12].copy_from_slice(&(self.front as u64).to_le_bytes());
+        header[12..20].copy_from_slice(&(self.back as u64).to_le_bytes());
+        header[20..].copy_from_slice(&(self.count as u32).to_le_bytes());
+        for (nth, segment) in segments.chunks_exact_mut(PLAN_SEGMENT_LEN).enumerate() {
+            segment[..8].copy_from_slice(&(self.start(nth) as u64).to_le_bytes());
+            segment[8] = self.sorted[self.slot(nth)] as u8;
+        }
+        Ok(len)
+    }
+
+    /// Un-consume the items consumed (at either end) since `checkpoint`, keeping all partitioning
+    /// done since. They are sorted already, so they become a sorted segment again (no comparisons).
+    /// Unless some of them got skipped (see [`Engine::skip_front()`]): then an unsorted one.
+    /// For speculative consumption: probe ahead, then rewind. The caller has to have kept those
+    /// items at their positions (as sorters over borrowed storage do), or put them back.
+    ///
+    /// If the segments have run out, this merges segments at the back (see [`OnFull::Merge`]).
+    /// Return [`crate::error::Error::InvalidArgument`] if `checkpoint` is ahead of the current
+    /// position (at either end).
+    pub fn rewind(&mut self, checkpoint: Checkpoint) -> Result<(), crate::error::Error> {
+        if checkpoint.front > self.front || checkpoint.back < self.back {
+            return Err(crate::error::Error::InvalidArgument);
+        }
+        if checkpoint.back > self.back {
+            let sorted = checkpoint.back <= self.skipped_back;
+            if !sorted || self.count == 0 || !self.sorted[self.slot(self.count - 1)] {
+                self.make_room_at_back();
+                self.push_back_segment(self.back, sorted);
+            }
+            self.back = checkpoint.back;
+            self.skipped_back = self.skipped_back.max(self.back);
+        }
+        if checkpoint.front < self.front {
+            let sorted = checkpoint.front >= self.skipped_front;
+            if sorted && self.count > 0 && self.sorted[self.slot(0)] {
+                self.set_start(0, checkpoint.front);
+            } else {
+                self.make_room_at_back();
+                self.insert(0, checkpoint.front, sorted);
+            }
+            self.front = checkpoint.front;
+            self.skipped_front = self.skipped_front.min(self.front);
+        }
+        Ok(())
+    }
+
+    /// Make room for one more segment (if needed) by merging the last one into the previous one.
+    fn make_room_at_back(&mut self) {
+        if self.count == DEPTH {
+            self.remove(self.count - 1);
+            let last = self.slot(self.count - 1);
+            self.sorted[last] = false;
+            self.stats.merged_segments += 1;
+        }
+    }
+
+    /// Record that the caller has partitioned the live items at `position`: none of the items
+    /// before it is greater than any item from it on. Return `false` (and record nothing, which is
+    /// still correct) if there's no room for another segment.
+    pub fn split_at(&mut self, position: usize) -> bool {
+        debug_assert!(self.front <= position && position <= self.back);
+        if position == self.front || position == self.back {
+            return true;
+        }
+        let nth = self.find(position);
+        if self.start(nth) == position {
+            return true;
+        }
+        if self.count == DEPTH {
+            return false;
+        }
+        let sorted = self.sorted[self.slot(nth)];
+        self.insert(nth + 1, position, sorted);
+        true
+    }
+
+    /// Like [`Engine::split_at()`], and then tag the segment starting at `position` (up to the
+    /// next split) with `tag`. Return `false` (and tag nothing) if there's no room for another
+    /// segment, or if `position` is [`Engine::back()`].
+    pub fn split_at_tagged(&mut self, position: usize, tag: M) -> bool {
+        if position == self.back || !self.split_at(position) {
+            return false;
+        }
+        let slot = self.slot(self.find(position));
+        self.tags[slot] = tag;
+        true
+    }
+
+    /// Tag of the segment containing (live) `position`.
+    pub fn tag_at(&self, position: usize) -> M {
+        debug_assert!(self.front <= position && position < self.back);
+        self.tags[self.slot(self.find(position))]
+    }
+
+    /// Partition (only as needed) so that the item at `position` is final: it's the item of that
+    /// rank (among all items, consumed or not). `position` has to be live.
+    pub fn resolve_at<T, CMP>(&mut self, live: &mut [T], position: usize, cmp: &mut CMP)
+    where
+        CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+    {
+        debug_assert_eq!(live.len(), self.len());
+        debug_assert!(self.front <= position && position < self.back);
+        while !self.resolve_segment(live, self.find(position), position, cmp) {}
+    }
+
+    /// Partition (only as needed) so that the smallest live item ends up at [`Engine::front()`].
+    /// Return that position, or [`None`] if there are no live items.
+    pub fn resolve_front<T, CMP>(&mut self, live: &mut [T], cmp: &mut CMP) -> Option<usize>
+    where
+        CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+    {
+        if self.is_empty() {
+            return None;
+        }
+        self.resolve_at(live, self.front, cmp);
+        Some(self.front)
+    }
+
+    /// Like [`Engine::resolve_front()`], and then consume that item: the caller has to take (or
+    /// forget) it from the returned position. Next time pass `live` WITHOUT that item.
+    pub fn next_front<T, CMP>(&mut self, live: &mut [T], cmp: &mut CMP) -> Option<usize>
+    where
+        CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+    {
+        #[cfg(feature = "instrument")]
+        let comparisons = core::cell::Cell::new(0);
+        // (Type-erased, so that a caller recursing through here doesn't instantiate it endlessly.)
+        #[cfg(feature = "instrument")]
+        let cmp: &mut DynCmp<'_, T> = &mut counting(cmp, &comparisons);
+        let position = self.resolve_front(live, cmp)?;
+        #[cfg(feature = "instrument")]
+        self.stats.comparisons_per_next.record(comparisons.get());
+        self.consume_front(1);
+        self.observer.on_yield(position);
+        Some(position)
+    }
+
+    /// Consume the (up to) `k` smallest live items at once: partition at rank `k`, and sort only
+    /// the items before it. Much cheaper than `k` times [`Engine::next_front()`] for a sizable `k`.
+    /// Return their positions (sorted). The caller has to take (or forget) them. Next time pass
+    /// `live` WITHOUT them.
+    pub fn next_chunk_front<T, CMP>(
+        &mut self,
+        live: &mut [T],
+        k: usize,
+        cmp: &mut CMP,
+    ) -> Range<usize>
+    where
+        CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+    {
+        let (lo, hi) = (self.front, self.front + k.min(self.len()));
+        self.sort_range(live, lo, hi, cmp);
+        self.consume_front(hi - lo);
+        for position in lo..hi {
+            self.observer.on_yield(position);
+        }
+        lo..hi
+    }
+
+    /// Consume the smallest live item, together with all live items equal to it. Return their
+    /// positions. The equal items come cheap: a three-way partition leaves them in a sorted segment
+    /// (one comparison each). The caller has to take (or forget) them. Next time pass `live`
+    /// WITHOUT them.
+    pub fn next_run_front<T, CMP>(&mut self, live: &mut [T], cmp: &mut CMP) -> Option<Range<usize>>
+    where
+        CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+    {
+        let first = self.resolve_front(live, cmp)?;
+        let mut end = first + 1;
+        while end < self.back {
+            self.resolve_at(live, end, cmp);
+            let front = self.front;
+            if cmp_at(live, first - front, end - front, cmp) != Ordering::Equal {
+                break;
+            }
+            end += 1;
+        }
+        self.consume_front(end - first);
+        for position in first..end {
+            self.observer.on_yield(position);
+        }
+        Some(first..end)
+    }
+
+    /// Consume the smallest live items for which `skip` holds, where `skip` has to hold for a
+    /// prefix of the sorted order (if it holds for an item, it holds for all smaller ones). Return
+    /// their positions (not sorted). The caller has to take (or forget) them. Next time pass `live`
+    /// WITHOUT them.
+    ///
+    /// Whole segments get skipped without being partitioned (further), once an item of the segment
+    /// after them (the pivot, for example) proves that `skip` holds for all of them. Only a segment
+    /// that straddles the boundary gets partitioned. Like a filter pushed down into the sorting:
+    /// "the 10 smallest items greater than x" costs about as much as "the 10 smallest items".
+    pub fn skip_front_while<T, CMP, SKIP>(
+        &mut self,
+        live: &mut [T],
+        cmp: &mut CMP,
+        skip: &mut SKIP,
+    ) -> Range<usize>
+    where
+        CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+        SKIP: FnMut(&T) -> bool + ?Sized,
+    {
+        debug_assert_eq!(live.len(), self.len());
+        let first = self.front;
+        while !self.is_empty() {
+            let (front, end) = (self.front, self.end(0));
+            if end < self.back && matches!(live.get(end - first), Some(item) if skip(item)) {
+                self.stats.skipped_segments += 1;
+                self.consume_front(end - front);
+                self.skipped_front = self.front;
+            } else if self.sorted[self.slot(0)] || end - front == 1 {
+                let segment = live.get(front - first..end - first).unwrap_or_default();
+                let skipped = segment.partition_point(|item| skip(item));
+                self.consume_front(skipped);
+                if skipped < segment.len() {
+                    break;
+                }
+            } else {
+                let live = live.get_mut(front - first..).unwrap_or_default();
+                self.resolve_segment(live, 0, front, cmp);
+            }
+        }
+        first..self.front
+    }
+
+    /// Consume the (up to) `n` smallest live items at once, without sorting them: partition only
+    /// until they're apart from the rest (resolving the item right after them). Whole segments of
+    /// them get dropped unsorted. Return their positions (not sorted). The caller has to take (or
+    /// forget) them. Next time pass `live` WITHOUT them.
+    pub fn skip_front<T, CMP>(&mut self, live: &mut [T], n: usize, cmp: &mut CMP) -> Range<usize>
+    where
+        CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+    {
+        debug_assert_eq!(live.len(), self.len());
+        let (first, n) = (self.front, n.min(self.len()));
+        if n < self.len() {
+            self.resolve_at(live, first + n, cmp);
+        }
+        self.consume_front(n);
+        self.skipped_front = self.front;
+        first..self.front
+    }
+
+    /// Move [`Engine::front()`] by `k` (resolved) items, dropping segments left behind.
+    fn consume_front(&mut self, k: usize) {
+        self.front += k;
+        while self.count > 0 && self.front >= self.end(0) {
+            self.remove(0);
+        }
+        if self.count > 0 {
+            self.set_start(0, self.front);
+        }
+    }
+
+    /// Partition (only as needed) so that the greatest live item ends up right before
+    /// [`Engine::back()`]. Return that position, or [`None`] if there are no live items.
+    pub fn resolve_back<T, CMP>(&mut self, live: &mut [T], cmp: &mut CMP) -> Option<usize>
+    where
+        CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+    {
+        if self.is_empty() {
+            return None;
+        }
+        self.resolve_at(live, self.back - 1, cmp);
+        Some(self.back - 1)
+    }
+
+    /// Like [`Engine::resolve_back()`], and then consume that item: the caller has to take (or
+    /// forget) it from the returned position. Next time pass `live` WITHOUT that item.
+    pub fn next_back<T, CMP>(&mut self, live: &mut [T], cmp: &mut CMP) -> Option<usize>
+    where
+        CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+    {
+        #[cfg(feature = "instrument")]
+        let comparisons = core::cell::Cell::new(0);
+        // (Type-erased, so that a caller recursing through here doesn't instantiate it endlessly.)
+        #[cfg(feature = "instrument")]
+        let cmp: &mut DynCmp<'_, T> = &mut counting(cmp, &comparisons);
+        let position = self.resolve_back(live, cmp)?;
+        #[cfg(feature = "instrument")]
+        self.stats.comparisons_per_next.record(comparisons.get());
+        self.back -= 1;
+        if self.back == self.start(self.count - 1) {
+            self.remove(self.count - 1);
+        }
+        self.observer.on_yield(position);
+        Some(position)
+    }
+
+    /// Like [`Engine::skip_front()`], but the (up to) `n` greatest live items.
+    pub fn skip_back<T, CMP>(&mut self, live: &mut [T], n: usize, cmp: &mut CMP) -> Range<usize>
+    where
+        CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+    {
+        debug_assert_eq!(live.len(), self.len());
+        let (last, n) = (self.back, n.min(self.len()));
+        if n < self.len() {
+            self.resolve_at(live, last - n - 1, cmp);
+        }
+        self.back -= n;
+        self.skipped_back = self.back;
+        while self.count > 0 && self.start(self.count - 1) >= self.back {
+            self.remove(self.count - 1);
+        }
+        self.back..last
+    }
+
+    /// Sort (fully) the live items at positions `lo..hi`, and partition the rest only as needed for
+    /// that. Afterwards `live[lo - front()..hi - front()]` is sorted, and it holds the items of
+    /// those ranks.
+    pub fn sort_range<T, CMP>(&mut self, live: &mut [T], lo: usize, hi: usize, cmp: &mut CMP)
+    where
+        CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+    {
+        debug_assert!(self.front <= lo && hi <= self.back);
+        if lo >= hi {
+            return;
+        }
+        self.resolve_at(live, lo, cmp);
+        self.resolve_at(live, hi - 1, cmp);
+        let mut nth = self.find(lo);
+        while nth < self.count && self.start(nth) < hi {
+            if !self.sorted[self.slot(nth)] {
+                let (start, end) = (self.start(nth), self.end(nth));
+                let segment = live.get_mut(start - self.front..end - self.front);
+                sort(segment.unwrap_or_default(), cmp);
+                let slot = self.slot(nth);
+                self.sorted[slot] = true;
+            }
+            nth = self.coalesce(nth) + 1;
+        }
+    }
+
+    /// Make sure segment `nth` (containing `position`) is sorted (or it's a single item). Return
+    /// `true` if it already was, or if it got sorted. Return `false` if it got partitioned instead
+    /// (then the caller has to look up the segment again).
+    fn resolve_segment<T, CMP>(
+        &mut self,
+        live: &mut [T],
+        mut nth: usize,
+        position: usize,
+        cmp: &mut CMP,
+    ) -> bool
+    where
+        CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+    {
+        let (lo, hi) = (self.start(nth), self.end(nth));
+        if self.sorted[self.slot(nth)] || hi - lo == 1 {
+            return true;
+        }
+        let Some(segment) = live.get_mut(lo - self.front..hi - self.front) else {
+            // (Never: `live` covers all segments.)
+            return true;
+        };
+        if hi - lo <= SMALL_SORT_LEN {
+            insertion_sort(segment, cmp);
+            let slot = self.slot(nth);
+            self.sorted[slot] = true;
+            self.coalesce(nth);
+            return true;
+        }
+        if self.count + 2 > DEPTH {
+            trace_event!(
+                debug,
+                depth = DEPTH,
+                lo,
+                hi,
+                on_full = ?self.on_full,
+                "segment limit reached"
+            );
+            // Merging can fail only with four segments: then heapsort is the only way forward.
+            if self.on_full == OnFull::Heapsort || !self.make_room(&mut nth) {
+                heapsort(segment, cmp);
+                self.stats.heapsorted_segments += 1;
+                let slot = self.slot(nth);
+                self.sorted[slot] = true;
+                self.coalesce(nth);
+                return true;
+            }
+        }
+        if let Pivot::Adaptive(adaptive) = &mut self.pivot {
+            if adaptive.take_heapsort() {
+                heapsort(segment, cmp);
+                self.stats.heapsorted_segments += 1;
+                let slot = self.slot(nth);
+                self.sorted[slot] = true;
+                self.coalesce(nth);
+                return true;
+            }
+        }
+        self.stats.partitions += 1;
+        self.observer.on_partition_start(lo, hi);
+        self.observer
+            .on_partition_tag(lo, hi, self.tags[self.slot(nth)]);
+        let pivot = choose_pivot(segment, position - lo, &mut self.pivot, cmp);
+        self.observer.on_pivot_chosen(lo + pivot);
+        let (lt, gt) = partition_around(segment, pivot, cmp);
+        self.observer.on_partition_end(lo, hi, lo + lt..lo + gt);
+        if let Pivot::Adaptive(adaptive) = &mut self.pivot {
+            if adaptive.record(hi - lo, lt, gt) {
+                self.stats.adaptive_switches += 1;
+            }
+        }
+        trace_event!(
+            trace,
+            lo,
+            hi,
+            pivot = lo + pivot,
+            lt = lo + lt,
+            gt = lo + gt,
+            "partition"
+        );
+        // Pieces: lo..lo+lt (less), lo+lt..lo+gt (equal, hence sorted), lo+gt..hi (greater).
+        let (mut less_sorted, mut greater_sorted) = (lt == 1, hi - lo - gt == 1);
+        if self.schedule == Schedule::SmallerSideFirst {
+            let target = position - lo;
+            if target < lt && hi - lo - gt < lt && !greater_sorted {
+                sort(segment.get_mut(gt..).unwrap_or_default(), cmp);
+                greater_sorted = true;
+                self.stats.eagerly_sorted_segments += 1;
+            } else if target >= gt && lt < hi - lo - gt && lt > 1 {
+                sort(segment.get_mut(..lt).unwrap_or_default(), cmp);
+                less_sorted = true;
+                self.stats.eagerly_sorted_segments += 1;
+            }
+        }
+        let mut equal = nth;
+        if lt > 0 {
+            self.set(nth, lo, less_sorted);
+            equal += 1;
+            self.insert(equal, lo + lt, true);
+        } else {
+            self.set(nth, lo, true);
+        }
+        if gt < hi - lo {
+            self.insert(equal + 1, lo + gt, greater_sorted);
+        }
+        if equal + 1 < self.count {
+            self.coalesce(equal + 1);
+        }
+        self.coalesce(equal);
+        false
+    }
+
+    /// Make room for (up to) two more segments, by merging segments at the end farther away from
+    /// segment `nth` (and updating `nth`). Return `false` if that would merge segment `nth` itself
+    /// (which might lose the progress of resolving it).
+    fn make_room(&mut self, nth: &mut usize) -> bool {
+        while self.count + 2 > DEPTH {
+            let (back_free, front_free) = (*nth + 2 < self.count, *nth >= 2);
+            if back_free && (*nth < self.count / 2 || !front_free) {
+                self.remove(self.count - 1);
+                let last = self.slot(self.count - 1);
+                self.sorted[last] = false;
+            } else if front_free {
+                self.remove(1);
+                let first = self.slot(0);
+                self.sorted[first] = false;
+                *nth -= 1;
+            } else {
+                return false;
+            }
+            self.stats.merged_segments += 1;
+        }
+        true
+    }
+
+    // ---- ring buffer of segments
+
+    #[inline(always)]
+    fn slot(&self, nth: usize) -> usize {
+        (self.head + nth) % DEPTH
+    }
+
+    /// Start of segment `nth`.
+    #[inline(always)]
+    fn start(&self, nth: usize) -> usize {
+        self.starts[self.slot(nth)].to_usize() - I::min_index_usize()
+    }
+
+    /// End (exclusive) of segment `nth`.
+    fn end(&self, nth: usize) -> usize {
+        if nth + 1 < self.count {
+            self.start(nth + 1)
+        } else {
+            self.back
+        }
+    }
+
+    #[inline(always)]
+    fn set_start(&mut self, nth: usize, start: usize) {
+        let slot = self.slot(nth);
+        // Never `None`: all positions are indexable by `I` (see `Engine::try_new()`).
+        if let Some(start) = I::try_from_usize(start + I::min_index_usize()) {
+            self.starts[slot] = start;
+        }
+    }
+
+    fn set(&mut self, nth: usize, start: usize, sorted: bool) {
+        self.set_start(nth, start);
+        let slot = self.slot(nth);
+        self.sorted[slot] = sorted;
+    }
+
+    /// Which segment contains (live) `position`.
+    fn find(&self, position: usize) -> usize {
+        // The last segment whose start <= position.
+        let (mut lo, mut hi) = (0, self.count);
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+            if self.start(mid) <= position {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        lo
+    }
+
+    /// Insert a segment, so that it becomes segment `nth`. There has to be room for it. It gets
+    /// the tag of the segment it's split from (if any).
+    fn insert(&mut self, nth: usize, start: usize, sorted: bool) {
+        debug_assert!(self.count < DEPTH);
+        if nth == 0 {
+            let next = self.slot(0);
+            self.head = (next + DEPTH - 1) % DEPTH;
+            self.tags[self.slot(0)] = self.tags[next];
+        } else {
+            for i in (nth..self.count).rev() {
+                let (from, to) = (self.slot(i), self.slot(i + 1));
+                self.starts[to] = self.starts[from];
+                self.sorted[to] = self.sorted[from];
+                self.tags[to] = self.tags[from];
+            }
+            let (parent, slot) = (self.slot(nth - 1), self.slot(nth));
+            self.tags[slot] = self.tags[parent];
+        }
+        self.count += 1;
+        self.set(nth, start, sorted);
+    }
+
+    /// Remove segment `nth`. The previous segment (if any) takes over its items.
+    fn remove(&mut self, nth: usize) {
+        if nth == 0 {
+            self.head = (self.head + 1) % DEPTH;
+        } else {
+            for i in nth..self.count - 1 {
+                let (from, to) = (self.slot(i + 1), self.slot(i));
+                self.starts[to] = self.starts[from];
+                self.sorted[to] = self.sorted[from];
+                self.tags[to] = self.tags[from];
+            }
+        }
+        self.count -= 1;
+    }
+
+    fn push_back_segment(&mut self, start: usize, sorted: bool) {
+        self.insert(self.count, start, sorted);
+    }
+
+    /// Merge segment `nth` with its neighbors, as long as they are all sorted. Return the new
+    /// position of segment `nth` (which now may include its former neighbors).
+    fn coalesce(&mut self, mut nth: usize) -> usize {
+        if !self.sorted[self.slot(nth)] {
+            return nth;
+        }
+        while nth + 1 < self.count && self.sorted[self.slot(nth + 1)] {
+            self.remove(nth + 1);
+        }
+        while nth > 0 && self.sorted[self.slot(nth - 1)] {
+            self.remove(nth);
+            nth -= 1;
+        }
+        nth
+    }
+}
+
+/// `cmp`, counting its calls in `comparisons`.
+#[cfg(feature = "instrument")]
+fn counting<'a, T, CMP>(
+    cmp: &'a mut CMP,
+    comparisons: &'a core::cell::Cell<usize>,
+) -> impl FnMut(&T, &T) -> Ordering + 'a
+where
+    CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+{
+    move |a, b| {
+        comparisons.set(comparisons.get() + 1);
+        cmp(a, b)
+    }
+}
+
+/// Sort `items` fully (non-recursive, no allocation).
+pub(crate) fn sort<T, CMP>(items: &mut [T], cmp: &mut CMP)
+where
+    CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+{
+    if items.len() <= SMALL_SORT_LEN {
+        insertion_sort(items, cmp);
+        return;
+    }
+    let mut engine = Engine::<usize>::try_new(items.len()).unwrap_or_else(|_| unreachable!());
+    // Consuming from the front leaves the consumed items in place: sorted.
+    while engine
+        .next_front(items.get_mut(engine.front..).unwrap_or_default(), cmp)
+        .is_some()
+    {}
+}
+
+/// One partition step, as [`Engine`] does it: a building block for custom algorithms.
+///
+/// The pivot is one of `items`, chosen as per `pivot` (and, for [`Pivot::TowardTarget`], close to
+/// rank `target`). Return `(left_len, right_len)`: afterwards
+/// - the first `left_len` items are less than the pivot,
+/// - the last `right_len` items are greater than the pivot, and
+/// - the items between them (at least one, unless `items` is empty) are equal to the pivot.
+///
+/// ```
+/// use lazysort_no_alloc::engine::{partition_at_pivot, Pivot};
+///
+/// let mut items = [5, 1, 9, 5, 3, 7, 5];
+/// let (left, right) = partition_at_pivot(&mut items, Pivot::MedianOfThree, 0, &mut i32::cmp);
+/// assert_eq!((left, right), (2, 2));
+/// assert_eq!(items[2..5], [5, 5, 5]);
+/// ```
+pub fn partition_at_pivot<T, CMP>(
+    items: &mut [T],
+    mut pivot: Pivot,
+    target: usize,
+    cmp: &mut CMP,
+) -> (usize, usize)
+where
+    CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+{
+    if items.is_empty() {
+        return (0, 0);
+    }
+    let (lt, gt) = partition(items, target.min(items.len() - 1), &mut pivot, cmp);
+    (lt, items.len() - gt)
+}
+
+/// Three-way partition of (non-empty) `items`, around a pivot chosen as per `pivot` (for `target`
+/// position; a [`Pivot::Random`] advances its [`Rng`]). Return `(lt, gt)`, so that
+/// - `items[..lt]` are less than the pivot,
+/// - `items[lt..gt]` are equal to the pivot (hence `lt < gt`), and
+/// - `items[gt..]` are greater than the pivot.
+pub(crate) fn partition<T, CMP>(
+    items: &mut [T],
+    target: usize,
+    pivot: &mut Pivot,
+    cmp: &mut CMP,
+) -> (usize, usize)
+where
+    CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+{
+    let pivot = choose_pivot(items, target, pivot, cmp);
+    partition_around(items, pivot, cmp)
+}
+
+/// Position of the pivot (in non-empty `items`), chosen as per `pivot` (for `target` position).
+fn choose_pivot<T, CMP>(items: &[T], target: usize, pivot: &mut Pivot, cmp: &mut CMP) -> usize
+where
+    CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+{
+    let len = items.len();
+    debug_assert!(len > 0);
+    match pivot {
+        Pivot::FastFirst if len >= SAMPLED_MIN_LEN => sampled(items, target, cmp),
+        Pivot::TowardTarget | Pivot::FastFirst if target < len / 3 => {
+            extreme_of_three(items, len, Ordering::Less, cmp)
+        }
+        Pivot::TowardTarget | Pivot::FastFirst if target >= len - len / 3 => {
+            extreme_of_three(items, len, Ordering::Greater, cmp)
+        }
+        Pivot::Random(rng)
+        | Pivot::Adaptive(Adaptive {
+            rng,
+            mode: AdaptiveMode::Random | AdaptiveMode::Heapsort,
+            ..
+        }) => {
+            let (a, b, c) = (rng.below(len), rng.below(len), rng.below(len));
+            median_of_three(items, a, b, c, cmp)
+        }
+        _ => median_of_three(items, 0, len / 2, len - 1, cmp),
+    }
+}
+
+/// Position of the item among [`SAMPLES`] ones (spread evenly over `items`) whose rank among them
+/// corresponds to the rank of `target`, rounded toward the end closer to `target`. (So for the
+/// first or the last item, it's the extreme sample: the piece containing the target is small.)
+fn sampled<T, CMP>(items: &[T], target: usize, cmp: &mut CMP) -> usize
+where
+    CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+{
+    let len = items.len();
+    let mut samples = [0; SAMPLES];
+    for (nth, sample) in samples.iter_mut().enumerate() {
+        *sample = nth * len / SAMPLES + len / SAMPLES / 2;
+    }
+    insertion_sort(&mut samples, &mut |&a: &usize, &b: &usize| {
+        cmp_at(items, a, b, cmp)
+    });
+    let rank = if 2 * target < len {
+        target * SAMPLES / len
+    } else {
+        (target * SAMPLES + len - 1) / len
+    };
+    samples[rank.min(SAMPLES - 1)]
+}
+
+/// Three-way partition of `items` around the one at position `pivot`. Return `(lt, gt)` as
+/// [`partition()`] does.
+pub(crate) fn partition_around<T, CMP>(
+    items: &mut [T],
+    pivot: usize,
+    cmp: &mut CMP,
+) -> (usize, usize)
+where
+    CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+{
+    let len = items.len();
+    swap_at(items, 0, pivot);
+    // Invariants: items[..lt] < pivot, items[lt..i] == pivot, items[gt..] > pivot. Since
+    // items[lt..i] is never empty, items[lt] serves as the pivot.
+    let (mut lt, mut i, mut gt) = (0, 1, len);
+    while i < gt {
+        match cmp_at(items, i, lt, cmp) {
+            Ordering::Less => {
+                swap_at(items, lt, i);
+                lt += 1;
+                i += 1;
+            }
+            Ordering::Equal => i += 1,
+            Ordering::Greater => {
+                gt -= 1;
+                swap_at(items, i, gt);
+            }
+        }
+    }
+    (lt, gt)
+}
+
+/// Position of the median of `items[a]`, `items[b]` and `items[c]`.
+pub(crate) fn median_of_three<T, CMP>(
+    items: &[T],
+    a: usize,
+    b: usize,
+    c: usize,
+    cmp: &mut CMP,
+) -> usize
+where
+    CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+{
+    let ab = cmp_at(items, a, b, cmp) == Ordering::Less;
+    let bc = cmp_at(items, b, c, cmp) == Ordering::Less;
+    if ab == bc {
+        return b;
+    }
+    let ac = cmp_at(items, a, c, cmp) == Ordering::Less;
+    if ab == ac {
+        c
+    } else {
+        a
+    }
+}
+
+/// Position of the minimum (for `Ordering::Less`) or the maximum (for `Ordering::Greater`) of the
+/// first, the middle and the last item.
+fn extreme_of_three<T, CMP>(items: &[T], len: usize, extreme: Ordering, cmp: &mut CMP) -> usize
+where
+    CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+{
+    let mut result = 0;
+    for candidate in [len / 2, len - 1] {
+        if cmp_at(items, candidate, result, cmp) == extreme {
+            result = candidate;
+        }
+    }
+    result
+}
+
+pub(crate) fn insertion_sort<T, CMP>(items: &mut [T], cmp: &mut CMP)
+where
+    CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+{
+    for i in 1..items.len() {
+        let mut j = i;
+        while j > 0 && cmp(&items[j], &items[j - 1]) == Ordering::Less {
+            items.swap(j, j - 1);
+            j -= 1;
+        }
+    }
+}
@@ -0,0 +1,829 @@
+use crate::engine::{Adaptive, Engine, Observer, OnFull, Pivot, Schedule, Stats};
+use crate::testing::scrambled;
+use crate::util::rng::Rng;
+use core::num::NonZeroU8;
+
+const LEN: usize = 200;
+
+/// Deterministic, (somewhat) shuffled data. `modulo` controls the number of duplicates.
+fn items(modulo: u32) -> [u32; LEN] {
+    let mut items = [0; LEN];
+    for (i, item) in items.iter_mut().enumerate() {
+        *item = scrambled(i, 13, modulo);
+    }
+    items
+}
+
+fn sorted(mut items: [u32; LEN]) -> [u32; LEN] {
+    items.sort_unstable();
+    items
+}
+
+/// Consume from the front if `from_front(step)`, otherwise from the back. Return the items in
+/// the order they would be in if sorted (front-consumed ascending, back-consumed descending).
+fn consume<const DEPTH: usize, F: Fn(usize) -> bool>(
+    mut items: [u32; LEN],
+    from_front: F,
+) -> [u32; LEN] {
+    let mut result = [0; LEN];
+    let mut engine = Engine::<u8, DEPTH>::try_new(LEN).unwrap();
+    let mut cmp = |a: &u32, b: &u32| a.cmp(b);
+    for step in 0..LEN {
+        let live = &mut items[engine.front()..engine.back()];
+        let position = if from_front(step) {
+            engine.next_front(live, &mut cmp)
+        } else {
+            engine.next_back(live, &mut cmp)
+        }
+        .unwrap();
+        result[position] = items[position];
+    }
+    assert!(engine.is_empty());
+    assert_eq!(engine.next_front(&mut items[0..0], &mut cmp), None);
+    result
+}
+
+#[test]
+fn front_only() {
+    for modulo in [1, 3, 1000] {
+        let items = items(modulo);
+        assert_eq!(consume::<64, _>(items, |_| true), sorted(items));
+    }
+}
+
+#[test]
+fn back_only() {
+    for modulo in [1, 3, 1000] {
+        let items = items(modulo);
+        assert_eq!(consume::<64, _>(items, |_| false), sorted(items));
+    }
+}
+
+#[test]
+fn mixed() {
+    for modulo in [2, 1000] {
+        let items = items(modulo);
+        assert_eq!(consume::<64, _>(items, |step| step % 3 != 0), sorted(items));
+    }
+}
+
+#[test]
+fn minimum_depth_merges_segments() {
+    let items = items(1000);
+    assert_eq!(consume::<4, _>(items, |_| true), sorted(items));
+    assert_eq!(consume::<4, _>(items, |step| step % 2 == 0), sorted(items));
+}
+
+#[test]
+fn resolve_does_not_consume() {
+    let mut items = items(1000);
+    let mut engine = Engine::<usize>::try_new(LEN).unwrap();
+    let mut cmp = |a: &u32, b: &u32| a.cmp(b);
+    let front = engine.resolve_front(&mut items, &mut cmp).unwrap();
+    assert_eq!(engine.resolve_front(&mut items, &mut cmp), Some(front));
+    assert_eq!(items[front], sorted(items)[0]);
+    assert_eq!(engine.len(), LEN);
+}
+
+#[test]
+fn append() {
+    let mut items = [5, 4, 3, 2, 1, 0];
+    let mut engine = Engine::<usize>::try_new(3).unwrap();
+    let mut cmp = |a: &u32, b: &u32| a.cmp(b);
+    let position = engine.next_front(&mut items[0..3], &mut cmp).unwrap();
+    assert_eq!(items[position], 3);
+    engine.try_append(3).unwrap();
+    let mut result = [0; 5];
+    for item in result.iter_mut() {
+        let live = &mut items[engine.front()..engine.back()];
+        *item = items[engine.next_front(live, &mut cmp).unwrap()];
+    }
+    assert_eq!(result, [0, 1, 2, 4, 5]);
+}
+
+#[test]
+fn index_type_limits() {
+    assert!(Engine::<u8>::try_new(256).is_ok());
+    assert!(Engine::<u8>::try_new(257).is_err());
+    assert!(Engine::<NonZeroU8>::try_new(255).is_ok());
+    assert!(Engine::<NonZeroU8>::try_new(256).is_err());
+
+    let mut engine = Engine::<u8>::try_new(200).unwrap();
+    assert!(engine.try_append(57).is_err());
+    assert_eq!(engine.back(), 200);
+    assert!(engine.try_append(56).is_ok());
+    assert!(engine.try_append(usize::MAX).is_err());
+}
+
+#[test]
+fn resolve_at_any_position() {
+    let expected = sorted(items(1000));
+    let mut items = items(1000);
+    let mut engine = Engine::<u8, 8>::try_new(LEN).unwrap();
+    let mut cmp = |a: &u32, b: &u32| a.cmp(b);
+    for position in [100, 3, 199, 150, 0, 42] {
+        engine.resolve_at(&mut items, position, &mut cmp);
+        assert_eq!(items[position], expected[position]);
+    }
+}
+
+#[test]
+fn sort_range() {
+    for modulo in [3, 1000] {
+        let expected = sorted(items(modulo));
+        let mut items = items(modulo);
+        let mut engine = Engine::<usize>::try_new(LEN).unwrap();
+        let mut cmp = |a: &u32, b: &u32| a.cmp(b);
+        engine.sort_range(&mut items, 50, 120, &mut cmp);
+        assert_eq!(items[50..120], expected[50..120]);
+        engine.sort_range(&mut items, 0, LEN, &mut cmp);
+        assert_eq!(items, expected);
+    }
+}
+
+#[test]
+fn pivot_toward_target() {
+    let expected = sorted(items(1000));
+    let mut items = items(1000);
+    let mut engine = Engine::<u8, 8>::try_new(LEN).unwrap();
+    engine.set_pivot(Pivot::TowardTarget);
+    assert_eq!(engine.pivot(), Pivot::TowardTarget);
+    let mut cmp = |a: &u32, b: &u32| a.cmp(b);
+    for position in [LEN - 1, 0, 100, LEN - 2, 1] {
+        engine.resolve_at(&mut items, position, &mut cmp);
+        assert_eq!(items[position], expected[position]);
+    }
+    while !engine.is_empty() {
+        let live = &mut items[engine.front()..engine.back()];
+        let position = engine.next_back(live, &mut cmp).unwrap();
+        assert_eq!(items[position], expected[position]);
+    }
+}
+
+#[test]
+fn pivot_random() {
+    let expected = sorted(items(30));
+    let mut items = items(30);
+    let mut engine = Engine::<usize>::try_new(LEN).unwrap();
+    engine.set_pivot(Pivot::Random(Rng::new(5)));
+    let mut cmp = |a: &u32, b: &u32| a.cmp(b);
+    engine.resolve_at(&mut items, LEN / 2, &mut cmp);
+    // The generator advanced.
+    assert_ne!(engine.pivot(), Pivot::Random(Rng::new(5)));
+    while let Some(position) = engine.next_front(&mut items[engine.front()..], &mut cmp) {
+        assert_eq!(items[position], expected[position]);
+    }
+}
+
+/// Comparisons until the first item (of many) gets yielded, with `pivot`. Then check the rest.
+fn first_yield_comparisons(pivot: Pivot) -> usize {
+    const BIG: usize = 20_000;
+    let mut items = [0u32; BIG];
+    let mut rng = Rng::new(7);
+    for i in 0..BIG {
+        // Fisher-Yates (inside-out): a random permutation of 0..BIG.
+        let j = rng.below(i + 1);
+        items[i] = items[j];
+        items[j] = i as u32;
+    }
+    let mut engine = Engine::<usize>::try_new(BIG).unwrap();
+    engine.set_pivot(pivot);
+    let mut comparisons = 0;
+    let mut cmp = |a: &u32, b: &u32| {
+        comparisons += 1;
+        a.cmp(b)
+    };
+    assert_eq!(engine.next_front(&mut items, &mut cmp), Some(0));
+    let first = comparisons;
+    assert_eq!(items[0], 0);
+    engine.resolve_at(&mut items[1..], BIG / 3, &mut u32::cmp);
+    assert_eq!(items[BIG / 3], (BIG / 3) as u32);
+    while let Some(position) = engine.next_back(&mut items[1..engine.back()], &mut u32::cmp) {
+        assert_eq!(items[position], position as u32);
+    }
+    first
+}
+
+#[test]
+fn pivot_fast_first() {
+    let fast = first_yield_comparisons(Pivot::FastFirst);
+    let toward = first_yield_comparisons(Pivot::TowardTarget);
+    let median = first_yield_comparisons(Pivot::MedianOfThree);
+    assert!(fast < toward && toward < median, "{fast} {toward} {median}");
+}
+
+#[test]
+fn split_at() {
+    // Partitioned at 3, 6 and 8, but not sorted.
+    let mut items = [2, 0, 1, 5, 3, 4, 7, 6, 9, 8];
+    let mut engine = Engine::<u8, 4>::try_new(items.len()).unwrap();
+    assert!(engine.split_at(0) && engine.split_at(10));
+    assert!(engine.split_at(6));
+    assert!(engine.split_at(3) && engine.split_at(3));
+    assert!(engine.split_at(8));
+    assert!(!engine.split_at(9));
+    // No comparisons across the recorded boundaries.
+    let group = |item: &u32| [0, 0, 0, 1, 1, 1, 2, 2, 3, 3][*item as usize];
+    let mut cmp = |a: &u32, b: &u32| {
+        assert_eq!(group(a), group(b));
+        a.cmp(b)
+    };
+    for position in 0..items.len() {
+        engine.resolve_at(&mut items, position, &mut cmp);
+        assert_eq!(items[position], position as u32);
+    }
+}
+
+#[test]
+fn next_chunk_front() {
+    let expected = sorted(items(1000));
+    let mut items = items(1000);
+    let mut engine = Engine::<u8, 8>::try_new(LEN).unwrap();
+    let mut cmp = |a: &u32, b: &u32| a.cmp(b);
+    engine.resolve_at(&mut items, 150, &mut cmp);
+    let mut front = 0;
+    for k in [1, 30, 0, 100, 500] {
+        let live = &mut items[engine.front()..engine.back()];
+        let chunk = engine.next_chunk_front(live, k, &mut cmp);
+        assert_eq!(chunk, front..(front + k).min(LEN));
+        assert_eq!(items[chunk.clone()], expected[chunk.clone()]);
+        front = chunk.end;
+    }
+    assert!(engine.is_empty());
+}
+
+#[test]
+fn partition_at_pivot() {
+    use crate::engine::partition_at_pivot;
+    let mut cmp = |a: &u32, b: &u32| a.cmp(b);
+    for pivot in [
+        Pivot::MedianOfThree,
+        Pivot::TowardTarget,
+        Pivot::Random(Rng::new(1)),
+    ] {
+        for target in [0, LEN / 2, LEN - 1, LEN * 2] {
+            let mut items = items(50);
+            let (left, right) = partition_at_pivot(&mut items, pivot, target, &mut cmp);
+            assert!(left + right < LEN);
+            let value = items[left];
+            assert!(items[..left].iter().all(|&item| item < value));
+            assert!(items[left..LEN - right].iter().all(|&item| item == value));
+            assert!(items[LEN - right..].iter().all(|&item| item > value));
+        }
+    }
+    assert_eq!(
+        partition_at_pivot(&mut [0u32; 0], Pivot::default(), 0, &mut cmp),
+        (0, 0)
+    );
+}
+
+#[test]
+fn next_run_front() {
+    let expected = sorted(items(7));
+    let mut items = items(7);
+    let mut engine = Engine::<u8, 8>::try_new(LEN).unwrap();
+    let mut cmp = |a: &u32, b: &u32| a.cmp(b);
+    let mut value = 0;
+    while !engine.is_empty() {
+        let live = &mut items[engine.front()..engine.back()];
+        let run = engine.next_run_front(live, &mut cmp).unwrap();
+        assert!(items[run.clone()].iter().all(|&item| item == value));
+        assert_eq!(items[run.clone()], expected[run]);
+        value += 1;
+    }
+    assert_eq!(value, 7);
+}
+
+#[derive(Debug, Default)]
+struct Recorder {
+    partitions: usize,
+    pivots: usize,
+    yielded: usize,
+    last_yield: Option<usize>,
+}
+
+impl Observer for Recorder {
+    fn on_partition_start(&mut self, lo: usize, hi: usize) {
+        assert!(lo < hi);
+        self.partitions += 1;
+    }
+
+    fn on_pivot_chosen(&mut self, _position: usize) {
+        self.pivots += 1;
+    }
+
+    fn on_partition_end(&mut self, lo: usize, hi: usize, equal: core::ops::Range<usize>) {
+        assert!(lo <= equal.start && equal.start < equal.end && equal.end <= hi);
+        assert_eq!(self.pivots, self.partitions);
+    }
+
+    fn on_yield(&mut self, position: usize) {
+        self.yielded += 1;
+        self.last_yield = Some(position);
+    }
+}
+
+#[test]
+fn observer() {
+    let mut items = items(1000);
+    let mut engine = Engine::<u8, 8>::try_new(LEN)
+        .unwrap()
+        .with_observer(Recorder::default());
+    let mut cmp = |a: &u32, b: &u32| a.cmp(b);
+    let position = engine.next_front(&mut items, &mut cmp);
+    assert_eq!(position, Some(0));
+    assert_eq!(engine.observer().last_yield, Some(0));
+    assert!(engine.observer().partitions > 0);
+    let live = &mut items[engine.front()..engine.back()];
+    let chunk = engine.next_chunk_front(live, 5, &mut cmp);
+    assert_eq!(chunk, 1..6);
+    assert_eq!(engine.observer().yielded, 6);
+    let live = &mut items[engine.front()..engine.back()];
+    engine.next_back(live, &mut cmp);
+    assert_eq!(engine.observer_mut().last_yield.take(), Some(LEN - 1));
+}
+
+/// Records the tags of the partitioned segments.
+#[derive(Debug, Default)]
+struct TagRecorder {
+    /// Lowest and highest position partitioned per tag (`b'a'` and `b'b'`).
+    ranges: [Option<(usize, usize)>; 2],
+}
+
+impl Observer<u8> for TagRecorder {
+    fn on_partition_tag(&mut self, lo: usize, hi: usize, tag: u8) {
+        let range = self.ranges[usize::from(tag - b'a')].get_or_insert((lo, hi));
+        *range = (range.0.min(lo), range.1.max(hi));
+    }
+}
+
+#[test]
+fn tags() {
+    // Two "sources": every item of the first half is less than any of the second.
+    let mut items = items(100);
+    for item in &mut items[LEN / 2..] {
+        *item += 100;
+    }
+    let mut engine = Engine::<u8, 16>::try_new(LEN)
+        .unwrap()
+        .with_tag(b'a')
+        .with_observer(TagRecorder::default());
+    assert!(engine.split_at_tagged(LEN / 2, b'b'));
+    assert!(!engine.split_at_tagged(LEN, b'c'));
+    let mut cmp = |a: &u32, b: &u32| a.cmp(b);
+    engine.resolve_at(&mut items, LEN / 4, &mut cmp);
+    engine.resolve_at(&mut items, LEN * 3 / 4, &mut cmp);
+    for position in [0, LEN / 4, LEN / 2 - 1] {
+        assert_eq!(engine.tag_at(position), b'a');
+    }
+    for position in [LEN / 2, LEN * 3 / 4, LEN - 1] {
+        assert_eq!(engine.tag_at(position), b'b');
+    }
+    let [a, b] = engine.observer().ranges;
+    assert!(
+        a.unwrap().1 <= LEN / 2 && b.unwrap().0 >= LEN / 2,
+        "{a:?} {b:?}"
+    );
+
+    // Consumption and appending keep the tags of the remaining segments.
+    let live = &mut items[engine.front()..engine.back()];
+    engine.next_chunk_front(live, LEN / 2, &mut cmp);
+    assert_eq!(engine.tag_at(LEN / 2), b'b');
+    engine.try_append(0).unwrap();
+    assert_eq!(engine.tag_at(LEN - 1), b'b');
+}
+
+/// Resolve a few positions, then consume all. Return the stats.
+fn resolve_with_few_segments<const DEPTH: usize>(on_full: OnFull) -> Stats {
+    let expected = sorted(items(1000));
+    let mut items = items(1000);
+    let mut cmp = |a: &u32, b: &u32| a.cmp(b);
+    let mut engine = Engine::<u8, DEPTH>::try_new(LEN).unwrap();
+    engine.set_on_full(on_full);
+    assert_eq!(engine.on_full(), on_full);
+    for position in [LEN / 2, LEN / 3, LEN - 10, 5] {
+        engine.resolve_at(&mut items, position, &mut cmp);
+        assert_eq!(items[position], expected[position]);
+    }
+    while let Some(position) = engine.next_front(&mut items[engine.front()..], &mut cmp) {
+        assert_eq!(items[position], expected[position]);
+    }
+    let stats = engine.stats();
+    assert!(stats.partitions > 0 && stats.degraded());
+    stats
+}
+
+#[test]
+fn on_full() {
+    let stats = resolve_with_few_segments::<6>(OnFull::Merge);
+    assert!(stats.merged_segments > 0);
+    assert_eq!(stats.heapsorted_segments, 0);
+    let stats = resolve_with_few_segments::<6>(OnFull::Heapsort);
+    assert!(stats.heapsorted_segments > 0);
+    assert_eq!(stats.merged_segments, 0);
+    // With four segments, merging alone can't always make room.
+    let stats = resolve_with_few_segments::<4>(OnFull::Merge);
+    assert!(stats.merged_segments > 0);
+}
+
+/// Consume all from the front, with pivots that leave the greater pieces large. Return the stats.
+fn consume_with_schedule<const DEPTH: usize>(schedule: Schedule) -> Stats {
+    let expected = sorted(items(1000));
+    let mut items = items(1000);
+    let mut engine = Engine::<u8, DEPTH>::try_new(LEN).unwrap();
+    engine.set_pivot(Pivot::TowardTarget);
+    engine.set_schedule(schedule);
+    assert_eq!(engine.schedule(), schedule);
+    while let Some(position) = engine.next_front(&mut items[engine.front()..], &mut u32::cmp) {
+        assert_eq!(items[position], expected[position]);
+    }
+    engine.stats()
+}
+
+#[test]
+fn schedule() {
+    let stats = consume_with_schedule::<6>(Schedule::TargetOnly);
+    assert!(stats.degraded());
+    assert_eq!(stats.eagerly_sorted_segments, 0);
+    let stats = consume_with_schedule::<6>(Schedule::SmallerSideFirst);
+    assert!(!stats.degraded());
+    assert!(stats.eagerly_sorted_segments > 0);
+}
+
+#[test]
+fn heapsort() {
+    let mut items = items(37);
+    super::heapsort(&mut items, &mut u32::cmp);
+    assert_eq!(items, sorted(self::items(37)));
+}
+
+#[test]
+fn dyn_cmp() {
+    use crate::engine::DynCmp;
+    let mut sorted_items = items(97);
+    let mut engine = Engine::<u16>::try_new(LEN).unwrap();
+    let cmp: &mut DynCmp<u32> = &mut u32::cmp;
+    while let Some(position) = engine.next_front(&mut sorted_items[engine.front()..], cmp) {
+        assert_eq!(position + 1, engine.front());
+    }
+    assert_eq!(sorted_items, sorted(items(97)));
+}
+
+#[cfg(not(feature = "no-panic"))]
+#[cfg_attr(not(feature = "tiny-panic"), should_panic(expected = "Too many items"))]
+#[cfg_attr(feature = "tiny-panic", should_panic(expected = "explicit panic"))]
+#[test]
+fn new_panics_beyond_index_type() {
+    let _ = Engine::<u8>::new(1000);
+}
+
+#[cfg(not(feature = "no-panic"))]
+#[cfg_attr(not(feature = "tiny-panic"), should_panic(expected = "Too many items"))]
+#[cfg_attr(feature = "tiny-panic", should_panic(expected = "explicit panic"))]
+#[test]
+fn append_panics_beyond_index_type() {
+    Engine::<u8>::try_new(200).unwrap().append(57);
+}
+
+/// Every `next_*()` call gets recorded, the first one (partitioning all items) among the costliest.
+#[cfg(feature = "instrument")]
+#[test]
+fn comparisons_per_next() {
+    use crate::engine::{WorkHistogram, WORK_BUCKETS};
+
+    let mut items = items(1000);
+    let mut engine = Engine::<u8>::try_new(LEN).unwrap();
+    let mut comparisons = 0;
+    let mut counting = |a: &u32, b: &u32| {
+        comparisons += 1;
+        a.cmp(b)
+    };
+    engine.next_back(&mut items, &mut counting);
+    let first = engine.stats().comparisons_per_next;
+    let bucket = first.buckets().iter().position(|&calls| calls > 0).unwrap();
+    assert!(WorkHistogram::bucket_range(bucket).contains(&comparisons));
+    assert!(comparisons >= LEN - 1);
+    while engine
+        .next_front(&mut items[engine.front()..engine.back()], &mut u32::cmp)
+        .is_some()
+    {}
+    let histogram = engine.stats().comparisons_per_next;
+    assert_eq!(histogram.calls(), LEN);
+    // Most calls cost a few comparisons at most.
+    assert!(histogram.buckets()[..4].iter().sum::<usize>() > LEN / 2);
+    assert_eq!(histogram.buckets()[bucket + 1..].iter().sum::<usize>(), 0);
+    assert_eq!(WorkHistogram::bucket_range(0), 0..1);
+    assert_eq!(WorkHistogram::bucket_range(3), 4..8);
+    assert_eq!(
+        WorkHistogram::bucket_range(WORK_BUCKETS - 1).end,
+        usize::MAX
+    );
+}
+
+/// Consume all of `items` from the front with `pivot`, checking the order. Return the stats.
+fn consume_all<const N: usize>(mut items: [u32; N], pivot: Pivot) -> Stats {
+    let mut expected = items;
+    expected.sort_unstable();
+    let mut engine = Engine::<usize>::try_new(N).unwrap();
+    engine.set_pivot(pivot);
+    while let Some(position) = engine.next_front(&mut items[engine.front()..], &mut u32::cmp) {
+        assert_eq!(items[position], expected[position]);
+    }
+    engine.stats()
+}
+
+/// Organ pipe input leaves median-of-three pivots at an end. Adaptive switches away from them,
+/// but not for lots of duplicates (which three-way partitioning handles well).
+#[test]
+fn pivot_adaptive() {
+    const N: usize = 4000;
+    let organ_pipe: [u32; N] = core::array::from_fn(|i| i.min(N - i) as u32);
+    let median = consume_all(organ_pipe, Pivot::MedianOfThree);
+    let adaptive = consume_all(organ_pipe, Pivot::Adaptive(Adaptive::new(1)));
+    assert!(adaptive.adaptive_switches > 0);
+    assert!(adaptive.partitions < median.partitions * 3 / 4);
+    let sawtooth: [u32; N] = core::array::from_fn(|i| (i % 64) as u32);
+    let adaptive = consume_all(sawtooth, Pivot::Adaptive(Adaptive::default()));
+    assert_eq!(adaptive.adaptive_switches, 0);
+}
+
+/// Keeps going bad even with random pivots: heapsort, once.
+#[test]
+fn pivot_adaptive_heapsort() {
+    let mut adaptive = Adaptive::new(1);
+    assert!(!adaptive.record(100, 99, 100));
+    assert!(adaptive.record(100, 0, 1));
+    assert!(adaptive.is_random());
+    // A balanced one resets the count.
+    assert!(!adaptive.record(100, 40, 50));
+    for _ in 1..super::ADAPTIVE_HEAPSORT_AFTER {
+        assert!(!adaptive.record(100, 95, 96));
+        assert!(!adaptive.take_heapsort());
+    }
+    assert!(adaptive.record(100, 95, 96));
+    assert!(adaptive.take_heapsort());
+    assert!(!adaptive.take_heapsort());
+    // Duplicates are progress.
+    assert!(!adaptive.record(100, 0, 80));
+}
+
+/// Rewinding replays the same items without partitioning again, at either end.
+#[test]
+fn checkpoint_rewind() {
+    use crate::error::Error;
+
+    let expected = sorted(items(37));
+    let mut items = items(37);
+    let mut engine = Engine::<u8, 8>::try_new(LEN).unwrap();
+    let checkpoint = engine.checkpoint();
+    for _ in 0..10 {
+        let live = &mut items[engine.front()..engine.back()];
+        let position = engine.next_front(live, &mut u32::cmp);
+        assert_eq!(items[position.unwrap()], expected[position.unwrap()]);
+        engine.next_back(&mut items[engine.front()..engine.back()], &mut u32::cmp);
+    }
+    let (partitions, probed) = (engine.stats().partitions, engine.checkpoint());
+    assert_eq!(engine.rewind(probed), Ok(()));
+    engine.rewind(checkpoint).unwrap();
+    assert_eq!((engine.front(), engine.back()), (0, LEN));
+    assert_eq!(engine.rewind(probed), Err(Error::InvalidArgument));
+    for position in 0..10 {
+        let live = &mut items[engine.front()..engine.back()];
+        assert_eq!(engine.next_front(live, &mut u32::cmp), Some(position));
+        let live = &mut items[engine.front()..engine.back()];
+        assert_eq!(
+            engine.next_back(live, &mut u32::cmp),
+            Some(LEN - 1 - position)
+        );
+    }
+    assert_eq!(engine.stats().partitions, partitions);
+    // All of it, also after running out of segments.
+    while engine
+        .next_front(&mut items[engine.front()..engine.back()], &mut u32::cmp)
+        .is_some()
+    {}
+    engine.rewind(checkpoint).unwrap();
+    while let Some(position) =
+        engine.next_front(&mut items[engine.front()..engine.back()], &mut u32::cmp)
+    {
+        assert_eq!(items[position], expected[position]);
+    }
+}
+
+/// A resumed engine continues without repeating any partitioning, also with other `I` & `DEPTH`.
+#[test]
+fn plan_round_trip() {
+    use crate::error::Error;
+
+    let expected = sorted(items(37));
+    let mut items = items(37);
+    let mut engine = Engine::<u8, 16>::try_new(LEN).unwrap();
+    for _ in 0..10 {
+        engine.next_front(&mut items[engine.front()..engine.back()], &mut u32::cmp);
+        engine.next_back(&mut items[engine.front()..engine.back()], &mut u32::cmp);
+    }
+    let mut plan = [0; 256];
+    assert_eq!(
+        engine.export_plan(&mut plan[..engine.plan_len() - 1]),
+        Err(Error::CapacityExceeded)
+    );
+    let len = engine.export_plan(&mut plan).unwrap();
+    assert_eq!(len, engine.plan_len());
+    let exported_partitions = engine.stats().partitions;
+
+    let mut resumed = Engine::<NonZeroU8, 32>::try_from_plan(&plan[..len]).unwrap();
+    assert_eq!(
+        (resumed.front(), resumed.back()),
+        (engine.front(), engine.back())
+    );
+    let mut replayed = items;
+    while let Some(position) =
+        engine.next_front(&mut items[engine.front()..engine.back()], &mut u32::cmp)
+    {
+        let live = &mut replayed[resumed.front()..resumed.back()];
+        assert_eq!(resumed.next_front(live, &mut u32::cmp), Some(position));
+        assert_eq!(replayed[position], expected[position]);
+    }
+    assert_eq!(
+        resumed.stats().partitions,
+        engine.stats().partitions - exported_partitions
+    );
+    assert!(resumed.is_empty());
+
+    // Fully consumed, and empty.
+    let len = engine.export_plan(&mut plan).unwrap();
+    assert!(Engine::<u8>::try_from_plan(&plan[..len])
+        .unwrap()
+        .is_empty());
+    let len = Engine::<u8>::try_new(0)
+        .unwrap()
+        .export_plan(&mut plan)
+        .unwrap();
+    assert_eq!(Engine::<u8>::try_from_plan(&plan[..len]).unwrap().back(), 0);
+}
+
+#[test]
+fn plan_rejects_malformed() {
+    use crate::error::Error;
+
+    let mut items = items(37);
+    let mut engine = Engine::<u16, 16>::try_new(LEN).unwrap();
+    engine.next_front(&mut items, &mut u32::cmp);
+    let mut plan = [0; 256];
+    let len = engine.export_plan(&mut plan).unwrap();
+    let plan = &plan[..len];
+    assert!(Engine::<u16, 16>::try_from_plan(plan).is_ok());
+
+    let corrupt = |at: usize, byte: u8| {
+        let mut corrupted = [0; 256];
+        corrupted[..len].copy_from_slice(plan);
+        corrupted[at] = byte;
+        Engine::<u16, 16>::try_from_plan(&corrupted[..len]).map(|_| ())
+    };
+    // Magic (version).
+    assert_eq!(corrupt(3, 2), Err(Error::InvalidArgument));
+    // `front` past the first segment's start.
+    assert_eq!(corrupt(4, 100), Err(Error::InvalidArgument));
+    // More segments than there are.
+    assert_eq!(corrupt(20, 100), Err(Error::InvalidArgument));
+    // Not a `bool`.
+    assert_eq!(corrupt(24 + 8, 2), Err(Error::InvalidArgument));
+    // Truncated.
+    assert_eq!(
+        Engine::<u16, 16>::try_from_plan(&plan[..len - 1]).map(|_| ()),
+        Err(Error::InvalidArgument)
+    );
+    assert_eq!(
+        Engine::<u16, 16>::try_from_plan(&plan[..10]).map(|_| ()),
+        Err(Error::InvalidArgument)
+    );
+    // Too many segments, or items, for the resuming engine.
+    assert!(len > 24 + 4 * 9);
+    assert_eq!(
+        Engine::<u16, 4>::try_from_plan(plan).map(|_| ()),
+        Err(Error::CapacityExceeded)
+    );
+    let mut items = [0u32; 300];
+    let mut engine = Engine::<u16>::try_new(items.len()).unwrap();
+    engine.next_front(&mut items, &mut u32::cmp);
+    let mut plan = [0; 256];
+    let len = engine.export_plan(&mut plan).unwrap();
+    assert_eq!(
+        Engine::<u8>::try_from_plan(&plan[..len]).map(|_| ()),
+        Err(Error::CapacityExceeded)
+    );
+}
+
+#[test]
+fn skip_front_while() {
+    const N: usize = 4000;
+    let shuffled: [u32; N] = core::array::from_fn(|i| (i as u32 * 7919 + 13) % N as u32);
+    for threshold in [0, 1, 17, 1500, 3999, 4000, 5000] {
+        let mut items = shuffled;
+        let mut engine = Engine::<usize>::try_new(N).unwrap();
+        let skipped =
+            engine.skip_front_while(&mut items, &mut u32::cmp, &mut |item| *item < threshold);
+        let expected = threshold.min(N as u32) as usize;
+        assert_eq!(skipped, 0..expected);
+        assert!(items[skipped].iter().all(|item| *item < threshold));
+        for expected in expected as u32..(expected as u32 + 10).min(N as u32) {
+            let position = engine.next_front(&mut items[engine.front()..], &mut u32::cmp);
+            assert_eq!(position.map(|position| items[position]), Some(expected));
+        }
+    }
+
+    // The skipped segments don't get partitioned: about as cheap as getting the first few items.
+    let mut items = shuffled;
+    let mut engine = Engine::<usize>::try_new(N).unwrap();
+    engine.skip_front_while(&mut items, &mut u32::cmp, &mut |item| *item < 3000);
+    assert_eq!(engine.len(), 1000);
+    let stats = engine.stats();
+    assert!(stats.skipped_segments > 0);
+    let mut items = shuffled;
+    let mut eager = Engine::<usize>::try_new(N).unwrap();
+    while eager.len() > 1000 {
+        eager.next_front(&mut items[eager.front()..], &mut u32::cmp);
+    }
+    assert!(stats.partitions * 4 < eager.stats().partitions);
+}
+
+/// Skipped items come back unsorted: draining after a rewind still yields them in order.
+#[test]
+fn skip_rewind() {
+    const N: usize = 400;
+    let shuffled: [u32; N] = core::array::from_fn(|i| (i as u32 * 7919 + 13) % N as u32);
+    let mut items = shuffled;
+    let mut engine = Engine::<u16>::try_new(N).unwrap();
+    let checkpoint = engine.checkpoint();
+    // Like `nth()`/`nth_back()`: skip, then take one.
+    assert_eq!(engine.next_front(&mut items, &mut u32::cmp), Some(0));
+    engine.skip_front(&mut items[1..], 150, &mut u32::cmp);
+    let position = engine.next_front(&mut items[151..], &mut u32::cmp);
+    assert_eq!(position.map(|position| items[position]), Some(151));
+    engine.skip_back(&mut items[152..], 100, &mut u32::cmp);
+    let between = engine.checkpoint();
+    let position = engine.next_back(&mut items[152..300], &mut u32::cmp);
+    assert_eq!(position.map(|position| items[position]), Some(299));
+
+    // Back to before the last one taken (at the back): no skipped items in between.
+    engine.rewind(between).unwrap();
+    let position = engine.next_back(&mut items[152..300], &mut u32::cmp);
+    assert_eq!(position.map(|position| items[position]), Some(299));
+    engine.rewind(checkpoint).unwrap();
+    for expected in 0..N as u32 {
+        let position = engine.next_front(&mut items[engine.front()..], &mut u32::cmp);
+        assert_eq!(position.map(|position| items[position]), Some(expected));
+    }
+    assert!(engine.is_empty());
+}
+
+#[test]
+fn skip_front_and_back() {
+    const N: usize = 4000;
+    let shuffled: [u32; N] = core::array::from_fn(|i| (i as u32 * 7919 + 13) % N as u32);
+    let mut items = shuffled;
+    let mut engine = Engine::<usize>::try_new(N).unwrap();
+    assert_eq!(engine.next_front(&mut items, &mut u32::cmp), Some(0));
+    let skipped = engine.skip_front(&mut items[1..], 1500, &mut u32::cmp);
+    assert_eq!(skipped, 1..1501);
+    let mut skipped_items = items[skipped].to_vec();
+    skipped_items.sort_unstable();
+    assert!(skipped_items.into_iter().eq(1..1501));
+    let skipped = engine.skip_back(&mut items[1501..], 1000, &mut u32::cmp);
+    assert_eq!(skipped, 3000..4000);
+    assert!(items[skipped].iter().all(|item| *item >= 3000));
+    assert_eq!(engine.len(), 1499);
+    let rest = engine.front();
+    assert_eq!(
+        engine.next_back(&mut items[rest..3000], &mut u32::cmp),
+        Some(2999)
+    );
+    assert_eq!(items[2999], 2999);
+    assert_eq!(
+        engine.next_front(&mut items[rest..2999], &mut u32::cmp),
+        Some(1501)
+    );
+    assert_eq!(items[1501], 1501);
+    // More than there are.
+    let rest = engine.front();
+    assert_eq!(
+        engine.skip_back(&mut items[rest..2999], N, &mut u32::cmp),
+        1502..2999
+    );
+    assert!(engine.is_empty());
+    assert_eq!(
+        engine.skip_front(&mut items[1502..1502], 1, &mut u32::cmp),
+        1502..1502
+    );
+
+    // Skipped items don't get sorted: far fewer partitions than taking them one by one.
+    let mut items = shuffled;
+    let mut engine = Engine::<usize>::try_new(N).unwrap();
+    engine.skip_front(&mut items, 3000, &mut u32::cmp);
+    let mut eager_items = shuffled;
+    let mut eager = Engine::<usize>::try_new(N).unwrap();
+    for _ in 0..3000 {
+        eager.next_front(&mut eager_items[eager.front()..], &mut u32::cmp);
+    }
+    assert!(engine.stats().partitions * 4 < eager.stats().partitions);
+}
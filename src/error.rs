@@ -0,0 +1,35 @@
+//! Error type shared by the fallible (`try_*`) API.
+//!
+//! With crate feature `no-panic`, functions that would panic (even in release) on a capacity,
+//! index or state violation are not compiled in: use their fallible alternatives. See
+//! `src/no_panic_tests.rs` for what is proven not to panic.
+
+use core::fmt::{Display, Formatter, Result as FmtResult};
+
+/// "non_exhaustive", so that we can add variants without breaking the clients' `match`.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The storage doesn't have enough (pre-allocated) capacity. We never re-allocate.
+    CapacityExceeded,
+    /// An operation was called in a state that doesn't permit it (for example, taking a
+    /// [`crate::store::cross::cross_vec::CrossVecPair`] out of its guard twice).
+    InvalidState,
+    /// An argument is out of its valid range (for example, a row length that doesn't divide the
+    /// buffer length).
+    InvalidArgument,
+    /// A storage's data doesn't lie where its (recorded) lengths say, or not within one
+    /// allocation.
+    NotContiguous,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::CapacityExceeded => f.write_str("capacity exceeded"),
+            Self::InvalidState => f.write_str("invalid state"),
+            Self::InvalidArgument => f.write_str("invalid argument"),
+            Self::NotContiguous => f.write_str("not contiguous"),
+        }
+    }
+}
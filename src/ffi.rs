@@ -0,0 +1,249 @@
+//! C API. Failures never panic (or abort) across the boundary: each function returns a
+//! [`LazysortError`] code (with its results in out-parameters), and it records the code for
+//! [`lazysort_last_error()`] and [`lazysort_last_error_message()`].
+//!
+//! ```text
+//! size_t positions[3], count;
+//! size_t indices[LEN];
+//! if (lazysort_smallest_positions_u32(data, LEN, indices, LEN, positions, 3, &count)
+//!         != LAZYSORT_OK) {
+//!     fprintf(stderr, "lazysort: %s\n", lazysort_last_error_message());
+//! }
+//! ```
+//!
+//! The last error is global (not per thread), like the rest of [`crate::sync`]-based state.
+
+use crate::engine::Engine;
+use crate::error::Error;
+use crate::idx::Index;
+use crate::index_sort::IndexSorter;
+use crate::sync::Code;
+use core::ffi::c_char;
+use core::ptr::NonNull;
+use core::slice;
+
+#[cfg(test)]
+mod ffi_tests;
+
+/// Error codes, stable across releases: [`Error`] variants, plus the failures that only a C caller
+/// can cause. Codes never get renumbered, only added.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LazysortError {
+    Ok = 0,
+    /// [`Error::CapacityExceeded`].
+    CapacityExceeded = 1,
+    /// [`Error::InvalidState`].
+    InvalidState = 2,
+    /// [`Error::InvalidArgument`].
+    InvalidArgument = 3,
+    /// [`Error::NotContiguous`].
+    NotContiguous = 4,
+    /// A null pointer for a non-empty buffer, or for an out-parameter.
+    NullPointer = 5,
+}
+
+impl From<Error> for LazysortError {
+    fn from(error: Error) -> Self {
+        match error {
+            Error::CapacityExceeded => Self::CapacityExceeded,
+            Error::InvalidState => Self::InvalidState,
+            Error::InvalidArgument => Self::InvalidArgument,
+            Error::NotContiguous => Self::NotContiguous,
+        }
+    }
+}
+
+impl LazysortError {
+    fn from_code(code: u8) -> Self {
+        match code {
+            1 => Self::CapacityExceeded,
+            2 => Self::InvalidState,
+            3 => Self::InvalidArgument,
+            4 => Self::NotContiguous,
+            5 => Self::NullPointer,
+            _ => Self::Ok,
+        }
+    }
+
+    /// NUL-terminated.
+    fn message(self) -> &'static [u8] {
+        match self {
+            Self::Ok => b"no error\0",
+            Self::CapacityExceeded => {
+                b"capacity exceeded: the scratch (like `indices`) is shorter than the data, or its \
+                  index type can't address all of the data\0"
+            }
+            Self::InvalidState => b"invalid state\0",
+            Self::InvalidArgument => b"invalid argument\0",
+            Self::NotContiguous => b"not contiguous\0",
+            Self::NullPointer => b"null pointer for a non-empty buffer, or for an out-parameter\0",
+        }
+    }
+}
+
+static LAST_ERROR: Code = Code::new(LazysortError::Ok as u8);
+
+/// Record `result` (if it's an error) for [`lazysort_last_error()`], and return its code.
+fn report(result: Result<(), LazysortError>) -> LazysortError {
+    match result {
+        Ok(()) => LazysortError::Ok,
+        Err(error) => {
+            LAST_ERROR.store(error as u8);
+            error
+        }
+    }
+}
+
+/// # Safety
+/// Unless null, `ptr` has to be valid for reads of `len` items.
+unsafe fn slice_of<'a, T>(ptr: *const T, len: usize) -> Result<&'a [T], LazysortError> {
+    match (ptr.is_null(), len) {
+        (_, 0) => Ok(&[]),
+        (true, _) => Err(LazysortError::NullPointer),
+        (false, _) => Ok(slice::from_raw_parts(ptr, len)),
+    }
+}
+
+/// # Safety
+/// Unless null, `ptr` has to be valid for reads and writes of `len` items.
+unsafe fn slice_of_mut<'a, T>(ptr: *mut T, len: usize) -> Result<&'a mut [T], LazysortError> {
+    match (ptr.is_null(), len) {
+        (_, 0) => Ok(slice::from_raw_parts_mut(NonNull::dangling().as_ptr(), 0)),
+        (true, _) => Err(LazysortError::NullPointer),
+        (false, _) => Ok(slice::from_raw_parts_mut(ptr, len)),
+    }
+}
+
+/// # Safety
+/// Unless null, `ptr` has to be valid for writes.
+unsafe fn write_out<T>(ptr: *mut T, value: T) -> Result<(), LazysortError> {
+    let mut ptr = NonNull::new(ptr).ok_or(LazysortError::NullPointer)?;
+    *ptr.as_mut() = value;
+    Ok(())
+}
+
+unsafe fn smallest_positions<I: Index>(
+    data: *const u32,
+    len: usize,
+    indices: *mut I,
+    indices_len: usize,
+    out: *mut usize,
+    k: usize,
+    out_count: *mut usize,
+) -> Result<(), LazysortError> {
+    let data = slice_of(data, len)?;
+    let indices = slice_of_mut(indices, indices_len)?;
+    let out = slice_of_mut(out, k)?;
+    if out_count.is_null() {
+        return Err(LazysortError::NullPointer);
+    }
+    let mut sorter = IndexSorter::new(data, indices)?;
+    let mut count = 0;
+    for slot in out.iter_mut() {
+        let Some(position) = sorter.next_index() else {
+            break;
+        };
+        *slot = position;
+        count += 1;
+    }
+    write_out(out_count, count)
+}
+
+/// Write the positions (in `data`) of the (up to) `k` smallest of the `len` items of `data` to
+/// `out`, in ascending order of the items, and their number to `out_count`. `indices` (of
+/// `indices_len`, at least `len`) is the scratch. Sorts only as much as needed for those `k`.
+///
+/// Return [`LazysortError::CapacityExceeded`] if `indices` is too short, or
+/// [`LazysortError::NullPointer`].
+///
+/// # Safety
+/// `data` has to be valid for reads of `len` items, `indices` for writes of `indices_len` items,
+/// `out` for writes of `k` items, and `out_count` for a write. (Pointers for empty buffers may be
+/// null.) `indices` and `out` must not overlap each other, nor `data`.
+#[no_mangle]
+pub unsafe extern "C" fn lazysort_smallest_positions_u32(
+    data: *const u32,
+    len: usize,
+    indices: *mut usize,
+    indices_len: usize,
+    out: *mut usize,
+    k: usize,
+    out_count: *mut usize,
+) -> LazysortError {
+    report(smallest_positions(
+        data,
+        len,
+        indices,
+        indices_len,
+        out,
+        k,
+        out_count,
+    ))
+}
+
+/// Like [`lazysort_smallest_positions_u32()`], but with a fraction of the scratch: `u16` indices,
+/// which can address only as many items as `u16` can index. Return
+/// [`LazysortError::CapacityExceeded`] for more.
+///
+/// # Safety
+/// As for [`lazysort_smallest_positions_u32()`].
+#[no_mangle]
+pub unsafe extern "C" fn lazysort_smallest_positions_u32_u16(
+    data: *const u32,
+    len: usize,
+    indices: *mut u16,
+    indices_len: usize,
+    out: *mut usize,
+    k: usize,
+    out_count: *mut usize,
+) -> LazysortError {
+    report(smallest_positions(
+        data,
+        len,
+        indices,
+        indices_len,
+        out,
+        k,
+        out_count,
+    ))
+}
+
+/// Sort the (up to) `k` smallest of the `len` items of `data` into its front, in place (no
+/// scratch). The rest is left in an unspecified order. Write their number to `out_sorted`.
+///
+/// # Safety
+/// `data` has to be valid for reads and writes of `len` items, and `out_sorted` for a write.
+/// (`data` may be null if `len` is 0.)
+#[no_mangle]
+pub unsafe extern "C" fn lazysort_partial_sort_i64(
+    data: *mut i64,
+    len: usize,
+    k: usize,
+    out_sorted: *mut usize,
+) -> LazysortError {
+    report((|| {
+        let data = slice_of_mut(data, len)?;
+        if out_sorted.is_null() {
+            return Err(LazysortError::NullPointer);
+        }
+        let mut engine: Engine = Engine::try_new(data.len())?;
+        let sorted = engine.next_chunk_front(data, k, &mut i64::cmp);
+        debug_assert_eq!(sorted.start, 0);
+        write_out(out_sorted, sorted.len())
+    })())
+}
+
+/// The code of the last failed call (successful calls don't reset it), or
+/// [`LazysortError::Ok`] if none failed yet.
+#[no_mangle]
+pub extern "C" fn lazysort_last_error() -> LazysortError {
+    LazysortError::from_code(LAST_ERROR.load())
+}
+
+/// A (static, NUL-terminated) description of [`lazysort_last_error()`]. Never null, never to be
+/// freed.
+#[no_mangle]
+pub extern "C" fn lazysort_last_error_message() -> *const c_char {
+    lazysort_last_error().message().as_ptr() as *const c_char
+}
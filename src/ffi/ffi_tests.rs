@@ -0,0 +1,102 @@
+use crate::ffi::*;
+use core::ffi::CStr;
+use core::ptr;
+
+const DATA: [u32; 8] = [50, 20, 70, 10, 80, 30, 60, 40];
+
+fn last_message() -> &'static str {
+    let message = unsafe { CStr::from_ptr(lazysort_last_error_message()) };
+    message.to_str().unwrap()
+}
+
+#[test]
+fn smallest_positions() {
+    let (mut indices, mut out, mut count) = ([0usize; 8], [0usize; 3], 0);
+    let code = unsafe {
+        lazysort_smallest_positions_u32(
+            DATA.as_ptr(),
+            DATA.len(),
+            indices.as_mut_ptr(),
+            indices.len(),
+            out.as_mut_ptr(),
+            out.len(),
+            &mut count,
+        )
+    };
+    assert_eq!((code, count, out), (LazysortError::Ok, 3, [3, 1, 5]));
+
+    // More asked for than there are.
+    let mut out = [usize::MAX; 10];
+    let mut indices = [0u16; 8];
+    let code = unsafe {
+        lazysort_smallest_positions_u32_u16(
+            DATA.as_ptr(),
+            DATA.len(),
+            indices.as_mut_ptr(),
+            indices.len(),
+            out.as_mut_ptr(),
+            out.len(),
+            &mut count,
+        )
+    };
+    assert_eq!((code, count), (LazysortError::Ok, 8));
+    assert_eq!(out[..8], [3, 1, 5, 7, 0, 6, 2, 4]);
+    assert_eq!(out[8], usize::MAX);
+}
+
+#[test]
+fn errors() {
+    let (mut indices, mut out, mut count) = ([0usize; 7], [0usize; 3], 0);
+    let code = unsafe {
+        lazysort_smallest_positions_u32(
+            DATA.as_ptr(),
+            DATA.len(),
+            indices.as_mut_ptr(),
+            indices.len(),
+            out.as_mut_ptr(),
+            out.len(),
+            &mut count,
+        )
+    };
+    assert_eq!(code, LazysortError::CapacityExceeded);
+    assert_eq!(lazysort_last_error(), LazysortError::CapacityExceeded);
+    assert!(last_message().starts_with("capacity exceeded"));
+
+    // The `u16` index type can't address this many.
+    let data = [0u32; u16::MAX as usize + 2];
+    let mut indices = [0u16; u16::MAX as usize + 2];
+    let code = unsafe {
+        lazysort_smallest_positions_u32_u16(
+            data.as_ptr(),
+            data.len(),
+            indices.as_mut_ptr(),
+            indices.len(),
+            out.as_mut_ptr(),
+            out.len(),
+            &mut count,
+        )
+    };
+    assert_eq!(code, LazysortError::CapacityExceeded);
+
+    let code = unsafe { lazysort_partial_sort_i64(ptr::null_mut(), 3, 1, &mut count) };
+    assert_eq!(code, LazysortError::NullPointer);
+    assert_eq!(lazysort_last_error(), LazysortError::NullPointer);
+    assert!(last_message().starts_with("null pointer"));
+
+    // A successful call doesn't reset it.
+    let code = unsafe { lazysort_partial_sort_i64(ptr::null_mut(), 0, 1, &mut count) };
+    assert_eq!((code, count), (LazysortError::Ok, 0));
+    assert_eq!(lazysort_last_error(), LazysortError::NullPointer);
+}
+
+#[test]
+fn partial_sort() {
+    let mut data = [5i64, -3, 9, 0, -7, 2, 8];
+    let mut sorted = 0;
+    let code = unsafe { lazysort_partial_sort_i64(data.as_mut_ptr(), data.len(), 3, &mut sorted) };
+    assert_eq!((code, sorted), (LazysortError::Ok, 3));
+    assert_eq!(data[..3], [-7, -3, 0]);
+    let mut rest = data[3..].to_vec();
+    rest.sort_unstable();
+    assert_eq!(rest, [2, 5, 8, 9]);
+}
@@ -0,0 +1,150 @@
+//! Binary min-heap operations on a slice: the heapsort that [`crate::engine::Engine`] falls back to
+//! (see [`crate::engine::OnFull::Heapsort`] and [`crate::engine::Pivot::Adaptive`]). No allocation,
+//! no recursion. Usable on any slice, including the ones of the crate's storage (like
+//! [`crate::store::lifos::double_stack::DoubleStack::as_mut_slices()`]).
+//!
+//! ```
+//! use lazysort_no_alloc::heap::{drain_sorted, heapify};
+//!
+//! let mut items = [50, 20, 40, 10, 30];
+//! heapify(&mut items, &mut i32::cmp);
+//! assert_eq!(items[0], 10);
+//! let smallest: Vec<i32> = drain_sorted(&mut items, i32::cmp).take(2).map(|item| *item).collect();
+//! assert_eq!(smallest, [10, 20]);
+//! ```
+//!
+//! The heap is ordered by `cmp`: each item is less than, or equal to, its children (at `2 * i + 1`
+//! and `2 * i + 2`). For a max-heap, reverse `cmp`.
+
+use crate::util::{cmp_at, swap_at};
+use core::cmp::Ordering;
+use core::iter::FusedIterator;
+use core::mem;
+
+#[cfg(test)]
+mod heap_tests;
+
+/// Restore the heap property of `items`, assuming it holds everywhere but at `root`: move the item
+/// at `root` down, as far as needed.
+pub fn sift_down<T, CMP>(items: &mut [T], mut root: usize, cmp: &mut CMP)
+where
+    CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+{
+    loop {
+        let mut child = 2 * root + 1;
+        if child >= items.len() {
+            return;
+        }
+        if child + 1 < items.len() && cmp(&items[child + 1], &items[child]) == Ordering::Less {
+            child += 1;
+        }
+        if cmp_at(items, child, root, cmp) != Ordering::Less {
+            return;
+        }
+        swap_at(items, root, child);
+        root = child;
+    }
+}
+
+/// Restore the heap property of `items`, assuming it holds everywhere but at `node` (like a newly
+/// pushed last item): move the item at `node` up, as far as needed.
+pub fn sift_up<T, CMP>(items: &mut [T], mut node: usize, cmp: &mut CMP)
+where
+    CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+{
+    while node > 0 {
+        let parent = (node - 1) / 2;
+        if cmp_at(items, node, parent, cmp) != Ordering::Less {
+            return;
+        }
+        swap_at(items, node, parent);
+        node = parent;
+    }
+}
+
+/// Arrange `items` into a heap, in place: O(n).
+pub fn heapify<T, CMP>(items: &mut [T], cmp: &mut CMP)
+where
+    CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+{
+    for start in (0..items.len() / 2).rev() {
+        sift_down(items, start, cmp);
+    }
+}
+
+/// Whether `items` is a heap.
+pub fn is_heap<T, CMP>(items: &[T], cmp: &mut CMP) -> bool
+where
+    CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+{
+    (1..items.len()).all(|child| cmp(&items[child], &items[(child - 1) / 2]) != Ordering::Less)
+}
+
+/// Sort `items` fully (in ascending order), in place: O(n log n) in the worst case. Not stable.
+pub fn heapsort<T, CMP>(items: &mut [T], cmp: &mut CMP)
+where
+    CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+{
+    // A max-heap, so that each greatest item gets swapped to its final place at the end.
+    let mut reversed = |a: &T, b: &T| cmp(b, a);
+    heapify(items, &mut reversed);
+    let mut heap = items;
+    while heap.len() > 1 {
+        let end = heap.len() - 1;
+        heap.swap(0, end);
+        let taken = mem::take(&mut heap);
+        heap = taken.split_at_mut(end).0;
+        sift_down(heap, 0, &mut reversed);
+    }
+}
+
+/// Take the items of the heap `items` (see [`heapify()`]) out smallest first, lazily: O(log n) per
+/// item. Taken items get moved to the end of `items`, so once all are taken, `items` is sorted in
+/// DESCENDING order.
+pub fn drain_sorted<T, CMP>(items: &mut [T], mut cmp: CMP) -> DrainSorted<'_, T, CMP>
+where
+    CMP: FnMut(&T, &T) -> Ordering,
+{
+    debug_assert!(is_heap(items, &mut cmp));
+    DrainSorted { heap: items, cmp }
+}
+
+/// See [`drain_sorted()`].
+#[derive(Debug)]
+pub struct DrainSorted<'a, T, CMP> {
+    /// The remaining heap.
+    heap: &'a mut [T],
+    cmp: CMP,
+}
+
+impl<'a, T, CMP> DrainSorted<'a, T, CMP> {
+    /// The remaining heap (its smallest item first).
+    pub fn heap(&self) -> &[T] {
+        self.heap
+    }
+}
+
+impl<'a, T, CMP> Iterator for DrainSorted<'a, T, CMP>
+where
+    CMP: FnMut(&T, &T) -> Ordering,
+{
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        let heap = mem::take(&mut self.heap);
+        let last = heap.len().checked_sub(1)?;
+        heap.swap(0, last);
+        let (rest, taken) = heap.split_at_mut(last);
+        sift_down(rest, 0, &mut self.cmp);
+        self.heap = rest;
+        taken.first_mut()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.heap.len(), Some(self.heap.len()))
+    }
+}
+
+impl<T, CMP> ExactSizeIterator for DrainSorted<'_, T, CMP> where CMP: FnMut(&T, &T) -> Ordering {}
+
+impl<T, CMP> FusedIterator for DrainSorted<'_, T, CMP> where CMP: FnMut(&T, &T) -> Ordering {}
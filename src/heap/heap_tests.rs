@@ -0,0 +1,77 @@
+use crate::heap::{drain_sorted, heapify, heapsort, is_heap, sift_down, sift_up};
+use crate::testing::scrambled;
+
+fn items(len: u32) -> [u32; 64] {
+    let mut items = [0; 64];
+    for (i, item) in items.iter_mut().enumerate().take(len as usize) {
+        *item = scrambled(i, 0, 23);
+    }
+    items
+}
+
+#[test]
+fn heapify_then_sift_down() {
+    for len in 0..64 {
+        let mut items = items(len);
+        let items = &mut items[..len as usize];
+        heapify(items, &mut u32::cmp);
+        assert!(is_heap(items, &mut u32::cmp));
+        if let Some(root) = items.first_mut() {
+            *root = 100;
+            assert_eq!(is_heap(items, &mut u32::cmp), items.len() == 1);
+            sift_down(items, 0, &mut u32::cmp);
+            assert!(is_heap(items, &mut u32::cmp));
+        }
+    }
+}
+
+#[test]
+fn sift_up_pushed() {
+    let mut items = items(64);
+    for len in 1..=64 {
+        sift_up(&mut items[..len], len - 1, &mut u32::cmp);
+        assert!(is_heap(&items[..len], &mut u32::cmp));
+    }
+    assert_eq!(items[0], 0);
+}
+
+#[test]
+fn heapsort_sorts() {
+    for len in 0..64 {
+        let mut items = items(len);
+        let items = &mut items[..len as usize];
+        heapsort(items, &mut u32::cmp);
+        assert!(items.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+    // A reversed comparison sorts descending.
+    let mut items = items(40);
+    heapsort(&mut items[..40], &mut |a: &u32, b: &u32| b.cmp(a));
+    assert!(items[..40].windows(2).all(|pair| pair[0] >= pair[1]));
+}
+
+#[test]
+fn drain_sorted_lazily() {
+    let mut items = items(30);
+    let items = &mut items[..30];
+    let mut expected = [0; 30];
+    expected.copy_from_slice(items);
+    expected.sort_unstable();
+
+    heapify(items, &mut u32::cmp);
+    let mut drain = drain_sorted(items, u32::cmp);
+    assert_eq!(drain.len(), 30);
+    for expected in &expected[..3] {
+        assert_eq!(drain.next().map(|item| *item), Some(*expected));
+    }
+    assert_eq!(drain.len(), 27);
+    assert!(drain.heap().iter().all(|item| *item >= expected[2]));
+    assert!(drain
+        .by_ref()
+        .map(|item| *item)
+        .eq(expected[3..].iter().copied()));
+    assert!(drain.next().is_none());
+
+    // Taken items pile up at the end: descending.
+    expected.reverse();
+    assert_eq!(items, expected);
+}
@@ -1,4 +1,7 @@
-use core::num::{NonZeroU8, NonZeroUsize};
+use core::num::{NonZeroU16, NonZeroU8, NonZeroUsize};
+
+#[cfg(test)]
+mod idx_tests;
 /// Non-recursive implementation
 ///
 /// Trait used for indexing of tree-like nodes within Vec/VecDeque-like linear storage.
@@ -6,23 +9,17 @@ use core::num::{NonZeroU8, NonZeroUsize};
 /// It leverages optimization with [`Option`] for [`NonZeroUsize`], and for some of [`NonZeroU8`],
 /// [`NonZeroU16`]... types. [`Option`] for those types doesn't take any extra space.
 ///
-/// We can use it with non-zero unsigned types (see [Index::max_index_excl_usize()]), because we
-/// (luckily) don't need to use the item at index 0.
+/// We can use it with non-zero unsigned types (see [`Index::min_index()`]), because we (luckily)
+/// don't need to use the item at index 0.
 ///
 /// However, out of `NonZeroUxyz` types, it's possible to implement it for [`NonZeroUsize`] and only
 /// for [`NonZeroU8`], [`NonZeroU16`]... types that are smaller or same width as [`NonZeroUsize`]
 /// (on a particular platform/target).
-///
-/// TODO
-///
-/// Trait replacing Vec/VecDeque for input + storage
-/// - TODO Elements for storage: Generic over u8/u16/u32/u64/usize INDEX type
-/// - TODO implementation struct: 1 Vec/SliceVec <-> VecDeque/SliceDeque for both custom items (ones
-///   being sorted, of type T), and for and INDEX and related metadata along in a struct.
-///   Disadvantage: When used as Vec/SliceVec (for read-only "input", rather than for mutable 2-lifo
-///   "storage"), INDEX+metadata slots are unused, hence unused memory throughout the Vec/SliceVec.
-/// - TODO implementation with 2 structs: 1 Vec/SliceVec + 1 VecDeque/SliceDeque.
-trait Index: Eq + Ord + Sized {
+pub trait Index: Copy + Eq + Ord + Sized {
+    /// Same as [`Index::max_indexable_len()`], but usable in `const` context (for example by
+    /// [`crate::budget::Budget`]).
+    const MAX_INDEXABLE_LEN: usize;
+
     fn min_index_usize() -> usize {
         Self::min_index().to_usize()
     }
@@ -40,27 +37,35 @@ trait Index: Eq + Ord + Sized {
     /// type minus 1. (Because an array/slice max. length is [`usize::MAX`], so any index has to be
     /// smaller.)
     fn max_index() -> Self;
-    fn max_indexable_len() -> usize;
+    fn max_indexable_len() -> usize {
+        Self::MAX_INDEXABLE_LEN
+    }
 
     /// Length (range width) indexable by this type, given a physical length.
+    ///
+    /// Not available with crate feature `no-panic`.
+    #[cfg(not(feature = "no-panic"))]
     fn indexable_len(physical_len: usize) -> usize {
-        assert!(physical_len >= Self::min_index_usize());
+        release_assert!(
+            physical_len >= Self::min_index_usize(),
+            "Physical length below the minimum index."
+        );
         physical_len - Self::min_index_usize()
     }
-    /// - u8/u16...usize: physical_len==3: `012` -> max. exl. 3
-    /// - NonZeroU8...  : physical_len==3: ` 12` -> max. exl. 3
-    /// - When we index by [`NonZeroU8`] etc, we do NOT subtract 1. We use the index as-is. Yes, we
-    ///   do "waste" the item at index 0.
-    fn max_index_excl_usize(physical_len: usize) -> usize {
-        panic!("not needed?")
-    }
-    /// - u8/u16...usize: physical_len==3: `012` -> max. incl. 2
-    /// - NonZeroU8...  : physical_len==3: ` 12` -> max. incl. 2
-    fn max_index_incl_usize(physical_len: usize) -> usize {
-        panic!("not needed?")
-    }
 
-    fn from_usize(index: usize) -> Self;
+    /// Panic if `index` is out of range (see [`Index::try_from_usize()`]).
+    ///
+    /// Not available with crate feature `no-panic`. Use [`Index::try_from_usize()`] instead.
+    #[cfg(not(feature = "no-panic"))]
+    fn from_usize(index: usize) -> Self {
+        match Self::try_from_usize(index) {
+            Some(index) => index,
+            None => release_panic!("Index out of range."),
+        }
+    }
+    /// [`None`] if `index` is below [`Index::min_index_usize()`] or above
+    /// [`Index::max_index_usize()`].
+    fn try_from_usize(index: usize) -> Option<Self>;
     fn to_usize(&self) -> usize;
 }
 
@@ -99,7 +104,9 @@ const _: () = {
 /// for `NonZeroUxyz` type with byte width same as that of `usize`). Why is it
 /// `NonZeroUsize::MAX.to_usize() - 1` and not just `NonZeroUsize::MAX.to_usize()`? See
 /// [`USIZE_MAX_INDEX_USIZE`].
-const NON_ZERO_USIZE_MAX_INDEX_USIZE: usize = NonZeroUsize::MAX.get() - 1;
+// Not using `NonZeroUsize::MAX` & `NonZeroUsize::MIN`, since those require Rust 1.70 (> our MSRV).
+const NON_ZERO_USIZE_MIN: NonZeroUsize = unwrap_option(NonZeroUsize::new(1));
+const NON_ZERO_USIZE_MAX_INDEX_USIZE: usize = usize::MAX - 1;
 const NON_ZERO_USIZE_MAX_INDEX: NonZeroUsize =
     unwrap_option(NonZeroUsize::new(NON_ZERO_USIZE_MAX_INDEX_USIZE));
 /// Different to most `NON_ZERO_Uxyz_MAX_INDEXABLE_LEN` (other than
@@ -126,8 +133,9 @@ const _: () = {
 };
 // --
 
-const NON_ZERO_U8_MAX_INDEX_USIZE: usize = NonZeroU8::MAX.get() as usize;
-const NON_ZERO_U8_MAX_INDEX: NonZeroU8 = NonZeroU8::MAX;
+const NON_ZERO_U8_MIN: NonZeroU8 = unwrap_option(NonZeroU8::new(1));
+const NON_ZERO_U8_MAX_INDEX_USIZE: usize = u8::MAX as usize;
+const NON_ZERO_U8_MAX_INDEX: NonZeroU8 = unwrap_option(NonZeroU8::new(u8::MAX));
 /// `1..=NON_ZERO_U8_MAX_INDEX_USIZE` == 1..=u8::MAX == 1..255 == 255 slots
 const NON_ZERO_U8_MAX_INDEXABLE_LEN: usize = NON_ZERO_U8_MAX_INDEX_USIZE;
 const _: () = {
@@ -137,7 +145,63 @@ const _: () = {
 };
 // --
 
+// `u16` (and [`NonZeroU16`]) has the same width as `usize` on 16 bit targets (MSP430, AVR). Then it
+// is an exception, just like `usize` (and [`NonZeroUsize`]) - see [`USIZE_MAX_INDEX_USIZE`].
+#[cfg(target_pointer_width = "16")]
+const U16_MAX_INDEX_USIZE: usize = u16::MAX as usize - 1;
+#[cfg(not(target_pointer_width = "16"))]
+const U16_MAX_INDEX_USIZE: usize = u16::MAX as usize;
+const U16_MAX_INDEX: u16 = U16_MAX_INDEX_USIZE as u16;
+/// `0..=U16_MAX_INDEX_USIZE`
+const U16_MAX_INDEXABLE_LEN: usize = U16_MAX_INDEX_USIZE + 1;
+#[cfg(target_pointer_width = "16")]
+const _: () = {
+    if U16_MAX_INDEXABLE_LEN != usize::MAX {
+        panic!()
+    }
+};
+#[cfg(not(target_pointer_width = "16"))]
+const _: () = {
+    if U16_MAX_INDEXABLE_LEN != 65536 {
+        panic!()
+    }
+};
+// --
+
+const NON_ZERO_U16_MIN: NonZeroU16 = unwrap_option(NonZeroU16::new(1));
+#[cfg(target_pointer_width = "16")]
+const NON_ZERO_U16_MAX_INDEX_USIZE: usize = u16::MAX as usize - 1;
+#[cfg(not(target_pointer_width = "16"))]
+const NON_ZERO_U16_MAX_INDEX_USIZE: usize = u16::MAX as usize;
+const NON_ZERO_U16_MAX_INDEX: NonZeroU16 =
+    unwrap_option(NonZeroU16::new(NON_ZERO_U16_MAX_INDEX_USIZE as u16));
+/// `1..=NON_ZERO_U16_MAX_INDEX_USIZE`
+const NON_ZERO_U16_MAX_INDEXABLE_LEN: usize = NON_ZERO_U16_MAX_INDEX_USIZE;
+#[cfg(target_pointer_width = "16")]
+const _: () = {
+    if NON_ZERO_U16_MAX_INDEXABLE_LEN != usize::MAX - 1 {
+        panic!()
+    }
+};
+#[cfg(not(target_pointer_width = "16"))]
+const _: () = {
+    if NON_ZERO_U16_MAX_INDEXABLE_LEN != 65535 {
+        panic!()
+    }
+};
+// --
+
+// Capacity math (see also [`crate::budget::Budget`]) relies on `usize` being at least 16 bit wide.
+const _: () = {
+    if usize::BITS < 16 {
+        panic!()
+    }
+};
+// --
+
 impl Index for usize {
+    const MAX_INDEXABLE_LEN: usize = USIZE_MAX_INDEXABLE_LEN;
+
     fn min_index_usize() -> usize {
         0
     }
@@ -152,11 +216,8 @@ impl Index for usize {
         USIZE_MAX_INDEX
     }
 
-    fn max_indexable_len() -> usize {
-        USIZE_MAX_INDEXABLE_LEN
-    }
-    fn from_usize(index: usize) -> Self {
-        index
+    fn try_from_usize(index: usize) -> Option<Self> {
+        (index <= USIZE_MAX_INDEX_USIZE).then_some(index)
     }
     fn to_usize(&self) -> usize {
         *self
@@ -164,11 +225,13 @@ impl Index for usize {
 }
 
 impl Index for NonZeroUsize {
+    const MAX_INDEXABLE_LEN: usize = NON_ZERO_USIZE_MAX_INDEXABLE_LEN;
+
     fn min_index_usize() -> usize {
-        NonZeroUsize::MIN.get()
+        NON_ZERO_USIZE_MIN.get()
     }
     fn min_index() -> Self {
-        NonZeroUsize::MIN
+        NON_ZERO_USIZE_MIN
     }
 
     fn max_index_usize() -> usize {
@@ -178,11 +241,8 @@ impl Index for NonZeroUsize {
         NON_ZERO_USIZE_MAX_INDEX
     }
 
-    fn max_indexable_len() -> usize {
-        NON_ZERO_USIZE_MAX_INDEXABLE_LEN
-    }
-    fn from_usize(index: usize) -> Self {
-        NonZeroUsize::new(index).unwrap()
+    fn try_from_usize(index: usize) -> Option<Self> {
+        NonZeroUsize::new(index).filter(|index| index.get() <= NON_ZERO_USIZE_MAX_INDEX_USIZE)
     }
     fn to_usize(&self) -> usize {
         self.get()
@@ -190,6 +250,8 @@ impl Index for NonZeroUsize {
 }
 
 impl Index for u8 {
+    const MAX_INDEXABLE_LEN: usize = U8_MAX_INDEXABLE_LEN;
+
     fn min_index_usize() -> usize {
         0
     }
@@ -204,12 +266,8 @@ impl Index for u8 {
         U8_MAX_INDEX
     }
 
-    fn max_indexable_len() -> usize {
-        U8_MAX_INDEXABLE_LEN
-    }
-    fn from_usize(index: usize) -> Self {
-        assert!(index <= Self::max_index_usize());
-        index as u8
+    fn try_from_usize(index: usize) -> Option<Self> {
+        u8::try_from(index).ok()
     }
     fn to_usize(&self) -> usize {
         *self as usize
@@ -217,8 +275,10 @@ impl Index for u8 {
 }
 
 impl Index for NonZeroU8 {
+    const MAX_INDEXABLE_LEN: usize = NON_ZERO_U8_MAX_INDEXABLE_LEN;
+
     fn min_index() -> Self {
-        NonZeroU8::MIN
+        NON_ZERO_U8_MIN
     }
 
     fn max_index_usize() -> usize {
@@ -228,19 +288,63 @@ impl Index for NonZeroU8 {
         NON_ZERO_U8_MAX_INDEX
     }
 
-    fn max_indexable_len() -> usize {
-        NON_ZERO_USIZE_MAX_INDEXABLE_LEN
+    fn try_from_usize(index: usize) -> Option<Self> {
+        NonZeroU8::try_from(NonZeroUsize::new(index)?).ok()
     }
-    fn from_usize(index: usize) -> Self {
-        NonZeroU8::try_from(NonZeroUsize::new(index).unwrap()).unwrap()
+    fn to_usize(&self) -> usize {
+        self.get() as usize
+    }
+}
+
+impl Index for u16 {
+    const MAX_INDEXABLE_LEN: usize = U16_MAX_INDEXABLE_LEN;
+
+    fn min_index_usize() -> usize {
+        0
+    }
+    fn min_index() -> Self {
+        0
+    }
+
+    fn max_index_usize() -> usize {
+        U16_MAX_INDEX_USIZE
+    }
+    fn max_index() -> Self {
+        U16_MAX_INDEX
+    }
+
+    fn try_from_usize(index: usize) -> Option<Self> {
+        (index <= U16_MAX_INDEX_USIZE).then_some(index as u16)
+    }
+    fn to_usize(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl Index for NonZeroU16 {
+    const MAX_INDEXABLE_LEN: usize = NON_ZERO_U16_MAX_INDEXABLE_LEN;
+
+    fn min_index() -> Self {
+        NON_ZERO_U16_MIN
+    }
+
+    fn max_index_usize() -> usize {
+        NON_ZERO_U16_MAX_INDEX_USIZE
+    }
+    fn max_index() -> Self {
+        NON_ZERO_U16_MAX_INDEX
+    }
+
+    fn try_from_usize(index: usize) -> Option<Self> {
+        let index =
+            NonZeroUsize::new(index).filter(|index| index.get() <= NON_ZERO_U16_MAX_INDEX_USIZE)?;
+        NonZeroU16::try_from(index).ok()
     }
     fn to_usize(&self) -> usize {
         self.get() as usize
     }
 }
 
-// TODO u16: different on 16 bit and 32+bit
-//
 // TODO u32: different on 32 bit and 64bit
 //
 // TODO u64: alias to usize
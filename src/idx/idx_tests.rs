@@ -0,0 +1,62 @@
+use crate::idx::Index;
+use core::num::{NonZeroU16, NonZeroU8, NonZeroUsize};
+
+#[test]
+fn min_max() {
+    assert_eq!(u8::min_index_usize(), 0);
+    assert_eq!(NonZeroU8::min_index_usize(), 1);
+    assert_eq!(u16::min_index_usize(), 0);
+    assert_eq!(NonZeroU16::min_index_usize(), 1);
+    assert_eq!(NonZeroUsize::min_index_usize(), 1);
+
+    assert_eq!(u8::max_indexable_len(), 256);
+    assert_eq!(NonZeroU8::max_indexable_len(), 255);
+    assert_eq!(usize::max_indexable_len(), usize::MAX);
+    assert_eq!(NonZeroUsize::max_indexable_len(), usize::MAX - 1);
+}
+
+#[cfg(target_pointer_width = "16")]
+#[test]
+fn u16_same_width_as_usize() {
+    assert_eq!(u16::max_index_usize(), usize::MAX - 1);
+    assert_eq!(u16::max_indexable_len(), usize::MAX);
+    assert_eq!(NonZeroU16::max_indexable_len(), usize::MAX - 1);
+}
+
+#[cfg(not(target_pointer_width = "16"))]
+#[test]
+fn u16_narrower_than_usize() {
+    assert_eq!(u16::max_index_usize(), 65535);
+    assert_eq!(u16::max_indexable_len(), 65536);
+    assert_eq!(NonZeroU16::max_indexable_len(), 65535);
+}
+
+#[cfg(not(feature = "no-panic"))]
+#[test]
+fn round_trip() {
+    assert_eq!(
+        u16::from_usize(u16::max_index_usize()).to_usize(),
+        u16::max_index_usize()
+    );
+    assert_eq!(NonZeroU16::from_usize(1).to_usize(), 1);
+    assert_eq!(NonZeroU8::from_usize(255).to_usize(), 255);
+}
+
+#[test]
+fn try_from_usize_range() {
+    assert_eq!(u8::try_from_usize(255), Some(255));
+    assert_eq!(u8::try_from_usize(256), None);
+    assert_eq!(NonZeroU8::try_from_usize(0), None);
+    assert_eq!(
+        NonZeroU8::try_from_usize(255).map(|index| index.get()),
+        Some(255)
+    );
+    assert_eq!(u16::try_from_usize(u16::max_index_usize() + 1), None);
+    assert_eq!(NonZeroU16::try_from_usize(0), None);
+    assert_eq!(usize::try_from_usize(usize::MAX), None);
+    assert_eq!(NonZeroUsize::try_from_usize(usize::MAX), None);
+    assert_eq!(
+        NonZeroUsize::try_from_usize(1).map(|index| index.get()),
+        Some(1)
+    );
+}
@@ -0,0 +1,403 @@
+//! Lazy sorting that never moves the items: for read-only data (in flash/ROM...).
+//!
+//! Only an index array (in RAM, provided by the caller) gets partitioned. With crate feature
+//! `alloc`, `argsort()` allocates that array itself.
+
+use crate::engine::{Checkpoint, Engine};
+use crate::error::Error;
+use crate::idx::Index;
+use crate::util::cmp_at;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+#[cfg(feature = "alloc")]
+use core::iter::FusedIterator;
+
+#[cfg(test)]
+mod index_sort_tests;
+
+/// Lazily sorts positions of the (read-only) `data`, using the (mutable) `indices` for storage.
+///
+/// It yields references (as an [`Iterator`]), or positions (see [`IndexSorter::next_index()`]), in
+/// ascending order of the referenced items. Use a narrow index type `I` (like [`u8`] or [`u16`]) to
+/// save RAM.
+pub struct IndexSorter<'d, 'i, T: Ord, I: Index = usize> {
+    data: &'d [T],
+    /// The first `data.len()` of them are used. Stored as [`Index`] values, hence shifted by
+    /// [`Index::min_index_usize()`].
+    indices: &'i mut [I],
+    engine: Engine<I>,
+    /// See [`IndexSorter::set_filter()`].
+    filter: Option<fn(&T) -> bool>,
+}
+
+impl<'d, 'i, T: Ord, I: Index> IndexSorter<'d, 'i, T, I> {
+    /// Return [`Error::CapacityExceeded`] if `indices` is shorter than `data`, or if `I` can't
+    /// index all of `data`.
+    pub fn new(data: &'d [T], indices: &'i mut [I]) -> Result<Self, Error> {
+        if indices.len() < data.len() {
+            return Err(Error::CapacityExceeded);
+        }
+        let engine = Engine::try_new(data.len())?;
+        let indices = &mut indices[..data.len()];
+        for (position, index) in indices.iter_mut().enumerate() {
+            *index = I::try_from_usize(position + I::min_index_usize())
+                .ok_or(Error::CapacityExceeded)?;
+        }
+        Ok(Self {
+            data,
+            indices,
+            engine,
+            filter: None,
+        })
+    }
+
+    /// Resume where another [`IndexSorter`] (possibly in another process) left off: `indices` as
+    /// it has left them (for example in shared memory, along with `data`), and `plan` as exported
+    /// by its [`IndexSorter::export_plan()`]. No partitioning gets repeated. Any filter (see
+    /// [`IndexSorter::set_filter()`]) has to be set again.
+    ///
+    /// Return [`Error::CapacityExceeded`] as for [`IndexSorter::new()`], or
+    /// [`Error::InvalidArgument`] if `plan` reaches past `data`, or if (the used part of) `indices`
+    /// contains an index out of `data`. See [`Engine::try_from_plan()`].
+    ///
+    /// ```
+    /// use lazysort_no_alloc::index_sort::IndexSorter;
+    ///
+    /// let data = [50, 20, 40, 10, 30];
+    /// let mut indices = [0u8; 5];
+    /// let mut plan = [0; 64];
+    /// let mut sorter = IndexSorter::new(&data, &mut indices).unwrap();
+    /// assert!(sorter.by_ref().take(2).eq(&[10, 20]));
+    /// let len = sorter.export_plan(&mut plan).unwrap();
+    ///
+    /// let resumed = IndexSorter::resume(&data, &mut indices, &plan[..len]).unwrap();
+    /// assert!(resumed.eq(&[30, 40, 50]));
+    /// ```
+    pub fn resume(data: &'d [T], indices: &'i mut [I], plan: &[u8]) -> Result<Self, Error> {
+        if indices.len() < data.len() {
+            return Err(Error::CapacityExceeded);
+        }
+        let engine = Engine::try_from_plan(plan)?;
+        let indices = &mut indices[..data.len()];
+        if engine.back() > data.len() || indices.iter().any(|index| position(*index) >= data.len())
+        {
+            return Err(Error::InvalidArgument);
+        }
+        Ok(Self {
+            data,
+            indices,
+            engine,
+            filter: None,
+        })
+    }
+
+    /// Write the partitioning done so far (not the indices themselves) to `out`, for
+    /// [`IndexSorter::resume()`]. Return the number of bytes written, or
+    /// [`Error::CapacityExceeded`] if `out` is too short. See [`Engine::export_plan()`].
+    pub fn export_plan(&self, out: &mut [u8]) -> Result<usize, Error> {
+        self.engine.export_plan(out)
+    }
+
+    /// Yield only the items for which `keep` holds, where `keep` has to hold for a suffix of the
+    /// sorted order (if it holds for an item, it holds for all greater ones): like "score > 0.9".
+    /// The others get skipped by [`Engine::skip_front_while()`], mostly without being partitioned.
+    /// Applies to what's not yielded yet.
+    ///
+    /// ```
+    /// use lazysort_no_alloc::index_sort::IndexSorter;
+    ///
+    /// let scores = [95, 40, 91, 99, 12, 93, 67, 90];
+    /// let mut indices = [0u8; 8];
+    /// let mut sorter = IndexSorter::new(&scores, &mut indices).unwrap();
+    /// sorter.set_filter(|score| *score > 90);
+    /// assert!(sorter.by_ref().take(2).eq(&[91, 93]));
+    /// assert!(sorter.eq(&[95, 99]));
+    /// ```
+    pub fn set_filter(&mut self, keep: fn(&T) -> bool) {
+        self.filter = Some(keep);
+    }
+
+    /// Number of items not yielded yet. With [`IndexSorter::set_filter()`], that includes the
+    /// items not skipped yet.
+    pub fn len(&self) -> usize {
+        self.engine.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.engine.is_empty()
+    }
+
+    /// Skip the items that [`IndexSorter::set_filter()`] doesn't keep (if any still come first).
+    fn skip_filtered(&mut self) {
+        if let Some(keep) = self.filter {
+            let data = self.data;
+            let live = live(self.indices, &self.engine);
+            let mut skip =
+                |index: &I| !matches!(data.get(position(*index)), Some(item) if keep(item));
+            self.engine
+                .skip_front_while(live, &mut Self::cmp(data), &mut skip);
+        }
+    }
+
+    /// Position (in `data`) of the next smallest item.
+    pub fn next_index(&mut self) -> Option<usize> {
+        self.skip_filtered();
+        let data = self.data;
+        let live = live(self.indices, &self.engine);
+        let slot = self.engine.next_front(live, &mut Self::cmp(data))?;
+        self.indices.get(slot).copied().map(position)
+    }
+
+    /// Position (in `data`) of the next greatest item.
+    pub fn next_back_index(&mut self) -> Option<usize> {
+        self.skip_filtered();
+        let data = self.data;
+        let live = live(self.indices, &self.engine);
+        let slot = self.engine.next_back(live, &mut Self::cmp(data))?;
+        self.indices.get(slot).copied().map(position)
+    }
+
+    /// The next (up to) `k` smallest items, in ascending order. It sorts only those `k` (after one
+    /// partitioning at rank `k`): much cheaper than `k` calls to [`Iterator::next()`] for a sizable
+    /// `k`.
+    pub fn next_chunk(&mut self, k: usize) -> impl ExactSizeIterator<Item = &'d T> + '_ {
+        self.skip_filtered();
+        let data = self.data;
+        let live = live(self.indices, &self.engine);
+        let slots = self.engine.next_chunk_front(live, k, &mut Self::cmp(data));
+        let chunk: &[I] = self.indices.get(slots).unwrap_or_default();
+        chunk.iter().map(move |index| &data[position(*index)])
+    }
+
+    /// The next smallest item, together with the positions (in `data`) of ALL items equal to it, in
+    /// ascending order of positions. Like a group-by after an argsort: successive calls yield the
+    /// distinct items in ascending order.
+    pub fn next_group(&mut self) -> Option<(&'d T, impl ExactSizeIterator<Item = usize> + '_)> {
+        self.skip_filtered();
+        let data = self.data;
+        let start = self.engine.front();
+        let first = data.get(self.next_index()?)?;
+        while let Some(next) = self.peek_index() {
+            if data.get(next) != Some(first) {
+                break;
+            }
+            self.next_index();
+        }
+        let group = self.indices.get_mut(start..self.engine.front());
+        let group = group.unwrap_or_default();
+        group.sort_unstable();
+        Some((first, group.iter().map(|index| position(*index))))
+    }
+
+    /// Position (in `data`) of the next smallest item, without consuming it.
+    pub fn peek_index(&mut self) -> Option<usize> {
+        self.skip_filtered();
+        let data = self.data;
+        let live = live(self.indices, &self.engine);
+        let slot = self.engine.resolve_front(live, &mut Self::cmp(data))?;
+        self.indices.get(slot).copied().map(position)
+    }
+
+    /// The consumption position: see [`IndexSorter::rewind()`].
+    pub fn checkpoint(&self) -> Checkpoint {
+        self.engine.checkpoint()
+    }
+
+    /// Un-yield the items yielded (from either end) since `checkpoint`, keeping the partitioning
+    /// done since: they come again, at no cost. For probing ahead. See [`Engine::rewind()`].
+    ///
+    /// ```
+    /// use lazysort_no_alloc::index_sort::IndexSorter;
+    ///
+    /// let data = [50, 20, 40, 10, 30];
+    /// let mut indices = [0u8; 5];
+    /// let mut sorter = IndexSorter::new(&data, &mut indices).unwrap();
+    /// let checkpoint = sorter.checkpoint();
+    /// assert!(sorter.by_ref().take(3).eq(&[10, 20, 30]));
+    /// sorter.rewind(checkpoint).unwrap();
+    /// assert!(sorter.eq(&[10, 20, 30, 40, 50]));
+    /// ```
+    pub fn rewind(&mut self, checkpoint: Checkpoint) -> Result<(), Error> {
+        self.engine.rewind(checkpoint)
+    }
+
+    fn cmp(data: &'d [T]) -> impl FnMut(&I, &I) -> Ordering + 'd {
+        cmp_by_data(data)
+    }
+}
+
+impl<'d, 'i, T: Ord, I: Index> Iterator for IndexSorter<'d, 'i, T, I> {
+    type Item = &'d T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let data = self.data;
+        self.next_index().and_then(|position| data.get(position))
+    }
+}
+
+impl<'d, 'i, T: Ord, I: Index> DoubleEndedIterator for IndexSorter<'d, 'i, T, I> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let data = self.data;
+        self.next_back_index()
+            .and_then(|position| data.get(position))
+    }
+}
+
+/// Positions of `data` in ascending order of their items: an argsort, lazily. The items don't
+/// move, so the positions can reorder parallel arrays (or large items) at the caller's pace. Like
+/// [`IndexSorter`], but with the index array allocated here (once, to the length of `data`).
+///
+/// For a narrow index type (to save memory), use [`ArgSort::try_new()`].
+///
+/// ```
+/// use lazysort_no_alloc::index_sort::argsort;
+///
+/// let names = ["delta", "alpha", "charlie", "bravo"];
+/// let ages = [41, 29, 35, 52];
+/// let by_name: Vec<_> = argsort(&names).map(|position| ages[position]).collect();
+/// assert_eq!(by_name, [29, 52, 35, 41]);
+/// ```
+#[cfg(feature = "alloc")]
+pub fn argsort<T: Ord>(data: &[T]) -> ArgSort<'_, T> {
+    ArgSort::try_new(data).unwrap_or_else(|_| unreachable!())
+}
+
+/// Iterator returned by [`argsort()`]: positions in `data`, as [`usize`], stored as `I`.
+#[cfg(feature = "alloc")]
+pub struct ArgSort<'d, T: Ord, I: Index = usize> {
+    data: &'d [T],
+    /// Stored as [`Index`] values, hence shifted by [`Index::min_index_usize()`].
+    indices: Vec<I>,
+    engine: Engine<I>,
+}
+
+#[cfg(feature = "alloc")]
+impl<'d, T: Ord, I: Index> ArgSort<'d, T, I> {
+    /// Return [`Error::CapacityExceeded`] if `I` can't index all of `data`.
+    pub fn try_new(data: &'d [T]) -> Result<Self, Error> {
+        let engine = Engine::try_new(data.len())?;
+        trace_event!(debug, len = data.len(), "allocating argsort indices");
+        let indices = (0..data.len())
+            .map(|position| I::try_from_usize(position + I::min_index_usize()))
+            .collect::<Option<_>>()
+            .ok_or(Error::CapacityExceeded)?;
+        Ok(Self {
+            data,
+            indices,
+            engine,
+        })
+    }
+
+    /// The remaining positions, fully sorted (ascending per their items): the rest of the
+    /// permutation. Reuses the index array (and the partitioning done so far).
+    pub fn into_permutation(mut self) -> Vec<I> {
+        let (front, back) = (self.engine.front(), self.engine.back());
+        let live = &mut self.indices[front..back];
+        self.engine
+            .sort_range(live, front, back, &mut cmp_by_data(self.data));
+        self.indices.truncate(back);
+        self.indices.drain(..front);
+        self.indices
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'d, T: Ord, I: Index> Iterator for ArgSort<'d, T, I> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let live = live(&mut self.indices, &self.engine);
+        let slot = self.engine.next_front(live, &mut cmp_by_data(self.data))?;
+        self.indices.get(slot).copied().map(position)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.engine.len(), Some(self.engine.len()))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'d, T: Ord, I: Index> DoubleEndedIterator for ArgSort<'d, T, I> {
+    fn next_back(&mut self) -> Option<usize> {
+        let live = live(&mut self.indices, &self.engine);
+        let slot = self.engine.next_back(live, &mut cmp_by_data(self.data))?;
+        self.indices.get(slot).copied().map(position)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'d, T: Ord, I: Index> ExactSizeIterator for ArgSort<'d, T, I> {}
+
+#[cfg(feature = "alloc")]
+impl<'d, T: Ord, I: Index> FusedIterator for ArgSort<'d, T, I> {}
+
+/// Reorder `items` in place so that `items[i]` becomes the item at position `permutation[i]`: for
+/// example by [`IndexSorter`]'s `indices` (or, with crate feature `alloc`, by the positions from
+/// `argsort()` or `ArgSort::into_permutation()`), to sort parallel arrays. O(n) swaps, following
+/// the cycles of the permutation. No allocation: it marks the positions done in `permutation`
+/// itself, which ends up as the identity. (Clone it first, to apply it to several arrays.)
+///
+/// Return [`Error::InvalidArgument`] if the lengths differ, or if `permutation` isn't a
+/// permutation of `0..items.len()`. In the latter case, `items` may be left partially reordered
+/// (but all there), and `permutation` partially marked.
+///
+/// ```
+/// use lazysort_no_alloc::index_sort::{apply_permutation, IndexSorter};
+///
+/// let mut ids = [30, 10, 20];
+/// let mut names = ["carol", "alice", "bob"];
+/// let mut permutation = [0u8; 3];
+/// let mut sorter = IndexSorter::new(&ids, &mut permutation).unwrap();
+/// while sorter.next().is_some() {}
+/// apply_permutation(&mut names, &mut permutation.clone()).unwrap();
+/// apply_permutation(&mut ids, &mut permutation).unwrap();
+/// assert_eq!((ids, names), ([10, 20, 30], ["alice", "bob", "carol"]));
+/// ```
+pub fn apply_permutation<T, I: Index>(items: &mut [T], permutation: &mut [I]) -> Result<(), Error> {
+    let len = items.len();
+    if permutation.len() != len || permutation.iter().any(|index| position(*index) >= len) {
+        return Err(Error::InvalidArgument);
+    }
+    // `None` only for a position that `I` can't index: then some index is out of range.
+    let done = |position: usize| {
+        I::try_from_usize(position + I::min_index_usize()).ok_or(Error::InvalidArgument)
+    };
+    for start in 0..len {
+        let mut current = start;
+        // The item of `start` travels along the cycle, till the position that takes it.
+        loop {
+            let next = position(permutation[current]);
+            permutation[current] = done(current)?;
+            if next == start {
+                break;
+            }
+            if position(permutation[next]) == next {
+                // Done already (or just now): `next` is taken twice.
+                return Err(Error::InvalidArgument);
+            }
+            items.swap(current, next);
+            current = next;
+        }
+    }
+    Ok(())
+}
+
+/// Compare indices by the items of `data` at their positions.
+fn cmp_by_data<'d, T: Ord, I: Index>(data: &'d [T]) -> impl FnMut(&I, &I) -> Ordering + 'd {
+    move |a, b| cmp_at(data, position(*a), position(*b), &mut T::cmp)
+}
+
+/// The live indices (not yielded yet) of `engine`.
+fn live<'a, I: Index>(indices: &'a mut [I], engine: &Engine<I>) -> &'a mut [I] {
+    // (Never empty instead: `indices` covers all positions of `engine`.)
+    indices
+        .get_mut(engine.front()..engine.back())
+        .unwrap_or_default()
+}
+
+/// Position stored in an [`Index`] (which is shifted by [`Index::min_index_usize()`]).
+#[inline(always)]
+pub(crate) fn position<I: Index>(index: I) -> usize {
+    index.to_usize() - I::min_index_usize()
+}
@@ -0,0 +1,240 @@
+use crate::error::Error;
+use crate::index_sort::IndexSorter;
+use core::num::NonZeroU8;
+
+static DATA: [&str; 6] = ["delta", "alpha", "foxtrot", "charlie", "bravo", "echo"];
+
+#[test]
+fn yields_references_in_order() {
+    let mut indices = [0u8; 8];
+    let mut sorter = IndexSorter::new(&DATA, &mut indices).unwrap();
+    assert_eq!(sorter.len(), 6);
+    assert_eq!(sorter.next(), Some(&"alpha"));
+    assert_eq!(sorter.next_back(), Some(&"foxtrot"));
+    assert_eq!(sorter.peek_index(), Some(4));
+    assert_eq!(sorter.next_index(), Some(4));
+    assert_eq!(sorter.next(), Some(&"charlie"));
+    assert_eq!(sorter.next(), Some(&"delta"));
+    assert_eq!(sorter.next(), Some(&"echo"));
+    assert_eq!(sorter.next(), None);
+}
+
+#[test]
+fn non_zero_index() {
+    let data: [u32; 100] = core::array::from_fn(|i| (i as u32 * 37) % 100);
+    let mut indices = [NonZeroU8::new(1).unwrap(); 100];
+    let sorter = IndexSorter::new(&data, &mut indices).unwrap();
+    let mut expected = 0;
+    for &item in sorter {
+        assert_eq!(item, expected);
+        expected += 1;
+    }
+    assert_eq!(expected, 100);
+}
+
+#[test]
+fn insufficient_indices() {
+    let mut indices = [0usize; 5];
+    assert_eq!(
+        IndexSorter::new(&DATA, &mut indices).err(),
+        Some(Error::CapacityExceeded)
+    );
+    let data = [0u8; 300];
+    let mut indices = [0u8; 300];
+    assert_eq!(
+        IndexSorter::new(&data, &mut indices).err(),
+        Some(Error::CapacityExceeded)
+    );
+}
+
+#[test]
+fn next_chunk() {
+    let data: [u32; 200] = core::array::from_fn(|i| (i as u32 * 37) % 200);
+    let mut indices = [0u16; 200];
+    let mut sorter = IndexSorter::new(&data, &mut indices).unwrap();
+    assert_eq!(sorter.next(), Some(&0));
+    assert!(sorter.next_chunk(50).copied().eq(1..51));
+    assert_eq!(sorter.next_back(), Some(&199));
+    assert_eq!(sorter.next(), Some(&51));
+    assert_eq!(sorter.next_chunk(0).len(), 0);
+    let rest = sorter.next_chunk(1000);
+    assert_eq!(rest.len(), 147);
+    assert!(rest.copied().eq(52..199));
+    assert!(sorter.is_empty());
+}
+
+#[test]
+fn next_group() {
+    let data = ["b", "a", "c", "a", "b", "a"];
+    let mut indices = [NonZeroU8::new(1).unwrap(); 6];
+    let mut sorter = IndexSorter::new(&data, &mut indices).unwrap();
+    let mut groups = [("", [usize::MAX; 3]); 3];
+    for group in groups.iter_mut() {
+        let (key, positions) = sorter.next_group().unwrap();
+        group.0 = key;
+        positions
+            .enumerate()
+            .for_each(|(i, position)| group.1[i] = position);
+    }
+    assert!(sorter.next_group().is_none());
+    assert_eq!(
+        groups,
+        [
+            ("a", [1, 3, 5]),
+            ("b", [0, 4, usize::MAX]),
+            ("c", [2, usize::MAX, usize::MAX])
+        ]
+    );
+}
+
+#[test]
+fn filter() {
+    let data: [u16; 300] = core::array::from_fn(|i| (i * 7919 % 300) as u16);
+    let mut indices = [0u16; 300];
+    let mut sorter = IndexSorter::new(&data, &mut indices).unwrap();
+    sorter.set_filter(|item| *item >= 250);
+    assert_eq!(
+        sorter.peek_index().map(|position| data[position]),
+        Some(250)
+    );
+    assert!(sorter.next_chunk(3).eq(&[250, 251, 252]));
+    assert_eq!(sorter.next_back(), Some(&299));
+    assert!(sorter.by_ref().copied().eq(253..299));
+    assert!(sorter.is_empty());
+
+    // Nothing kept at all.
+    let mut sorter = IndexSorter::new(&data, &mut indices).unwrap();
+    sorter.set_filter(|_| false);
+    assert_eq!(sorter.next_back(), None);
+}
+
+/// Rewinding brings back the skipped items, too (unsorted), also for a looser filter.
+#[test]
+fn filter_rewind() {
+    let data: [u16; 300] = core::array::from_fn(|i| (i * 7919 % 300) as u16);
+    let mut indices = [0u16; 300];
+    let mut sorter = IndexSorter::new(&data, &mut indices).unwrap();
+    sorter.set_filter(|item| *item >= 250);
+    let checkpoint = sorter.checkpoint();
+    assert!(sorter.by_ref().take(5).copied().eq(250..255));
+    sorter.rewind(checkpoint).unwrap();
+    sorter.set_filter(|item| *item >= 100);
+    assert!(sorter.copied().eq(100..300));
+}
+
+/// Groups hold only the positions of the items kept.
+#[test]
+fn filter_next_group() {
+    let data = [5, 1, 5, 2, 9, 1];
+    let mut indices = [0u8; 6];
+    let mut sorter = IndexSorter::new(&data, &mut indices).unwrap();
+    sorter.set_filter(|item| *item >= 5);
+    let (key, positions) = sorter.next_group().unwrap();
+    assert_eq!(*key, 5);
+    assert!(positions.eq([0, 2]));
+    let (key, positions) = sorter.next_group().unwrap();
+    assert_eq!(*key, 9);
+    assert!(positions.eq([4]));
+    assert!(sorter.next_group().is_none());
+}
+
+#[test]
+fn resume() {
+    let data: [u16; 300] = core::array::from_fn(|i| (i * 7919 % 300) as u16);
+    let mut indices = [0u16; 300];
+    let mut plan = [0; 1024];
+    let mut sorter = IndexSorter::new(&data, &mut indices).unwrap();
+    assert!(sorter.by_ref().take(5).copied().eq(0..5));
+    assert_eq!(sorter.next_back(), Some(&299));
+    let len = sorter.export_plan(&mut plan).unwrap();
+
+    let mut resumed = IndexSorter::resume(&data, &mut indices, &plan[..len]).unwrap();
+    assert_eq!(resumed.len(), 294);
+    assert!(resumed.by_ref().copied().eq(5..299));
+
+    // A plan for other data.
+    assert_eq!(
+        IndexSorter::resume(&data[..200], &mut indices, &plan[..len]).map(|_| ()),
+        Err(Error::InvalidArgument)
+    );
+    // Indices not from a sorter of `data`.
+    let mut sorter = IndexSorter::new(&data[..100], &mut indices).unwrap();
+    sorter.next();
+    let len = sorter.export_plan(&mut plan).unwrap();
+    indices[7] = 100;
+    assert_eq!(
+        IndexSorter::resume(&data[..100], &mut indices, &plan[..len]).map(|_| ()),
+        Err(Error::InvalidArgument)
+    );
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn argsort() {
+    use crate::index_sort::{argsort, ArgSort};
+    use alloc::vec::Vec;
+
+    let mut positions = argsort(&DATA);
+    assert_eq!(positions.len(), 6);
+    assert_eq!(
+        (positions.next(), positions.next_back()),
+        (Some(1), Some(2))
+    );
+    assert!(positions.eq([4, 3, 0, 5]));
+
+    let data: Vec<u32> = (0..300).map(|i| (i * 7919) % 300).collect();
+    let mut narrow = ArgSort::<_, NonZeroU8>::try_new(&data[..200]).unwrap();
+    assert_eq!(narrow.next().map(|position| data[position]), Some(0));
+    let rest = narrow.into_permutation();
+    assert_eq!(rest.len(), 199);
+    let items = rest.iter().map(|index| data[usize::from(index.get()) - 1]);
+    assert!(items.clone().zip(items.skip(1)).all(|(a, b)| a <= b));
+    assert_eq!(
+        ArgSort::<_, u8>::try_new(&data).map(|_| ()),
+        Err(Error::CapacityExceeded)
+    );
+}
+
+#[test]
+fn apply_permutation() {
+    use crate::index_sort::apply_permutation;
+
+    // Sorting positions, from an `IndexSorter` run to completion.
+    let mut indices = [0u8; 6];
+    let mut sorter = IndexSorter::new(&DATA, &mut indices).unwrap();
+    while sorter.next().is_some() {}
+    let mut items = DATA;
+    let mut permutation = indices;
+    apply_permutation(&mut items, &mut permutation).unwrap();
+    assert_eq!(
+        items,
+        ["alpha", "bravo", "charlie", "delta", "echo", "foxtrot"]
+    );
+    assert_eq!(permutation, [0, 1, 2, 3, 4, 5]);
+
+    // Stored shifted, for `NonZeroU8`.
+    let mut items = [10, 11, 12, 13];
+    let mut permutation = [3, 1, 4, 2].map(|index| NonZeroU8::new(index).unwrap());
+    apply_permutation(&mut items, &mut permutation).unwrap();
+    assert_eq!(items, [12, 10, 13, 11]);
+
+    let mut items = [1, 2, 3];
+    assert_eq!(
+        apply_permutation(&mut items, &mut [0usize, 1]),
+        Err(Error::InvalidArgument)
+    );
+    assert_eq!(
+        apply_permutation(&mut items, &mut [0usize, 1, 3]),
+        Err(Error::InvalidArgument)
+    );
+    for mut duplicates in [[1usize, 1, 0], [0, 2, 2], [2, 0, 0]] {
+        assert_eq!(
+            apply_permutation(&mut items, &mut duplicates),
+            Err(Error::InvalidArgument)
+        );
+        let mut sorted = items;
+        sorted.sort_unstable();
+        assert_eq!(sorted, [1, 2, 3]);
+    }
+    apply_permutation(&mut [0u8; 0], &mut [0usize; 0]).unwrap();
+}
@@ -0,0 +1,649 @@
+//! A lazily sorting iterator over owned items, with its stability guarantee in its type. Or by a
+//! caller-supplied comparator: see [`sorted_by()`]. Or by keys computed once per item: see
+//! [`sorted_by_cached_key()`].
+//!
+//! ```
+//! use lazysort_no_alloc::lazy_sort::{LazySort, Stable};
+//!
+//! /// Downstream code can require a stable sort at compile time.
+//! fn first_by_priority(sorted: LazySort<(u8, &'static str), Stable>) -> Option<&'static str> {
+//!     sorted.map(|(_, name)| name).next()
+//! }
+//! let tasks = vec![(2, "b"), (1, "z"), (1, "a")];
+//! assert_eq!(first_by_priority(LazySort::new(tasks)), Some("a"));
+//! ```
+
+use crate::engine::Engine;
+use crate::owned::{collect_items, OwnedLive};
+use crate::sink::Sink;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::mem::{ManuallyDrop, MaybeUninit};
+use core::ptr;
+
+#[cfg(test)]
+mod lazy_sort_tests;
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Stability guarantee of a [`LazySort`]: either [`Stable`] or [`Unstable`]. Sealed, so that no
+/// other implementation can claim a guarantee that it doesn't keep.
+pub trait Stability: sealed::Sealed {
+    /// Whether equal items are yielded in their original order.
+    const STABLE: bool;
+
+    /// What gets partitioned for each item.
+    #[doc(hidden)]
+    type Slot<T>;
+
+    #[doc(hidden)]
+    fn slot<T>(position: usize, item: T) -> Self::Slot<T>;
+
+    #[doc(hidden)]
+    fn into_item<T>(slot: Self::Slot<T>) -> T;
+
+    #[doc(hidden)]
+    fn item<T>(slot: &Self::Slot<T>) -> &T;
+
+    #[doc(hidden)]
+    fn cmp<T: Ord>(a: &Self::Slot<T>, b: &Self::Slot<T>) -> Ordering;
+}
+
+/// Which end of a [`LazySort`] its [`Iterator::next()`] consumes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Direction {
+    /// Smallest first.
+    #[default]
+    Ascending,
+    /// Greatest first. (For [`Stable`], equal items then come in reverse of their original order.)
+    Descending,
+}
+
+/// Equal items come in no particular order. No extra memory.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Unstable;
+
+/// Equal items come in their original order. Each item carries its original position (one extra
+/// [`usize`] per item), which breaks the ties.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stable;
+
+impl sealed::Sealed for Unstable {}
+impl sealed::Sealed for Stable {}
+
+impl Stability for Unstable {
+    const STABLE: bool = false;
+    type Slot<T> = T;
+
+    fn slot<T>(_: usize, item: T) -> T {
+        item
+    }
+
+    fn into_item<T>(slot: T) -> T {
+        slot
+    }
+
+    fn item<T>(slot: &T) -> &T {
+        slot
+    }
+
+    fn cmp<T: Ord>(a: &T, b: &T) -> Ordering {
+        a.cmp(b)
+    }
+}
+
+impl Stability for Stable {
+    const STABLE: bool = true;
+    type Slot<T> = (T, usize);
+
+    fn slot<T>(position: usize, item: T) -> (T, usize) {
+        (item, position)
+    }
+
+    fn into_item<T>(slot: (T, usize)) -> T {
+        slot.0
+    }
+
+    fn item<T>(slot: &(T, usize)) -> &T {
+        &slot.0
+    }
+
+    fn cmp<T: Ord>(a: &(T, usize), b: &(T, usize)) -> Ordering {
+        a.0.cmp(&b.0).then(a.1.cmp(&b.1))
+    }
+}
+
+/// Owned items, yielded (moved out) in ascending order. Sorted lazily, as consumed.
+///
+/// `S` ([`Unstable`] by default, or [`Stable`]) determines the order of equal items.
+pub struct LazySort<T: Ord, S: Stability = Unstable> {
+    items: OwnedLive<S::Slot<T>>,
+    direction: Direction,
+    stability: PhantomData<S>,
+}
+
+impl<T: Ord, S: Stability> LazySort<T, S> {
+    /// O(n) for [`Stable`] (to record the positions), O(1) otherwise: no sorting happens here.
+    pub fn new(items: Vec<T>) -> Self {
+        let engine = Engine::try_new(items.len()).unwrap_or_else(|_| unreachable!());
+        let items = if S::STABLE {
+            trace_event!(debug, len = items.len(), "allocating stable positions");
+            items
+                .into_iter()
+                .enumerate()
+                .map(|(position, item)| S::slot(position, item))
+                .collect()
+        } else {
+            // In-place (no re-allocation), since `S::Slot<T>` is `T`.
+            items.into_iter().map(|item| S::slot(0, item)).collect()
+        };
+        Self {
+            items: OwnedLive::new(items, engine),
+            direction: Direction::Ascending,
+            stability: PhantomData,
+        }
+    }
+
+    /// Number of items not yielded yet.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.len() == 0
+    }
+
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Switch the end that the following [`Iterator::next()`] calls consume. Any time: the
+    /// partitioning done so far (for either end) is kept, and it benefits the other end, too. For
+    /// example, take a few smallest, and then a few greatest outliers.
+    pub fn set_direction(&mut self, direction: Direction) {
+        self.direction = direction;
+    }
+
+    /// The item that [`Iterator::next()`] would yield, without consuming it. (`&mut self`, since it
+    /// may partition - only as far as `next()` would.)
+    ///
+    /// ```
+    /// use lazysort_no_alloc::lazy_sort::LazySort;
+    ///
+    /// let mut sorted: LazySort<u32> = [50, 20, 40, 10, 30].into_iter().collect();
+    /// assert_eq!(sorted.peek(), Some(&10));
+    /// assert_eq!(sorted.next(), Some(10));
+    /// ```
+    pub fn peek(&mut self) -> Option<&T> {
+        let slot = match self.direction {
+            Direction::Ascending => self.items.peek_front(&mut S::cmp),
+            Direction::Descending => self.items.peek_back(&mut S::cmp),
+        };
+        slot.map(S::item)
+    }
+
+    /// Like [`Iterator::next()`], but yield a reference: the item stays in place, until
+    /// [`LazySort::take_last()`] moves it out - or until the next call (of any consuming method)
+    /// drops it. For consumers that filter out (many of) large items: those never get moved.
+    ///
+    /// ```
+    /// use lazysort_no_alloc::lazy_sort::LazySort;
+    ///
+    /// let mut sorted: LazySort<[u8; 256]> = (0..8).map(|i| [i; 256]).rev().collect();
+    /// let mut kept = Vec::new();
+    /// while let Some(item) = sorted.next_ref() {
+    ///     if item[0] % 4 == 0 {
+    ///         kept.extend(sorted.take_last());
+    ///     }
+    /// }
+    /// assert_eq!((kept[0][0], kept[1][0]), (0, 4));
+    /// ```
+    pub fn next_ref(&mut self) -> Option<&T> {
+        let slot = match self.direction {
+            Direction::Ascending => self.items.next_ref_front(&mut S::cmp),
+            Direction::Descending => self.items.next_ref_back(&mut S::cmp),
+        };
+        slot.map(S::item)
+    }
+
+    /// Move out the item last yielded by [`LazySort::next_ref()`]. [`None`] if there's none (or if
+    /// it was taken already, or dropped by another consuming call since).
+    pub fn take_last(&mut self) -> Option<T> {
+        self.items.take_pending().map(S::into_item)
+    }
+
+    /// Move the items out into `sink` (from the end per [`LazySort::direction()`]), until `sink`
+    /// breaks or the items run out. Return the number of items moved. See [`crate::sink`].
+    ///
+    /// ```
+    /// use lazysort_no_alloc::lazy_sort::LazySort;
+    /// use lazysort_no_alloc::sink::SliceSink;
+    ///
+    /// let mut sorted: LazySort<u32> = [50, 20, 40, 10, 30].into_iter().collect();
+    /// let mut buffer = [0; 2];
+    /// let mut sink = SliceSink::new(&mut buffer);
+    /// assert_eq!(sorted.drain_into(&mut sink), 2);
+    /// assert_eq!(sink.filled(), [10, 20]);
+    /// assert_eq!(sorted.len(), 3);
+    /// ```
+    pub fn drain_into<SINK: Sink<T> + ?Sized>(&mut self, sink: &mut SINK) -> usize {
+        let mut rank = 0;
+        for item in self.by_ref() {
+            rank += 1;
+            if sink.accept(rank - 1, item).is_break() {
+                break;
+            }
+        }
+        rank
+    }
+
+    /// [`Iterator::next()`], with the position.
+    fn next_slot(&mut self) -> Option<S::Slot<T>> {
+        match self.direction {
+            Direction::Ascending => self.items.next_front(&mut S::cmp),
+            Direction::Descending => self.items.next_back(&mut S::cmp),
+        }
+    }
+
+    /// [`DoubleEndedIterator::next_back()`], with the position.
+    fn next_back_slot(&mut self) -> Option<S::Slot<T>> {
+        match self.direction {
+            Direction::Ascending => self.items.next_back(&mut S::cmp),
+            Direction::Descending => self.items.next_front(&mut S::cmp),
+        }
+    }
+
+    /// The items not yielded yet, fully sorted: in the order that [`Iterator::next()`] would yield
+    /// them (per [`LazySort::direction()`]). Sorting keeps (and builds on) the partitioning done
+    /// so far. For [`Unstable`] this reuses the buffer the items came in.
+    ///
+    /// ```
+    /// use lazysort_no_alloc::lazy_sort::LazySort;
+    ///
+    /// let mut sorted: LazySort<u32> = [50, 20, 40, 10, 30].into_iter().collect();
+    /// assert_eq!(sorted.next(), Some(10));
+    /// // Needs everything after all.
+    /// assert_eq!(sorted.into_sorted_vec(), [20, 30, 40, 50]);
+    /// ```
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        self.items.sort_live(&mut S::cmp);
+        let descending = self.direction == Direction::Descending;
+        let mut items = self.into_inner();
+        if descending {
+            items.reverse();
+        }
+        items
+    }
+
+    /// The items not yielded yet, in no particular order. For [`Unstable`] this reuses the buffer
+    /// the items came in (hence abandoning the iteration mid-way loses no memory).
+    pub fn into_inner(self) -> Vec<T> {
+        self.items
+            .into_inner()
+            .into_iter()
+            .map(S::into_item)
+            .collect()
+    }
+
+    /// Like [`LazySort::into_inner()`], with the number of items yielded so far (from either end):
+    /// for abandoning the sort without losing the rest of the items.
+    ///
+    /// ```
+    /// use lazysort_no_alloc::lazy_sort::LazySort;
+    ///
+    /// let mut sorted: LazySort<u32> = (0..100).rev().collect();
+    /// assert_eq!((sorted.next(), sorted.next_back()), (Some(0), Some(99)));
+    /// let (yielded, rest) = sorted.into_parts();
+    /// assert_eq!((yielded, rest.len()), (2, 98));
+    /// ```
+    pub fn into_parts(self) -> (usize, Vec<T>) {
+        (self.items.consumed(), self.into_inner())
+    }
+}
+
+impl<T: Ord> LazySort<T, Stable> {
+    /// Yield each item with its position in the original input (as [`Stable`] keeps it anyway):
+    /// for ranks, or for the caller's own tie-breaking. Applies to what's not yielded yet, in the
+    /// same order (and direction).
+    ///
+    /// ```
+    /// use lazysort_no_alloc::lazy_sort::{LazySort, Stable};
+    ///
+    /// let scores = vec![70, 95, 70, 60];
+    /// let ranked = LazySort::<_, Stable>::new(scores).enumerate_original();
+    /// assert!(ranked.eq([(3, 60), (0, 70), (2, 70), (1, 95)]));
+    /// ```
+    pub fn enumerate_original(self) -> EnumerateOriginal<T> {
+        EnumerateOriginal { sorted: self }
+    }
+}
+
+impl<T: Ord, S: Stability> From<Vec<T>> for LazySort<T, S> {
+    fn from(items: Vec<T>) -> Self {
+        Self::new(items)
+    }
+}
+
+/// Collects the items into a [`Vec`] first. If the [`Iterator::size_hint()`] is exact, that's one
+/// allocation of just the right size - or none, for a [`Vec`]'s own `into_iter()` (its buffer gets
+/// re-used). Otherwise the buffer starts at the lower bound of the hint, and grows by doubling (as
+/// a [`Vec`] does): up to twice the memory of the items, which get moved on each growth. Where that
+/// matters, collect into a [`Vec::with_capacity()`] yourself, and use [`LazySort::new()`].
+impl<T: Ord, S: Stability> FromIterator<T> for LazySort<T, S> {
+    fn from_iter<ITER: IntoIterator<Item = T>>(iter: ITER) -> Self {
+        Self::new(collect_items(iter))
+    }
+}
+
+impl<T: Ord, S: Stability> Iterator for LazySort<T, S> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.next_slot().map(S::into_item)
+    }
+
+    /// Drops the `n` items before, without sorting them (nor moving them out one by one).
+    fn nth(&mut self, n: usize) -> Option<T> {
+        match self.direction {
+            Direction::Ascending => self.items.skip_front(n, &mut S::cmp),
+            Direction::Descending => self.items.skip_back(n, &mut S::cmp),
+        }
+        self.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len(), Some(self.len()))
+    }
+}
+
+/// Consumes the end opposite to [`LazySort::direction()`], so that [`Iterator::rev()`] reverses
+/// the order, and so that mixing both ends shares one partitioning. For [`Stable`], the order from
+/// the back is the exact reverse (equal items too).
+///
+/// ```
+/// use lazysort_no_alloc::lazy_sort::LazySort;
+///
+/// let mut sorted: LazySort<u32> = [50, 20, 40, 10, 30].into_iter().collect();
+/// assert_eq!((sorted.next(), sorted.next_back()), (Some(10), Some(50)));
+/// assert!(sorted.rev().eq([40, 30, 20]));
+/// ```
+impl<T: Ord, S: Stability> DoubleEndedIterator for LazySort<T, S> {
+    fn next_back(&mut self) -> Option<T> {
+        self.next_back_slot().map(S::into_item)
+    }
+
+    /// Like [`Iterator::nth()`], from the back.
+    fn nth_back(&mut self, n: usize) -> Option<T> {
+        match self.direction {
+            Direction::Ascending => self.items.skip_back(n, &mut S::cmp),
+            Direction::Descending => self.items.skip_front(n, &mut S::cmp),
+        }
+        self.next_back()
+    }
+}
+
+impl<T: Ord, S: Stability> ExactSizeIterator for LazySort<T, S> {}
+
+impl<T: Ord, S: Stability> FusedIterator for LazySort<T, S> {}
+
+/// Iterator returned by [`LazySort::enumerate_original()`]: `(original position, item)` pairs.
+pub struct EnumerateOriginal<T: Ord> {
+    sorted: LazySort<T, Stable>,
+}
+
+impl<T: Ord> EnumerateOriginal<T> {
+    /// Back to yielding the items only.
+    pub fn into_inner(self) -> LazySort<T, Stable> {
+        self.sorted
+    }
+}
+
+impl<T: Ord> Iterator for EnumerateOriginal<T> {
+    type Item = (usize, T);
+
+    fn next(&mut self) -> Option<(usize, T)> {
+        let (item, position) = self.sorted.next_slot()?;
+        Some((position, item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.sorted.size_hint()
+    }
+}
+
+impl<T: Ord> DoubleEndedIterator for EnumerateOriginal<T> {
+    fn next_back(&mut self) -> Option<(usize, T)> {
+        let (item, position) = self.sorted.next_back_slot()?;
+        Some((position, item))
+    }
+}
+
+impl<T: Ord> ExactSizeIterator for EnumerateOriginal<T> {}
+
+impl<T: Ord> FusedIterator for EnumerateOriginal<T> {}
+
+/// Owned `items`, yielded (moved out) in the order of `cmp`, sorted lazily: like [`LazySort`] (with
+/// [`Unstable`] order), for items that aren't [`Ord`], or for another order, without wrapping the
+/// items in newtypes. Equal items come in no particular order.
+///
+/// ```
+/// use lazysort_no_alloc::lazy_sort::sorted_by;
+///
+/// let readings = vec![2.5, -1.0, 9.75, 0.5];
+/// let mut sorted = sorted_by(readings, |a: &f64, b: &f64| a.total_cmp(b));
+/// assert_eq!(sorted.next(), Some(-1.0));
+/// assert_eq!(sorted.next_back(), Some(9.75));
+/// ```
+pub fn sorted_by<T, CMP>(items: Vec<T>, cmp: CMP) -> LazySortBy<T, CMP>
+where
+    CMP: FnMut(&T, &T) -> Ordering,
+{
+    let engine = Engine::try_new(items.len()).unwrap_or_else(|_| unreachable!());
+    LazySortBy {
+        items: OwnedLive::new(items, engine),
+        cmp,
+    }
+}
+
+/// Like [`sorted_by()`], by a key of each item. Like [`slice::sort_by_key()`], this calls `key` on
+/// every comparison (rather than caching the keys), so it suits cheap keys, like a field.
+///
+/// ```
+/// use lazysort_no_alloc::lazy_sort::sorted_by_key;
+///
+/// struct Job {
+///     name: &'static str,
+///     priority: u8,
+/// }
+/// let jobs = vec![
+///     Job { name: "backup", priority: 3 },
+///     Job { name: "alert", priority: 0 },
+///     Job { name: "report", priority: 2 },
+/// ];
+/// let mut by_priority = sorted_by_key(jobs, |job| job.priority);
+/// assert_eq!(by_priority.next().map(|job| job.name), Some("alert"));
+/// ```
+pub fn sorted_by_key<T, K, KEY>(
+    items: Vec<T>,
+    mut key: KEY,
+) -> LazySortBy<T, impl FnMut(&T, &T) -> Ordering>
+where
+    K: Ord,
+    KEY: FnMut(&T) -> K,
+{
+    sorted_by(items, move |a, b| key(a).cmp(&key(b)))
+}
+
+/// Iterator returned by [`sorted_by()`] and [`sorted_by_key()`].
+/// [`DoubleEndedIterator::next_back()`] yields the greatest items, sharing the partitioning with
+/// [`Iterator::next()`].
+pub struct LazySortBy<T, CMP> {
+    items: OwnedLive<T>,
+    cmp: CMP,
+}
+
+impl<T, CMP> LazySortBy<T, CMP> {
+    /// The items not yielded yet, in no particular order. Reuses the buffer the items came in.
+    pub fn into_inner(self) -> Vec<T> {
+        self.items.into_inner()
+    }
+
+    /// Like [`LazySortBy::into_inner()`], with the count of items yielded so far (from either end).
+    pub fn into_parts(self) -> (usize, Vec<T>) {
+        (self.items.consumed(), self.into_inner())
+    }
+}
+
+impl<T, CMP> LazySortBy<T, CMP>
+where
+    CMP: FnMut(&T, &T) -> Ordering,
+{
+    /// The item that [`Iterator::next()`] would yield, without consuming it.
+    pub fn peek(&mut self) -> Option<&T> {
+        self.items.peek_front(&mut self.cmp)
+    }
+
+    /// The items not yielded yet, fully sorted (ascending per `cmp`). Reuses the buffer the items
+    /// came in.
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        self.items.sort_live(&mut self.cmp);
+        self.items.into_inner()
+    }
+}
+
+impl<T, CMP> Iterator for LazySortBy<T, CMP>
+where
+    CMP: FnMut(&T, &T) -> Ordering,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.items.next_front(&mut self.cmp)
+    }
+
+    /// Drops the `n` items before, without sorting them (nor moving them out one by one).
+    fn nth(&mut self, n: usize) -> Option<T> {
+        self.items.skip_front(n, &mut self.cmp);
+        self.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.items.len(), Some(self.items.len()))
+    }
+}
+
+impl<T, CMP> DoubleEndedIterator for LazySortBy<T, CMP>
+where
+    CMP: FnMut(&T, &T) -> Ordering,
+{
+    fn next_back(&mut self) -> Option<T> {
+        self.items.next_back(&mut self.cmp)
+    }
+
+    fn nth_back(&mut self, n: usize) -> Option<T> {
+        self.items.skip_back(n, &mut self.cmp);
+        self.next_back()
+    }
+}
+
+impl<T, CMP> ExactSizeIterator for LazySortBy<T, CMP> where CMP: FnMut(&T, &T) -> Ordering {}
+
+impl<T, CMP> FusedIterator for LazySortBy<T, CMP> where CMP: FnMut(&T, &T) -> Ordering {}
+
+/// Like [`sorted_by_key()`], but call `key` only once per item, up front: for expensive keys. Like
+/// [`slice::sort_by_cached_key()`], the order is stable.
+///
+/// The keys (with the position of their item) go to a separate buffer, allocated once, to the
+/// number of items. Only the keys get reordered: the items stay where they are until moved out.
+///
+/// ```
+/// use lazysort_no_alloc::lazy_sort::sorted_by_cached_key;
+///
+/// let paths = vec!["/usr/lib", "/", "/usr/local/bin", "/etc"];
+/// let mut by_depth = sorted_by_cached_key(paths, |path| path.matches('/').count());
+/// assert_eq!(by_depth.next(), Some("/"));
+/// assert_eq!(by_depth.next(), Some("/etc"));
+/// assert_eq!(by_depth.next_back(), Some("/usr/local/bin"));
+/// ```
+pub fn sorted_by_cached_key<T, K, KEY>(items: Vec<T>, mut key: KEY) -> LazySortByCachedKey<T, K>
+where
+    K: Ord,
+    KEY: FnMut(&T) -> K,
+{
+    trace_event!(debug, len = items.len(), "allocating cached keys");
+    let mut keys = Vec::with_capacity(items.len());
+    // Within capacity: no re-allocation. (If `key` panics, `items` get dropped as usual.)
+    keys.extend(
+        items
+            .iter()
+            .enumerate()
+            .map(|(position, item)| (key(item), position)),
+    );
+    let engine = Engine::try_new(keys.len()).unwrap_or_else(|_| unreachable!());
+    let mut items = ManuallyDrop::new(items);
+    let (ptr, len, capacity) = (items.as_mut_ptr(), items.len(), items.capacity());
+    // `MaybeUninit<T>` has the same layout as `T`.
+    let items = unsafe { Vec::from_raw_parts(ptr as *mut MaybeUninit<T>, len, capacity) };
+    LazySortByCachedKey {
+        keys: OwnedLive::new(keys, engine),
+        items,
+    }
+}
+
+/// Iterator returned by [`sorted_by_cached_key()`].
+pub struct LazySortByCachedKey<T, K: Ord> {
+    /// Each live key comes with the position of its item (which is initialized), in `items`.
+    /// Comparing positions on equal keys makes the order stable.
+    keys: OwnedLive<(K, usize)>,
+    items: Vec<MaybeUninit<T>>,
+}
+
+impl<T, K: Ord> LazySortByCachedKey<T, K> {
+    /// The items not yielded yet, in no particular order, in a new buffer.
+    pub fn into_inner(self) -> Vec<T> {
+        let this = ManuallyDrop::new(self);
+        // Moved out of `this`, which doesn't get dropped. If collecting panics, the remaining items
+        // leak (but don't get dropped twice).
+        let (keys, items) = unsafe { (ptr::read(&this.keys), ptr::read(&this.items)) };
+        keys.live()
+            .iter()
+            .map(|&(_, position)| unsafe { items[position].assume_init_read() })
+            .collect()
+    }
+}
+
+impl<T, K: Ord> Iterator for LazySortByCachedKey<T, K> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let (_, position) = self.keys.next_front(&mut <(K, usize)>::cmp)?;
+        Some(unsafe { self.items[position].assume_init_read() })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.keys.len(), Some(self.keys.len()))
+    }
+}
+
+impl<T, K: Ord> DoubleEndedIterator for LazySortByCachedKey<T, K> {
+    fn next_back(&mut self) -> Option<T> {
+        let (_, position) = self.keys.next_back(&mut <(K, usize)>::cmp)?;
+        Some(unsafe { self.items[position].assume_init_read() })
+    }
+}
+
+impl<T, K: Ord> ExactSizeIterator for LazySortByCachedKey<T, K> {}
+
+impl<T, K: Ord> FusedIterator for LazySortByCachedKey<T, K> {}
+
+impl<T, K: Ord> Drop for LazySortByCachedKey<T, K> {
+    fn drop(&mut self) {
+        for &(_, position) in self.keys.live() {
+            unsafe { self.items[position].assume_init_drop() };
+        }
+    }
+}
@@ -0,0 +1,477 @@
+use crate::lazy_sort::{Direction, LazySort, Stability, Stable, Unstable};
+use crate::testing::scrambled;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+/// Ordered by `key` only, so that equal items can be told apart by `position`.
+#[derive(Clone, Copy, Debug)]
+struct Item {
+    key: u32,
+    position: usize,
+}
+
+impl PartialEq for Item {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+impl Eq for Item {}
+impl PartialOrd for Item {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Item {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+fn items() -> Vec<Item> {
+    (0..400)
+        .map(|position| Item {
+            key: scrambled(position, 0, 13),
+            position,
+        })
+        .collect()
+}
+
+#[test]
+fn stable_keeps_original_order_of_equal_items() {
+    let sorted: LazySort<Item, Stable> = LazySort::new(items());
+    assert_eq!(sorted.len(), 400);
+    let sorted: Vec<_> = sorted.map(|item| (item.key, item.position)).collect();
+    let mut expected: Vec<_> = items()
+        .into_iter()
+        .map(|item| (item.key, item.position))
+        .collect();
+    expected.sort();
+    assert_eq!(sorted, expected);
+}
+
+#[test]
+fn unstable_yields_in_ascending_order() {
+    let sorted: LazySort<Item> = items().into_iter().collect();
+    let keys: Vec<_> = sorted.map(|item| item.key).collect();
+    assert_eq!(keys.len(), 400);
+    assert!(keys.windows(2).all(|pair| pair[0] <= pair[1]));
+}
+
+#[test]
+fn markers() {
+    assert_eq!((Stable::STABLE, Unstable::STABLE), (true, false));
+    let mut empty = LazySort::<u8, Stable>::new(Vec::new());
+    assert!(empty.is_empty());
+    assert_eq!(empty.next(), None);
+}
+
+#[test]
+fn into_inner_after_partial_iteration() {
+    let items: Vec<u32> = (0..100).map(|i| (i * 37) % 100).collect();
+    let ptr = items.as_ptr();
+    let mut sorted = LazySort::<u32>::new(items);
+    assert!(sorted.by_ref().take(10).eq(0..10));
+    let mut rest = sorted.into_inner();
+    assert_eq!(rest.as_ptr(), ptr);
+    rest.sort_unstable();
+    assert!(rest.into_iter().eq(10..100));
+
+    let mut sorted = LazySort::<u32, Stable>::new((0..10).rev().collect());
+    assert_eq!(sorted.next(), Some(0));
+    assert_eq!(sorted.into_inner().len(), 9);
+}
+
+#[test]
+fn switch_direction() {
+    let mut sorted = LazySort::<u32>::new((0..1000).map(|i| (i * 7919) % 1000).collect());
+    assert!(sorted.by_ref().take(5).eq(0..5));
+    sorted.set_direction(Direction::Descending);
+    assert_eq!(sorted.direction(), Direction::Descending);
+    assert!(sorted.by_ref().take(5).eq((995..1000).rev()));
+    sorted.set_direction(Direction::Ascending);
+    assert!(sorted.by_ref().take(5).eq(5..10));
+    assert_eq!(sorted.len(), 985);
+    let mut rest = sorted.into_inner();
+    rest.sort_unstable();
+    assert!(rest.into_iter().eq(10..995));
+
+    let mut sorted: LazySort<Item, Stable> = LazySort::new(items());
+    sorted.set_direction(Direction::Descending);
+    let greatest: Vec<_> = sorted.take(3).map(|item| item.position).collect();
+    let mut expected: Vec<_> = items().into_iter().filter(|item| item.key == 12).collect();
+    expected.reverse();
+    assert!(greatest
+        .into_iter()
+        .eq(expected.iter().take(3).map(|item| item.position)));
+}
+
+#[test]
+fn double_ended() {
+    let mut sorted = LazySort::<u32>::new((0..1000).map(|i| (i * 7919) % 1000).collect());
+    assert_eq!((sorted.next(), sorted.next_back()), (Some(0), Some(999)));
+    assert!(sorted.by_ref().rev().take(3).eq([998, 997, 996]));
+    // `next_back()` follows the direction: it consumes the other end.
+    sorted.set_direction(Direction::Descending);
+    assert_eq!((sorted.next(), sorted.next_back()), (Some(995), Some(1)));
+    let mut rest: Vec<_> = sorted.collect();
+    rest.reverse();
+    assert!(rest.into_iter().eq(2..995));
+
+    // Stable: the reverse of the ascending order, equal items too.
+    let ascending: Vec<_> = LazySort::<Item, Stable>::new(items())
+        .map(|item| item.position)
+        .collect();
+    let mut descending: Vec<_> = LazySort::<Item, Stable>::new(items())
+        .rev()
+        .map(|item| item.position)
+        .collect();
+    descending.reverse();
+    assert_eq!(ascending, descending);
+}
+
+/// Items yielded by reference and not taken get dropped (once): by the next consuming call, or
+/// with the rest.
+#[test]
+fn next_ref_and_take_last() {
+    use crate::testing::DropCounter;
+
+    let counter = DropCounter::new();
+    let mut sorted: LazySort<_, Stable> = (0..10).rev().map(|i| counter.track(i)).collect();
+    assert_eq!(sorted.take_last(), None);
+    assert_eq!(sorted.next_ref().map(|item| **item), Some(0));
+    assert_eq!(counter.live(), 10);
+    assert_eq!(sorted.next_ref().map(|item| **item), Some(1));
+    assert_eq!(counter.live(), 9);
+    let taken = sorted.take_last();
+    assert_eq!(taken.as_deref(), Some(&1));
+    assert_eq!(sorted.take_last(), None);
+    sorted.set_direction(Direction::Descending);
+    assert_eq!(sorted.next_ref().map(|item| **item), Some(9));
+    assert_eq!(sorted.next().as_deref(), Some(&8));
+    assert_eq!(sorted.take_last(), None);
+    assert_eq!(sorted.next_ref().map(|item| **item), Some(7));
+    let rest = sorted.into_inner();
+    assert_eq!(rest.len(), 5);
+    drop((rest, taken));
+    counter.assert_all_dropped();
+}
+
+#[test]
+fn drain_into_moves_items() {
+    use crate::sink::SliceSink;
+    use crate::testing::{DropCounter, DropTracker};
+    use core::ops::ControlFlow;
+
+    let counter = DropCounter::new();
+    let mut sorted: LazySort<_> = (0..20).rev().map(|i| counter.track(i)).collect();
+    sorted.set_direction(Direction::Descending);
+    let mut ranks = Vec::new();
+    let drained = sorted.drain_into(&mut |rank, item: DropTracker<'_, i32>| {
+        ranks.push((rank, *item));
+        ControlFlow::Continue(())
+    });
+    assert_eq!(drained, 20);
+    assert!(ranks
+        .iter()
+        .copied()
+        .eq((0..20).map(|rank| (rank, 19 - rank as i32))));
+    counter.assert_all_dropped();
+
+    let mut sorted: LazySort<u8, Stable> = [4, 2, 3, 1].into_iter().collect();
+    let mut buffer = [0; 3];
+    let mut sink = SliceSink::new(&mut buffer[..1]);
+    assert_eq!(sorted.drain_into(&mut sink), 1);
+    let mut sink = SliceSink::new(&mut buffer[1..]);
+    assert_eq!(sorted.drain_into(&mut sink), 2);
+    assert_eq!((buffer, sorted.next()), ([1, 2, 3], Some(4)));
+}
+
+#[test]
+fn sorted_by_comparator() {
+    use crate::lazy_sort::{sorted_by, sorted_by_key};
+    use crate::testing::DropCounter;
+    use alloc::vec;
+
+    // Not `Ord`.
+    let readings: Vec<f32> = (0..50).map(|i| ((i * 37) % 50) as f32 - 10.5).collect();
+    let mut sorted = sorted_by(readings, |a: &f32, b: &f32| a.total_cmp(b));
+    assert_eq!(sorted.len(), 50);
+    assert_eq!(
+        (sorted.next(), sorted.next_back()),
+        (Some(-10.5), Some(38.5))
+    );
+    assert!(sorted.by_ref().take(3).eq([-9.5, -8.5, -7.5]));
+    assert_eq!(sorted.into_inner().len(), 45);
+
+    // A custom order: descending by length, then ascending.
+    let words = vec!["kiwi", "fig", "banana", "apple", "pear", "plum"];
+    let sorted = sorted_by(words, |a: &&str, b: &&str| {
+        b.len().cmp(&a.len()).then_with(|| a.cmp(b))
+    });
+    assert!(sorted.eq(["banana", "apple", "kiwi", "pear", "plum", "fig"]));
+
+    let counter = DropCounter::new();
+    let items: Vec<_> = (0..20).map(|i| counter.track((i * 7) % 20)).collect();
+    let mut sorted = sorted_by_key(items, |item| -**item);
+    assert_eq!(sorted.next().map(|item| *item), Some(19));
+    // Dropping mid-way drops the rest.
+    drop(sorted);
+    counter.assert_all_dropped();
+}
+
+/// By a field, and by a derived key (`Reverse` for descending).
+#[test]
+fn sorted_by_key_field() {
+    use crate::lazy_sort::sorted_by_key;
+    use core::cmp::Reverse;
+
+    #[derive(Debug, PartialEq)]
+    struct Row {
+        id: u32,
+        score: i64,
+    }
+    let rows = || -> Vec<Row> {
+        (0..100)
+            .map(|id| Row {
+                id,
+                score: ((id * 7919) % 100) as i64 - 50,
+            })
+            .collect()
+    };
+    let mut by_score = sorted_by_key(rows(), |row| row.score);
+    assert_eq!(by_score.next().map(|row| row.score), Some(-50));
+    assert_eq!(by_score.next_back().map(|row| row.score), Some(49));
+    let scores: Vec<i64> = by_score.map(|row| row.score).collect();
+    assert!(scores.windows(2).all(|pair| pair[0] <= pair[1]));
+
+    let by_id_descending = sorted_by_key(rows(), |row| Reverse(row.id));
+    assert!(by_id_descending.map(|row| row.id).eq((0..100).rev()));
+}
+
+#[test]
+fn sorted_by_cached_key_calls_key_once() {
+    use crate::lazy_sort::sorted_by_cached_key;
+    use crate::testing::DropCounter;
+    use alloc::format;
+    use alloc::string::String;
+
+    let mut calls = 0;
+    let words: Vec<String> = (0..60).map(|i| format!("{}", (i * 37) % 60)).collect();
+    let mut sorted = sorted_by_cached_key(words, |word| {
+        calls += 1;
+        word.len()
+    });
+    assert_eq!(sorted.len(), 60);
+    // Stable: equal keys (lengths) keep the original order.
+    assert!(sorted.by_ref().take(3).eq(["0", "5", "1"]));
+    assert_eq!(sorted.next_back().as_deref(), Some("23"));
+    let rest = sorted.into_inner();
+    assert_eq!(rest.len(), 56);
+    assert_eq!(calls, 60);
+
+    let counter = DropCounter::new();
+    let items: Vec<_> = (0..30).map(|i| counter.track((i * 7) % 30)).collect();
+    let mut sorted = sorted_by_cached_key(items, |item| **item);
+    assert_eq!(sorted.next().map(|item| *item), Some(0));
+    assert_eq!(sorted.next_back().map(|item| *item), Some(29));
+    // Dropping mid-way drops the rest, and so does `into_inner()` (once).
+    drop(sorted);
+    counter.assert_all_dropped();
+    let items: Vec<_> = (0..30).map(|i| counter.track(i)).collect();
+    let mut sorted = sorted_by_cached_key(items, |item| **item);
+    let _ = sorted.next();
+    drop(sorted.into_inner());
+    counter.assert_all_dropped();
+}
+
+#[test]
+fn from_iterators() {
+    // Exact size hint: one allocation, of the exact size.
+    let sorted: LazySort<u32> = (0..100).rev().collect();
+    assert_eq!(sorted.into_inner().capacity(), 100);
+    // A `Vec`'s own: its buffer.
+    let items: Vec<u32> = (0..100).rev().collect();
+    let buffer = items.as_ptr();
+    let mut sorted: LazySort<u32> = items.into_iter().collect();
+    assert_eq!(sorted.next(), Some(0));
+    let rest = sorted.into_inner();
+    assert_eq!(rest.as_ptr(), buffer);
+    // No exact hint: it grows.
+    let sorted: LazySort<u32, Stable> = (0..100).filter(|i| i % 3 == 0).rev().collect();
+    assert!(sorted.eq((0..100).step_by(3)));
+}
+
+#[test]
+fn into_sorted_vec() {
+    use crate::lazy_sort::sorted_by;
+
+    let shuffled: Vec<u32> = (0..1000).map(|i| (i * 7919) % 1000).collect();
+    let buffer = shuffled.as_ptr();
+    let mut sorted = LazySort::<u32>::new(shuffled);
+    assert!(sorted.by_ref().take(10).eq(0..10));
+    assert_eq!(sorted.next_back(), Some(999));
+    let rest = sorted.into_sorted_vec();
+    assert_eq!(rest.as_ptr(), buffer);
+    assert!(rest.into_iter().eq(10..999));
+
+    let mut sorted = LazySort::<u32>::new((0..100).collect());
+    sorted.set_direction(Direction::Descending);
+    assert_eq!(sorted.next(), Some(99));
+    assert!(sorted.into_sorted_vec().into_iter().eq((0..99).rev()));
+
+    let mut sorted: LazySort<Item, Stable> = LazySort::new(items());
+    let _ = sorted.next();
+    let rest = sorted.into_sorted_vec();
+    let mut expected = items();
+    expected.sort();
+    expected.remove(0);
+    assert!(rest
+        .iter()
+        .map(|item| item.position)
+        .eq(expected.iter().map(|item| item.position)));
+
+    let mut sorted = sorted_by((0..50).collect(), |a: &i32, b: &i32| b.cmp(a));
+    assert_eq!(sorted.next(), Some(49));
+    assert!(sorted.into_sorted_vec().into_iter().eq((0..49).rev()));
+}
+
+#[test]
+fn into_parts() {
+    use crate::lazy_sort::sorted_by;
+    use crate::testing::DropCounter;
+
+    let counter = DropCounter::new();
+    let mut sorted: LazySort<_, Stable> = (0..50).map(|i| counter.track((i * 7) % 50)).collect();
+    assert!(sorted.by_ref().take(3).map(|item| *item).eq(0..3));
+    sorted.set_direction(Direction::Descending);
+    // Yielded by reference, and not taken: yielded (and dropped) all the same.
+    assert_eq!(sorted.next_ref().map(|item| **item), Some(49));
+    let (yielded, rest) = sorted.into_parts();
+    assert_eq!((yielded, rest.len()), (4, 46));
+    let mut rest: Vec<_> = rest.into_iter().map(|item| *item).collect();
+    rest.sort_unstable();
+    assert!(rest.into_iter().eq(3..49));
+    counter.assert_all_dropped();
+
+    let sorted = sorted_by((0..10).collect(), |a: &u8, b: &u8| a.cmp(b));
+    assert_eq!(sorted.into_parts(), (0, (0..10).collect()));
+}
+
+#[test]
+fn peek() {
+    use crate::lazy_sort::sorted_by;
+
+    let mut sorted = LazySort::<u32>::new((0..1000).map(|i| (i * 7919) % 1000).collect());
+    assert_eq!(sorted.peek(), Some(&0));
+    assert_eq!(sorted.peek(), Some(&0));
+    assert_eq!(sorted.len(), 1000);
+    assert_eq!(sorted.next(), Some(0));
+    sorted.set_direction(Direction::Descending);
+    assert_eq!(sorted.peek(), Some(&999));
+    // Peeking keeps the item yielded by reference.
+    assert_eq!(sorted.next_ref(), Some(&999));
+    assert_eq!(sorted.peek(), Some(&998));
+    assert_eq!(sorted.take_last(), Some(999));
+    assert_eq!(sorted.next(), Some(998));
+
+    let mut empty = LazySort::<u8>::new(Vec::new());
+    assert_eq!(empty.peek(), None);
+
+    let mut sorted = sorted_by((0..10).collect(), |a: &u8, b: &u8| b.cmp(a));
+    assert_eq!(sorted.peek(), Some(&9));
+    assert_eq!(sorted.len(), 10);
+}
+
+#[test]
+fn nth_skips_in_bulk() {
+    use crate::lazy_sort::sorted_by;
+    use crate::testing::DropCounter;
+
+    let counter = DropCounter::new();
+    let mut sorted: LazySort<_> = (0..1000)
+        .map(|i| counter.track((i * 7919) % 1000))
+        .collect();
+    assert_eq!(sorted.nth(10).map(|item| *item), Some(10));
+    assert_eq!(sorted.next_back().map(|item| *item), Some(999));
+    assert_eq!(sorted.nth_back(9).map(|item| *item), Some(989));
+    sorted.set_direction(Direction::Descending);
+    assert_eq!(sorted.nth(2).map(|item| *item), Some(986));
+    assert_eq!(sorted.nth_back(100).map(|item| *item), Some(111));
+    assert_eq!(sorted.len(), 1000 - 11 - 11 - 3 - 101);
+    assert!(sorted.nth(1000).is_none());
+    assert!(sorted.is_empty());
+    // The skipped ones got dropped (once).
+    counter.assert_all_dropped();
+
+    let mut stable: LazySort<Item, Stable> = LazySort::new(items());
+    let mut expected = items();
+    expected.sort();
+    assert_eq!(
+        stable.nth(200).map(|item| item.position),
+        Some(expected[200].position)
+    );
+
+    let mut sorted = sorted_by((0..100).collect(), |a: &u32, b: &u32| b.cmp(a));
+    assert_eq!((sorted.nth(5), sorted.nth_back(5)), (Some(94), Some(5)));
+    assert_eq!(sorted.len(), 88);
+}
+
+#[test]
+fn exact_size_and_fused() {
+    use crate::lazy_sort::{sorted_by, sorted_by_cached_key};
+    use core::iter::FusedIterator;
+
+    fn assert_fused<ITER: FusedIterator + ExactSizeIterator>(mut iter: ITER, len: usize) {
+        assert_eq!((iter.len(), iter.size_hint()), (len, (len, Some(len))));
+        for remaining in (0..len).rev() {
+            assert!(iter.next().is_some());
+            assert_eq!(iter.len(), remaining);
+        }
+        assert!(iter.next().is_none());
+        assert!(iter.next().is_none());
+        assert_eq!(iter.size_hint(), (0, Some(0)));
+    }
+    assert_fused(LazySort::<u32>::new((0..20).rev().collect()), 20);
+    assert_fused(LazySort::<u32, Stable>::new((0..20).collect()), 20);
+    assert_fused(sorted_by((0..5).collect(), |a: &u8, b: &u8| a.cmp(b)), 5);
+    assert_fused(
+        sorted_by_cached_key((0..5).collect(), |item: &u8| !*item),
+        5,
+    );
+
+    // Exact sizes through adapters.
+    let sorted = LazySort::<u32>::new((0..20).rev().collect());
+    let pairs = sorted.zip(100..120).rev().skip(2);
+    assert_eq!(pairs.len(), 18);
+    let pairs: Vec<_> = pairs.collect();
+    assert_eq!((pairs[0], pairs[17]), ((17, 117), (0, 100)));
+}
+
+#[test]
+fn enumerate_original() {
+    let mut sorted: LazySort<Item, Stable> = LazySort::new(items());
+    let first = sorted.next().unwrap();
+    let mut enumerated = sorted.enumerate_original();
+    assert_eq!(enumerated.len(), 399);
+    let (position, last) = enumerated.next_back().unwrap();
+    assert_eq!(position, last.position);
+    let mut expected = items();
+    expected.sort();
+    assert_eq!(
+        (first.position, last.position),
+        (expected[0].position, expected[399].position)
+    );
+    // The same order, from where it was.
+    let mut sorted = enumerated.into_inner();
+    sorted.set_direction(Direction::Descending);
+    let rest: Vec<_> = sorted
+        .enumerate_original()
+        .map(|(position, item)| {
+            assert_eq!(position, item.position);
+            position
+        })
+        .collect();
+    assert!(rest
+        .into_iter()
+        .eq(expected[1..399].iter().rev().map(|item| item.position)));
+}
@@ -1,9 +1,58 @@
 #![no_std]
-#![cfg_attr(feature = "_internal_use_allocator_api", feature(allocator_api))]
+#![cfg_attr(
+    all(lazysort_nightly, feature = "_internal_use_allocator_api"),
+    feature(allocator_api)
+)]
 #![allow(incomplete_features)]
 #![cfg_attr(not(feature = "nightly_lazy_type_alias"), allow(type_alias_bounds))]
-#![cfg_attr(feature = "nightly_lazy_type_alias", feature(lazy_type_alias))]
-#![cfg_attr(feature = "nightly_strict_provenance", feature(strict_provenance))]
+#![cfg_attr(
+    all(lazysort_nightly, feature = "nightly_lazy_type_alias"),
+    feature(lazy_type_alias)
+)]
+#![cfg_attr(
+    all(lazysort_nightly, feature = "nightly_strict_provenance"),
+    feature(strict_provenance)
+)]
+
+// All the other `nightly_*` features enable one of these.
+#[cfg(all(
+    not(lazysort_nightly),
+    any(
+        feature = "nightly_lazy_type_alias",
+        feature = "nightly_strict_provenance",
+        feature = "_internal_use_allocator_api"
+    )
+))]
+compile_error!(
+    "Crate features `nightly_*` and `_internal_use_allocator_api` require a nightly toolchain \
+     (for example `cargo +nightly build`). On stable, use feature `stable-full` (or `alloc`) \
+     instead."
+);
+
+#[cfg(all(
+    feature = "stable-full",
+    any(
+        feature = "nightly_lazy_type_alias",
+        feature = "nightly_strict_provenance",
+        feature = "_internal_use_allocator_api"
+    )
+))]
+compile_error!(
+    "Crate feature `stable-full` promises a stable build: don't combine it with `nightly_*` or \
+     `_internal_use_allocator_api` features."
+);
+
+#[cfg(all(feature = "stable-full", feature = "no-panic"))]
+compile_error!(
+    "Crate feature `stable-full` promises the complete API, but `no-panic` leaves out the \
+     panicking functions: enable `alloc` instead."
+);
+
+#[cfg(all(feature = "no_alloc_proof", feature = "alloc"))]
+compile_error!(
+    "Crate feature `no_alloc_proof` verifies the alloc-free configuration: don't combine it with \
+     `alloc` (or with features that enable it, like `stable-full`)."
+);
 
 #[cfg(feature = "alloc")]
 extern crate alloc;
@@ -14,21 +63,181 @@ use calloc::{Allocator, Global};
 use core::{mem, ops::Deref};
 //use cross;
 
+/// Emit a [`tracing`](https://docs.rs/tracing) event at `$level` (`trace`, `debug`...) with crate
+/// feature `tracing`. Otherwise nothing (the arguments are not even evaluated).
+macro_rules! trace_event {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        tracing::$level!($($arg)+);
+    };
+}
+
+/// Panic (even in release). With crate feature `tiny-panic` the message is left out (the arguments
+/// are not even evaluated), so that no `core::fmt` machinery gets pulled in for it.
+macro_rules! release_panic {
+    ($($arg:tt)+) => {{
+        #[cfg(not(feature = "tiny-panic"))]
+        panic!($($arg)+);
+        #[cfg(feature = "tiny-panic")]
+        $crate::util::tiny_panic();
+    }};
+}
+
+/// Like [`assert!`], but routed through `release_panic!`.
+macro_rules! release_assert {
+    ($condition:expr, $($arg:tt)+) => {
+        if !$condition {
+            release_panic!($($arg)+);
+        }
+    };
+}
+
 #[cfg(feature = "alloc")]
 pub mod calloc;
 
-mod idx;
-mod store;
+pub mod bucket;
+pub mod budget;
+#[cfg(feature = "alloc")]
+pub mod collections;
+pub mod engine;
+pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod heap;
+pub mod idx;
+pub mod index_sort;
+#[cfg(feature = "alloc")]
+pub mod lazy_sort;
+#[cfg(feature = "alloc")]
+pub mod median;
+pub mod merge;
+pub mod micro;
+pub mod natural;
+#[cfg(feature = "alloc")]
+pub mod nested;
+#[cfg(feature = "no_alloc_proof")]
+#[doc(hidden)]
+pub mod no_alloc_proof;
+#[cfg(feature = "alloc")]
+pub mod pairs;
+#[cfg(feature = "alloc")]
+pub mod ping_pong;
+#[cfg(feature = "alloc")]
+pub mod plan;
+#[cfg(feature = "alloc")]
+pub mod prelude;
+pub mod quantile;
+#[cfg(feature = "alloc")]
+pub mod queue;
+pub mod rows;
+pub mod scratch;
+pub mod segmented;
+pub mod select;
+pub mod set_ops;
+pub mod sink;
+pub mod store;
+pub mod strings;
+pub mod sync;
+#[cfg(feature = "alloc")]
+pub mod tee;
+#[cfg(any(test, feature = "test-support"))]
+pub mod testing;
+#[cfg(feature = "alloc")]
+pub mod top_k;
+pub mod util;
+pub mod windows;
 
 mod re;
 
 #[cfg(feature = "alloc")]
 mod lib_vec;
+#[cfg(feature = "alloc")]
+mod owned;
+
+#[cfg(feature = "alloc")]
+pub use lib_vec::{LazySortIter, LazySortStableIter};
+
+#[cfg(all(test, feature = "no-panic", not(debug_assertions)))]
+mod no_panic_tests;
 
 #[cfg(test)]
 mod test {
+    /// Converting between the storages moves the items, but it never drops them.
+    #[cfg(feature = "alloc")]
     #[test]
-    fn convert_not_invoking_drop() {}
+    fn convert_not_invoking_drop() {
+        use crate::calloc::calloc_vec::{Vec, VecDeque};
+        use crate::store::lifos::lifos_vec::FixedDequeLifos;
+        use crate::store::lifos::Lifos;
+        use crate::testing::DropCounter;
+
+        let counter = DropCounter::new();
+        let vec_deque: VecDeque<_> = VecDeque::from(Vec::with_capacity(4));
+        let mut lifos = FixedDequeLifos::new_from_empty(vec_deque);
+        for value in 0..2 {
+            assert!(lifos.try_push_left(counter.track(value)).is_ok());
+            assert!(lifos.try_push_right(counter.track(value)).is_ok());
+        }
+        let vec = Vec::from(lifos.into_vec_deque());
+        let vec_deque = VecDeque::from(vec);
+        assert_eq!((vec_deque.len(), counter.dropped()), (4, 0));
+        drop(vec_deque);
+        counter.assert_all_dropped();
+    }
+
+    /// Only as much partitioning as the items taken so far need.
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn lazy_sort_iter() {
+        use crate::LazySortIter;
+        use alloc::vec::Vec;
+
+        let items: Vec<u32> = (0..1000).map(|i| (i * 7919 + 13) % 1000).collect();
+        let mut sorted = LazySortIter::new(items);
+        assert!(sorted.by_ref().take(3).eq(0..3));
+        assert_eq!(sorted.len(), 997);
+        let rest = sorted.into_inner();
+        // The rest stays (mostly) unsorted.
+        assert!(rest.windows(2).any(|pair| pair[0] > pair[1]));
+        assert_eq!(rest.len(), 997);
+    }
+
+    /// Equal items (by [`Ord`]) in their original order.
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn lazy_sort_stable_iter() {
+        use crate::LazySortStableIter;
+        use alloc::vec::Vec;
+        use core::cmp::Ordering;
+
+        /// Ordered by `key` only.
+        #[derive(Debug, PartialEq, Eq)]
+        struct Record {
+            key: u32,
+            position: usize,
+        }
+        impl Ord for Record {
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.key.cmp(&other.key)
+            }
+        }
+        impl PartialOrd for Record {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        let records: Vec<_> = (0..1000)
+            .map(|position| Record {
+                key: (position as u32 * 7919) % 13,
+                position,
+            })
+            .collect();
+        let sorted: Vec<_> = LazySortStableIter::new(records).collect();
+        assert!(sorted
+            .windows(2)
+            .all(|pair| { (pair[0].key, pair[0].position) < (pair[1].key, pair[1].position) }));
+    }
 }
 
 /// For ensuring we use the result returned from closures.
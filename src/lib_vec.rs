@@ -5,6 +5,52 @@ use crate::MustUse;
 use alloc::vec::Vec as StdVec;
 use core::{mem, ops::Deref};
 
+/// The entry point for owned items: a [`Vec`] of them, yielded (moved out) in ascending order by
+/// [`Iterator::next()`], sorted lazily - each call partitions only as much as it needs for its
+/// item. In place: beyond the items' own buffer, the auxiliary memory is only the fixed-size
+/// bookkeeping of [`crate::engine::Engine`], so well within linear. It's
+/// [`crate::lazy_sort::LazySort`] with its default (unstable) order: see there for a stable order,
+/// descending order, or yielding by reference.
+///
+/// ```
+/// use lazysort_no_alloc::LazySortIter;
+///
+/// let mut sorted = LazySortIter::new(vec![50, 20, 40, 10, 30]);
+/// assert_eq!(sorted.next(), Some(10));
+/// assert_eq!(sorted.len(), 4);
+/// assert!(sorted.eq([20, 30, 40, 50]));
+/// ```
+pub type LazySortIter<T: Ord> = crate::lazy_sort::LazySort<T>;
+
+/// Like [`LazySortIter`], but equal items come in their original order: for records ordered by one
+/// key. Each item carries its original position (one [`usize`]) to break the ties. It's
+/// [`crate::lazy_sort::LazySort`] with [`crate::lazy_sort::Stable`] order. (For a key given by a
+/// closure rather than by [`Ord`], see [`crate::lazy_sort::sorted_by_cached_key()`], stable too.)
+///
+/// ```
+/// use core::cmp::Ordering;
+/// use lazysort_no_alloc::LazySortStableIter;
+///
+/// /// Ordered by priority only.
+/// #[derive(Debug, PartialEq, Eq)]
+/// struct Task(u8, &'static str);
+/// impl Ord for Task {
+///     fn cmp(&self, other: &Self) -> Ordering {
+///         self.0.cmp(&other.0)
+///     }
+/// }
+/// impl PartialOrd for Task {
+///     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+///         Some(self.cmp(other))
+///     }
+/// }
+///
+/// let tasks = vec![Task(2, "b"), Task(1, "z"), Task(1, "a")];
+/// let names: Vec<_> = LazySortStableIter::new(tasks).map(|task| task.1).collect();
+/// assert_eq!(names, ["z", "a", "b"]);
+/// ```
+pub type LazySortStableIter<T: Ord> = crate::lazy_sort::LazySort<T, crate::lazy_sort::Stable>;
+
 /// Array of two mutable [`Vec`] references.
 ///
 /// Handling [`core::mem::MaybeUninit`] directly could be a little bit more efficient, but too
@@ -0,0 +1,121 @@
+//! Running median of a stream.
+
+use crate::heap::{sift_down, sift_up};
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+#[cfg(test)]
+mod median_tests;
+
+/// Median of the items pushed so far, maintained incrementally.
+///
+/// The items are kept in two regions: the lower half (a max-heap) and the upper half (a min-heap),
+/// both binary heaps of [`crate::heap`]. Each [`RunningMedian::push()`] adds to one of them, and
+/// moves at most one item across. So the median is always at the top of the lower half (or of
+/// both), and the rest of each half stays only partially ordered.
+///
+/// Cost: O(log n) comparisons per push, and O(1) for the median. No memory beyond the items.
+#[derive(Clone, Debug)]
+pub struct RunningMedian<T: Ord> {
+    /// Items not greater than any item of `upper`, as a max-heap. It has as many items as `upper`,
+    /// or one more.
+    lower: Vec<T>,
+    /// The other items, as a min-heap.
+    upper: Vec<T>,
+}
+
+impl<T: Ord> Default for RunningMedian<T> {
+    fn default() -> Self {
+        Self {
+            lower: Vec::new(),
+            upper: Vec::new(),
+        }
+    }
+}
+
+impl<T: Ord> FromIterator<T> for RunningMedian<T> {
+    fn from_iter<ITER: IntoIterator<Item = T>>(iter: ITER) -> Self {
+        let mut median = Self::new();
+        iter.into_iter().for_each(|value| median.push(value));
+        median
+    }
+}
+
+impl<T: Ord> RunningMedian<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lower.len() + self.upper.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lower.is_empty()
+    }
+
+    /// Order of the `lower` max-heap.
+    fn reversed(a: &T, b: &T) -> Ordering {
+        b.cmp(a)
+    }
+
+    pub fn push(&mut self, value: T) {
+        let to_lower = match self.lower.first() {
+            Some(max_lower) => value <= *max_lower,
+            None => true,
+        };
+        if to_lower {
+            push(&mut self.lower, value, &mut Self::reversed);
+            if self.lower.len() > self.upper.len() + 1 {
+                let moved = pop(&mut self.lower, &mut Self::reversed);
+                push(&mut self.upper, moved, &mut T::cmp);
+            }
+        } else {
+            push(&mut self.upper, value, &mut T::cmp);
+            if self.upper.len() > self.lower.len() {
+                let moved = pop(&mut self.upper, &mut T::cmp);
+                push(&mut self.lower, moved, &mut Self::reversed);
+            }
+        }
+    }
+
+    /// The (lower) median: the item of rank `(len() - 1) / 2`. [`None`] if empty.
+    pub fn median(&self) -> Option<&T> {
+        self.lower.first()
+    }
+
+    /// Both middle items: the same one twice for an odd number of items. [`None`] if empty.
+    pub fn medians(&self) -> Option<(&T, &T)> {
+        let lower = self.lower.first()?;
+        if self.lower.len() > self.upper.len() {
+            Some((lower, lower))
+        } else {
+            Some((lower, self.upper.first()?))
+        }
+    }
+
+    /// The lower half and the upper half, each in no particular order.
+    pub fn into_parts(self) -> (Vec<T>, Vec<T>) {
+        (self.lower, self.upper)
+    }
+}
+
+/// Push `value` onto `heap` (ordered by `cmp`).
+fn push<T, CMP>(heap: &mut Vec<T>, value: T, cmp: &mut CMP)
+where
+    CMP: FnMut(&T, &T) -> Ordering,
+{
+    let node = heap.len();
+    heap.push(value);
+    sift_up(heap, node, cmp);
+}
+
+/// Pop the top of (non-empty) `heap` (ordered by `cmp`).
+fn pop<T, CMP>(heap: &mut Vec<T>, cmp: &mut CMP) -> T
+where
+    CMP: FnMut(&T, &T) -> Ordering,
+{
+    let top = heap.swap_remove(0);
+    sift_down(heap, 0, cmp);
+    top
+}
@@ -0,0 +1,69 @@
+use crate::median::RunningMedian;
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::cmp::Ordering;
+
+#[test]
+fn matches_sorted() {
+    let mut median = RunningMedian::new();
+    let mut pushed = Vec::new();
+    assert_eq!(median.median(), None);
+    assert_eq!(median.medians(), None);
+    for i in 0..300u32 {
+        let value = (i * 7919 + 5) % 97;
+        median.push(value);
+        pushed.push(value);
+        pushed.sort_unstable();
+        let len = pushed.len();
+        assert_eq!(median.len(), len);
+        assert_eq!(median.median(), Some(&pushed[(len - 1) / 2]));
+        assert_eq!(
+            median.medians(),
+            Some((&pushed[(len - 1) / 2], &pushed[len / 2]))
+        );
+    }
+}
+
+#[test]
+fn monotone_streams() {
+    let ascending: RunningMedian<u32> = (0..101).collect();
+    assert_eq!(ascending.median(), Some(&50));
+    let descending: RunningMedian<u32> = (0..100).rev().collect();
+    assert_eq!(descending.medians(), Some((&49, &50)));
+}
+
+#[test]
+fn into_parts() {
+    let median: RunningMedian<u32> = (0..11).rev().collect();
+    let (mut lower, mut upper) = median.into_parts();
+    lower.sort_unstable();
+    upper.sort_unstable();
+    assert_eq!(lower, [0, 1, 2, 3, 4, 5]);
+    assert_eq!(upper, [6, 7, 8, 9, 10]);
+}
+
+#[test]
+fn push_cost() {
+    #[derive(PartialEq, Eq)]
+    struct Counted<'a>(u32, &'a Cell<usize>);
+    impl PartialOrd for Counted<'_> {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+    impl Ord for Counted<'_> {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.1.set(self.1.get() + 1);
+            self.0.cmp(&other.0)
+        }
+    }
+
+    let comparisons = Cell::new(0);
+    let mut median = RunningMedian::new();
+    for i in 0..10_000u32 {
+        median.push(Counted((i * 7919) % 10_000, &comparisons));
+    }
+    assert_eq!(median.median().map(|c| c.0), Some(4_999));
+    // O(log n) per push: 3 * log2(10,000) is about 40.
+    assert!(comparisons.get() < 10_000 * 40, "{}", comparisons.get());
+}
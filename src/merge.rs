@@ -0,0 +1,101 @@
+//! Streaming k-way merge of sorted key-value runs: for example runs sorted (lazily, or by an
+//! external sort) one memory load at a time, and read back chunk by chunk. No allocation.
+//!
+//! With a reducer ([`MergeRuns::reduce_equal()`]) the values of equal keys get combined during the
+//! merge, so each key comes out once: a building block for out-of-core group-by/aggregation.
+//!
+//! ```
+//! use lazysort_no_alloc::merge::MergeRuns;
+//!
+//! // (word, count) runs, each sorted by word.
+//! let runs = [
+//!     [("apple", 1), ("kiwi", 2), ("pear", 1)].into_iter(),
+//!     [("apple", 3), ("fig", 1), ("pear", 4)].into_iter(),
+//! ];
+//! let totals = MergeRuns::new(runs).reduce_equal(|a, b| a + b);
+//! assert!(totals.eq([("apple", 4), ("fig", 1), ("kiwi", 2), ("pear", 5)]));
+//! ```
+
+use core::iter::{FusedIterator, Peekable};
+
+#[cfg(test)]
+mod merge_tests;
+
+/// Merges `RUNS` iterators of `(key, value)`, each sorted by key, into one sorted by key. Equal
+/// keys come in the order of their runs (and within a run, in its order): the merge is stable.
+///
+/// It compares the next key of each run for every item yielded: O(`RUNS`) per item, like
+/// [`crate::segmented::SegmentedSorter`]. Only one item per run is buffered.
+pub struct MergeRuns<K: Ord, V, R: Iterator<Item = (K, V)>, const RUNS: usize> {
+    runs: [Peekable<R>; RUNS],
+    reduce: Option<fn(V, V) -> V>,
+}
+
+impl<K: Ord, V, R: Iterator<Item = (K, V)>, const RUNS: usize> MergeRuns<K, V, R, RUNS> {
+    pub fn new(runs: [R; RUNS]) -> Self {
+        Self {
+            runs: runs.map(Iterator::peekable),
+            reduce: None,
+        }
+    }
+
+    /// Combine the values of equal keys (across and within the runs) with `reduce`, in the merge
+    /// order (the accumulated value first). Each key is then yielded once.
+    pub fn reduce_equal(mut self, reduce: fn(V, V) -> V) -> Self {
+        self.reduce = Some(reduce);
+        self
+    }
+
+    /// The run with the smallest next key (the first such run, if tied).
+    fn smallest(&mut self) -> Option<usize> {
+        let mut smallest: Option<(usize, &K)> = None;
+        for (run, iter) in self.runs.iter_mut().enumerate() {
+            let Some((key, _)) = iter.peek() else {
+                continue;
+            };
+            if smallest.map_or(true, |(_, smallest)| key < smallest) {
+                smallest = Some((run, key));
+            }
+        }
+        smallest.map(|(run, _)| run)
+    }
+}
+
+impl<K: Ord, V, R: Iterator<Item = (K, V)>, const RUNS: usize> Iterator
+    for MergeRuns<K, V, R, RUNS>
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        let run = self.smallest()?;
+        let (key, mut value) = self.runs[run].next()?;
+        if let Some(reduce) = self.reduce {
+            while let Some(run) = self.smallest() {
+                match self.runs[run].next_if(|(next, _)| *next == key) {
+                    Some((_, next)) => value = reduce(value, next),
+                    None => break,
+                }
+            }
+        }
+        Some((key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (mut lower, mut upper) = (0usize, Some(0usize));
+        for run in &self.runs {
+            let (run_lower, run_upper) = run.size_hint();
+            lower = lower.saturating_add(run_lower);
+            upper = upper.zip(run_upper).and_then(|(a, b)| a.checked_add(b));
+        }
+        if self.reduce.is_some() {
+            // All keys may be equal.
+            lower = lower.min(1);
+        }
+        (lower, upper)
+    }
+}
+
+impl<K: Ord, V, R: FusedIterator<Item = (K, V)>, const RUNS: usize> FusedIterator
+    for MergeRuns<K, V, R, RUNS>
+{
+}
@@ -0,0 +1,47 @@
+use crate::merge::MergeRuns;
+
+fn runs() -> [core::array::IntoIter<(u32, char), 3>; 3] {
+    [
+        [(1, 'a'), (4, 'a'), (4, 'b')].into_iter(),
+        [(0, 'c'), (4, 'c'), (9, 'c')].into_iter(),
+        [(1, 'd'), (2, 'd'), (9, 'd')].into_iter(),
+    ]
+}
+
+/// Sorted by key, equal keys in run order.
+#[test]
+fn merge_stable() {
+    let merged = MergeRuns::new(runs());
+    assert_eq!(merged.size_hint(), (9, Some(9)));
+    assert!(merged.eq([
+        (0, 'c'),
+        (1, 'a'),
+        (1, 'd'),
+        (2, 'd'),
+        (4, 'a'),
+        (4, 'b'),
+        (4, 'c'),
+        (9, 'c'),
+        (9, 'd'),
+    ]));
+}
+
+/// Each key once, its values reduced in merge order.
+#[test]
+fn reduce_equal() {
+    let runs = runs().map(|run| run.map(|(key, tag)| (key, tag as u32 - 'a' as u32)));
+    // Keeps the order in which the values got reduced (base 10 digits).
+    let merged = MergeRuns::new(runs).reduce_equal(|a, b| a * 10 + b);
+    assert_eq!(merged.size_hint(), (1, Some(9)));
+    assert!(merged.eq([(0, 2), (1, 3), (2, 3), (4, 12), (9, 23)]));
+}
+
+#[test]
+fn empty_runs() {
+    let runs: [core::iter::Empty<(u8, u8)>; 2] = [core::iter::empty(), core::iter::empty()];
+    let mut merged = MergeRuns::new(runs).reduce_equal(|a, b| a.max(b));
+    assert_eq!(merged.size_hint(), (0, Some(0)));
+    assert_eq!(merged.next(), None);
+    let runs: [core::iter::Empty<(u8, u8)>; 0] = [];
+    assert_eq!(MergeRuns::new(runs).next(), None);
+}
@@ -0,0 +1,144 @@
+//! Lazy sorter for small batches (up to [`MICRO_CAPACITY`] items) on micro controllers.
+
+use crate::engine::Engine;
+use core::mem::MaybeUninit;
+use core::{ptr, slice};
+
+#[cfg(test)]
+mod micro_tests;
+
+/// Maximum number of items in a [`MicroSorter`]: all that [`u8`] can index.
+pub const MICRO_CAPACITY: usize = 256;
+
+/// Enough for well balanced partitions of [`MICRO_CAPACITY`] items. (Less balanced ones only cost
+/// some re-partitioning, never correctness.)
+const MICRO_DEPTH: usize = 16;
+
+/// Lazy sorter of up to [`MICRO_CAPACITY`] items, stored in an array (no allocation). Segment
+/// boundaries are [`u8`]-indexed.
+///
+/// Intended for sorting small (sensor...) batches repeatedly: [`MicroSorter::push()`] a batch, then
+/// [`MicroSorter::pop_min()`] (or [`MicroSorter::pop_max()`]) only as many as you need, then
+/// [`MicroSorter::clear()`] (or keep pushing).
+///
+/// Minimal code size: [`Ord`] only (no comparator closures), no formatting, no
+/// [`alloc::collections::VecDeque`]. It doesn't panic on full capacity either - see
+/// [`MicroSorter::push()`].
+pub struct MicroSorter<T: Ord> {
+    /// Live items are at `engine.front()..engine.back()`. All other slots are uninitialized (or
+    /// moved out).
+    items: [MaybeUninit<T>; MICRO_CAPACITY],
+    engine: Engine<u8, MICRO_DEPTH>,
+}
+
+impl<T: Ord> MicroSorter<T> {
+    pub fn new() -> Self {
+        Self {
+            // An array of `MaybeUninit` doesn't require initialization. See
+            // <https://doc.rust-lang.org/nightly/core/mem/union.MaybeUninit.html#initializing-an-array-element-by-element>.
+            items: unsafe { MaybeUninit::uninit().assume_init() },
+            engine: Self::empty_engine(),
+        }
+    }
+
+    fn empty_engine() -> Engine<u8, MICRO_DEPTH> {
+        match Engine::try_new(0) {
+            Ok(engine) => engine,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Number of live items (pushed and not popped yet).
+    pub fn len(&self) -> usize {
+        self.engine.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.engine.is_empty()
+    }
+
+    /// Add an item. Return it back in [`Err`] if there are [`MICRO_CAPACITY`] live items already.
+    ///
+    /// Pushing after some items have been popped is allowed, but it discards the partitioning done
+    /// so far.
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        if self.engine.back() == MICRO_CAPACITY {
+            if self.engine.front() == 0 {
+                return Err(value);
+            }
+            self.compact();
+        }
+        let back = self.engine.back();
+        if self.engine.try_append(1).is_err() {
+            return Err(value);
+        }
+        self.items[back].write(value);
+        Ok(())
+    }
+
+    /// Move the live items to the start of the array.
+    fn compact(&mut self) {
+        let len = self.len();
+        unsafe {
+            let base = self.items.as_mut_ptr();
+            ptr::copy(base.add(self.engine.front()), base, len);
+        }
+        self.engine = Self::empty_engine();
+        // (Never fails: `u8` indexes all of `MICRO_CAPACITY`.)
+        let _ = self.engine.try_append(len);
+    }
+
+    /// The live items. Borrowing only `self.items`, so that `self.engine` stays available.
+    fn live<'a>(
+        items: &'a mut [MaybeUninit<T>; MICRO_CAPACITY],
+        engine: &Engine<u8, MICRO_DEPTH>,
+    ) -> &'a mut [T] {
+        unsafe {
+            slice::from_raw_parts_mut(
+                items.as_mut_ptr().add(engine.front()) as *mut T,
+                engine.len(),
+            )
+        }
+    }
+
+    /// Remove and return the smallest live item.
+    pub fn pop_min(&mut self) -> Option<T> {
+        let live = Self::live(&mut self.items, &self.engine);
+        let position = self.engine.next_front(live, &mut T::cmp)?;
+        Some(unsafe { self.items[position].assume_init_read() })
+    }
+
+    /// Remove and return the greatest live item.
+    pub fn pop_max(&mut self) -> Option<T> {
+        let live = Self::live(&mut self.items, &self.engine);
+        let position = self.engine.next_back(live, &mut T::cmp)?;
+        Some(unsafe { self.items[position].assume_init_read() })
+    }
+
+    /// The smallest live item, without removing it.
+    pub fn peek_min(&mut self) -> Option<&T> {
+        let live = Self::live(&mut self.items, &self.engine);
+        let position = self.engine.resolve_front(live, &mut T::cmp)?;
+        Some(unsafe { self.items[position].assume_init_ref() })
+    }
+
+    /// Drop all live items.
+    pub fn clear(&mut self) {
+        let live = Self::live(&mut self.items, &self.engine) as *mut [T];
+        // Reset first, so that a panicking `Drop` of an item can't cause a double drop.
+        self.engine = Self::empty_engine();
+        unsafe { ptr::drop_in_place(live) };
+    }
+}
+
+impl<T: Ord> Default for MicroSorter<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord> Drop for MicroSorter<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
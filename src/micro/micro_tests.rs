@@ -0,0 +1,46 @@
+use crate::micro::{MicroSorter, MICRO_CAPACITY};
+
+#[test]
+fn pop_min_max() {
+    let mut sorter = MicroSorter::new();
+    for i in 0..MICRO_CAPACITY as u32 {
+        sorter.push((i * 97 + 5) % 256).unwrap();
+    }
+    assert_eq!(sorter.push(0), Err(0));
+    assert_eq!(sorter.len(), MICRO_CAPACITY);
+
+    assert_eq!(sorter.peek_min(), Some(&0));
+    assert_eq!(sorter.pop_min(), Some(0));
+    assert_eq!(sorter.pop_min(), Some(1));
+    assert_eq!(sorter.pop_max(), Some(255));
+    assert_eq!(sorter.pop_min(), Some(2));
+    assert_eq!(sorter.len(), MICRO_CAPACITY - 4);
+}
+
+#[test]
+fn push_after_pop_compacts() {
+    let mut sorter = MicroSorter::new();
+    for i in 0..MICRO_CAPACITY as u32 {
+        sorter.push(i + 10).unwrap();
+    }
+    assert_eq!(sorter.pop_min(), Some(10));
+    sorter.push(3).unwrap();
+    assert_eq!(sorter.push(4), Err(4));
+    assert_eq!(sorter.pop_min(), Some(3));
+    assert_eq!(sorter.pop_min(), Some(11));
+    assert_eq!(sorter.pop_max(), Some(MICRO_CAPACITY as u32 + 9));
+}
+
+#[test]
+fn clear_and_reuse() {
+    let mut sorter = MicroSorter::new();
+    for batch in 0..3u8 {
+        for i in (0..10u8).rev() {
+            sorter.push(i + batch).unwrap();
+        }
+        assert_eq!(sorter.pop_min(), Some(batch));
+        sorter.clear();
+        assert!(sorter.is_empty());
+        assert_eq!(sorter.pop_min(), None);
+    }
+}
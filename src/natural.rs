@@ -0,0 +1,175 @@
+//! Lazy sorting that reuses the sorted runs already in the input (a natural merge, like Timsort's
+//! run detection).
+
+use crate::engine::Engine;
+use core::marker::PhantomData;
+use core::slice;
+
+#[cfg(test)]
+mod natural_tests;
+
+/// Runs shorter than this are not worth merging: [`NaturalSorter`] leaves them to lazy sorting.
+pub const MIN_RUN: usize = 32;
+
+/// Lazily sorts `items` in place, reusing their existing runs. A pre-pass detects (up to `RUNS`)
+/// consecutive runs from the start, each ascending, or strictly descending (then reversed in
+/// place), and at least [`MIN_RUN`] long. Those are consumed directly, never partitioned: only the
+/// merging is lazy. The rest of the items (from the first short run on) gets lazily sorted by an
+/// [`Engine`].
+///
+/// Every [`Iterator::next()`] compares the smallest remaining item of each run (and of the rest):
+/// O(`RUNS`) comparisons per item, like [`crate::segmented::SegmentedSorter`]. Hence for nearly
+/// sorted inputs (appended batches, a reversed log...) the first items come with no partitioning.
+///
+/// ```
+/// use lazysort_no_alloc::natural::NaturalSorter;
+///
+/// // Two sorted batches, then a few stragglers.
+/// let mut items: Vec<u32> = (0..100).map(|i| 2 * i).chain((0..100).map(|i| 2 * i + 1)).collect();
+/// items.extend([7, 3, 5]);
+/// let sorter = NaturalSorter::<_>::new(&mut items);
+/// assert_eq!(sorter.runs(), 2);
+/// assert!(sorter.take(5).eq(&[0, 1, 2, 3, 3]));
+/// ```
+pub struct NaturalSorter<'a, T: Ord, const RUNS: usize = 16> {
+    /// Start of the items. The runs are never moved (after the pre-pass). The rest is accessed
+    /// (mutably) only through `engine`, which never moves the items it has consumed. Hence the
+    /// yielded references stay valid.
+    items: *mut T,
+    /// Next (not yielded) position of each run.
+    heads: [usize; RUNS],
+    /// End (exclusive) of each run.
+    ends: [usize; RUNS],
+    runs: usize,
+    /// The rest of the items starts here. `engine` positions are relative to it.
+    rest: usize,
+    engine: Engine,
+    remaining: usize,
+    lifetime: PhantomData<&'a mut [T]>,
+}
+
+impl<'a, T: Ord, const RUNS: usize> NaturalSorter<'a, T, RUNS> {
+    /// O(n) pre-pass (at most), no sorting.
+    pub fn new(items: &'a mut [T]) -> Self {
+        let (mut heads, mut ends, mut runs, mut start) = ([0; RUNS], [0; RUNS], 0, 0);
+        while runs < RUNS && start < items.len() {
+            let end = run_end(items, start);
+            if end - start < MIN_RUN {
+                break;
+            }
+            (heads[runs], ends[runs]) = (start, end);
+            runs += 1;
+            start = end;
+        }
+        trace_event!(
+            debug,
+            len = items.len(),
+            runs,
+            rest = items.len() - start,
+            "natural runs"
+        );
+        let engine = Engine::try_new(items.len() - start).unwrap_or_else(|_| unreachable!());
+        Self {
+            items: items.as_mut_ptr(),
+            heads,
+            ends,
+            runs,
+            rest: start,
+            remaining: items.len(),
+            engine,
+            lifetime: PhantomData,
+        }
+    }
+
+    /// Number of runs detected (and consumed without sorting).
+    pub fn runs(&self) -> usize {
+        self.runs
+    }
+
+    /// Number of items not yielded yet.
+    pub fn len(&self) -> usize {
+        self.remaining
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.remaining == 0
+    }
+
+    /// The live items of the rest.
+    ///
+    /// # Safety
+    /// No reference to those items may be alive.
+    unsafe fn live(&mut self) -> &'a mut [T] {
+        let start = self.rest + self.engine.front();
+        slice::from_raw_parts_mut(self.items.add(start), self.engine.len())
+    }
+
+    /// Position (in the items, after the pre-pass) of the smallest remaining item, and consume it.
+    pub fn next_index(&mut self) -> Option<usize> {
+        // (Run, or `None` for the rest; the item.)
+        let mut smallest: Option<(Option<usize>, &'a T)> = None;
+        for run in 0..self.runs {
+            if self.heads[run] < self.ends[run] {
+                // Runs are never moved, nor accessed mutably.
+                let candidate = unsafe { &*self.items.add(self.heads[run]) };
+                if smallest.map_or(true, |(_, item)| candidate < item) {
+                    smallest = Some((Some(run), candidate));
+                }
+            }
+        }
+        // The live items are disjoint from the (consumed) items referenced so far.
+        let live = unsafe { self.live() };
+        if let Some(front) = self.engine.resolve_front(live, &mut T::cmp) {
+            let candidate = &live[front - self.engine.front()];
+            if smallest.map_or(true, |(_, item)| candidate < item) {
+                smallest = Some((None, candidate));
+            }
+        }
+        let position = match smallest? {
+            (None, _) => {
+                let live = unsafe { self.live() };
+                self.rest + self.engine.next_front(live, &mut T::cmp)?
+            }
+            (Some(run), _) => {
+                self.heads[run] += 1;
+                self.heads[run] - 1
+            }
+        };
+        self.remaining -= 1;
+        Some(position)
+    }
+}
+
+impl<'a, T: Ord, const RUNS: usize> Iterator for NaturalSorter<'a, T, RUNS> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let position = self.next_index()?;
+        // Consumed: never moved again.
+        Some(unsafe { &*self.items.add(position) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T: Ord, const RUNS: usize> ExactSizeIterator for NaturalSorter<'a, T, RUNS> {}
+
+/// End of the run starting at `start` (which has to be in range): ascending (non-strictly), or
+/// strictly descending - then reversed, so that it's ascending, too. (Strictly, so that reversing
+/// doesn't swap equal items.)
+fn run_end<T: Ord>(items: &mut [T], start: usize) -> usize {
+    let mut end = start + 1;
+    if end < items.len() && items[end] < items[end - 1] {
+        while end < items.len() && items[end] < items[end - 1] {
+            end += 1;
+        }
+        items[start..end].reverse();
+    } else {
+        while end < items.len() && items[end - 1] <= items[end] {
+            end += 1;
+        }
+    }
+    end
+}
@@ -0,0 +1,55 @@
+use crate::natural::{NaturalSorter, MIN_RUN};
+use crate::testing::scrambled;
+
+const LEN: usize = 1000;
+
+/// Ascending, descending and ascending runs, then unsorted items.
+fn items() -> [u32; LEN] {
+    core::array::from_fn(|i| match i {
+        0..=299 => i as u32 * 3,
+        300..=599 => (600 - i) as u32 * 2,
+        600..=799 => i as u32 - 500,
+        _ => scrambled(i, 0, 1000),
+    })
+}
+
+#[test]
+fn runs_and_rest() {
+    let mut expected = items();
+    expected.sort_unstable();
+    let mut items = items();
+    let sorter = NaturalSorter::<u32>::new(&mut items);
+    assert_eq!((sorter.runs(), sorter.len()), (3, LEN));
+    assert!(sorter.copied().eq(expected));
+}
+
+/// With fewer `RUNS`, the rest starts earlier. Short runs are left to lazy sorting.
+#[test]
+fn few_runs_and_short_runs() {
+    let mut expected = items();
+    expected.sort_unstable();
+    let mut items = items();
+    let sorter = NaturalSorter::<u32, 1>::new(&mut items);
+    assert_eq!(sorter.runs(), 1);
+    assert!(sorter.copied().eq(expected));
+
+    let mut short: [u32; MIN_RUN] = core::array::from_fn(|i| i as u32);
+    short[MIN_RUN - 1] = 0;
+    let mut sorter = NaturalSorter::<u32>::new(&mut short);
+    assert_eq!(sorter.runs(), 0);
+    assert_eq!(sorter.nth(1), Some(&0));
+}
+
+/// Positions refer to the items after the pre-pass (descending runs reversed).
+#[test]
+fn positions() {
+    let mut items: [u32; 100] = core::array::from_fn(|i| 100 - i as u32);
+    let mut sorter = NaturalSorter::<u32>::new(&mut items);
+    assert_eq!(sorter.runs(), 1);
+    assert_eq!(sorter.next_index(), Some(0));
+    assert_eq!(sorter.len(), 99);
+    assert_eq!(items[0], 1);
+    let mut empty: [u8; 0] = [];
+    let mut sorter = NaturalSorter::<u8>::new(&mut empty);
+    assert_eq!((sorter.runs(), sorter.next()), (0, None));
+}
@@ -0,0 +1,124 @@
+//! Sorting nested collections (like `Vec<Vec<T>>`, or `Vec<&[T]>`) lazily, by a property of each
+//! inner collection.
+
+use crate::engine::Engine;
+use crate::owned::OwnedLive;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::mem::{self, MaybeUninit};
+use core::ops::Deref;
+
+#[cfg(test)]
+mod nested_tests;
+
+/// The inner `collections` sorted (lazily, as consumed) by `cmp`. Equal ones come in no particular
+/// order.
+///
+/// Only indices get partitioned. Each inner collection is moved once (when it's yielded), and never
+/// cloned.
+pub fn sorted_lazy_nested_by<C, CMP>(collections: Vec<C>, cmp: CMP) -> SortedLazyNested<C, CMP>
+where
+    CMP: FnMut(&C, &C) -> Ordering,
+{
+    let engine = Engine::try_new(collections.len()).unwrap_or_else(|_| unreachable!());
+    let indices = (0..collections.len()).collect();
+    SortedLazyNested {
+        indices: OwnedLive::new(indices, engine),
+        collections: collections.into_iter().map(MaybeUninit::new).collect(),
+        cmp,
+    }
+}
+
+/// Like [`sorted_lazy_nested_by()`], by a key of each inner collection. Like
+/// [`slice::sort_by_key()`], this calls `key` on every comparison (rather than caching the keys).
+pub fn sorted_lazy_nested_by_key<C, K, KEY>(
+    collections: Vec<C>,
+    mut key: KEY,
+) -> SortedLazyNested<C, impl FnMut(&C, &C) -> Ordering>
+where
+    K: Ord,
+    KEY: FnMut(&C) -> K,
+{
+    sorted_lazy_nested_by(collections, move |a, b| key(a).cmp(&key(b)))
+}
+
+/// Like [`sorted_lazy_nested_by()`], shortest first.
+pub fn sorted_lazy_nested_by_len<C, T>(
+    collections: Vec<C>,
+) -> SortedLazyNested<C, impl FnMut(&C, &C) -> Ordering>
+where
+    C: Deref<Target = [T]>,
+{
+    sorted_lazy_nested_by(collections, |a, b| a.len().cmp(&b.len()))
+}
+
+/// Like [`sorted_lazy_nested_by()`], by the first item of each inner collection. Empty ones come
+/// first.
+pub fn sorted_lazy_nested_by_first<C, T>(
+    collections: Vec<C>,
+) -> SortedLazyNested<C, impl FnMut(&C, &C) -> Ordering>
+where
+    C: Deref<Target = [T]>,
+    T: Ord,
+{
+    sorted_lazy_nested_by(collections, |a, b| a.first().cmp(&b.first()))
+}
+
+/// Iterator returned by [`sorted_lazy_nested_by()`] and the like.
+pub struct SortedLazyNested<C, CMP> {
+    indices: OwnedLive<usize>,
+    /// Those whose indices are still among the live `indices` are initialized.
+    collections: Vec<MaybeUninit<C>>,
+    cmp: CMP,
+}
+
+impl<C, CMP> Iterator for SortedLazyNested<C, CMP>
+where
+    CMP: FnMut(&C, &C) -> Ordering,
+{
+    type Item = C;
+
+    fn next(&mut self) -> Option<C> {
+        let (collections, cmp) = (&self.collections, &mut self.cmp);
+        let index = self
+            .indices
+            .next_front(&mut |&a: &usize, &b: &usize| unsafe {
+                cmp(
+                    collections[a].assume_init_ref(),
+                    collections[b].assume_init_ref(),
+                )
+            })?;
+        Some(unsafe { self.collections[index].assume_init_read() })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.indices.len(), Some(self.indices.len()))
+    }
+}
+
+impl<C, CMP> ExactSizeIterator for SortedLazyNested<C, CMP> where CMP: FnMut(&C, &C) -> Ordering {}
+
+impl<C, CMP> SortedLazyNested<C, CMP> {
+    /// The inner collections not yielded yet, in no particular order.
+    pub fn into_inner(mut self) -> Vec<C> {
+        let indices = mem::replace(&mut self.indices, empty());
+        indices
+            .into_inner()
+            .into_iter()
+            .map(|index| unsafe { self.collections[index].assume_init_read() })
+            .collect()
+    }
+}
+
+impl<C, CMP> Drop for SortedLazyNested<C, CMP> {
+    fn drop(&mut self) {
+        for &index in self.indices.live() {
+            unsafe { self.collections[index].assume_init_drop() };
+        }
+    }
+}
+
+fn empty() -> OwnedLive<usize> {
+    let engine = Engine::try_new(0).unwrap_or_else(|_| unreachable!());
+    OwnedLive::new(Vec::new(), engine)
+}
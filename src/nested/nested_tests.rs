@@ -0,0 +1,65 @@
+use crate::nested::{
+    sorted_lazy_nested_by, sorted_lazy_nested_by_first, sorted_lazy_nested_by_key,
+    sorted_lazy_nested_by_len,
+};
+use crate::testing::DropCounter;
+use alloc::vec;
+use alloc::vec::Vec;
+
+fn nested() -> Vec<Vec<u32>> {
+    (0..50u32)
+        .map(|i| {
+            let len = (i * 7) % 50;
+            (0..len).map(|item| (item + i) % 13).collect()
+        })
+        .collect()
+}
+
+#[test]
+fn by_len_moves_inner_vecs() {
+    let collections = nested();
+    let buffers: Vec<*const u32> = collections.iter().map(|inner| inner.as_ptr()).collect();
+    let sorted = sorted_lazy_nested_by_len(collections);
+    assert_eq!(sorted.len(), 50);
+    let mut previous = 0;
+    for inner in sorted {
+        assert!(inner.len() >= previous);
+        previous = inner.len();
+        // Not cloned: the same heap buffer.
+        assert!(inner.is_empty() || buffers.contains(&inner.as_ptr()));
+    }
+}
+
+#[test]
+fn by_first_and_by_key() {
+    let slices = [&[3, 1][..], &[], &[2], &[1, 9, 9]];
+    let sorted: Vec<&[u8]> = sorted_lazy_nested_by_first(slices.to_vec()).collect();
+    assert_eq!(sorted, [&[][..], &[1, 9, 9], &[2], &[3, 1]]);
+
+    let sums = sorted_lazy_nested_by_key(nested(), |inner| inner.iter().sum::<u32>());
+    let sums: Vec<u32> = sums.map(|inner| inner.iter().sum()).collect();
+    assert!(sums.windows(2).all(|pair| pair[0] <= pair[1]));
+
+    let longest_first = sorted_lazy_nested_by(nested(), |a, b| b.len().cmp(&a.len()));
+    assert!(longest_first
+        .map(|inner| inner.len())
+        .eq((1..50).rev().chain([0])));
+}
+
+#[test]
+fn partially_consumed() {
+    let counter = DropCounter::new();
+    let collections: Vec<Vec<_>> = (0..10)
+        .map(|len| vec![counter.track(()); (len * 3) % 10])
+        .collect();
+    let mut sorted = sorted_lazy_nested_by_len(collections);
+    assert_eq!(sorted.next().map(|inner| inner.len()), Some(0));
+    let rest = sorted.into_inner();
+    assert_eq!(rest.len(), 9);
+    assert_eq!(counter.live(), 45);
+
+    let mut sorted = sorted_lazy_nested_by_len(rest);
+    assert_eq!(sorted.next().map(|inner| inner.len()), Some(1));
+    drop(sorted);
+    counter.assert_all_dropped();
+}
@@ -0,0 +1,215 @@
+//! Compile-time proof that the alloc-free API needs no `alloc` crate. With crate feature
+//! `no_alloc_proof` (which excludes `alloc`) this module exercises every public no-alloc API path,
+//! monomorphized for concrete types. Hence any accidental dependency on `alloc` (or on `std`) in
+//! the alloc-free configuration fails the build:
+//!
+//! `cargo build --no-default-features --features no_alloc_proof`
+//!
+//! Nothing here runs: the functions only need to compile (and to link, as they're `pub`). Keep it
+//! in sync when adding a public API that is available without `alloc`.
+
+use crate::bucket::{distribute, NumericKey};
+use crate::budget::{plan_for, Budget, StaticPlan};
+use crate::engine::{partition_at_pivot, Adaptive, Engine, OnFull, Pivot, Schedule};
+use crate::error::Error;
+use crate::heap::{drain_sorted, heapify, heapsort, is_heap, sift_down};
+use crate::idx::Index;
+use crate::index_sort::{apply_permutation, IndexSorter};
+use crate::merge::MergeRuns;
+use crate::micro::MicroSorter;
+use crate::natural::NaturalSorter;
+use crate::quantile::{weighted_quantile, WeightedQuantiles};
+use crate::rows::RowSorter;
+use crate::scratch::{Lease, ScratchProvider, SliceScratch};
+use crate::segmented::SegmentedSorter;
+use crate::select::{median, quantile, select_nth, select_nth_guaranteed};
+use crate::set_ops::{difference_sorted, intersect_sorted, union_sorted};
+use crate::sink::{drain_into, Sink, SliceSink};
+use crate::store::input::SharedItems;
+use crate::store::lifos::double_stack::DoubleStack;
+use crate::store::lifos::Lifos;
+use crate::strings::StrSorter;
+use crate::sync::{Code, Counter, Flag};
+use crate::util::rng::Rng;
+use crate::windows::{sorted_windows, SortedWindows};
+use core::num::NonZeroU16;
+
+pub fn engine(items: &mut [u32]) -> Result<Option<u32>, Error> {
+    let mut engine = Engine::<u16, 16>::try_new(items.len())?;
+    engine.set_pivot(Pivot::Random(Rng::new(1)));
+    engine.set_pivot(Pivot::FastFirst);
+    engine.set_pivot(Pivot::Adaptive(Adaptive::new(1)));
+    engine.set_on_full(OnFull::Heapsort);
+    engine.set_schedule(Schedule::SmallerSideFirst);
+    let mut engine = engine.with_tag(0u8).with_observer(());
+    engine.split_at_tagged(items.len() / 2, 1);
+    let cmp = &mut u32::cmp;
+    let _ = engine.next_back(items, cmp);
+    let _ = engine.next_run_front(items, cmp);
+    let _ = engine.skip_front_while(items, cmp, &mut |item| *item < 10);
+    let _ = (
+        engine.skip_front(items, 1, cmp),
+        engine.skip_back(items, 1, cmp),
+    );
+    let front = engine
+        .next_front(items, cmp)
+        .map(|position| items[position]);
+    let _ = (engine.stats().degraded(), engine.tag_at(engine.front()));
+    let _ = partition_at_pivot(items, Pivot::MedianOfThree, 0, cmp);
+    let mut plan = [0; 256];
+    let len = engine.export_plan(&mut plan)?;
+    let _ = (
+        engine.plan_len(),
+        Engine::<u8, 8>::try_from_plan(&plan[..len])?,
+    );
+    engine.try_append(0)?;
+    Ok(front)
+}
+
+pub fn bucket(items: &mut [u64]) -> Result<usize, Error> {
+    let mut engine = Engine::<usize>::try_new(items.len())?;
+    let _ = 7u128.ordinal();
+    Ok(distribute(items, 8, |item| item, &mut engine))
+}
+
+pub fn budget() -> Option<usize> {
+    const PLAN: StaticPlan = plan_for::<u32, u8, 100>();
+    let _indices = [0u8; PLAN.index_len];
+    Budget::<1024>::assert_fits::<u32, u8, 100>();
+    let _ = Budget::<1024>::fits::<u32, u16, 200>();
+    Budget::<1024>::required_bytes::<u32, NonZeroU16>(100)
+}
+
+pub fn index_based(data: &[u32], indices: &mut [u8]) -> Result<Option<usize>, Error> {
+    let _ = (u8::max_indexable_len(), u8::try_from_usize(1));
+    let mut sorter = IndexSorter::new(data, indices)?;
+    let checkpoint = sorter.checkpoint();
+    sorter.set_filter(|item| *item > 0);
+    let _ = (sorter.peek_index(), sorter.next_back_index(), sorter.next());
+    sorter.rewind(checkpoint)?;
+    let _ = sorter
+        .next_group()
+        .map(|(item, positions)| (item, positions.len()));
+    let _ = sorter.next_chunk(2).count();
+    let mut plan = [0; 256];
+    let len = sorter.export_plan(&mut plan)?;
+    let mut sorter = IndexSorter::resume(data, indices, &plan[..len])?;
+    let next = sorter.next_index();
+    apply_permutation(&mut [0u32; 4][..], indices)?;
+    Ok(next)
+}
+
+pub fn rows(data: &[u32], indices: &mut [u16], out: &mut [u32]) -> Result<usize, Error> {
+    let mut sorter = RowSorter::new(data, 4, 1, indices)?;
+    let _ = (sorter.next_row_index(), sorter.next_back_row_index());
+    sorter.materialize_into(out)
+}
+
+pub fn segmented(items: &mut [u32]) -> Result<Option<usize>, Error> {
+    let mut sorter = SegmentedSorter::<_>::new(items)?;
+    let _ = (sorter.segments(), sorter.next());
+    Ok(sorter.next_index())
+}
+
+pub fn natural(items: &mut [u32]) -> Option<usize> {
+    let mut sorter = NaturalSorter::<_>::new(items);
+    let _ = (sorter.runs(), sorter.next());
+    sorter.next_index()
+}
+
+pub fn micro() -> Option<u32> {
+    let mut sorter = MicroSorter::new();
+    let _ = (sorter.push(3), sorter.push(1), sorter.peek_min().copied());
+    let _ = sorter.pop_max();
+    sorter.pop_min()
+}
+
+pub fn strings(items: &mut [&str]) -> Option<usize> {
+    let mut sorter = StrSorter::new(items);
+    let _ = (
+        sorter.unsorted_parts().0.len(),
+        sorter.sorted_prefix().len(),
+    );
+    sorter.next().map(|item| item.len())
+}
+
+pub fn selection(items: &mut [(u32, f64)]) -> Option<usize> {
+    let _ = select_nth_guaranteed(items, 0, &mut |a, b| a.0.cmp(&b.0));
+    let _ = select_nth(items, 0, &mut |a, b| a.0.cmp(&b.0)).map(|(before, _, _)| before.len());
+    let _ = quantile(items, 0.9, &mut |a, b| a.0.cmp(&b.0)).map(|(_, _, fraction)| fraction);
+    let _ = median(items, &mut |a, b| a.0.cmp(&b.0)).map(|(lower, _)| lower.0);
+    let _ = weighted_quantile(items, 0.5, |item| item.1, &mut |a, b| a.0.cmp(&b.0));
+    let mut quantiles =
+        WeightedQuantiles::<_, _, _, u8, 8>::try_new(items, |item| item.1, |a, b| a.0.cmp(&b.0))
+            .ok()?;
+    quantiles.quantile(0.9)
+}
+
+pub fn set_ops(a: &[u32], b: &[u32]) -> usize {
+    intersect_sorted(a, b).count() + difference_sorted(a, b).count() + union_sorted(a, b).count()
+}
+
+pub fn merge(a: &[(u32, u64)], b: &[(u32, u64)]) -> usize {
+    let merged = MergeRuns::new([a.iter().copied(), b.iter().copied()]);
+    merged.reduce_equal(|x, y| x + y).count()
+}
+
+pub fn sink(items: &mut [u32], out: &mut [u32]) -> usize {
+    let mut sink = SliceSink::new(out);
+    let _ = (sink.accept(0, 1), sink.is_full());
+    let drained = drain_into(items, &mut sink) + sink.filled().len();
+    drained + drain_into(items, &mut |_, _| core::ops::ControlFlow::Break(()))
+}
+
+pub fn scratch(buffer: &mut [u32]) -> Result<usize, Error> {
+    let mut provider = SliceScratch::new(buffer);
+    let region = provider.acquire(2)?;
+    provider.release(region);
+    let lease = Lease::new(&mut provider, 1)?;
+    Ok(lease.into_inner().len())
+}
+
+pub fn heap(items: &mut [u32]) -> Option<u32> {
+    heapsort(items, &mut u32::cmp);
+    heapify(items, &mut u32::cmp);
+    sift_down(items, 0, &mut u32::cmp);
+    let _ = is_heap(items, &mut u32::cmp);
+    drain_sorted(items, u32::cmp).next().copied()
+}
+
+pub fn lifos() -> Option<u32> {
+    let mut lifos = DoubleStack::<u32, 4>::new();
+    let _ = (lifos.try_push_left(1), lifos.try_push_right(2));
+    let _ = (lifos.extend_left_from_slice(&[3]), lifos.extend_right([4]));
+    let _ = lifos.as_slices();
+    lifos.pop_right()
+}
+
+/// # Safety
+/// As for [`SharedItems::from_raw_parts()`].
+pub unsafe fn shared(ptr: *mut u32, len: usize) -> Result<Option<usize>, Error> {
+    let items = SharedItems::from_raw_parts(ptr, len, &())?;
+    let _ = items.len();
+    let mut sorter = NaturalSorter::<_>::new(items.into_slice());
+    let _ = sorter.next();
+    Ok(sorter.next_index())
+}
+
+pub fn windows(items: &mut [u32]) -> Option<u32> {
+    let mut windows = SortedWindows::try_new(items, 2).ok()?;
+    let _ = (windows.width(), windows.len());
+    windows.next().map(|window| window[1] - window[0])
+}
+
+pub fn windows_panicking(items: &mut [u32]) -> usize {
+    sorted_windows(items, 2).count()
+}
+
+pub fn util() -> (bool, u32) {
+    let flag = Flag::new();
+    flag.set();
+    let _ = (Code::new(1).load(), Counter::new().next());
+    let mut rng = Rng::new(7);
+    let _ = (rng.next_u64(), rng.below(10));
+    (flag.is_set(), rng.range_u32(1, 5))
+}
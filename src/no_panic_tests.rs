@@ -0,0 +1,169 @@
+//! Link-time proof that the fallible API doesn't panic. Run with:
+//!
+//! `cargo test --release --features "alloc no-panic" no_panic`
+//!
+//! Like the `no-panic` crate: if the compiler can't prove that the guarded expression never
+//! unwinds, the (unwinding) landing pad drops [`PanicDetector`], which references a non-existing
+//! symbol - and the linking fails. Hence this works with optimizations (release) only, and with one
+//! codegen unit (as set in `Cargo.toml`).
+
+use crate::budget::Budget;
+use crate::engine::{partition_at_pivot, Engine, Pivot};
+use crate::heap::heapsort;
+use crate::idx::Index;
+use crate::index_sort::IndexSorter;
+use crate::quantile::WeightedQuantiles;
+use crate::windows::SortedWindows;
+use core::hint::black_box;
+use core::mem;
+
+struct PanicDetector;
+impl Drop for PanicDetector {
+    fn drop(&mut self) {
+        extern "C" {
+            #[link_name = "\n\nERROR[no-panic]: an expression guarded by assert_no_panic!(...) may panic.\n\n"]
+            fn may_panic() -> !;
+        }
+        unsafe { may_panic() }
+    }
+}
+
+macro_rules! assert_no_panic {
+    ($e:expr) => {{
+        let detector = PanicDetector;
+        let result = $e;
+        mem::forget(detector);
+        result
+    }};
+}
+
+#[test]
+fn budget() {
+    let n = black_box(10usize);
+    let bytes = assert_no_panic!(Budget::<0>::required_bytes::<u32, u8>(n));
+    assert_eq!(bytes, Some(50));
+}
+
+#[test]
+fn index() {
+    let position = black_box(300usize);
+    assert_eq!(assert_no_panic!(u8::try_from_usize(position)), None);
+    assert_eq!(assert_no_panic!(u16::try_from_usize(position)), Some(300));
+}
+
+#[test]
+fn engine_setup() {
+    let len = black_box(5usize);
+    let mut engine = assert_no_panic!(Engine::<u8>::try_new(len)).unwrap();
+    assert_no_panic!(engine.try_append(black_box(3))).unwrap();
+    let checkpoint = engine.checkpoint();
+    let mut items = black_box([5u32, 3, 9, 1, 7, 2, 8, 4]);
+    engine.skip_front(&mut items, 2, &mut u32::cmp);
+    assert_no_panic!(engine.rewind(checkpoint)).unwrap();
+    assert_eq!(engine.front(), 0);
+
+    let mut plan = [0; 64];
+    let plan_len = assert_no_panic!(engine.export_plan(&mut plan)).unwrap();
+    let plan = &plan[..plan_len];
+    let resumed = assert_no_panic!(Engine::<u16>::try_from_plan(plan));
+    assert!(resumed.is_ok());
+}
+
+#[test]
+fn engine_iterate() {
+    let mut items = black_box([5u32, 3, 9, 1, 7, 2, 8]);
+    let mut engine = Engine::<u8>::try_new(items.len()).unwrap();
+    let cmp = &mut u32::cmp;
+
+    assert_eq!(
+        assert_no_panic!(engine.next_front(&mut items, cmp)),
+        Some(0)
+    );
+    assert_eq!(assert_no_panic!(engine.next_back(&mut items, cmp)), Some(6));
+    assert_no_panic!(engine.skip_front(&mut items, 1, cmp));
+    assert_no_panic!(engine.skip_back(&mut items, 1, cmp));
+    let chunk = assert_no_panic!(engine.next_chunk_front(&mut items, 2, cmp));
+    assert_eq!(items[chunk], [3, 5]);
+    assert_no_panic!(engine.skip_front_while(&mut items, cmp, &mut |item| *item < 8));
+    assert_eq!(
+        assert_no_panic!(engine.next_run_front(&mut items, cmp)),
+        None
+    );
+}
+
+#[test]
+fn sort() {
+    let mut items = black_box([5u32, 3, 9, 1, 7, 2, 8]);
+    let mut engine = Engine::<u8>::try_new(items.len()).unwrap();
+    let (start, end) = black_box((2, 5));
+    assert_no_panic!(engine.sort_range(&mut items, start, end, &mut u32::cmp));
+    assert_eq!(items[start..end], [3, 5, 7]);
+
+    let mut items = black_box([5u32, 3, 9, 1, 7, 2, 8]);
+    let pivot = black_box(3);
+    assert_no_panic!(partition_at_pivot(
+        &mut items,
+        Pivot::MedianOfThree,
+        pivot,
+        &mut u32::cmp
+    ));
+    assert_no_panic!(heapsort(&mut items, &mut u32::cmp));
+    assert_eq!(items, [1, 2, 3, 5, 7, 8, 9]);
+}
+
+#[test]
+fn constructors() {
+    let data = black_box([5u32, 3, 9, 1, 7]);
+    let mut indices = [0u8; 5];
+    let sorter = assert_no_panic!(IndexSorter::new(&data, &mut indices));
+    assert!(sorter.is_ok());
+
+    let mut items = black_box([5u32, 3, 9, 1, 7]);
+    let windows = assert_no_panic!(SortedWindows::try_new(&mut items, black_box(0)));
+    assert!(windows.is_err());
+
+    let mut weighted = black_box([(3, 1.0), (1, 1.0)]);
+    let quantiles = assert_no_panic!(WeightedQuantiles::<_, _, _, u8>::try_new(
+        &mut weighted,
+        |item: &(u32, f64)| item.1,
+        |a: &(u32, f64), b: &(u32, f64)| a.0.cmp(&b.0)
+    ));
+    assert!(quantiles.is_ok());
+}
+
+#[test]
+fn lifos() {
+    use crate::calloc::calloc_vec::VecDeque;
+    use crate::store::cross::cross_vec::CrossVecPairGuard;
+    use crate::store::lifos::lifos_vec::FixedDequeLifos;
+    use crate::store::lifos::Lifos;
+
+    let mut lifos = FixedDequeLifos::<u32>::new_from_empty(VecDeque::with_capacity(3));
+    assert_eq!(assert_no_panic!(lifos.try_push_right(black_box(1))), Ok(()));
+    assert_eq!(assert_no_panic!(lifos.try_push_left(black_box(2))), Ok(()));
+    assert_eq!(assert_no_panic!(lifos.try_push_right(black_box(3))), Ok(()));
+    assert_eq!(assert_no_panic!(lifos.try_push_left(black_box(4))), Err(4));
+    assert_eq!(assert_no_panic!(lifos.peek_right()), Some(&3));
+
+    let mut guard = CrossVecPairGuard::try_from_lifos(lifos).unwrap();
+    let pair = assert_no_panic!(guard.try_temp_take()).unwrap();
+    let joined = assert_no_panic!(guard.try_move_back_join_into(pair));
+    assert_eq!(
+        joined.ok().as_deref().map(|vec| &vec[..]),
+        Some(&[3, 1, 2][..])
+    );
+
+    let mut vec_deque = VecDeque::with_capacity(3);
+    let mut lifos = assert_no_panic!(FixedDequeLifos::from_mut(&mut vec_deque)).unwrap();
+    assert_eq!(assert_no_panic!(lifos.try_push_left(black_box(5))), Ok(()));
+    assert_no_panic!(drop(lifos));
+    assert_eq!(vec_deque.len(), 1);
+}
+
+// NOT covered: `IndexSorter`'s iteration. It runs on the (covered) `Engine` methods above, but
+// first it applies any `IndexSorter::set_filter(...)`: an opaque `fn`, which may panic. (That is
+// the only call in there that may unwind.)
+//
+// NOT covered: `CrossVecPairGuard::try_from_lifos(...)`. It makes the items contiguous with
+// `alloc::collections::VecDeque::make_contiguous()`, which the compiler can't prove panic-free.
+// (Taking the pair out, and moving it back, are covered.)
@@ -0,0 +1,227 @@
+//! Owned items, handed out (moved out) lazily in sorted order.
+
+use crate::engine::Engine;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::mem::{self, ManuallyDrop, MaybeUninit};
+use core::ptr;
+
+/// `items` in a [`Vec`], to be sorted: see the [`FromIterator`] implementation of
+/// [`crate::lazy_sort::LazySort`] for the allocations.
+pub(crate) fn collect_items<T, ITER: IntoIterator<Item = T>>(items: ITER) -> Vec<T> {
+    let items = items.into_iter();
+    let (lower, upper) = items.size_hint();
+    if upper != Some(lower) {
+        trace_event!(
+            debug,
+            lower,
+            ?upper,
+            "collecting items without an exact size hint"
+        );
+    }
+    // `Vec`'s own: exact (or in place) for exact hints, from the lower bound otherwise.
+    items.collect()
+}
+
+/// Owns `items` tracked by an [`Engine`], and moves them out from the front, as they get resolved.
+pub(crate) struct OwnedLive<T> {
+    /// Live items are at `engine.front()..engine.back()`. The ones before were moved out or
+    /// dropped.
+    items: Vec<MaybeUninit<T>>,
+    engine: Engine,
+    /// Position of the (consumed) item last yielded by reference, if it's still ours: see
+    /// [`OwnedLive::next_ref_front()`].
+    pending: Option<usize>,
+}
+
+impl<T> OwnedLive<T> {
+    /// `engine` has to track exactly `items` (none consumed yet).
+    pub(crate) fn new(items: Vec<T>, engine: Engine) -> Self {
+        debug_assert!(engine.front() == 0 && engine.back() == items.len());
+        let mut items = ManuallyDrop::new(items);
+        let (ptr, len, capacity) = (items.as_mut_ptr(), items.len(), items.capacity());
+        // `MaybeUninit<T>` has the same layout as `T`.
+        let items = unsafe { Vec::from_raw_parts(ptr as *mut MaybeUninit<T>, len, capacity) };
+        Self {
+            items,
+            engine,
+            pending: None,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.engine.len()
+    }
+
+    /// Number of items consumed (from either end), out of all items.
+    pub(crate) fn consumed(&self) -> usize {
+        self.items.len() - self.engine.len()
+    }
+
+    /// The live items (partitioned as far as consumed).
+    pub(crate) fn live(&self) -> &[T] {
+        let live = &self.items[self.engine.front()..self.engine.back()];
+        unsafe { &*(live as *const [MaybeUninit<T>] as *const [T]) }
+    }
+
+    fn live_ptr(items: &mut [MaybeUninit<T>], engine: &Engine) -> *mut [T] {
+        &mut items[engine.front()..engine.back()] as *mut [MaybeUninit<T>] as *mut [T]
+    }
+
+    /// Consume the smallest live item, but keep it (until the next call, or until
+    /// [`OwnedLive::take_pending()`]). Drop the one kept before (if any).
+    pub(crate) fn next_ref_front<CMP>(&mut self, cmp: &mut CMP) -> Option<&T>
+    where
+        CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+    {
+        self.release_pending();
+        let live = unsafe { &mut *Self::live_ptr(&mut self.items, &self.engine) };
+        let position = self.engine.next_front(live, cmp)?;
+        self.pending = Some(position);
+        Some(unsafe { self.items[position].assume_init_ref() })
+    }
+
+    /// Like [`OwnedLive::next_ref_front()`], but the greatest live item.
+    pub(crate) fn next_ref_back<CMP>(&mut self, cmp: &mut CMP) -> Option<&T>
+    where
+        CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+    {
+        self.release_pending();
+        let live = unsafe { &mut *Self::live_ptr(&mut self.items, &self.engine) };
+        let position = self.engine.next_back(live, cmp)?;
+        self.pending = Some(position);
+        Some(unsafe { self.items[position].assume_init_ref() })
+    }
+
+    /// Partition (only as needed) to find the smallest live item, without consuming it (nor
+    /// dropping the one kept by [`OwnedLive::next_ref_front()`]).
+    pub(crate) fn peek_front<CMP>(&mut self, cmp: &mut CMP) -> Option<&T>
+    where
+        CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+    {
+        let live = unsafe { &mut *Self::live_ptr(&mut self.items, &self.engine) };
+        let position = self.engine.resolve_front(live, cmp)?;
+        Some(unsafe { self.items[position].assume_init_ref() })
+    }
+
+    /// Like [`OwnedLive::peek_front()`], but the greatest live item.
+    pub(crate) fn peek_back<CMP>(&mut self, cmp: &mut CMP) -> Option<&T>
+    where
+        CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+    {
+        let live = unsafe { &mut *Self::live_ptr(&mut self.items, &self.engine) };
+        let position = self.engine.resolve_back(live, cmp)?;
+        Some(unsafe { self.items[position].assume_init_ref() })
+    }
+
+    /// Move out the item kept by [`OwnedLive::next_ref_front()`] (or `next_ref_back()`), if any.
+    pub(crate) fn take_pending(&mut self) -> Option<T> {
+        let position = self.pending.take()?;
+        Some(unsafe { self.items[position].assume_init_read() })
+    }
+
+    fn release_pending(&mut self) {
+        // Taken first, so that a panicking `Drop` of the item can't cause a double drop.
+        if let Some(position) = self.pending.take() {
+            unsafe { self.items[position].assume_init_drop() };
+        }
+    }
+
+    /// Move out the smallest live item.
+    pub(crate) fn next_front<CMP>(&mut self, cmp: &mut CMP) -> Option<T>
+    where
+        CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+    {
+        self.release_pending();
+        let live = unsafe { &mut *Self::live_ptr(&mut self.items, &self.engine) };
+        let position = self.engine.next_front(live, cmp)?;
+        Some(unsafe { self.items[position].assume_init_read() })
+    }
+
+    /// Move out the greatest live item.
+    pub(crate) fn next_back<CMP>(&mut self, cmp: &mut CMP) -> Option<T>
+    where
+        CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+    {
+        self.release_pending();
+        let live = unsafe { &mut *Self::live_ptr(&mut self.items, &self.engine) };
+        let position = self.engine.next_back(live, cmp)?;
+        Some(unsafe { self.items[position].assume_init_read() })
+    }
+
+    /// Drop the (up to) `n` smallest live items, unsorted: see [`Engine::skip_front()`].
+    pub(crate) fn skip_front<CMP>(&mut self, n: usize, cmp: &mut CMP)
+    where
+        CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+    {
+        self.release_pending();
+        let live = unsafe { &mut *Self::live_ptr(&mut self.items, &self.engine) };
+        let skipped = self.engine.skip_front(live, n, cmp);
+        // Consumed first, so that a panicking `Drop` of an item can't cause a double drop.
+        let skipped = &mut self.items[skipped] as *mut [MaybeUninit<T>] as *mut [T];
+        unsafe { ptr::drop_in_place(skipped) };
+    }
+
+    /// Like [`OwnedLive::skip_front()`], but the greatest live items.
+    pub(crate) fn skip_back<CMP>(&mut self, n: usize, cmp: &mut CMP)
+    where
+        CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+    {
+        self.release_pending();
+        let live = unsafe { &mut *Self::live_ptr(&mut self.items, &self.engine) };
+        let skipped = self.engine.skip_back(live, n, cmp);
+        let skipped = &mut self.items[skipped] as *mut [MaybeUninit<T>] as *mut [T];
+        unsafe { ptr::drop_in_place(skipped) };
+    }
+
+    /// Move out the smallest live item, and drop all (live) items equal to it. Return it with the
+    /// number of such items (including itself).
+    pub(crate) fn next_run_front<CMP>(&mut self, cmp: &mut CMP) -> Option<(T, usize)>
+    where
+        CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+    {
+        self.release_pending();
+        let live = unsafe { &mut *Self::live_ptr(&mut self.items, &self.engine) };
+        let run = self.engine.next_run_front(live, cmp)?;
+        let count = run.len();
+        let first = unsafe { self.items[run.start].assume_init_read() };
+        for duplicate in &mut self.items[run.start + 1..run.end] {
+            unsafe { duplicate.assume_init_drop() };
+        }
+        Some((first, count))
+    }
+
+    /// Finish sorting the live items (in place, without consuming them).
+    pub(crate) fn sort_live<CMP>(&mut self, cmp: &mut CMP)
+    where
+        CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+    {
+        let live = unsafe { &mut *Self::live_ptr(&mut self.items, &self.engine) };
+        let (front, back) = (self.engine.front(), self.engine.back());
+        self.engine.sort_range(live, front, back, cmp);
+    }
+
+    /// The live items (partitioned as far as consumed), moved to the start of the same buffer.
+    pub(crate) fn into_inner(mut self) -> Vec<T> {
+        self.release_pending();
+        let mut this = ManuallyDrop::new(self);
+        let (front, len) = (this.engine.front(), this.engine.len());
+        // `Engine` owns no memory, so leaving it undropped (in `this`) is fine.
+        let mut items = ManuallyDrop::new(mem::take(&mut this.items));
+        let (ptr, capacity) = (items.as_mut_ptr() as *mut T, items.capacity());
+        unsafe {
+            ptr::copy(ptr.add(front), ptr, len);
+            Vec::from_raw_parts(ptr, len, capacity)
+        }
+    }
+}
+
+impl<T> Drop for OwnedLive<T> {
+    fn drop(&mut self) {
+        self.release_pending();
+        let live = Self::live_ptr(&mut self.items, &self.engine);
+        // Consume all first, so that a panicking `Drop` of an item can't cause a double drop.
+        self.engine = Engine::try_new(0).unwrap_or_else(|_| unreachable!());
+        unsafe { ptr::drop_in_place(live) };
+    }
+}
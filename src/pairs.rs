@@ -0,0 +1,239 @@
+//! Sorting (key, value) pairs lazily, adapting to the size of the values (payloads).
+
+use crate::budget::Budget;
+use crate::engine::Engine;
+use crate::idx::Index;
+use crate::owned::OwnedLive;
+use alloc::vec::Vec;
+use core::mem::{self, MaybeUninit};
+
+#[cfg(test)]
+mod pairs_tests;
+
+/// Values (payloads) up to this size (in bytes) get moved along with their keys while
+/// partitioning. Bigger ones stay in place (until yielded): only keys with indices get partitioned.
+pub const MAX_MOVED_PAYLOAD: usize = 4 * mem::size_of::<usize>();
+
+/// The pairs sorted (lazily, as consumed) by their keys. Equal keys come in no particular order.
+///
+/// Partitioning moves items around several times. That's cheap for small values, but not for
+/// jumbo ones. So, if `size_of::<V>()` exceeds [`MAX_MOVED_PAYLOAD`], this sorts a side array of
+/// keys and indices instead, and it moves each value only once: when it's yielded.
+pub fn sorted_lazy_pairs<K: Ord, V>(pairs: Vec<(K, V)>) -> SortedLazyPairs<K, V> {
+    sorted_lazy_pairs_with_layout(pairs, Layout::Auto)
+}
+
+/// Like [`sorted_lazy_pairs()`], but in the given [`Layout`].
+pub fn sorted_lazy_pairs_with_layout<K: Ord, V>(
+    pairs: Vec<(K, V)>,
+    layout: Layout,
+) -> SortedLazyPairs<K, V> {
+    let engine = Engine::try_new(pairs.len()).unwrap_or_else(|_| unreachable!());
+    with_engine(pairs, layout, engine)
+}
+
+/// How [`SortedLazyPairs`] lays out the pairs for partitioning.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Layout {
+    /// As per the size of the values: see [`sorted_lazy_pairs()`].
+    #[default]
+    Auto,
+    /// Structure of arrays: the keys densely packed in one array, the values in another, and
+    /// neither moved until yielded. Only [`u32`] indices get partitioned (comparing the keys they
+    /// point to). So partitioning moves 4 bytes per item, and its comparisons read a compact key
+    /// array rather than whole pairs (or key and index pairs): for large keys, or large structs
+    /// with small keys. (More than [`u32::MAX`] pairs get [`Layout::Auto`].)
+    Blocked,
+}
+
+/// Bytes that [`with_engine()`] holds at its peak: the `pairs` buffer (of `capacity`, with an `I`
+/// per pair, as per [`Budget::required_bytes()`]), plus any side arrays for `len` pairs (as per
+/// `layout` and the size of the values). [`None`] on overflow.
+pub(crate) const fn peak_bytes<K, V, I: Index>(
+    capacity: usize,
+    len: usize,
+    layout: Layout,
+) -> Option<usize> {
+    let side = if matches!(layout, Layout::Blocked) && len as u64 <= u32::MAX as u64 {
+        mem::size_of::<K>() + mem::size_of::<V>() + mem::size_of::<u32>()
+    } else if mem::size_of::<V>() <= MAX_MOVED_PAYLOAD {
+        0
+    } else {
+        mem::size_of::<(K, usize)>() + mem::size_of::<V>()
+    };
+    let Some(pairs) = Budget::<0>::required_bytes::<(K, V), I>(capacity) else {
+        return None;
+    };
+    let Some(side) = len.checked_mul(side) else {
+        return None;
+    };
+    pairs.checked_add(side)
+}
+
+/// [`sorted_lazy_pairs_with_layout()`] with a pre-configured `engine` (tracking all `pairs`).
+pub(crate) fn with_engine<K: Ord, V>(
+    pairs: Vec<(K, V)>,
+    layout: Layout,
+    engine: Engine,
+) -> SortedLazyPairs<K, V> {
+    if layout == Layout::Blocked && u32::try_from(pairs.len()).is_ok() {
+        trace_event!(debug, len = pairs.len(), "allocating blocked arrays");
+        let (mut keys, mut values) = (
+            Vec::with_capacity(pairs.len()),
+            Vec::with_capacity(pairs.len()),
+        );
+        for (key, value) in pairs {
+            keys.push(MaybeUninit::new(key));
+            values.push(MaybeUninit::new(value));
+        }
+        let order = (0..keys.len() as u32).collect();
+        return SortedLazyPairs(Strategy::Blocked {
+            order: OwnedLive::new(order, engine),
+            keys,
+            values,
+        });
+    }
+    if mem::size_of::<V>() <= MAX_MOVED_PAYLOAD {
+        return SortedLazyPairs(Strategy::Moved(OwnedLive::new(pairs, engine)));
+    }
+    trace_event!(
+        debug,
+        len = pairs.len(),
+        "allocating side arrays for jumbo payloads"
+    );
+    let (mut keys, mut values) = (
+        Vec::with_capacity(pairs.len()),
+        Vec::with_capacity(pairs.len()),
+    );
+    for (index, (key, value)) in pairs.into_iter().enumerate() {
+        keys.push((key, index));
+        values.push(MaybeUninit::new(value));
+    }
+    SortedLazyPairs(Strategy::Indexed {
+        keys: OwnedLive::new(keys, engine),
+        values,
+    })
+}
+
+/// Iterator returned by [`sorted_lazy_pairs()`].
+pub struct SortedLazyPairs<K: Ord, V>(Strategy<K, V>);
+
+enum Strategy<K, V> {
+    Moved(OwnedLive<(K, V)>),
+    Indexed {
+        keys: OwnedLive<(K, usize)>,
+        /// Those whose indices are still among the live `keys` are initialized.
+        values: Vec<MaybeUninit<V>>,
+    },
+    Blocked {
+        /// Indices of the pairs.
+        order: OwnedLive<u32>,
+        /// Those whose indices are still among the live `order` are initialized (as are their
+        /// values).
+        keys: Vec<MaybeUninit<K>>,
+        values: Vec<MaybeUninit<V>>,
+    },
+}
+
+impl<K: Ord, V> Iterator for SortedLazyPairs<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        match &mut self.0 {
+            Strategy::Moved(pairs) => pairs.next_front(&mut |a, b| a.0.cmp(&b.0)),
+            Strategy::Indexed { keys, values } => {
+                let (key, index) = keys.next_front(&mut |a, b| a.0.cmp(&b.0))?;
+                Some((key, unsafe { values[index].assume_init_read() }))
+            }
+            Strategy::Blocked {
+                order,
+                keys,
+                values,
+            } => {
+                let index = order.next_front(&mut |&a: &u32, &b: &u32| unsafe {
+                    let (a, b) = (&keys[a as usize], &keys[b as usize]);
+                    a.assume_init_ref().cmp(b.assume_init_ref())
+                })? as usize;
+                Some(unsafe {
+                    (
+                        keys[index].assume_init_read(),
+                        values[index].assume_init_read(),
+                    )
+                })
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = match &self.0 {
+            Strategy::Moved(pairs) => pairs.len(),
+            Strategy::Indexed { keys, .. } => keys.len(),
+            Strategy::Blocked { order, .. } => order.len(),
+        };
+        (len, Some(len))
+    }
+}
+
+impl<K: Ord, V> ExactSizeIterator for SortedLazyPairs<K, V> {}
+
+impl<K: Ord, V> SortedLazyPairs<K, V> {
+    /// The pairs not yielded yet, in no particular order. For small values (see
+    /// [`MAX_MOVED_PAYLOAD`]) this reuses the buffer the pairs came in. For jumbo values (or
+    /// [`Layout::Blocked`]) it re-assembles the pairs (and it frees the side arrays).
+    pub fn into_inner(mut self) -> Vec<(K, V)> {
+        let empty = Engine::try_new(0).unwrap_or_else(|_| unreachable!());
+        let placeholder = Strategy::Moved(OwnedLive::new(Vec::new(), empty));
+        match mem::replace(&mut self.0, placeholder) {
+            Strategy::Moved(pairs) => pairs.into_inner(),
+            Strategy::Indexed { keys, values } => keys
+                .into_inner()
+                .into_iter()
+                .map(|(key, index)| (key, unsafe { values[index].assume_init_read() }))
+                .collect(),
+            Strategy::Blocked {
+                order,
+                keys,
+                values,
+            } => order
+                .into_inner()
+                .into_iter()
+                .map(|index| {
+                    let index = index as usize;
+                    unsafe {
+                        (
+                            keys[index].assume_init_read(),
+                            values[index].assume_init_read(),
+                        )
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<K: Ord, V> Drop for SortedLazyPairs<K, V> {
+    fn drop(&mut self) {
+        match &mut self.0 {
+            Strategy::Moved(_) => {}
+            Strategy::Indexed { keys, values } => {
+                // The live keys get dropped by `OwnedLive`, but their values are ours to drop.
+                for &(_, index) in keys.live() {
+                    unsafe { values[index].assume_init_drop() };
+                }
+            }
+            Strategy::Blocked {
+                order,
+                keys,
+                values,
+            } => {
+                for &index in order.live() {
+                    let index = index as usize;
+                    unsafe {
+                        keys[index].assume_init_drop();
+                        values[index].assume_init_drop();
+                    }
+                }
+            }
+        }
+    }
+}
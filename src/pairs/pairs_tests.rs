@@ -0,0 +1,92 @@
+use crate::pairs::{sorted_lazy_pairs, Strategy};
+use crate::testing::DropCounter;
+use alloc::vec::Vec;
+
+fn keys() -> impl Iterator<Item = u32> {
+    (0..300u32).map(|i| (i * 7919) % 300)
+}
+
+#[test]
+fn small_payloads_are_moved() {
+    let pairs: Vec<(u32, u8)> = keys().map(|key| (key, key as u8)).collect();
+    let sorted = sorted_lazy_pairs(pairs);
+    assert!(matches!(sorted.0, Strategy::Moved(_)));
+    assert_eq!(sorted.len(), 300);
+    assert!(sorted.eq((0..300u32).map(|key| (key, key as u8))));
+}
+
+#[test]
+fn jumbo_payloads_stay_in_place() {
+    let pairs: Vec<(u32, [u32; 64])> = keys().map(|key| (key, [key; 64])).collect();
+    let mut sorted = sorted_lazy_pairs(pairs);
+    assert!(matches!(sorted.0, Strategy::Indexed { .. }));
+    for key in 0..300 {
+        assert_eq!(sorted.next(), Some((key, [key; 64])));
+    }
+    assert_eq!(sorted.next(), None);
+}
+
+#[test]
+fn dropping_partially_consumed() {
+    let counter = DropCounter::new();
+    let pairs: Vec<_> = keys()
+        .map(|key| (key, core::array::from_fn::<_, 8, _>(|_| counter.track(()))))
+        .collect();
+    let mut sorted = sorted_lazy_pairs(pairs);
+    assert!(matches!(sorted.0, Strategy::Indexed { .. }));
+    let (_, first) = sorted.next().unwrap();
+    drop(first);
+    assert_eq!(counter.live(), 299 * 8);
+    drop(sorted);
+    counter.assert_all_dropped();
+}
+
+#[test]
+fn into_inner_returns_the_rest() {
+    let pairs: Vec<(u32, u8)> = keys().map(|key| (key, key as u8)).collect();
+    let ptr = pairs.as_ptr();
+    let mut sorted = sorted_lazy_pairs(pairs);
+    assert_eq!(sorted.next(), Some((0, 0)));
+    let mut rest = sorted.into_inner();
+    assert_eq!((rest.len(), rest.as_ptr()), (299, ptr));
+    rest.sort_unstable();
+    assert!(rest
+        .iter()
+        .copied()
+        .eq((1..300).map(|key| (key, key as u8))));
+
+    let counter = DropCounter::new();
+    let pairs: Vec<_> = keys()
+        .map(|key| (key, core::array::from_fn::<_, 8, _>(|_| counter.track(()))))
+        .collect();
+    let mut sorted = sorted_lazy_pairs(pairs);
+    drop(sorted.next());
+    let rest = sorted.into_inner();
+    assert_eq!(rest.len(), 299);
+    assert_eq!(counter.live(), 299 * 8);
+    drop(rest);
+    counter.assert_all_dropped();
+}
+
+#[test]
+fn blocked_layout() {
+    use crate::pairs::{sorted_lazy_pairs_with_layout, Layout};
+
+    let counter = DropCounter::new();
+    let pairs: Vec<_> = keys().map(|key| (counter.track(key), [key; 16])).collect();
+    let mut sorted = sorted_lazy_pairs_with_layout(pairs, Layout::Blocked);
+    assert!(matches!(sorted.0, Strategy::Blocked { .. }));
+    for key in 0..10 {
+        let (got, value) = sorted.next().unwrap();
+        assert_eq!((*got, value), (key, [key; 16]));
+    }
+    assert_eq!((sorted.len(), counter.live()), (290, 290));
+    let mut rest = sorted.into_inner();
+    rest.truncate(100);
+    assert_eq!(counter.live(), 100);
+    // Dropping a partially consumed one drops the rest of the keys, too.
+    let mut sorted = sorted_lazy_pairs_with_layout(rest, Layout::Blocked);
+    drop(sorted.next());
+    drop(sorted);
+    counter.assert_all_dropped();
+}
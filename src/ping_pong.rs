@@ -0,0 +1,186 @@
+//! An alternative to [`crate::engine::Engine`] for [`Copy`] items: partitioning copies the items
+//! back and forth between two equal-size buffers ("ping-pong"), rather than swapping them within
+//! one. Sequential writes and no swaps are often faster, at the cost of twice the memory (see
+//! [`PingPongSort::memory_bytes()`]).
+//!
+//! ```
+//! use lazysort_no_alloc::ping_pong::PingPongSort;
+//!
+//! let sorted = PingPongSort::new((0..1000u32).map(|i| (i * 7919) % 1000).collect());
+//! // Both buffers, plus a little for the pending segments.
+//! assert!(sorted.memory_bytes() >= 2 * 1000 * 4);
+//! assert!(sorted.take(3).eq([0, 1, 2]));
+//! ```
+
+use crate::engine::{insertion_sort, median_of_three};
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::mem;
+
+#[cfg(test)]
+mod ping_pong_tests;
+
+/// Segments this short get insertion-sorted (in whichever buffer they are).
+const SMALL_SORT_LEN: usize = 16;
+
+/// Positions `start..end` of one of the two buffers.
+#[derive(Clone, Copy, Debug)]
+struct Segment {
+    start: usize,
+    end: usize,
+    /// Index of the buffer holding the (live) items.
+    buffer: usize,
+    sorted: bool,
+}
+
+/// Owned [`Copy`] items, yielded in ascending order. Sorted lazily, as consumed, by out-of-place
+/// partitioning. Equal items come in no particular order.
+#[derive(Clone, Debug)]
+pub struct PingPongSort<T> {
+    /// Two buffers of the same length. Either may hold the live items of a segment.
+    buffers: [Vec<T>; 2],
+    /// Segments of live items, the first one on top. Each item of a segment is less than or equal
+    /// to every item of the segments below.
+    pending: Vec<Segment>,
+    len: usize,
+}
+
+impl<T: Copy + Ord> PingPongSort<T> {
+    /// O(n): this copies `items` into the second buffer (which is allocated here). No sorting
+    /// happens here.
+    pub fn new(items: Vec<T>) -> Self {
+        let len = items.len();
+        trace_event!(debug, len, "allocating ping-pong buffer");
+        let other = items.clone();
+        let mut pending = Vec::new();
+        if len > 0 {
+            pending.push(Segment {
+                start: 0,
+                end: len,
+                buffer: 0,
+                sorted: len == 1,
+            });
+        }
+        Self {
+            buffers: [items, other],
+            pending,
+            len,
+        }
+    }
+
+    /// Number of items not yielded yet.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Bytes of memory held: both buffers, that is 2n items (where the in-place sorters hold n),
+    /// plus the pending segments. Yielding items doesn't lower it.
+    pub fn memory_bytes(&self) -> usize {
+        let items = self.buffers[0].capacity() + self.buffers[1].capacity();
+        items * mem::size_of::<T>() + self.pending.capacity() * mem::size_of::<Segment>()
+    }
+
+    /// Three-way partition of (unsorted) `segment` into the other buffer: the lesser items go to
+    /// its start, the greater ones to its end, and then the equal ones (compacted in the source
+    /// buffer meanwhile) in between.
+    fn partition(&mut self, segment: Segment) {
+        let Segment { start, end, .. } = segment;
+        let to = 1 - segment.buffer;
+        let [first, second] = &mut self.buffers;
+        let (source, target) = if to == 1 {
+            (&mut first[start..end], &mut second[start..end])
+        } else {
+            (&mut second[start..end], &mut first[start..end])
+        };
+        let len = end - start;
+        let pivot = source[median_of_three(source, 0, len / 2, len - 1, &mut T::cmp)];
+        let (mut lt, mut gt, mut eq) = (0, len, 0);
+        for i in 0..len {
+            let item = source[i];
+            match item.cmp(&pivot) {
+                Ordering::Less => {
+                    target[lt] = item;
+                    lt += 1;
+                }
+                Ordering::Greater => {
+                    gt -= 1;
+                    target[gt] = item;
+                }
+                Ordering::Equal => {
+                    source[eq] = item;
+                    eq += 1;
+                }
+            }
+        }
+        target[lt..gt].copy_from_slice(&source[..eq]);
+        trace_event!(
+            trace,
+            start,
+            end,
+            lt = start + lt,
+            gt = start + gt,
+            "ping-pong partition"
+        );
+
+        let piece = |from: usize, to_end: usize, sorted: bool| Segment {
+            start: start + from,
+            end: start + to_end,
+            buffer: to,
+            sorted,
+        };
+        if gt < len {
+            self.pending.push(piece(gt, len, len - gt == 1));
+        }
+        self.pending.push(piece(lt, gt, true));
+        if lt > 0 {
+            self.pending.push(piece(0, lt, lt == 1));
+        }
+    }
+}
+
+impl<T: Copy + Ord> From<Vec<T>> for PingPongSort<T> {
+    fn from(items: Vec<T>) -> Self {
+        Self::new(items)
+    }
+}
+
+impl<T: Copy + Ord> Iterator for PingPongSort<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        loop {
+            let top = self.pending.last_mut()?;
+            if top.sorted {
+                let item = self.buffers[top.buffer][top.start];
+                top.start += 1;
+                if top.start == top.end {
+                    self.pending.pop();
+                }
+                self.len -= 1;
+                return Some(item);
+            }
+            let segment = *top;
+            self.pending.pop();
+            if segment.end - segment.start <= SMALL_SORT_LEN {
+                let items = &mut self.buffers[segment.buffer][segment.start..segment.end];
+                insertion_sort(items, &mut T::cmp);
+                self.pending.push(Segment {
+                    sorted: true,
+                    ..segment
+                });
+            } else {
+                self.partition(segment);
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T: Copy + Ord> ExactSizeIterator for PingPongSort<T> {}
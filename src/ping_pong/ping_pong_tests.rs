@@ -0,0 +1,78 @@
+use crate::ping_pong::PingPongSort;
+use crate::testing::scrambled;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::mem;
+
+fn items(len: u32, modulo: u32) -> Vec<u32> {
+    (0..len as usize)
+        .map(|i| scrambled(i, 13, modulo))
+        .collect()
+}
+
+#[test]
+fn sorts() {
+    for (len, modulo) in [
+        (0, 1),
+        (1, 1),
+        (15, 100),
+        (1000, 1000),
+        (1000, 7),
+        (1000, 1),
+    ] {
+        let mut expected = items(len, modulo);
+        expected.sort_unstable();
+        let sorted = PingPongSort::new(items(len, modulo));
+        assert_eq!(sorted.len(), len as usize);
+        assert!(sorted.eq(expected), "{len} {modulo}");
+    }
+}
+
+#[test]
+fn partially_consumed() {
+    let mut sorted = PingPongSort::from(items(500, 500));
+    assert!(sorted.by_ref().take(10).eq(0..10));
+    assert_eq!(sorted.size_hint(), (490, Some(490)));
+    assert_eq!(sorted.next(), Some(10));
+}
+
+/// Equal by key only: the partitioning must keep the items, not just copies of the pivot.
+#[derive(Clone, Copy, Debug, Eq)]
+struct Keyed(u8, u32);
+
+impl PartialEq for Keyed {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+impl PartialOrd for Keyed {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Keyed {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+#[test]
+fn equal_items_kept() {
+    let items: Vec<Keyed> = (0..300).map(|i| Keyed((i % 3) as u8, i)).collect();
+    let sorted: Vec<Keyed> = PingPongSort::new(items).collect();
+    assert!(sorted.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+    let mut payloads: Vec<u32> = sorted.iter().map(|keyed| keyed.1).collect();
+    payloads.sort_unstable();
+    assert!(payloads.into_iter().eq(0..300));
+}
+
+#[test]
+fn memory_bytes() {
+    let buffers = 2 * 1000 * mem::size_of::<u32>();
+    let mut sorted = PingPongSort::new(items(1000, 1000));
+    let initial = sorted.memory_bytes();
+    assert!(initial >= buffers && initial < buffers + 256, "{initial}");
+    // The pending segments may grow, but the buffers never shrink.
+    sorted.by_ref().take(500).for_each(drop);
+    assert!(sorted.memory_bytes() >= initial);
+}
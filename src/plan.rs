@@ -0,0 +1,65 @@
+//! Planning which ranks (sorted positions) get resolved, before resolving any of them.
+
+use crate::engine::{Engine, Observer};
+use crate::idx::Index;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::ops::Range;
+
+#[cfg(test)]
+mod plan_tests;
+
+/// Ranks that the caller declared up front, normalized into sorted, disjoint and non-adjacent runs.
+///
+/// [`Plan::execute()`] resolves them in one sweep, from the smallest rank up: every partition is
+/// kept (see [`Engine`]), so each one splits the ranks still needed, like a multi-select. Nothing
+/// gets partitioned that none of the ranks needs, and runs of consecutive ranks get sorted as
+/// blocks (rather than selected rank by rank).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Plan {
+    runs: Vec<Range<usize>>,
+}
+
+impl Plan {
+    /// Plan for `ranks`: in any order, possibly with duplicates.
+    pub fn new(ranks: &[usize]) -> Self {
+        trace_event!(debug, len = ranks.len(), "allocating plan");
+        let mut sorted = ranks.to_vec();
+        sorted.sort_unstable();
+        let mut runs: Vec<Range<usize>> = Vec::new();
+        for rank in sorted {
+            match runs.last_mut() {
+                Some(run) if rank <= run.end => run.end = run.end.max(rank + 1),
+                _ => runs.push(rank..rank + 1),
+            }
+        }
+        Self { runs }
+    }
+
+    /// The runs of consecutive ranks, in ascending order.
+    pub fn runs(&self) -> &[Range<usize>] {
+        &self.runs
+    }
+
+    /// Resolve all planned ranks that are live in `engine`. (Other ranks are ignored.)
+    pub fn execute<T, I, O, M, CMP, const DEPTH: usize>(
+        &self,
+        engine: &mut Engine<I, DEPTH, O, M>,
+        live: &mut [T],
+        cmp: &mut CMP,
+    ) where
+        I: Index,
+        O: Observer<M>,
+        M: Copy,
+        CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+    {
+        for run in &self.runs {
+            let (lo, hi) = (run.start.max(engine.front()), run.end.min(engine.back()));
+            if lo + 1 == hi {
+                engine.resolve_at(live, lo, cmp);
+            } else if lo < hi {
+                engine.sort_range(live, lo, hi, cmp);
+            }
+        }
+    }
+}
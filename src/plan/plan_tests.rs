@@ -0,0 +1,35 @@
+use crate::engine::Engine;
+use crate::plan::Plan;
+use alloc::vec::Vec;
+
+#[test]
+fn runs_are_normalized() {
+    let plan = Plan::new(&[999, 3, 0, 1, 2, 500, 3, 9, 8, 501]);
+    assert_eq!(plan.runs(), &[0..4, 8..10, 500..502, 999..1000]);
+    assert!(Plan::new(&[]).runs().is_empty());
+}
+
+#[test]
+fn execute_resolves_planned_ranks() {
+    let mut items: Vec<u32> = (0..1000u32).map(|i| (i * 7919 + 1) % 1000).collect();
+    let mut engine = Engine::<usize>::try_new(items.len()).unwrap();
+    let ranks: Vec<usize> = (0..10).chain([500, 999, 1000, 5000]).collect();
+    Plan::new(&ranks).execute(&mut engine, &mut items, &mut u32::cmp);
+    for rank in ranks.into_iter().filter(|&rank| rank < 1000) {
+        assert_eq!(items[rank], rank as u32);
+    }
+}
+
+#[test]
+fn execute_after_consumption() {
+    let mut items: Vec<u32> = (0..100u32).rev().collect();
+    let mut engine = Engine::<u8, 8>::try_new(items.len()).unwrap();
+    for _ in 0..10 {
+        let live = &mut items[engine.front()..engine.back()];
+        engine.next_front(live, &mut u32::cmp);
+    }
+    let plan = Plan::new(&[5, 10, 11, 12, 60]);
+    plan.execute(&mut engine, &mut items[10..], &mut u32::cmp);
+    assert_eq!(items[10..13], [10, 11, 12]);
+    assert_eq!(items[60], 60);
+}
@@ -0,0 +1,119 @@
+//! Extension traits, for method-call style: `items.into_lazy_sorted()` rather than
+//! `LazySort::new(items)`. Import them all by `use lazysort_no_alloc::prelude::*;`.
+//!
+//! ```
+//! use lazysort_no_alloc::prelude::*;
+//!
+//! let mut sorted = vec![50, 20, 40, 10, 30].into_lazy_sorted();
+//! assert_eq!(sorted.next(), Some(10));
+//! let words = ["kiwi", "fig", "banana"].into_iter();
+//! assert!(words.lazy_sorted_by(|a, b| a.len().cmp(&b.len())).eq(["fig", "kiwi", "banana"]));
+//! ```
+//!
+//! (There's no `lazy_sorted_by_key()`: it would return an unnameable type. Use
+//! [`LazySortIteratorExt::lazy_sorted_by()`] with `|a, b| key(a).cmp(&key(b))`, or
+//! [`LazySortIteratorExt::lazy_sorted_by_cached_key()`].)
+
+use crate::lazy_sort::{
+    sorted_by, sorted_by_cached_key, LazySort, LazySortBy, LazySortByCachedKey, Stable,
+};
+use crate::owned::collect_items;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+#[cfg(test)]
+mod prelude_tests;
+
+/// Lazy sorting of a [`Vec`], in its own buffer.
+pub trait LazySortVecExt<T> {
+    /// See [`LazySort`].
+    fn into_lazy_sorted(self) -> LazySort<T>
+    where
+        T: Ord;
+
+    /// See [`LazySort`] and [`Stable`].
+    fn into_lazy_sorted_stable(self) -> LazySort<T, Stable>
+    where
+        T: Ord;
+
+    /// See [`sorted_by()`].
+    fn into_lazy_sorted_by<CMP>(self, cmp: CMP) -> LazySortBy<T, CMP>
+    where
+        CMP: FnMut(&T, &T) -> Ordering;
+
+    /// See [`sorted_by_cached_key()`].
+    fn into_lazy_sorted_by_cached_key<K, KEY>(self, key: KEY) -> LazySortByCachedKey<T, K>
+    where
+        K: Ord,
+        KEY: FnMut(&T) -> K;
+}
+
+impl<T> LazySortVecExt<T> for Vec<T> {
+    fn into_lazy_sorted(self) -> LazySort<T>
+    where
+        T: Ord,
+    {
+        LazySort::new(self)
+    }
+
+    fn into_lazy_sorted_stable(self) -> LazySort<T, Stable>
+    where
+        T: Ord,
+    {
+        LazySort::new(self)
+    }
+
+    fn into_lazy_sorted_by<CMP>(self, cmp: CMP) -> LazySortBy<T, CMP>
+    where
+        CMP: FnMut(&T, &T) -> Ordering,
+    {
+        sorted_by(self, cmp)
+    }
+
+    fn into_lazy_sorted_by_cached_key<K, KEY>(self, key: KEY) -> LazySortByCachedKey<T, K>
+    where
+        K: Ord,
+        KEY: FnMut(&T) -> K,
+    {
+        sorted_by_cached_key(self, key)
+    }
+}
+
+/// Lazy sorting of the items of any [`Iterator`]. They get collected into a [`Vec`] first (see the
+/// [`FromIterator`] implementation of [`LazySort`] for the allocations), and sorted lazily there.
+pub trait LazySortIteratorExt: Iterator + Sized {
+    /// See [`LazySort`].
+    fn lazy_sorted(self) -> LazySort<Self::Item>
+    where
+        Self::Item: Ord,
+    {
+        self.collect()
+    }
+
+    /// See [`LazySort`] and [`Stable`].
+    fn lazy_sorted_stable(self) -> LazySort<Self::Item, Stable>
+    where
+        Self::Item: Ord,
+    {
+        self.collect()
+    }
+
+    /// See [`sorted_by()`].
+    fn lazy_sorted_by<CMP>(self, cmp: CMP) -> LazySortBy<Self::Item, CMP>
+    where
+        CMP: FnMut(&Self::Item, &Self::Item) -> Ordering,
+    {
+        sorted_by(collect_items(self), cmp)
+    }
+
+    /// See [`sorted_by_cached_key()`].
+    fn lazy_sorted_by_cached_key<K, KEY>(self, key: KEY) -> LazySortByCachedKey<Self::Item, K>
+    where
+        K: Ord,
+        KEY: FnMut(&Self::Item) -> K,
+    {
+        sorted_by_cached_key(collect_items(self), key)
+    }
+}
+
+impl<ITER: Iterator> LazySortIteratorExt for ITER {}
@@ -0,0 +1,33 @@
+use crate::prelude::*;
+use alloc::vec;
+use alloc::vec::Vec;
+
+#[test]
+fn vec_ext() {
+    let items: Vec<u32> = (0..100).map(|i| (i * 37) % 100).collect();
+    let buffer = items.as_ptr();
+    let mut sorted = items.into_lazy_sorted();
+    assert!(sorted.by_ref().take(3).eq(0..3));
+    // Sorted in the buffer the items came in.
+    let rest = sorted.into_inner();
+    assert_eq!(rest.as_ptr(), buffer);
+
+    let pairs = vec![(1, 'b'), (0, 'z'), (1, 'a'), (0, 'y')];
+    let by_key = pairs.clone().into_lazy_sorted_by(|a, b| a.0.cmp(&b.0));
+    assert!(by_key.map(|pair| pair.0).eq([0, 0, 1, 1]));
+    let stable = pairs.clone().into_lazy_sorted_by_cached_key(|pair| pair.0);
+    assert!(stable.map(|pair| pair.1).eq(['z', 'y', 'b', 'a']));
+    assert!(pairs
+        .into_lazy_sorted_stable()
+        .eq([(0, 'y'), (0, 'z'), (1, 'a'), (1, 'b')]));
+}
+
+#[test]
+fn iterator_ext() {
+    assert!((0..10).rev().lazy_sorted().eq(0..10));
+    assert!((0..10).lazy_sorted_stable().rev().eq((0..10).rev()));
+    assert!((0..10).lazy_sorted_by(|a, b| b.cmp(a)).eq((0..10).rev()));
+    let words = ["banana", "fig", "kiwi", "plum"].into_iter();
+    let by_len = words.lazy_sorted_by_cached_key(|word| word.len());
+    assert!(by_len.eq(["fig", "kiwi", "plum", "banana"]));
+}
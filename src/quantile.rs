@@ -0,0 +1,220 @@
+//! Weighted selection.
+
+use crate::engine::{partition_at_pivot, Pivot, DEFAULT_DEPTH};
+use crate::error::Error;
+use crate::idx::Index;
+use core::cmp::Ordering;
+
+#[cfg(test)]
+mod quantile_tests;
+
+/// Weighted quantile: the smallest item at which the cumulative weight (of the items in ascending
+/// order, including that item) reaches `q` times the total weight. `q` is clamped to `0.0..=1.0`.
+///
+/// It partitions like a quickselect, summing the weights of each partition to decide which side to
+/// continue on: O(n) expected. Afterwards the returned position holds that item, and `items` are
+/// partitioned around it. Return [`None`] if `items` are empty (or if `q` is NaN).
+///
+/// ```
+/// use lazysort_no_alloc::quantile::weighted_quantile;
+///
+/// // (value, weight)
+/// let mut items = [(3, 1.0), (1, 1.0), (2, 6.0), (4, 2.0)];
+/// let median = weighted_quantile(&mut items, 0.5, |item| item.1, &mut |a, b| a.0.cmp(&b.0));
+/// assert_eq!(median.map(|position| items[position].0), Some(2));
+/// ```
+pub fn weighted_quantile<T, W, CMP>(
+    items: &mut [T],
+    q: f64,
+    mut weight: W,
+    cmp: &mut CMP,
+) -> Option<usize>
+where
+    W: FnMut(&T) -> f64,
+    CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+{
+    if items.is_empty() || q.is_nan() {
+        return None;
+    }
+    let total: f64 = items.iter().map(&mut weight).sum();
+    Some(weighted_select(items, q.clamp(0.0, 1.0) * total, weight, cmp).0)
+}
+
+/// Repeated weighted quantiles of the same items. The partitions made by each query stay, and their
+/// boundaries get cached (as `I`, up to `DEPTH` of them) along with the prefix sums of the weights
+/// up to each boundary. A query then partitions (and sums weights) only within the cached partition
+/// that its answer is in - rather than rescanning all items, as [`weighted_quantile()`] does.
+///
+/// ```
+/// use lazysort_no_alloc::quantile::WeightedQuantiles;
+///
+/// // (value, weight)
+/// let mut items = [(3, 1.0), (1, 1.0), (2, 6.0), (4, 2.0)];
+/// let mut quantiles: WeightedQuantiles<_, _, _> =
+///     WeightedQuantiles::try_new(&mut items, |item| item.1, |a, b| a.0.cmp(&b.0)).unwrap();
+/// let median = quantiles.quantile(0.5);
+/// assert_eq!(median.map(|position| quantiles.items()[position].0), Some(2));
+/// let p90 = quantiles.quantile(0.9);
+/// assert_eq!(p90.map(|position| quantiles.items()[position].0), Some(4));
+/// ```
+pub struct WeightedQuantiles<'a, T, W, CMP, I: Index = usize, const DEPTH: usize = DEFAULT_DEPTH>
+where
+    W: FnMut(&T) -> f64,
+    CMP: FnMut(&T, &T) -> Ordering,
+{
+    items: &'a mut [T],
+    weight: W,
+    cmp: CMP,
+    /// Boundaries (offset by [`Index::min_index_usize()`]) strictly within `items`, ascending. All
+    /// items before a boundary are less than (or equal to) all items after it.
+    bounds: [I; DEPTH],
+    /// Weight of the items before each boundary.
+    before: [f64; DEPTH],
+    count: usize,
+    total: f64,
+}
+
+impl<'a, T, W, CMP, I: Index, const DEPTH: usize> WeightedQuantiles<'a, T, W, CMP, I, DEPTH>
+where
+    W: FnMut(&T) -> f64,
+    CMP: FnMut(&T, &T) -> Ordering,
+{
+    /// Sum the total weight (the only pass over all items). Panic if the length of `items` is not
+    /// indexable by `I`.
+    ///
+    /// Not available with crate feature `no-panic`. Use [`WeightedQuantiles::try_new()`] instead.
+    #[cfg(not(feature = "no-panic"))]
+    pub fn new(items: &'a mut [T], weight: W, cmp: CMP) -> Self {
+        match Self::try_new(items, weight, cmp) {
+            Ok(quantiles) => quantiles,
+            Err(_) => release_panic!("Too many items for the index type."),
+        }
+    }
+
+    /// Like [`WeightedQuantiles::new()`], but return [`Error::CapacityExceeded`] if the length of
+    /// `items` is not indexable by `I`.
+    pub fn try_new(items: &'a mut [T], mut weight: W, cmp: CMP) -> Result<Self, Error> {
+        if items.len() > I::MAX_INDEXABLE_LEN {
+            return Err(Error::CapacityExceeded);
+        }
+        let total = items.iter().map(&mut weight).sum();
+        Ok(Self {
+            items,
+            weight,
+            cmp,
+            bounds: [I::min_index(); DEPTH],
+            before: [0.0; DEPTH],
+            count: 0,
+            total,
+        })
+    }
+
+    /// The items, partitioned by the queries so far.
+    pub fn items(&self) -> &[T] {
+        self.items
+    }
+
+    pub fn total_weight(&self) -> f64 {
+        self.total
+    }
+
+    /// Number of cached partition boundaries.
+    pub fn cached_bounds(&self) -> usize {
+        self.count
+    }
+
+    /// Like [`weighted_quantile()`]: position of the smallest item at which the cumulative weight
+    /// reaches `q` (clamped to `0.0..=1.0`) times the total weight. [`None`] if there are no items
+    /// (or if `q` is NaN).
+    pub fn quantile(&mut self, q: f64) -> Option<usize> {
+        if self.items.is_empty() || q.is_nan() {
+            return None;
+        }
+        let target = q.clamp(0.0, 1.0) * self.total;
+        // The first partition whose (cumulative) weight reaches `target` - or the last one.
+        let nth = self.before[..self.count].partition_point(|&before| before < target);
+        let (start, before_start) = match nth {
+            0 => (0, 0.0),
+            _ => (self.bound(nth - 1), self.before[nth - 1]),
+        };
+        let end = if nth == self.count {
+            self.items.len()
+        } else {
+            self.bound(nth)
+        };
+        let (position, before) = weighted_select(
+            &mut self.items[start..end],
+            target - before_start,
+            &mut self.weight,
+            &mut self.cmp,
+        );
+        let (position, before) = (start + position, before_start + before);
+        // The item at `position` is a partition (of its own) now.
+        if position > start {
+            self.insert_bound(nth, position, before);
+        }
+        if position + 1 < end {
+            let after = before + (self.weight)(&self.items[position]);
+            let nth = nth + usize::from(position > start);
+            self.insert_bound(nth, position + 1, after);
+        }
+        Some(position)
+    }
+
+    fn bound(&self, nth: usize) -> usize {
+        self.bounds[nth].to_usize() - I::min_index_usize()
+    }
+
+    /// Insert a boundary as the `nth`, unless the cache is full.
+    fn insert_bound(&mut self, nth: usize, bound: usize, before: f64) {
+        // (`I` indexes all items, see `WeightedQuantiles::try_new()`: the bound always fits.)
+        let Some(index) = I::try_from_usize(bound + I::min_index_usize()) else {
+            return;
+        };
+        if self.count == DEPTH {
+            return;
+        }
+        self.bounds.copy_within(nth..self.count, nth + 1);
+        self.before.copy_within(nth..self.count, nth + 1);
+        self.bounds[nth] = index;
+        self.before[nth] = before;
+        self.count += 1;
+    }
+}
+
+/// Position of the smallest item at which the cumulative weight (of the items in ascending order,
+/// including that item) reaches `target` - or of the greatest one, if the total weight is less.
+/// `items` then are partitioned around it, and the items equal to it follow it (the target may be
+/// reached only within that run). Return that position, and the weight of the items before it.
+/// `items` must not be empty.
+pub(crate) fn weighted_select<T, W, CMP>(
+    items: &mut [T],
+    target: f64,
+    mut weight: W,
+    cmp: &mut CMP,
+) -> (usize, f64)
+where
+    W: FnMut(&T) -> f64,
+    CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+{
+    debug_assert!(!items.is_empty());
+    // Invariant: the answer is within `lo..hi`, and `before` is the weight of `items[..lo]`.
+    let (mut lo, mut hi, mut before) = (0, items.len(), 0.0);
+    loop {
+        let segment = &mut items[lo..hi];
+        let (left, right) = partition_at_pivot(segment, Pivot::MedianOfThree, 0, cmp);
+        let (less, rest) = segment.split_at(left);
+        let (equal, _) = rest.split_at(rest.len() - right);
+        let less: f64 = less.iter().map(&mut weight).sum();
+        let equal: f64 = equal.iter().map(&mut weight).sum();
+        if left > 0 && before + less >= target {
+            hi = lo + left;
+        } else if right == 0 || before + less + equal >= target {
+            // (Also if `target` is above the total weight.)
+            return (lo + left, before + less);
+        } else {
+            before += less + equal;
+            lo = hi - right;
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use crate::quantile::weighted_quantile;
+
+fn items() -> [(u32, f64); 100] {
+    // Value `v` has weight `v + 1`: the cumulative weight up to `v` is `(v + 1) * (v + 2) / 2`.
+    core::array::from_fn(|i| {
+        let value = (i as u32 * 37) % 100;
+        (value, f64::from(value + 1))
+    })
+}
+
+fn quantile(items: &mut [(u32, f64)], q: f64) -> Option<u32> {
+    let position = weighted_quantile(items, q, |item| item.1, &mut |a, b| a.0.cmp(&b.0))?;
+    Some(items[position].0)
+}
+
+#[test]
+fn crossings() {
+    let total = 5050.0;
+    for (q, expected) in [
+        (0.0, 0),
+        (1.0 / total, 0),
+        (1.5 / total, 1),
+        (2.5 / total, 1),
+        (0.5, 70),
+        (0.9, 94),
+        (1.0, 99),
+        (2.0, 99),
+    ] {
+        assert_eq!(quantile(&mut items(), q), Some(expected), "q = {q}");
+    }
+}
+
+#[test]
+fn duplicates_and_edge_cases() {
+    let mut same = [(7, 1.0); 20];
+    assert_eq!(quantile(&mut same, 0.5), Some(7));
+    assert_eq!(quantile(&mut [], 0.5), None);
+    assert_eq!(quantile(&mut items(), f64::NAN), None);
+    // Zero weights don't count.
+    let mut zeros = [(1, 0.0), (2, 1.0), (3, 0.0)];
+    assert_eq!(quantile(&mut zeros, 1.0), Some(2));
+}
+
+/// Same answers as `weighted_quantile()`, in any order of queries, also once the cache is full.
+#[test]
+fn cached_quantiles() {
+    use crate::quantile::WeightedQuantiles;
+
+    let qs = [
+        0.5, 0.9, 0.0, 0.25, 0.5, 1.0, 0.1, 0.75, 0.33, 2.0, 0.66, 0.05,
+    ];
+    let mut cached_items = items();
+    let mut cached: WeightedQuantiles<_, _, _, u8, 4> =
+        WeightedQuantiles::try_new(&mut cached_items, |item| item.1, |a, b| a.0.cmp(&b.0)).unwrap();
+    for q in qs {
+        let position = cached.quantile(q).unwrap();
+        assert_eq!(
+            Some(cached.items()[position].0),
+            quantile(&mut items(), q),
+            "q = {q}"
+        );
+    }
+    assert_eq!(cached.cached_bounds(), 4);
+    assert_eq!(cached.quantile(f64::NAN), None);
+}
+
+/// A repeated query doesn't rescan the items.
+#[test]
+fn cached_quantiles_reuse_partitions() {
+    use crate::quantile::WeightedQuantiles;
+    use core::cell::Cell;
+
+    let weighed = Cell::new(0);
+    let mut items = items();
+    let mut cached: WeightedQuantiles<_, _, _> = WeightedQuantiles::try_new(
+        &mut items,
+        |item| {
+            weighed.set(weighed.get() + 1);
+            item.1
+        },
+        |a, b| a.0.cmp(&b.0),
+    )
+    .unwrap();
+    assert_eq!(weighed.get(), 100);
+    let median = cached.quantile(0.5);
+    let first = weighed.get() - 100;
+    assert!(first > 50, "{first}");
+    assert_eq!(cached.quantile(0.5), median);
+    assert!(weighed.get() - 100 - first <= 2, "{}", weighed.get());
+    assert_eq!(cached.items()[median.unwrap()].0, 70);
+}
+
+#[test]
+fn cached_quantiles_capacity() {
+    use crate::error::Error;
+    use crate::quantile::WeightedQuantiles;
+
+    let mut items = [(0, 1.0); 300];
+    let cached =
+        WeightedQuantiles::<_, _, _, u8>::try_new(&mut items, |item| item.1, |a, b| a.0.cmp(&b.0));
+    assert!(matches!(cached, Err(Error::CapacityExceeded)));
+    assert_eq!(
+        WeightedQuantiles::<_, _, _, u8>::try_new(
+            &mut [],
+            |item: &(u32, f64)| item.1,
+            |a, b| a.0.cmp(&b.0)
+        )
+        .unwrap()
+        .quantile(0.5),
+        None
+    );
+}
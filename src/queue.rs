@@ -0,0 +1,167 @@
+//! Priority queue for "bulk insert once, pop a few".
+
+use crate::engine::{Engine, Pivot};
+use alloc::vec::Vec;
+
+#[cfg(test)]
+mod queue_tests;
+
+/// Up to this many items pushed after the bulk load are kept aside (unpartitioned), and they get
+/// scanned by each [`LazyPriorityQueue::pop()`]. Once there are more, all live items are
+/// re-partitioned lazily from scratch.
+const MAX_UNPARTITIONED: usize = 32;
+
+/// A max-queue (like [`alloc::collections::BinaryHeap`]): [`LazyPriorityQueue::pop()`] returns the
+/// greatest item.
+///
+/// Construction from a [`Vec`] is O(1) and it does no comparisons at all (no heapify). Each
+/// [`LazyPriorityQueue::pop()`] partitions only as much as needed to find the greatest item (with
+/// [`Pivot::TowardTarget`]), and that work is kept for the next pops.
+///
+/// Pushes mixed with pops are supported, but they are not this queue's strength: up to 32 of them
+/// are kept aside (and scanned by each pop); beyond that, the partitioning done so far is
+/// discarded.
+///
+/// A comparison count (not a timing benchmark) against [`alloc::collections::BinaryHeap`]: the
+/// number of [`Ord::cmp()`] calls for popping the 10 greatest out of 10,000 items.
+/// ```
+/// use core::cell::Cell;
+/// use core::cmp::Ordering;
+/// use lazysort_no_alloc::queue::LazyPriorityQueue;
+/// use std::collections::BinaryHeap;
+///
+/// thread_local!(static COMPARISONS: Cell<usize> = Cell::new(0));
+///
+/// #[derive(PartialEq, Eq)]
+/// struct Counted(u32);
+/// impl PartialOrd for Counted {
+///     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+///         Some(self.cmp(other))
+///     }
+/// }
+/// impl Ord for Counted {
+///     fn cmp(&self, other: &Self) -> Ordering {
+///         COMPARISONS.with(|c| c.set(c.get() + 1));
+///         self.0.cmp(&other.0)
+///     }
+/// }
+/// let items = || (0..10_000u32).map(|i| Counted(i.wrapping_mul(2_654_435_761) % 10_000));
+/// let comparisons = |f: &dyn Fn()| {
+///     COMPARISONS.with(|c| c.set(0));
+///     f();
+///     COMPARISONS.with(|c| c.get())
+/// };
+///
+/// let heap = comparisons(&|| {
+///     let mut heap: BinaryHeap<Counted> = items().collect();
+///     (0..10).for_each(|_| drop(heap.pop()));
+/// });
+/// let lazy = comparisons(&|| {
+///     let mut queue: LazyPriorityQueue<Counted> = items().collect();
+///     assert_eq!(queue.pop().map(|c| c.0), Some(9_999));
+///     (0..9).for_each(|_| drop(queue.pop()));
+/// });
+/// // Construction alone: heapify vs. none.
+/// let heapify = comparisons(&|| drop(items().collect::<BinaryHeap<_>>()));
+/// assert!(heapify > 10_000);
+/// assert_eq!(comparisons(&|| drop(items().collect::<LazyPriorityQueue<_>>())), 0);
+/// // Overall, about as many comparisons. (Wall-clock time depends on the items and the target.)
+/// assert!(lazy < heap * 3 / 2, "lazy: {lazy}, heap: {heap}");
+/// ```
+#[derive(Clone, Debug)]
+pub struct LazyPriorityQueue<T: Ord> {
+    /// `items[..engine.back()]` are tracked by `engine`. The rest were pushed later.
+    items: Vec<T>,
+    engine: Engine,
+}
+
+impl<T: Ord> From<Vec<T>> for LazyPriorityQueue<T> {
+    /// O(1), no comparisons.
+    fn from(items: Vec<T>) -> Self {
+        let mut engine = Engine::try_new(items.len()).unwrap_or_else(|_| unreachable!());
+        engine.set_pivot(Pivot::TowardTarget);
+        Self { items, engine }
+    }
+}
+
+impl<T: Ord> FromIterator<T> for LazyPriorityQueue<T> {
+    fn from_iter<ITER: IntoIterator<Item = T>>(iter: ITER) -> Self {
+        iter.into_iter().collect::<Vec<_>>().into()
+    }
+}
+
+impl<T: Ord> Default for LazyPriorityQueue<T> {
+    fn default() -> Self {
+        Vec::new().into()
+    }
+}
+
+impl<T: Ord> LazyPriorityQueue<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Vec::with_capacity(capacity).into()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.items.push(value);
+        let unpartitioned = self.items.len() - self.engine.back();
+        if unpartitioned > MAX_UNPARTITIONED {
+            // A `Vec` never has more items than `usize` indexes.
+            self.engine
+                .try_append(unpartitioned)
+                .unwrap_or_else(|_| unreachable!());
+        }
+    }
+
+    /// Position of the greatest item.
+    fn position_of_max(&mut self) -> Option<usize> {
+        let back = self.engine.back();
+        let (partitioned, unpartitioned) = self.items.split_at_mut(back);
+        let max_partitioned = self.engine.resolve_back(partitioned, &mut T::cmp);
+        let max_unpartitioned = unpartitioned
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.cmp(b))
+            .map(|(position, _)| back + position);
+        match (max_partitioned, max_unpartitioned) {
+            (Some(a), Some(b)) if self.items[b] > self.items[a] => Some(b),
+            (Some(a), _) => Some(a),
+            (None, b) => b,
+        }
+    }
+
+    /// The greatest item. (`&mut self`, since it may partition.)
+    pub fn peek(&mut self) -> Option<&T> {
+        let position = self.position_of_max()?;
+        Some(&self.items[position])
+    }
+
+    /// Remove and return the greatest item.
+    pub fn pop(&mut self) -> Option<T> {
+        let position = self.position_of_max()?;
+        if position < self.engine.back() {
+            let partitioned = &mut self.items[..self.engine.back()];
+            let consumed = self.engine.next_back(partitioned, &mut T::cmp);
+            debug_assert_eq!(consumed, Some(position));
+        }
+        // For a partitioned item this moves the last unpartitioned item (if any) in its place,
+        // which is now outside of the partitioned items. Either way, it keeps the rest in place.
+        Some(self.items.swap_remove(position))
+    }
+
+    /// The items, in no particular order.
+    pub fn into_vec(self) -> Vec<T> {
+        self.items
+    }
+}
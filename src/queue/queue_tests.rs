@@ -0,0 +1,32 @@
+use crate::queue::LazyPriorityQueue;
+use alloc::vec::Vec;
+
+#[test]
+fn pops_in_descending_order() {
+    let mut queue: LazyPriorityQueue<u32> = (0..1000u32).map(|i| (i * 7919) % 1000).collect();
+    assert_eq!(queue.peek(), Some(&999));
+    let popped: Vec<u32> = core::iter::from_fn(|| queue.pop()).collect();
+    assert_eq!(popped, (0..1000).rev().collect::<Vec<_>>());
+}
+
+#[test]
+fn mixed_pushes() {
+    let mut queue: LazyPriorityQueue<u32> = (0..100u32).collect();
+    assert_eq!(queue.pop(), Some(99));
+    queue.push(500);
+    queue.push(7);
+    assert_eq!(queue.pop(), Some(500));
+    assert_eq!(queue.pop(), Some(98));
+    // More pushes than kept aside: re-partitioned from scratch.
+    for i in 0..100 {
+        queue.push(1000 + i);
+    }
+    assert_eq!(queue.len(), 199);
+    assert_eq!(queue.pop(), Some(1099));
+    let mut previous = u32::MAX;
+    while let Some(item) = queue.pop() {
+        assert!(item <= previous);
+        previous = item;
+    }
+    assert!(queue.is_empty());
+}
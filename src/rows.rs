@@ -0,0 +1,131 @@
+//! Lazy sorting of the rows of a flat 2D buffer, by a key column.
+//!
+//! Only an index array (provided by the caller) gets partitioned: the rows don't move, unless you
+//! ask for them to be materialized (copied) in order.
+
+use crate::engine::Engine;
+use crate::error::Error;
+use crate::idx::Index;
+use crate::index_sort::position;
+use core::cmp::Ordering;
+
+#[cfg(test)]
+mod rows_tests;
+
+/// Lazily sorts the rows of `data` (row-major, `row_len` items per row) by the item in `column`,
+/// using the (mutable) `indices` (one per row) for storage.
+///
+/// It yields row slices (as an [`Iterator`]) in ascending order of their keys. Use a narrow index
+/// type `I` (like [`u8`] or [`u16`]) to save RAM.
+pub struct RowSorter<'d, 'i, T: Ord, I: Index = usize> {
+    data: &'d [T],
+    row_len: usize,
+    column: usize,
+    /// The first `data.len() / row_len` of them are used, storing row numbers.
+    indices: &'i mut [I],
+    engine: Engine<I>,
+}
+
+impl<'d, 'i, T: Ord, I: Index> RowSorter<'d, 'i, T, I> {
+    /// Return [`Error::InvalidArgument`] if `row_len` is zero or it doesn't divide `data.len()`, or
+    /// if `column` is not less than `row_len`. Return [`Error::CapacityExceeded`] if `indices` has
+    /// fewer items than there are rows, or if `I` can't index all rows.
+    pub fn new(
+        data: &'d [T],
+        row_len: usize,
+        column: usize,
+        indices: &'i mut [I],
+    ) -> Result<Self, Error> {
+        if row_len == 0 || data.len() % row_len != 0 || column >= row_len {
+            return Err(Error::InvalidArgument);
+        }
+        let rows = data.len() / row_len;
+        if indices.len() < rows {
+            return Err(Error::CapacityExceeded);
+        }
+        let engine = Engine::try_new(rows)?;
+        let indices = &mut indices[..rows];
+        for (row, index) in indices.iter_mut().enumerate() {
+            *index =
+                I::try_from_usize(row + I::min_index_usize()).ok_or(Error::CapacityExceeded)?;
+        }
+        Ok(Self {
+            data,
+            row_len,
+            column,
+            indices,
+            engine,
+        })
+    }
+
+    /// Number of rows not yielded yet.
+    pub fn len(&self) -> usize {
+        self.engine.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.engine.is_empty()
+    }
+
+    fn row(&self, row: usize) -> &'d [T] {
+        let data = self.data;
+        &data[row * self.row_len..(row + 1) * self.row_len]
+    }
+
+    /// Row number (in `data`) of the next row (with the smallest key).
+    pub fn next_row_index(&mut self) -> Option<usize> {
+        let cmp = &mut Self::cmp(self.data, self.row_len, self.column);
+        let live = &mut self.indices[self.engine.front()..self.engine.back()];
+        let slot = self.engine.next_front(live, cmp)?;
+        Some(position(self.indices[slot]))
+    }
+
+    /// Row number (in `data`) of the last row (with the greatest key).
+    pub fn next_back_row_index(&mut self) -> Option<usize> {
+        let cmp = &mut Self::cmp(self.data, self.row_len, self.column);
+        let live = &mut self.indices[self.engine.front()..self.engine.back()];
+        let slot = self.engine.next_back(live, cmp)?;
+        Some(position(self.indices[slot]))
+    }
+
+    /// Copy all the rows not yielded yet, in order, into `out` (which has to have room for them).
+    /// Return the number of rows copied, or [`Error::CapacityExceeded`] (having copied nothing).
+    pub fn materialize_into(mut self, out: &mut [T]) -> Result<usize, Error>
+    where
+        T: Clone,
+    {
+        let rows = self.len();
+        if out.len() < rows * self.row_len {
+            return Err(Error::CapacityExceeded);
+        }
+        let cmp = &mut Self::cmp(self.data, self.row_len, self.column);
+        let live = &mut self.indices[self.engine.front()..self.engine.back()];
+        let slots = self.engine.next_chunk_front(live, rows, cmp);
+        for (slot, target) in slots.zip(out.chunks_exact_mut(self.row_len)) {
+            let row = self.row(position(self.indices[slot]));
+            target.clone_from_slice(row);
+        }
+        Ok(rows)
+    }
+
+    fn cmp(data: &'d [T], row_len: usize, column: usize) -> impl FnMut(&I, &I) -> Ordering + 'd {
+        move |a, b| {
+            let key = |index: &I| &data[position(*index) * row_len + column];
+            key(a).cmp(key(b))
+        }
+    }
+}
+
+impl<'d, 'i, T: Ord, I: Index> Iterator for RowSorter<'d, 'i, T, I> {
+    type Item = &'d [T];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_row_index().map(|row| self.row(row))
+    }
+}
+
+impl<'d, 'i, T: Ord, I: Index> DoubleEndedIterator for RowSorter<'d, 'i, T, I> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.next_back_row_index().map(|row| self.row(row))
+    }
+}
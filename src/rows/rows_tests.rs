@@ -0,0 +1,62 @@
+use crate::error::Error;
+use crate::rows::RowSorter;
+
+/// 5 rows of (id, score, age).
+static DATA: [u32; 15] = [
+    1, 50, 30, //
+    2, 20, 40, //
+    3, 90, 20, //
+    4, 10, 50, //
+    5, 70, 10, //
+];
+
+#[test]
+fn yields_rows_by_column() {
+    let mut indices = [0u8; 5];
+    let mut sorter = RowSorter::new(&DATA, 3, 1, &mut indices).unwrap();
+    assert_eq!(sorter.len(), 5);
+    assert_eq!(sorter.next(), Some(&[4, 10, 50][..]));
+    assert_eq!(sorter.next_back(), Some(&[3, 90, 20][..]));
+    assert_eq!(sorter.next_row_index(), Some(1));
+    assert_eq!(sorter.next(), Some(&[1, 50, 30][..]));
+    assert_eq!(sorter.next(), Some(&[5, 70, 10][..]));
+    assert_eq!(sorter.next(), None);
+}
+
+#[test]
+fn materialize_into() {
+    let mut indices = [0u16; 5];
+    let mut sorter = RowSorter::new(&DATA, 3, 2, &mut indices).unwrap();
+    assert_eq!(sorter.next(), Some(&[5, 70, 10][..]));
+    let mut out = [0; 12];
+    assert_eq!(sorter.materialize_into(&mut out), Ok(4));
+    assert_eq!(out, [3, 90, 20, 1, 50, 30, 2, 20, 40, 4, 10, 50]);
+
+    let mut indices = [0u16; 5];
+    let sorter = RowSorter::new(&DATA, 3, 2, &mut indices).unwrap();
+    assert_eq!(
+        sorter.materialize_into(&mut out),
+        Err(Error::CapacityExceeded)
+    );
+}
+
+#[test]
+fn invalid_arguments() {
+    let mut indices = [0usize; 5];
+    assert!(matches!(
+        RowSorter::new(&DATA, 0, 0, &mut indices),
+        Err(Error::InvalidArgument)
+    ));
+    assert!(matches!(
+        RowSorter::new(&DATA, 4, 0, &mut indices),
+        Err(Error::InvalidArgument)
+    ));
+    assert!(matches!(
+        RowSorter::new(&DATA, 3, 3, &mut indices),
+        Err(Error::InvalidArgument)
+    ));
+    assert!(matches!(
+        RowSorter::new(&DATA, 3, 0, &mut indices[..4]),
+        Err(Error::CapacityExceeded)
+    ));
+}
@@ -0,0 +1,191 @@
+//! Scratch memory supplied by the caller, on demand.
+//!
+//! A sorter that needs scratch (like the index array of [`crate::index_sort::IndexSorter`]) takes
+//! a plain slice. A [`Lease`] gets such a slice of exactly the needed length from any
+//! [`ScratchProvider`] (a pool, an arena, a ring buffer, a static region...), and returns it to the
+//! provider once dropped. Hence neither the sorter nor the caller depend on `alloc`, or on one
+//! fixed slice per sorter.
+//!
+//! ```
+//! use lazysort_no_alloc::index_sort::IndexSorter;
+//! use lazysort_no_alloc::scratch::{Lease, SliceScratch};
+//!
+//! let mut region = [0u16; 64];
+//! let mut provider = SliceScratch::new(&mut region);
+//! let data = [30, 10, 20];
+//! {
+//!     let mut indices = Lease::new(&mut provider, data.len()).unwrap();
+//!     let sorter = IndexSorter::new(&data, &mut indices).unwrap();
+//!     assert!(sorter.copied().eq([10, 20, 30]));
+//! }
+//! assert_eq!(provider.available(), 64);
+//! ```
+
+use crate::error::Error;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+use core::slice;
+
+#[cfg(test)]
+mod scratch_tests;
+
+/// Lends regions of scratch memory: exactly as many items as requested.
+pub trait ScratchProvider<T> {
+    type Region: DerefMut<Target = [T]>;
+
+    /// A region of exactly `len` items (with unspecified values). Return
+    /// [`Error::CapacityExceeded`] if the provider can't supply that much now.
+    fn acquire(&mut self, len: usize) -> Result<Self::Region, Error>;
+
+    /// Take back a region, once the borrower is done with it. By default it's simply dropped.
+    fn release(&mut self, region: Self::Region) {
+        let _ = region;
+    }
+}
+
+/// A region acquired from a [`ScratchProvider`], released back to it when dropped.
+pub struct Lease<'p, T, P: ScratchProvider<T>> {
+    provider: &'p mut P,
+    /// [`None`] only while dropping.
+    region: Option<P::Region>,
+    item: PhantomData<T>,
+}
+
+impl<'p, T, P: ScratchProvider<T>> Lease<'p, T, P> {
+    /// Acquire `len` items from `provider`. See [`ScratchProvider::acquire()`].
+    pub fn new(provider: &'p mut P, len: usize) -> Result<Self, Error> {
+        let region = provider.acquire(len)?;
+        Ok(Self {
+            provider,
+            region: Some(region),
+            item: PhantomData,
+        })
+    }
+
+    /// Keep the region, rather than releasing it back to the provider. (For [`SliceScratch`] its
+    /// room then stays in use, until the provider itself is dropped.)
+    pub fn into_inner(mut self) -> P::Region {
+        self.region.take().unwrap_or_else(|| unreachable!())
+    }
+}
+
+impl<'p, T, P: ScratchProvider<T>> Deref for Lease<'p, T, P> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.region.as_deref().unwrap_or(&[])
+    }
+}
+
+impl<'p, T, P: ScratchProvider<T>> DerefMut for Lease<'p, T, P> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.region.as_deref_mut().unwrap_or(&mut [])
+    }
+}
+
+impl<'p, T, P: ScratchProvider<T>> Drop for Lease<'p, T, P> {
+    fn drop(&mut self) {
+        if let Some(region) = self.region.take() {
+            self.provider.release(region);
+        }
+    }
+}
+
+/// Carves regions out of one (static, stack...) slice, like a stack: releasing the most recently
+/// acquired region makes its room available again. Releasing any other region doesn't (until all
+/// regions acquired after it are released, too).
+pub struct SliceScratch<'a, T> {
+    start: *mut T,
+    len: usize,
+    /// Items `..used` are lent out (or released out of order).
+    used: usize,
+    buffer: PhantomData<&'a mut [T]>,
+}
+
+impl<'a, T> SliceScratch<'a, T> {
+    pub fn new(buffer: &'a mut [T]) -> Self {
+        Self {
+            start: buffer.as_mut_ptr(),
+            len: buffer.len(),
+            used: 0,
+            buffer: PhantomData,
+        }
+    }
+
+    /// Number of items that can be acquired now (at most, in one region).
+    pub fn available(&self) -> usize {
+        self.len - self.used
+    }
+}
+
+impl<'a, T> ScratchProvider<T> for SliceScratch<'a, T> {
+    type Region = &'a mut [T];
+
+    fn acquire(&mut self, len: usize) -> Result<&'a mut [T], Error> {
+        if len > self.available() {
+            return Err(Error::CapacityExceeded);
+        }
+        // Items `used..` are not lent out, and `buffer` is borrowed for `'a`.
+        let region = unsafe { slice::from_raw_parts_mut(self.start.add(self.used), len) };
+        self.used += len;
+        Ok(region)
+    }
+
+    fn release(&mut self, region: &'a mut [T]) {
+        // Only (the tail of) the most recent region ends right at `used`. Having been moved in
+        // here, `region` can't be accessed anymore.
+        let end = unsafe { self.start.add(self.used) };
+        if region.as_mut_ptr_range().end == end {
+            self.used -= region.len();
+        }
+    }
+}
+
+/// Allocates regions on the heap, keeping (the allocation of) the largest released one for reuse.
+/// Items are reset to [`Default::default()`].
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Default)]
+pub struct HeapScratch<T> {
+    spare: alloc::vec::Vec<T>,
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Default + Clone> HeapScratch<T> {
+    pub fn new() -> Self {
+        Self {
+            spare: alloc::vec::Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> HeapScratch<T> {
+    /// The spare (released) region kept for reuse, if any. Otherwise an empty [`alloc::vec::Vec`].
+    pub fn into_inner(self) -> alloc::vec::Vec<T> {
+        self.spare
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Default + Clone> ScratchProvider<T> for HeapScratch<T> {
+    type Region = alloc::vec::Vec<T>;
+
+    fn acquire(&mut self, len: usize) -> Result<alloc::vec::Vec<T>, Error> {
+        let mut region = core::mem::take(&mut self.spare);
+        region.clear();
+        trace_event!(
+            debug,
+            len,
+            reused = region.capacity() >= len,
+            "acquiring heap scratch"
+        );
+        region.resize(len, T::default());
+        Ok(region)
+    }
+
+    fn release(&mut self, region: alloc::vec::Vec<T>) {
+        if region.capacity() > self.spare.capacity() {
+            self.spare = region;
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use crate::error::Error;
+use crate::scratch::{Lease, ScratchProvider, SliceScratch};
+
+#[test]
+fn slice_scratch_is_a_stack() {
+    let mut buffer = [0u8; 10];
+    let mut provider = SliceScratch::new(&mut buffer);
+    let first = provider.acquire(4).unwrap();
+    let second = provider.acquire(6).unwrap();
+    assert_eq!((first.len(), second.len()), (4, 6));
+    assert_eq!(provider.acquire(1), Err(Error::CapacityExceeded));
+    first.fill(1);
+    second.fill(2);
+    // Out of order: no room gets available yet.
+    provider.release(first);
+    assert_eq!(provider.available(), 0);
+    provider.release(second);
+    assert_eq!(provider.available(), 6);
+    assert_eq!(provider.acquire(0).map(|region| region.len()), Ok(0));
+}
+
+#[test]
+fn lease_releases_on_drop() {
+    let mut buffer = [0u32; 8];
+    let mut provider = SliceScratch::new(&mut buffer);
+    {
+        let mut lease = Lease::new(&mut provider, 5).unwrap();
+        assert_eq!(lease.len(), 5);
+        lease[4] = 7;
+    }
+    assert_eq!(provider.available(), 8);
+    assert!(Lease::new(&mut provider, 9).is_err());
+    assert_eq!(buffer[4], 7);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn heap_scratch_reuses_allocation() {
+    use crate::scratch::HeapScratch;
+    let mut provider = HeapScratch::<u16>::new();
+    let mut region = provider.acquire(100).unwrap();
+    region[0] = 5;
+    let ptr = region.as_ptr();
+    provider.release(region);
+    let region = provider.acquire(50).unwrap();
+    assert_eq!((region.len(), region.as_ptr()), (50, ptr));
+    assert_eq!(region[0], 0);
+}
+
+#[test]
+fn lease_into_inner_keeps_the_region() {
+    let mut buffer = [0u32; 8];
+    let mut provider = SliceScratch::new(&mut buffer);
+    let region = Lease::new(&mut provider, 5).unwrap().into_inner();
+    assert_eq!(region.len(), 5);
+    assert_eq!(provider.available(), 3);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn heap_scratch_into_inner() {
+    use crate::scratch::HeapScratch;
+    let mut provider = HeapScratch::<u16>::new();
+    let region = provider.acquire(100).unwrap();
+    provider.release(region);
+    assert!(provider.into_inner().capacity() >= 100);
+}
@@ -0,0 +1,127 @@
+//! Lazy sorting of more items than a (compact) index type can address.
+
+use crate::engine::Engine;
+use crate::error::Error;
+use crate::idx::Index;
+use core::marker::PhantomData;
+use core::slice;
+
+#[cfg(test)]
+mod segmented_tests;
+
+/// Lazily sorts `items` in place, split into (up to `SEGMENTS`) segments of (at most)
+/// [`Index::MAX_INDEXABLE_LEN`] items each, so that every segment's bookkeeping fits index type
+/// `I`. Each segment gets sorted lazily on its own, and the segments get merged lazily: every
+/// [`Iterator::next()`] compares the smallest remaining item of each segment.
+///
+/// So with `I` = [`u16`], even millions of items need only `SEGMENTS` compact [`Engine`]s of
+/// metadata. The cost is O(`SEGMENTS`) comparisons per item yielded, on top of the lazy sorting.
+pub struct SegmentedSorter<'a, T: Ord, I: Index = u16, const SEGMENTS: usize = 16> {
+    /// Start of the items. Each segment's live items are accessed (mutably) only through its
+    /// [`Engine`]. Items consumed by an [`Engine`] are never moved again, hence the yielded
+    /// references stay valid.
+    items: *mut T,
+    items_len: usize,
+    engines: [Engine<I>; SEGMENTS],
+    /// Number of segments in use.
+    segments: usize,
+    remaining: usize,
+    lifetime: PhantomData<&'a mut [T]>,
+}
+
+impl<'a, T: Ord, I: Index, const SEGMENTS: usize> SegmentedSorter<'a, T, I, SEGMENTS> {
+    /// Return [`Error::CapacityExceeded`] if `items` need more than `SEGMENTS` segments.
+    pub fn new(items: &'a mut [T]) -> Result<Self, Error> {
+        let (len, segment_len) = (items.len(), Self::segment_len());
+        let segments = if len == 0 {
+            0
+        } else {
+            (len - 1) / segment_len + 1
+        };
+        if segments > SEGMENTS {
+            return Err(Error::CapacityExceeded);
+        }
+        let engines: [_; SEGMENTS] = core::array::from_fn(|segment| {
+            let start = segment.saturating_mul(segment_len);
+            Engine::try_new(len.saturating_sub(start).min(segment_len))
+        });
+        Ok(Self {
+            items: items.as_mut_ptr(),
+            items_len: len,
+            engines: engines.map(|engine| engine.unwrap_or_else(|_| unreachable!())),
+            segments,
+            remaining: len,
+            lifetime: PhantomData,
+        })
+    }
+
+    fn segment_len() -> usize {
+        I::MAX_INDEXABLE_LEN
+    }
+
+    /// Number of segments the items were split into.
+    pub fn segments(&self) -> usize {
+        self.segments
+    }
+
+    /// Number of items not yielded yet.
+    pub fn len(&self) -> usize {
+        self.remaining
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.remaining == 0
+    }
+
+    /// The live items of `segment`.
+    ///
+    /// # Safety
+    /// No reference to those items may be alive.
+    unsafe fn live(&mut self, segment: usize) -> &'a mut [T] {
+        let engine = &self.engines[segment];
+        let start = segment * Self::segment_len() + engine.front();
+        debug_assert!(start + engine.len() <= self.items_len);
+        slice::from_raw_parts_mut(self.items.add(start), engine.len())
+    }
+
+    /// Position (in the original items) of the smallest remaining item, and consume it.
+    pub fn next_index(&mut self) -> Option<usize> {
+        let mut smallest: Option<(usize, &'a T)> = None;
+        for segment in 0..self.segments {
+            // The live items are disjoint from the (consumed) items referenced so far.
+            let live = unsafe { self.live(segment) };
+            let engine = &mut self.engines[segment];
+            let Some(front) = engine.resolve_front(live, &mut T::cmp) else {
+                continue;
+            };
+            let candidate = &live[front - engine.front()];
+            if smallest.map_or(true, |(_, item)| candidate < item) {
+                smallest = Some((segment, candidate));
+            }
+        }
+        let (segment, _) = smallest?;
+        let live = unsafe { self.live(segment) };
+        let position = self.engines[segment].next_front(live, &mut T::cmp)?;
+        self.remaining -= 1;
+        Some(segment * Self::segment_len() + position)
+    }
+}
+
+impl<'a, T: Ord, I: Index, const SEGMENTS: usize> Iterator for SegmentedSorter<'a, T, I, SEGMENTS> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let position = self.next_index()?;
+        // Consumed: the engine never moves it again.
+        Some(unsafe { &*self.items.add(position) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T: Ord, I: Index, const SEGMENTS: usize> ExactSizeIterator
+    for SegmentedSorter<'a, T, I, SEGMENTS>
+{
+}
@@ -0,0 +1,38 @@
+use crate::error::Error;
+use crate::segmented::SegmentedSorter;
+use crate::testing::scrambled;
+use core::num::NonZeroU8;
+
+const LEN: usize = 1000;
+
+fn items() -> [u32; LEN] {
+    core::array::from_fn(|i| scrambled(i, 0, 600))
+}
+
+#[test]
+fn merges_segments_in_order() {
+    let mut expected = items();
+    expected.sort_unstable();
+    let mut items = items();
+    // `u8` addresses 256 items: 4 segments.
+    let sorter = SegmentedSorter::<u32, u8, 4>::new(&mut items).unwrap();
+    assert_eq!((sorter.segments(), sorter.len()), (4, LEN));
+    assert!(sorter.copied().eq(expected));
+}
+
+#[test]
+fn positions_and_too_few_segments() {
+    let mut items = items();
+    assert!(matches!(
+        SegmentedSorter::<u32, NonZeroU8, 3>::new(&mut items),
+        Err(Error::CapacityExceeded)
+    ));
+    let original = self::items();
+    let mut sorter = SegmentedSorter::<u32, NonZeroU8, 4>::new(&mut items).unwrap();
+    let position = sorter.next_index().unwrap();
+    assert_eq!(sorter.len(), LEN - 1);
+    assert_eq!(original[position], 0);
+    let mut empty: [u8; 0] = [];
+    let mut sorter = SegmentedSorter::<u8>::new(&mut empty).unwrap();
+    assert_eq!((sorter.segments(), sorter.next()), (0, None));
+}
@@ -0,0 +1,182 @@
+//! Selection: by the lazy partitioning of [`Engine`] (fast on average), or with a worst-case
+//! guarantee.
+
+use crate::engine::{insertion_sort, partition_around, Engine, Pivot};
+use core::cmp::Ordering;
+
+#[cfg(test)]
+mod select_tests;
+
+/// Segments this short get insertion-sorted.
+const SMALL_SELECT_LEN: usize = 10;
+
+/// Like [`slice::select_nth_unstable_by()`], by quickselect with the pivots and the segment
+/// bookkeeping of [`Engine`]: partition `items` so that the item of rank `n` (0-based) ends up at
+/// position `n`. Return the items before it (not greater, in no particular order), that item, and
+/// the items after it (not less, in no particular order). Or [`None`] if `n` is out of range.
+///
+/// O(n) on average. No allocation.
+///
+/// ```
+/// use lazysort_no_alloc::select::select_nth;
+///
+/// let mut latencies = [40, 12, 95, 31, 18, 27, 60];
+/// let (faster, median, slower) = select_nth(&mut latencies, 3, &mut u32::cmp).unwrap();
+/// assert_eq!(*median, 31);
+/// assert!(faster.iter().all(|&latency| latency <= 31));
+/// assert_eq!(slower.len(), 3);
+/// ```
+pub fn select_nth<'a, T, CMP>(
+    items: &'a mut [T],
+    n: usize,
+    cmp: &mut CMP,
+) -> Option<(&'a mut [T], &'a mut T, &'a mut [T])>
+where
+    CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+{
+    if n >= items.len() {
+        return None;
+    }
+    let mut engine: Engine = Engine::try_new(items.len()).unwrap_or_else(|_| unreachable!());
+    engine.set_pivot(Pivot::TowardTarget);
+    engine.resolve_at(items, n, cmp);
+    let (before, rest) = items.split_at_mut(n);
+    let (nth, after) = rest.split_first_mut()?;
+    Some((before, nth, after))
+}
+
+/// The item at fractional rank `q * (len - 1)`: the two items around it (the same one twice, if
+/// the rank is whole), and how far between them it is (in `0.0..1.0`). For linear interpolation
+/// (as by the default of R and NumPy): `lower + (upper - lower) * fraction`. [`None`] if `items` is
+/// empty or `q` is not within `0.0..=1.0`.
+///
+/// One [`select_nth()`], and a scan for the minimum of the items after. Partitions `items` as
+/// [`select_nth()`] does (around the lower item).
+///
+/// ```
+/// use lazysort_no_alloc::select::quantile;
+///
+/// let mut latencies = [40, 12, 95, 31, 18, 27, 60];
+/// assert_eq!(quantile(&mut latencies, 0.5, &mut u32::cmp), Some((&31, &31, 0.0)));
+/// // The 90th percentile lies 40% of the way from 60 to 95.
+/// let (lower, upper, fraction) = quantile(&mut latencies, 0.9, &mut u32::cmp).unwrap();
+/// assert_eq!((lower, upper), (&60, &95));
+/// assert!((fraction - 0.4).abs() < 1e-9);
+/// ```
+pub fn quantile<'a, T, CMP>(
+    items: &'a mut [T],
+    q: f64,
+    cmp: &mut CMP,
+) -> Option<(&'a T, &'a T, f64)>
+where
+    CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+{
+    if items.is_empty() || !(0.0..=1.0).contains(&q) {
+        return None;
+    }
+    let rank = q * (items.len() - 1) as f64;
+    // Truncation is `floor()` here (`rank` isn't negative), which `core` lacks.
+    let lower_rank = (rank as usize).min(items.len() - 1);
+    let fraction = rank - lower_rank as f64;
+    let (_, lower, after) = select_nth(items, lower_rank, cmp)?;
+    let (lower, after) = (&*lower, &*after);
+    let upper = match fraction > 0.0 {
+        true => after.iter().min_by(|a, b| cmp(a, b)).unwrap_or(lower),
+        false => lower,
+    };
+    Some((lower, upper, fraction))
+}
+
+/// Both middle items: the same one twice for an odd number of `items`. [`None`] if empty. See
+/// [`quantile()`].
+///
+/// ```
+/// use lazysort_no_alloc::select::median;
+///
+/// let mut readings = [7, 1, 9, 4];
+/// assert_eq!(median(&mut readings, &mut i32::cmp), Some((&4, &7)));
+/// ```
+pub fn median<'a, T, CMP>(items: &'a mut [T], cmp: &mut CMP) -> Option<(&'a T, &'a T)>
+where
+    CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+{
+    quantile(items, 0.5, cmp).map(|(lower, upper, _)| (lower, upper))
+}
+
+/// Partition `items` so that the item of rank `k` (0-based) ends up at position `k`, the ones
+/// before it are not greater, and the ones after it are not less. Return that item, or [`None`] if
+/// `k` is out of range.
+///
+/// Pivots are medians of medians (of groups of five): O(n) in the worst case, unlike quickselect
+/// (and [`crate::engine::Engine`]), which is O(n) on average, but O(n²) on unlucky (or crafted)
+/// inputs. The constant factor is several times higher, though. Prefer this where predictable
+/// latency matters more than average speed.
+///
+/// No allocation. It recurses (only) to choose pivots: at most log₅(n) deep.
+///
+/// ```
+/// use lazysort_no_alloc::select::select_nth_guaranteed;
+///
+/// let mut items = [9, 1, 8, 2, 7, 3, 6, 4, 5];
+/// assert_eq!(select_nth_guaranteed(&mut items, 4, &mut i32::cmp), Some(&5));
+/// assert!(items[..4].iter().all(|&item| item < 5));
+/// ```
+pub fn select_nth_guaranteed<'a, T, CMP>(
+    items: &'a mut [T],
+    k: usize,
+    cmp: &mut CMP,
+) -> Option<&'a T>
+where
+    CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+{
+    if k >= items.len() {
+        return None;
+    }
+    select(items, k, cmp);
+    Some(&items[k])
+}
+
+/// [`select_nth_guaranteed()`] for (in range) `k`.
+fn select<T, CMP>(items: &mut [T], k: usize, cmp: &mut CMP)
+where
+    CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+{
+    // Invariant: rank `k` is within `lo..hi`, and `items` are partitioned around that range.
+    let (mut lo, mut hi) = (0, items.len());
+    loop {
+        let segment = &mut items[lo..hi];
+        if segment.len() <= SMALL_SELECT_LEN {
+            insertion_sort(segment, cmp);
+            return;
+        }
+        let pivot = median_of_medians(segment, cmp);
+        let (lt, gt) = partition_around(segment, pivot, cmp);
+        if k < lo + lt {
+            hi = lo + lt;
+        } else if k < lo + gt {
+            return;
+        } else {
+            lo += gt;
+        }
+    }
+}
+
+/// Position of a pivot that has at least (about) 3/10 of `items` on either side. It moves the
+/// median of each group of five to the front, and selects the median of those.
+fn median_of_medians<T, CMP>(items: &mut [T], cmp: &mut CMP) -> usize
+where
+    CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+{
+    let len = items.len();
+    let (mut medians, mut start) = (0, 0);
+    while start < len {
+        let group = &mut items[start..(start + 5).min(len)];
+        insertion_sort(group, cmp);
+        let median = start + (group.len() - 1) / 2;
+        items.swap(medians, median);
+        medians += 1;
+        start += 5;
+    }
+    select(&mut items[..medians], (medians - 1) / 2, cmp);
+    (medians - 1) / 2
+}
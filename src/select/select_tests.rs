@@ -0,0 +1,76 @@
+use crate::select::{median, quantile, select_nth, select_nth_guaranteed};
+
+const LEN: usize = 500;
+
+fn check(mut items: [u32; LEN]) {
+    let mut expected = items;
+    expected.sort_unstable();
+    for k in [0, 1, LEN / 3, LEN / 2, LEN - 2, LEN - 1] {
+        let selected = *select_nth_guaranteed(&mut items, k, &mut u32::cmp).unwrap();
+        assert_eq!(selected, expected[k]);
+        assert!(items[..k].iter().all(|&item| item <= selected));
+        assert!(items[k + 1..].iter().all(|&item| item >= selected));
+
+        let (before, nth, after) = select_nth(&mut items, k, &mut u32::cmp).unwrap();
+        assert_eq!(
+            (*nth, before.len(), after.len()),
+            (expected[k], k, LEN - k - 1)
+        );
+        assert!(before.iter().all(|item| item <= nth));
+        assert!(after.iter().all(|item| item >= nth));
+    }
+}
+
+#[test]
+fn shuffled_sorted_and_reversed() {
+    check(core::array::from_fn(|i| (i as u32 * 7919) % LEN as u32));
+    check(core::array::from_fn(|i| i as u32));
+    check(core::array::from_fn(|i| (LEN - i) as u32));
+}
+
+#[test]
+fn duplicates() {
+    check(core::array::from_fn(|i| (i as u32 * 31) % 7));
+    check([3; LEN]);
+}
+
+#[test]
+fn out_of_range() {
+    let mut items = [2, 1];
+    assert_eq!(select_nth_guaranteed(&mut items, 2, &mut i32::cmp), None);
+    assert_eq!(select_nth_guaranteed(&mut [0u8; 0], 0, &mut u8::cmp), None);
+    assert!(select_nth(&mut items, 2, &mut i32::cmp).is_none());
+    assert_eq!(
+        select_nth_guaranteed(&mut items, 1, &mut i32::cmp),
+        Some(&2)
+    );
+}
+
+#[test]
+fn median_and_quantiles() {
+    let mut items: [u32; LEN] = core::array::from_fn(|i| (i as u32 * 7919) % LEN as u32);
+    assert_eq!(median(&mut items, &mut u32::cmp), Some((&249, &250)));
+    // Odd: without the greatest item (the last, after the median above).
+    let greatest = items.iter().position(|&item| item == 499).unwrap();
+    items.swap(greatest, LEN - 1);
+    assert_eq!(
+        median(&mut items[..LEN - 1], &mut u32::cmp),
+        Some((&249, &249))
+    );
+    assert_eq!(
+        quantile(&mut items, 0.0, &mut u32::cmp),
+        Some((&0, &0, 0.0))
+    );
+    assert_eq!(
+        quantile(&mut items, 1.0, &mut u32::cmp),
+        Some((&499, &499, 0.0))
+    );
+    let (lower, upper, fraction) = quantile(&mut items, 0.25, &mut u32::cmp).unwrap();
+    assert_eq!((lower, upper), (&124, &125));
+    assert!((fraction - 0.75).abs() < 1e-9);
+
+    assert_eq!(median(&mut [5u8], &mut u8::cmp), Some((&5, &5)));
+    assert_eq!(median(&mut [0u8; 0], &mut u8::cmp), None);
+    assert_eq!(quantile(&mut items, 1.5, &mut u32::cmp), None);
+    assert_eq!(quantile(&mut items, f64::NAN, &mut u32::cmp), None);
+}
@@ -0,0 +1,237 @@
+//! Set operations on two ascending iterators (like the lazy sorters, for example
+//! [`crate::index_sort::IndexSorter`]), pulling each side only as far as needed. Hence, for
+//! example, the few smallest items present in both lists cost only as much sorting as it takes to
+//! reach them.
+//!
+//! The sides may contain duplicates (multi-sets): an item of one side matches (at most) one equal
+//! item of the other side. Matched pairs yield the item of the first side.
+//!
+//! ```
+//! use lazysort_no_alloc::index_sort::IndexSorter;
+//! use lazysort_no_alloc::set_ops::intersect_sorted;
+//!
+//! let (a, b) = ([9, 4, 7, 1, 3], [8, 3, 5, 7, 2, 9]);
+//! let (mut a_indices, mut b_indices) = ([0u8; 5], [0u8; 6]);
+//! let a = IndexSorter::new(&a, &mut a_indices).unwrap();
+//! let b = IndexSorter::new(&b, &mut b_indices).unwrap();
+//! // The two smallest common items. The greater ones don't get sorted.
+//! assert!(intersect_sorted(a, b).take(2).eq([&3, &7]));
+//! ```
+
+use core::cmp::Ordering;
+use core::fmt::{Debug, Formatter, Result as FmtResult};
+use core::iter::{FusedIterator, Peekable};
+
+#[cfg(test)]
+mod set_ops_tests;
+
+/// Items present in both `a` and `b` (both ascending), ascending. Stops as soon as either side
+/// runs out.
+pub fn intersect_sorted<T, A, B>(a: A, b: B) -> IntersectSorted<A::IntoIter, B::IntoIter>
+where
+    T: Ord,
+    A: IntoIterator<Item = T>,
+    B: IntoIterator<Item = T>,
+{
+    IntersectSorted(Sides::new(a, b))
+}
+
+/// Items of `a` not matched by `b` (both ascending), ascending. Pulls from `b` only up to the
+/// items of `a` being yielded.
+pub fn difference_sorted<T, A, B>(a: A, b: B) -> DifferenceSorted<A::IntoIter, B::IntoIter>
+where
+    T: Ord,
+    A: IntoIterator<Item = T>,
+    B: IntoIterator<Item = T>,
+{
+    DifferenceSorted(Sides::new(a, b))
+}
+
+/// Items of `a` and `b` (both ascending) merged, ascending, with each matched pair yielded once.
+pub fn union_sorted<T, A, B>(a: A, b: B) -> UnionSorted<A::IntoIter, B::IntoIter>
+where
+    T: Ord,
+    A: IntoIterator<Item = T>,
+    B: IntoIterator<Item = T>,
+{
+    UnionSorted(Sides::new(a, b))
+}
+
+/// `Clone` and `Debug` for a wrapper of [`Sides`], as far as [`Peekable`] has them. (Deriving
+/// would require them of the items of the sides, rather than of the sides.)
+macro_rules! clone_and_debug {
+    ($name:ident) => {
+        impl<A: Iterator, B: Iterator> Clone for $name<A, B>
+        where
+            Sides<A, B>: Clone,
+        {
+            fn clone(&self) -> Self {
+                Self(self.0.clone())
+            }
+        }
+
+        impl<A: Iterator, B: Iterator> Debug for $name<A, B>
+        where
+            Sides<A, B>: Debug,
+        {
+            fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+                f.debug_tuple(stringify!($name)).field(&self.0).finish()
+            }
+        }
+    };
+}
+
+/// Both sides, with a look-ahead of (at most) one item each.
+struct Sides<A: Iterator, B: Iterator> {
+    a: Peekable<A>,
+    b: Peekable<B>,
+}
+
+impl<A: Iterator, B: Iterator> Clone for Sides<A, B>
+where
+    Peekable<A>: Clone,
+    Peekable<B>: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            a: self.a.clone(),
+            b: self.b.clone(),
+        }
+    }
+}
+
+impl<A: Iterator, B: Iterator> Debug for Sides<A, B>
+where
+    Peekable<A>: Debug,
+    Peekable<B>: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("Sides")
+            .field("a", &self.a)
+            .field("b", &self.b)
+            .finish()
+    }
+}
+
+impl<T: Ord, A: Iterator<Item = T>, B: Iterator<Item = T>> Sides<A, B> {
+    fn new<IA, IB>(a: IA, b: IB) -> Self
+    where
+        IA: IntoIterator<IntoIter = A>,
+        IB: IntoIterator<IntoIter = B>,
+    {
+        Self {
+            a: a.into_iter().peekable(),
+            b: b.into_iter().peekable(),
+        }
+    }
+
+    /// Compare the next items of both sides. [`None`] if either side has run out.
+    fn cmp_next(&mut self) -> Option<Ordering> {
+        let (a, b) = (self.a.peek()?, self.b.peek()?);
+        Some(a.cmp(b))
+    }
+}
+
+/// Iterator returned by [`intersect_sorted()`].
+pub struct IntersectSorted<A: Iterator, B: Iterator>(Sides<A, B>);
+clone_and_debug!(IntersectSorted);
+
+impl<T: Ord, A: Iterator<Item = T>, B: Iterator<Item = T>> Iterator for IntersectSorted<A, B> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let sides = &mut self.0;
+        loop {
+            match sides.cmp_next()? {
+                Ordering::Less => drop(sides.a.next()),
+                Ordering::Greater => drop(sides.b.next()),
+                Ordering::Equal => {
+                    sides.b.next();
+                    return sides.a.next();
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (a, b) = (self.0.a.size_hint().1, self.0.b.size_hint().1);
+        let upper = match (a, b) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        (0, upper)
+    }
+}
+
+impl<T: Ord, A: FusedIterator<Item = T>, B: FusedIterator<Item = T>> FusedIterator
+    for IntersectSorted<A, B>
+{
+}
+
+/// Iterator returned by [`difference_sorted()`].
+pub struct DifferenceSorted<A: Iterator, B: Iterator>(Sides<A, B>);
+clone_and_debug!(DifferenceSorted);
+
+impl<T: Ord, A: Iterator<Item = T>, B: Iterator<Item = T>> Iterator for DifferenceSorted<A, B> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let sides = &mut self.0;
+        loop {
+            match sides.cmp_next() {
+                // `b` has run out (or `a` has, and then this yields `None`).
+                None | Some(Ordering::Less) => return sides.a.next(),
+                Some(Ordering::Greater) => drop(sides.b.next()),
+                Some(Ordering::Equal) => {
+                    sides.a.next();
+                    sides.b.next();
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.0.a.size_hint().1)
+    }
+}
+
+impl<T: Ord, A: FusedIterator<Item = T>, B: FusedIterator<Item = T>> FusedIterator
+    for DifferenceSorted<A, B>
+{
+}
+
+/// Iterator returned by [`union_sorted()`].
+pub struct UnionSorted<A: Iterator, B: Iterator>(Sides<A, B>);
+clone_and_debug!(UnionSorted);
+
+impl<T: Ord, A: Iterator<Item = T>, B: Iterator<Item = T>> Iterator for UnionSorted<A, B> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let sides = &mut self.0;
+        match sides.cmp_next() {
+            Some(Ordering::Less) => sides.a.next(),
+            Some(Ordering::Greater) => sides.b.next(),
+            Some(Ordering::Equal) => {
+                sides.b.next();
+                sides.a.next()
+            }
+            // Either side has run out: the rest of the other one.
+            None => sides.a.next().or_else(|| sides.b.next()),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (a, b) = (self.0.a.size_hint(), self.0.b.size_hint());
+        let upper = match (a.1, b.1) {
+            (Some(a), Some(b)) => a.checked_add(b),
+            _ => None,
+        };
+        (a.0.max(b.0), upper)
+    }
+}
+
+impl<T: Ord, A: FusedIterator<Item = T>, B: FusedIterator<Item = T>> FusedIterator
+    for UnionSorted<A, B>
+{
+}
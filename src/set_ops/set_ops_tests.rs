@@ -0,0 +1,78 @@
+use crate::index_sort::IndexSorter;
+use crate::set_ops::{difference_sorted, intersect_sorted, union_sorted};
+use core::cell::Cell;
+
+const A: [u32; 8] = [1, 2, 2, 2, 5, 8, 9, 9];
+const B: [u32; 7] = [0, 2, 2, 5, 6, 9, 12];
+
+#[test]
+fn intersect() {
+    assert!(intersect_sorted(A, B).eq([2, 2, 5, 9]));
+    assert!(intersect_sorted(B, A).eq([2, 2, 5, 9]));
+    assert_eq!(intersect_sorted(A, []).next(), None);
+}
+
+#[test]
+fn difference() {
+    assert!(difference_sorted(A, B).eq([1, 2, 8, 9]));
+    assert!(difference_sorted(B, A).eq([0, 6, 12]));
+    assert!(difference_sorted(A, []).eq(A));
+    assert_eq!(difference_sorted([], A).next(), None);
+}
+
+#[test]
+fn union() {
+    assert!(union_sorted(A, B).eq([0, 1, 2, 2, 2, 5, 6, 8, 9, 9, 12]));
+    assert!(union_sorted(B, A).eq([0, 1, 2, 2, 2, 5, 6, 8, 9, 9, 12]));
+    assert!(union_sorted([], B).eq(B));
+    assert_eq!(union_sorted(A, B).size_hint(), (8, Some(15)));
+}
+
+/// Matched pairs yield the item of the first side.
+#[test]
+fn first_side_wins() {
+    /// Compares by the key only.
+    #[derive(Clone, Copy, Debug)]
+    struct Keyed(u32, char);
+    impl PartialEq for Keyed {
+        fn eq(&self, other: &Self) -> bool {
+            self.0 == other.0
+        }
+    }
+    impl Eq for Keyed {}
+    impl PartialOrd for Keyed {
+        fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+    impl Ord for Keyed {
+        fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+            self.0.cmp(&other.0)
+        }
+    }
+    let a = [Keyed(1, 'a'), Keyed(2, 'a')];
+    let b = [Keyed(1, 'b'), Keyed(3, 'b')];
+    let tags = |iter: &mut dyn Iterator<Item = Keyed>| iter.map(|k| k.1).eq(['a', 'a', 'b']);
+    assert!(tags(&mut union_sorted(a, b)));
+    assert_eq!(intersect_sorted(a, b).next().map(|k| k.1), Some('a'));
+    assert_eq!(intersect_sorted(b, a).next().map(|k| k.1), Some('b'));
+}
+
+/// Only as much of the sides gets pulled (hence sorted) as needed.
+#[test]
+fn lazy() {
+    let data_a: [u32; 100] = core::array::from_fn(|i| (i as u32 * 37) % 100);
+    let data_b: [u32; 100] = core::array::from_fn(|i| (i as u32 * 41) % 100 + 50);
+    let (mut indices_a, mut indices_b) = ([0u8; 100], [0u8; 100]);
+    let pulled = Cell::new(0);
+    let a = IndexSorter::new(&data_a, &mut indices_a)
+        .unwrap()
+        .inspect(|_| pulled.set(pulled.get() + 1));
+    let b = IndexSorter::new(&data_b, &mut indices_b)
+        .unwrap()
+        .inspect(|_| pulled.set(pulled.get() + 1));
+    let mut common = intersect_sorted(a, b);
+    assert_eq!(common.next(), Some(&50));
+    // 51 of `a` (0..=50, the last one peeked), and 1 of `b`.
+    assert_eq!(pulled.get(), 52);
+}
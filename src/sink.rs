@@ -0,0 +1,96 @@
+//! Sorted output pushed straight into a consumer (a caller's buffer, a serializer, a DMA-backed
+//! region...), rather than pulled through an [`Iterator`]: see [`Sink`] and [`drain_into()`].
+//!
+//! ```
+//! use core::ops::ControlFlow;
+//! use lazysort_no_alloc::sink::drain_into;
+//!
+//! let mut items = [50, 20, 40, 10, 30];
+//! let mut report = [0u8; 8];
+//! // Stop once the items reach 30: the ones after don't get sorted at all.
+//! let drained = drain_into(&mut items, &mut |rank, item: u32| {
+//!     report[rank] = item as u8;
+//!     if item < 30 { ControlFlow::Continue(()) } else { ControlFlow::Break(()) }
+//! });
+//! assert_eq!((drained, &report[..3]), (3, &[10, 20, 30][..]));
+//! ```
+
+use crate::engine::Engine;
+use core::ops::ControlFlow;
+
+#[cfg(test)]
+mod sink_tests;
+
+/// A consumer of sorted items, one at a time.
+///
+/// Implemented for closures `FnMut(usize, T) -> ControlFlow<()>`, and by [`SliceSink`].
+pub trait Sink<T> {
+    /// Take `item`: the one of `rank` (the number of items accepted before it, in this drain).
+    /// Return [`ControlFlow::Break`] to stop the drain (after this item).
+    fn accept(&mut self, rank: usize, item: T) -> ControlFlow<()>;
+}
+
+impl<T, F> Sink<T> for F
+where
+    F: FnMut(usize, T) -> ControlFlow<()>,
+{
+    fn accept(&mut self, rank: usize, item: T) -> ControlFlow<()> {
+        self(rank, item)
+    }
+}
+
+/// A [`Sink`] that fills a caller-provided buffer, in order, and stops the drain once it's full.
+#[derive(Debug)]
+pub struct SliceSink<'a, T> {
+    out: &'a mut [T],
+    len: usize,
+}
+
+impl<'a, T> SliceSink<'a, T> {
+    /// An empty `out` stops the drain right away (dropping the one item drained).
+    pub fn new(out: &'a mut [T]) -> Self {
+        Self { out, len: 0 }
+    }
+
+    /// The part of the buffer filled so far.
+    pub fn filled(&self) -> &[T] {
+        &self.out[..self.len]
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == self.out.len()
+    }
+}
+
+impl<T> Sink<T> for SliceSink<'_, T> {
+    fn accept(&mut self, _rank: usize, item: T) -> ControlFlow<()> {
+        let Some(slot) = self.out.get_mut(self.len) else {
+            return ControlFlow::Break(());
+        };
+        *slot = item;
+        self.len += 1;
+        match self.is_full() {
+            true => ControlFlow::Break(()),
+            false => ControlFlow::Continue(()),
+        }
+    }
+}
+
+/// Feed (clones of) `items` into `sink`, smallest first, until `sink` breaks or the items run out.
+/// Return the number of items fed. Sorts `items` in place, only as far as fed: the sorted ones
+/// first, the rest (partitioned) after.
+pub fn drain_into<T, SINK>(items: &mut [T], sink: &mut SINK) -> usize
+where
+    T: Ord + Clone,
+    SINK: Sink<T> + ?Sized,
+{
+    let mut engine: Engine = Engine::try_new(items.len()).unwrap_or_else(|_| unreachable!());
+    let mut rank = 0;
+    while let Some(position) = engine.next_front(&mut items[engine.front()..], &mut T::cmp) {
+        rank += 1;
+        if sink.accept(rank - 1, items[position].clone()).is_break() {
+            break;
+        }
+    }
+    rank
+}
@@ -0,0 +1,45 @@
+use crate::sink::{drain_into, Sink, SliceSink};
+use core::ops::ControlFlow;
+
+#[test]
+fn slice_sink() {
+    let mut items: [u32; 100] = core::array::from_fn(|i| (i as u32 * 37 + 11) % 100);
+    let mut buffer = [0; 10];
+    let mut sink = SliceSink::new(&mut buffer);
+    assert_eq!(drain_into(&mut items, &mut sink), 10);
+    assert!(sink.is_full());
+    assert!(sink.filled().iter().copied().eq(0..10));
+    // Sorted as far as drained.
+    assert!(items[..10].iter().copied().eq(0..10));
+
+    let mut all = [0; 200];
+    let mut sink = SliceSink::new(&mut all);
+    assert_eq!(drain_into(&mut items, &mut sink), 100);
+    assert!(!sink.is_full());
+    assert!(sink.filled().iter().copied().eq(0..100));
+
+    let mut empty: [u32; 0] = [];
+    let mut sink = SliceSink::new(&mut empty);
+    assert_eq!(sink.accept(0, 7), ControlFlow::Break(()));
+    assert!(sink.filled().is_empty());
+}
+
+#[test]
+fn closure_sink_ranks_and_break() {
+    let mut items = [9, 3, 7, 1, 5];
+    let mut seen = [(0, 0); 5];
+    let drained = drain_into(&mut items, &mut |rank, item: u8| {
+        seen[rank] = (rank, item);
+        match rank {
+            2 => ControlFlow::Break(()),
+            _ => ControlFlow::Continue(()),
+        }
+    });
+    assert_eq!(drained, 3);
+    assert_eq!(seen[..3], [(0, 1), (1, 3), (2, 5)]);
+
+    assert_eq!(
+        drain_into(&mut [0u8; 0], &mut |_, _| ControlFlow::Continue(())),
+        0
+    );
+}
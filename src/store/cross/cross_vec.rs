@@ -1,13 +1,23 @@
 //! Restricted functionality, crossing data access with other structures in an `unsafe` way.
 
-use crate::store::lifos::lifos_vec::FixedDequeLifos;
+use crate::calloc::calloc_vec::{self, VecDeque};
+use crate::calloc::{Allocator, Global};
+use crate::error::Error;
+use crate::store::lifos::lifos_vec::{CapacityPolicy, FixedDequeLifos};
+use crate::store::lifos::Lifos;
+use crate::sync::Counter;
 use alloc::vec::Vec;
 use core::fmt::{Debug, Formatter, Result as FmtResult};
-use core::mem;
+use core::mem::{self, ManuallyDrop};
+use core::ops::{Deref, DerefMut};
+use core::ptr;
 
 #[cfg(test)]
 mod cross_vec_tests;
 
+/// Source of [`CrossVecPairGuard`] generations.
+static GENERATIONS: Counter = Counter::new();
+
 // ======= CrossVec:
 #[cfg(not(feature = "nightly_guard_cross_alloc"))]
 pub type CrossVec<T> = Vec<T>;
@@ -25,12 +35,96 @@ pub type CrossVec<T> = Vec<T>;
 pub type CrossVec<T> = Vec<T>;
 // ======= end of: CrossVec
 
+/// A [`CrossVec`] that can't re-allocate: it can't grow beyond its capacity (no `push` past it, no
+/// `reserve`), and it can't shrink its capacity either. Its buffer is (a part of) someone else's
+/// allocation, so dropping a [`FixedCapVec`] drops its items, but it never frees the buffer.
+pub struct FixedCapVec<T>(ManuallyDrop<CrossVec<T>>);
+
+impl<T> FixedCapVec<T> {
+    /// `vec`'s buffer will never be freed (through the result).
+    pub(crate) fn new(vec: CrossVec<T>) -> Self {
+        Self(ManuallyDrop::new(vec))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Fixed, for the whole lifetime.
+    pub fn capacity(&self) -> usize {
+        self.0.capacity()
+    }
+
+    /// Append `value`, unless the capacity is full: then give it back.
+    pub fn try_push(&mut self, value: T) -> Result<(), T> {
+        if self.0.len() == self.0.capacity() {
+            return Err(value);
+        }
+        self.0.push(value);
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.0.pop()
+    }
+
+    /// Like [`Vec::truncate()`]: the capacity stays.
+    pub fn truncate(&mut self, len: usize) {
+        self.0.truncate(len);
+    }
+
+    /// Like [`Vec::clear()`]: the capacity stays.
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    /// The underlying [`CrossVec`], for this crate only. It MUST NOT re-allocate, nor get dropped.
+    pub(crate) fn into_inner(self) -> CrossVec<T> {
+        let mut this = ManuallyDrop::new(self);
+        unsafe { ManuallyDrop::take(&mut this.0) }
+    }
+}
+
+impl<T> Deref for FixedCapVec<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for FixedCapVec<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        &mut self.0
+    }
+}
+
+impl<T: Debug> Debug for FixedCapVec<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        <[T] as Debug>::fmt(self, f)
+    }
+}
+
+impl<T> Drop for FixedCapVec<T> {
+    fn drop(&mut self) {
+        // Drop the items, but leave the buffer (and the `Vec` itself) alone.
+        unsafe { ptr::drop_in_place(self.0.as_mut_slice()) };
+    }
+}
+
 /// "Front" and "back" RESTRICTED [`Vec`]-s (in this order). Each based on the respective part of
 /// the [`alloc::collections::VecDeque`] that was a part of [`FixedDequeLifos`] used to create the
 /// [`CrossVecPairGuard`] which (in turn) has created this [`CrossVecPair`] instance.
 ///
-/// You MUST NOT exceed the existing capacity of these [`Vec`]-s (neither shrink them, or cause any
-/// re-allocation)!
+/// They are [`FixedCapVec`]s, so they can't exceed their existing capacity, shrink it, or cause
+/// any re-allocation.
+///
+/// It carries (privately) the generation of its [`CrossVecPairGuard`], so that it can't be moved
+/// back to another guard: see [`CrossVecPairGuard::check_pair()`].
 ///
 /// "non_exhaustive" so that
 /// - clients can't instantiate this. Also,
@@ -38,7 +132,7 @@ pub type CrossVec<T> = Vec<T>;
 ///   the clients.
 #[non_exhaustive]
 #[derive(Debug)]
-pub struct CrossVecPair<T>(pub CrossVec<T>, pub CrossVec<T>);
+pub struct CrossVecPair<T>(pub FixedCapVec<T>, pub FixedCapVec<T>, usize);
 
 enum CrossVecPairGuardState<T> {
     /// The two [`Vec`]s correspond to [`FixedDequeLifos::front()`] & [`FixedDequeLifos::back()`],
@@ -53,10 +147,11 @@ enum CrossVecPairGuardState<T> {
     #[cfg(not(feature = "nightly_guard_cross_cleanup"))]
     TakenOut,
     #[cfg(feature = "nightly_guard_cross_cleanup")]
-    /// TODO a field with 2x Arc - one per Vec.
+    /// TODO a field with 2x [`crate::sync::Shared`] - one per Vec.
     ///
-    /// Using [Arc], instead of [Rc], in case [`CrossVecPair`] or any of its [`Vec`]-s is sent to a
-    /// different thread and gets dropped there.
+    /// Using `Arc` (which [`crate::sync::Shared`] is, where atomics are available), instead of
+    /// `Rc`, in case [`CrossVecPair`] or any of its [`Vec`]-s is sent to a different thread and
+    /// gets dropped there.
     TakenOut,
     MovedBack,
 }
@@ -83,50 +178,98 @@ impl<T> Debug for CrossVecPairGuardState<T> {
 
 /// A wrapper around two [`Vec`]s based on (backed by, shadowing) the same [`FixedDequeLifos`].
 ///
-/// At the end of use, call [`CrossVecPair::forget()`]. Do not let it go out of scope in any other
-/// way
-/// - otherwise its [`Drop::drop()`] will panic.
+/// Once you've taken the pair out (see [`CrossVecPairGuard::try_temp_take()`]), give it back with
+/// [`CrossVecPairGuard::try_move_back_join_into()`]. Do not let the guard go out of scope before
+/// that - otherwise its [`Drop::drop()`] panics in debug builds (and the buffer leaks). A guard
+/// whose pair was never taken out frees the buffer (and drops the items) on drop.
+///
+/// It's safe to move in any state (and it's [`Unpin`] if `T` and `A` are): no field points into
+/// the guard itself (or into the [`CrossVecPair`]). Its pointers point into the heap buffer of the
+/// original [`alloc::collections::VecDeque`], which never moves (nor gets freed) for as long as the
+/// guard and the pair live. So the guard may be returned from functions, boxed, or stored in other
+/// structs, before or after "taking" the pair.
 //
 // After use, the original [`FixedDequeLifos::vec_deque`] would be corrupted if still kept around!
-pub struct CrossVecPairGuard<T> {
+pub struct CrossVecPairGuard<T, A: Allocator = Global> {
     state: CrossVecPairGuardState<T>,
     orig_front_len: usize,
     orig_back_len: usize,
+    /// Start of the original buffer, and of its front part (on the heap, so not affected by moving
+    /// `self`). Kept to verify the pair that gets moved back, and to join it.
     front_ptr: *mut T,
+    /// Start of the back part: right after the front part. See `front_ptr`.
     back_ptr: *mut T,
     /// Potentially MORE than the total of capacities of both [`Vec`]-s "temporarily taken out" in
     /// the generated [`CrossVecPair`]. Why? because `full_capacity` is the capacity of the original
-    /// [`alloc::collections::VecDeque`].
+    /// [`alloc::collections::VecDeque`]: it includes the free capacity of the [`FixedDequeLifos`],
+    /// and its slack.
     full_capacity: usize,
+    /// [`FixedDequeLifos::slack()`]: the part of `full_capacity` that the [`FixedDequeLifos`]
+    /// didn't use (as opposed to not filling it).
+    slack: usize,
+    /// The allocator of the original [`FixedDequeLifos`]' [`alloc::collections::VecDeque`]. Moved
+    /// (out) into the joined [`Vec`], see [`CrossVecPairGuard::try_move_back_join_into()`].
+    alloc: ManuallyDrop<A>,
+    /// Distinct from the generation of any other guard (alive or not, until it wraps around after
+    /// [`usize::MAX`] guards). Carried by the pair, too.
+    generation: usize,
 }
-impl<T> From<FixedDequeLifos<T>> for CrossVecPairGuard<T> {
-    fn from(lifos: FixedDequeLifos<T>) -> Self {
-        let mut vec_deque = lifos.into_vec_deque();
-        let (front, back) = vec_deque.as_mut_slices();
-
-        let orig_front_len = front.len();
-        let orig_back_len = back.len();
+/// Shows the state, the original front & back lengths, the full capacity and the slack - but not
+/// the items (hence no `T: Debug` needed), and not the pointers.
+impl<T, A: Allocator> Debug for CrossVecPairGuard<T, A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("CrossVecPairGuard")
+            .field("state", &self.state)
+            .field("orig_front_len", &self.orig_front_len)
+            .field("orig_back_len", &self.orig_back_len)
+            .field("full_capacity", &self.full_capacity)
+            .field("slack", &self.slack)
+            .field("generation", &self.generation)
+            .finish()
+    }
+}
+impl<T, A: Allocator, P: CapacityPolicy> From<FixedDequeLifos<T, A, P>>
+    for CrossVecPairGuard<T, A>
+{
+    fn from(lifos: FixedDequeLifos<T, A, P>) -> Self {
+        let slack = lifos.slack();
+        Self::from_vec_deque(lifos.into_vec_deque(), slack)
+    }
+}
+impl<T, A: Allocator> CrossVecPairGuard<T, A> {
+    fn from_vec_deque(vec_deque: VecDeque<T, A>, slack: usize) -> Self {
+        let (front, back) = vec_deque.as_slices();
+        let (orig_front_len, orig_back_len) = (front.len(), back.len());
+        // The front items, then the back ones, from the start of the buffer. That never
+        // re-allocates, and it moves the items only if the deque wraps around. Never dropped: its
+        // buffer lives on in the pair.
+        let mut vec = ManuallyDrop::new(calloc_vec::Vec::from(vec_deque));
 
-        let front_ptr = front.as_mut_ptr();
-        let back_ptr = back.as_mut_ptr();
+        let front_ptr = vec.as_mut_ptr();
+        let back_ptr = unsafe { front_ptr.add(orig_front_len) };
 
         let front = unsafe { Vec::from_raw_parts(front_ptr, orig_front_len, orig_front_len) };
         let back = unsafe { Vec::from_raw_parts(back_ptr, orig_back_len, orig_back_len) };
+        let (front, back) = (FixedCapVec::new(front), FixedCapVec::new(back));
 
-        let full_capacity = vec_deque.capacity();
+        let full_capacity = vec.capacity();
+        // Moved out (rather than cloned), since `vec` itself never gets dropped.
+        let alloc = ManuallyDrop::new(unsafe { ptr::read(vec.allocator()) });
 
-        mem::forget(vec_deque);
+        let generation = GENERATIONS.next();
         Self {
-            state: CrossVecPairGuardState::NotTakenYet(CrossVecPair(front, back)),
+            state: CrossVecPairGuardState::NotTakenYet(CrossVecPair(front, back, generation)),
             orig_front_len,
             orig_back_len,
             front_ptr,
             back_ptr,
             full_capacity,
+            slack,
+            alloc,
+            generation,
         }
     }
-}
-impl<T> CrossVecPairGuard<T> {
+
     /// TODO: Should this be marked as `unsafe`? But: this function itself does NOT cause any
     /// undefined behavior. Its inappropriate use of [`Vec`]-s from a [`CrossVecPair`] "taken" from
     /// a [`CrossVecPairGuard`] that can lead to undefined behavior.
@@ -141,10 +284,47 @@ impl<T> CrossVecPairGuard<T> {
     /// You MUST not let a [`CrossVecPairGuard`] instance go out of scope without taking the pair
     /// out & then putting it back and discarding as per above.
     #[must_use]
-    pub fn new_from_lifos(fixed_deque_lifos: FixedDequeLifos<T>) -> Self {
+    pub fn new_from_lifos<P: CapacityPolicy>(fixed_deque_lifos: FixedDequeLifos<T, A, P>) -> Self {
         fixed_deque_lifos.into()
     }
 
+    /// Like [`CrossVecPairGuard::new_from_lifos()`], but first (even in release) verify that the
+    /// backing [`alloc::collections::VecDeque`]'s slices correspond to the LEFT & RIGHT lengths of
+    /// `fixed_deque_lifos`, and that they lie within its one allocation. Otherwise return
+    /// [`Error::NotContiguous`] (and drop `fixed_deque_lifos` as usual).
+    ///
+    /// [`FixedDequeLifos`] maintains that, so this guards against (future) storage backends that
+    /// don't.
+    pub fn try_from_lifos<P: CapacityPolicy>(
+        fixed_deque_lifos: FixedDequeLifos<T, A, P>,
+    ) -> Result<Self, Error> {
+        let (left, right) = (fixed_deque_lifos.left(), fixed_deque_lifos.right());
+        let slack = fixed_deque_lifos.slack();
+        let vec_deque = fixed_deque_lifos.into_vec_deque();
+        let (front, back) = vec_deque.as_slices();
+        // RIGHT items (if any) come first. See `FixedDequeLifos::debug_assert_consistent()`.
+        let expected_lens = if right > 0 { (right, left) } else { (left, 0) };
+        if (front.len(), back.len()) != expected_lens
+            || !is_within_one_allocation(front, back, vec_deque.capacity())
+        {
+            trace_event!(warn, left, right, "lifos not contiguous");
+            return Err(Error::NotContiguous);
+        }
+        Ok(Self::from_vec_deque(vec_deque, slack))
+    }
+
+    /// Capacity of the original [`alloc::collections::VecDeque`] that the [`FixedDequeLifos`] this
+    /// was created from didn't use (see [`FixedDequeLifos::slack()`]). The free capacity of the
+    /// [`CrossVecPair`] is the rest of the full capacity beyond its lengths.
+    pub fn slack(&self) -> usize {
+        self.slack
+    }
+
+    /// The allocator of the [`FixedDequeLifos`] this was created from.
+    pub fn allocator(&self) -> &A {
+        &self.alloc
+    }
+
     /// "Take" the (whole). Like "moving out".
     ///
     /// We need this temporary "move out" ability, so that we can then transform the [`Vec`]
@@ -154,6 +334,10 @@ impl<T> CrossVecPairGuard<T> {
     ///
     /// Once you're finished using the [`CrossVecPair`], undo this with
     /// [CrossVecPairGuard::move_back_join_into()].
+    ///
+    /// Not available with crate feature `no-panic`. Use [`CrossVecPairGuard::try_temp_take()`]
+    /// instead.
+    #[cfg(not(feature = "no-panic"))]
     #[must_use]
     pub fn temp_take(&mut self) -> CrossVecPair<T> {
         // self.state does get checked later in this function, too - and even in release.
@@ -164,7 +348,7 @@ impl<T> CrossVecPairGuard<T> {
 
         let previous_state = mem::replace(&mut self.state, CrossVecPairGuardState::TakenOut);
         let CrossVecPairGuardState::NotTakenYet(pair) = previous_state else {
-            panic!("Expecting the CrossVecPair NOT to be taken out yet. But CrossVecPairGuard::state is: {:?}.", self.state);
+            release_panic!("Expecting the CrossVecPair NOT to be taken out yet. But CrossVecPairGuard::state is: {:?}.", self.state);
             // It gets checked by the following,
         };
         pair
@@ -179,49 +363,126 @@ impl<T> CrossVecPairGuard<T> {
         */
     }
 
+    /// Like [`CrossVecPairGuard::temp_take()`], but if the pair is NOT available (any more), return
+    /// [`Error::InvalidState`] (and leave the state unchanged) instead of panicking.
+    pub fn try_temp_take(&mut self) -> Result<CrossVecPair<T>, Error> {
+        match mem::replace(&mut self.state, CrossVecPairGuardState::TakenOut) {
+            CrossVecPairGuardState::NotTakenYet(pair) => Ok(pair),
+            previous_state => {
+                self.state = previous_state;
+                Err(Error::InvalidState)
+            }
+        }
+    }
+
     #[inline(always)]
     fn debug_assert_consistent(&self, pair: &CrossVecPair<T>) {}
 
-    /// Safely discard the given [`CrossVecPair`] that was "taken" from this [`CrossVecPairGuard`]
-    /// instance, and discard this this [`CrossVecPairGuard`] instance itself.
-    ///
-    /// Check that the parameter `pair` are [`Vec`]s based on this [`CrossVecPairGuard`] instance.
-    /// Then "move" the pair back, move any leftover items from the "back" (right) side to the
-    /// "front" (left) side. Then consume this [`CrossVecPairGuard`] instance (without releasing
-    /// any memory), and transform it back to a single [`Vec`] (but NOT back to a
-    /// [`alloc::collections::VecDeque`], since in such a stage this crate doesn't need it as
-    /// [`alloc::collections::VecDeque`] anymore. The result [`Vec`]) will have its `capacity` same
-    /// as the original [`alloc::collections::VecDeque`].
+    /// Check (even in release) that `pair` was taken from this guard: not from another one - not
+    /// even from an earlier guard over the same buffer (which the pointers can't tell apart).
+    /// Return [`Error::InvalidArgument`] otherwise.
+    pub fn check_pair(&self, pair: &CrossVecPair<T>) -> Result<(), Error> {
+        if pair.2 != self.generation {
+            trace_event!(warn, pair = pair.2, guard = self.generation, "foreign pair");
+            return Err(Error::InvalidArgument);
+        }
+        Ok(())
+    }
+
+    /// Give back the [`CrossVecPair`] that was "taken" from this [`CrossVecPairGuard`], and join
+    /// its items into a single [`Vec`]: the (remaining) items of `pair.0`, then those of `pair.1`.
+    /// That [`Vec`] owns the original buffer (and its allocator): its capacity is the full
+    /// capacity of the original [`alloc::collections::VecDeque`]. It doesn't re-allocate, and it
+    /// moves the items of `pair.1` only. Re-use the result where you can, so as to minimize
+    /// allocations (this crate's main purpose).
     ///
-    /// You MUST call this before the instance (if you "took" a [CrossVecPair] from it) before this
-    /// ([`CrossVecPairGuard`] instance) goes out of scope.
+    /// Panic (even in release) if `pair` was not taken from this guard, or if it was given back
+    /// already.
     ///
-    /// You don't have to re-use this function's result [`Vec`]. But it's advantageous to re-use it,
-    /// so as to minimize reallocation (which is this crate's main purpose).
+    /// Not available with crate feature `no-panic`. Use
+    /// [`CrossVecPairGuard::try_move_back_join_into()`] instead.
+    #[cfg(not(feature = "no-panic"))]
     #[must_use]
-    pub fn move_back_join_into(mut self, pair: CrossVecPair<T>) -> Vec<T> {
-        debug_assert!(
-            self.state.is_taken_out(),
-            "Expecting CrossVecPairGuardState to be 'taken out', but it's: {:?}.",
-            self.state
-        );
-        // TODO should these asserts be run also in release?
-        debug_assert_eq!(pair.0.as_ptr(), self.front_ptr);
-        debug_assert_eq!(pair.1.as_ptr(), self.back_ptr);
+    pub fn move_back_join_into(mut self, pair: CrossVecPair<T>) -> calloc_vec::Vec<T, A> {
+        match self.try_move_back_join_into(pair) {
+            Ok(vec) => vec,
+            Err((error, _pair)) => release_panic!(
+                "Can't move the CrossVecPair back to this CrossVecPairGuard: {:?}.",
+                error
+            ),
+        }
+    }
+
+    /// Like [`CrossVecPairGuard::move_back_join_into()`], but rather than panicking, give `pair`
+    /// back (and leave the guard as it was), with
+    /// - [`Error::InvalidArgument`] if `pair` was taken from another guard (see
+    ///   [`CrossVecPairGuard::check_pair()`]), or
+    /// - [`Error::InvalidState`] if the pair is not taken out (any more).
+    pub fn try_move_back_join_into(
+        &mut self,
+        pair: CrossVecPair<T>,
+    ) -> Result<calloc_vec::Vec<T, A>, (Error, CrossVecPair<T>)> {
+        if !self.state.is_taken_out() {
+            return Err((Error::InvalidState, pair));
+        }
+        if let Err(error) = self.check_pair(&pair) {
+            return Err((error, pair));
+        }
+        // The same generation, so the same buffer: a `FixedCapVec` never re-allocates.
+        debug_assert_eq!(pair.0.as_ptr(), self.front_ptr as *const T);
+        debug_assert_eq!(pair.1.as_ptr(), self.back_ptr as *const T);
         debug_assert!(pair.0.len() <= self.orig_front_len);
         debug_assert!(pair.1.len() <= self.orig_back_len);
-        debug_assert!(pair.0.capacity() == self.orig_front_len);
-        debug_assert!(pair.1.capacity() == self.orig_back_len);
-        let CrossVecPair(front, back) = pair;
-        mem::forget(front);
-        mem::forget(back);
+        let CrossVecPair(front, back, _) = pair;
+        let (front, back) = (front.into_inner(), back.into_inner());
+        let (front_len, back_len) = (front.len(), back.len());
+        // Their buffers live on in the result.
+        mem::forget((front, back));
+        // The back items right after the (remaining) front items. The ranges may overlap.
+        unsafe { ptr::copy(self.back_ptr, self.front_ptr.add(front_len), back_len) };
 
         self.state = CrossVecPairGuardState::MovedBack;
-        todo!()
+        let alloc = unsafe { ManuallyDrop::take(&mut self.alloc) };
+        Ok(unsafe {
+            calloc_vec::from_raw_parts_in(
+                self.front_ptr,
+                front_len + back_len,
+                self.full_capacity,
+                alloc,
+            )
+        })
     }
 }
-impl<T> Drop for CrossVecPairGuard<T> {
+/// Whether `front` and `back` don't overlap, and they both fit within `capacity` items (from the
+/// lower of their starts).
+fn is_within_one_allocation<T>(front: &[T], back: &[T], capacity: usize) -> bool {
+    let size = mem::size_of::<T>();
+    if size == 0 {
+        return true;
+    }
+    let range = |slice: &[T]| {
+        let start = slice.as_ptr() as usize;
+        (start, start + mem::size_of_val(slice))
+    };
+    let (front_start, front_end) = range(front);
+    if back.is_empty() {
+        return front.len() <= capacity;
+    }
+    let (back_start, back_end) = range(back);
+    let disjoint = front_end <= back_start || back_end <= front_start;
+    disjoint && front_end.max(back_end) - front_start.min(back_start) <= capacity * size
+}
+
+impl<T, A: Allocator> Drop for CrossVecPairGuard<T, A> {
     fn drop(&mut self) {
+        if let Ok(pair) = self.try_temp_take() {
+            // Drops the items, and frees the buffer.
+            drop(self.try_move_back_join_into(pair));
+        }
+        if !self.state.is_moved_back() {
+            // The buffer leaks (the pair may still be using it), but the allocator goes.
+            unsafe { ManuallyDrop::drop(&mut self.alloc) };
+        }
         debug_assert!(
             self.state.is_moved_back(),
             "Expecting the CrossVecPair to be moved back, but it's: {:?}.'",
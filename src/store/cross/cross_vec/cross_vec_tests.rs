@@ -1,12 +1,249 @@
-use crate::store::cross::cross_vec::{CrossVec, CrossVecPair, CrossVecPairGuardState};
+use crate::calloc::calloc_vec::VecDeque;
+use crate::store::cross::cross_vec::{
+    is_within_one_allocation, CrossVec, CrossVecPair, CrossVecPairGuard, CrossVecPairGuardState,
+    FixedCapVec,
+};
+use crate::store::lifos::lifos_vec::FixedDequeLifos;
+use crate::store::lifos::Lifos;
 
-use alloc::vec;
+use crate::testing::{DropCounter, DropTracker};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::mem::ManuallyDrop;
 
 #[test]
 fn cross_vec_pair_guard_state() {
-    let pair: CrossVecPair<()> = CrossVecPair(vec![], vec![]);
+    let pair: CrossVecPair<()> =
+        CrossVecPair(FixedCapVec::new(vec![]), FixedCapVec::new(vec![]), 0);
     assert!(CrossVecPairGuardState::<()>::NotTakenYet(pair).is_not_taken_yet());
 
     assert!(CrossVecPairGuardState::<()>::TakenOut.is_taken_out());
     assert!(CrossVecPairGuardState::<()>::MovedBack.is_moved_back());
 }
+
+#[test]
+fn cross_vec_pair_guard_debug() {
+    let mut lifos = FixedDequeLifos::<u8>::new_from_empty(VecDeque::<u8>::with_capacity(5));
+    assert_eq!(lifos.try_push_left(1), Ok(()));
+    assert_eq!(lifos.try_push_right(2), Ok(()));
+    assert_eq!(lifos.try_push_right(3), Ok(()));
+    let guard = CrossVecPairGuard::new_from_lifos(lifos);
+    let debug = format!("{guard:?}");
+    assert!(debug.contains("state: Self::NotTakenYet(_)"), "{debug}");
+    assert!(
+        debug.contains("orig_front_len: 2, orig_back_len: 1"),
+        "{debug}"
+    );
+    assert!(debug.contains("full_capacity: "), "{debug}");
+}
+
+#[cfg(not(feature = "_internal_use_allocator_api"))]
+#[test]
+fn allocator_propagates_to_guard() {
+    use crate::calloc::calloc_vec::Vec;
+    use crate::calloc::Allocator;
+
+    #[derive(Debug, PartialEq)]
+    struct Tagged(u32);
+    impl Allocator for Tagged {}
+
+    let vec = Vec::<u8, Tagged>::with_capacity_in(4, Tagged(7));
+    let vec_deque = VecDeque::from(vec);
+    assert_eq!(vec_deque.allocator(), &Tagged(7));
+    assert_eq!(Vec::from(vec_deque).allocator(), &Tagged(7));
+
+    let vec_deque = VecDeque::<u8, Tagged>::with_capacity_in(4, Tagged(8));
+    let mut lifos = FixedDequeLifos::new_from_empty(vec_deque);
+    assert_eq!(lifos.allocator(), &Tagged(8));
+    assert_eq!(lifos.try_push_left(1), Ok(()));
+    let guard = CrossVecPairGuard::new_from_lifos(lifos);
+    assert_eq!(guard.allocator(), &Tagged(8));
+}
+
+/// A pair from another guard is rejected, even in release (and whatever its pointers).
+#[test]
+fn check_pair_generation() {
+    use crate::error::Error;
+
+    let new_guard = || {
+        let lifos = FixedDequeLifos::<u8>::new_from_empty(VecDeque::with_capacity(4));
+        CrossVecPairGuard::new_from_lifos(lifos)
+    };
+    let (mut earlier, mut guard) = (new_guard(), new_guard());
+    let (stale, pair) = (
+        earlier.try_temp_take().unwrap(),
+        guard.try_temp_take().unwrap(),
+    );
+    assert_eq!(earlier.check_pair(&stale), Ok(()));
+    assert_eq!(guard.check_pair(&pair), Ok(()));
+    assert_eq!(guard.check_pair(&stale), Err(Error::InvalidArgument));
+    assert_eq!(earlier.check_pair(&pair), Err(Error::InvalidArgument));
+    assert!(earlier.try_move_back_join_into(stale).is_ok());
+    assert!(guard.try_move_back_join_into(pair).is_ok());
+}
+
+/// A foreign pair (or any pair, once the guard's own is back) is given back, and the guard left
+/// as it was.
+#[test]
+fn try_move_back_join_into() {
+    use crate::error::Error;
+
+    let counter = DropCounter::new();
+    let new_guard = |values: [u8; 3]| {
+        let mut lifos = FixedDequeLifos::<_>::new_from_empty(VecDeque::with_capacity(6));
+        assert!(lifos.try_push_left(counter.track(values[0])).is_ok());
+        assert!(lifos.try_push_left(counter.track(values[1])).is_ok());
+        assert!(lifos.try_push_right(counter.track(values[2])).is_ok());
+        CrossVecPairGuard::new_from_lifos(lifos)
+    };
+    let (mut earlier, mut guard) = (new_guard([1, 2, 3]), new_guard([4, 5, 6]));
+    let full_capacity = guard.full_capacity;
+    let (stale, pair) = (
+        earlier.try_temp_take().unwrap(),
+        guard.try_temp_take().unwrap(),
+    );
+
+    let (error, stale) = guard.try_move_back_join_into(stale).err().unwrap();
+    assert_eq!(error, Error::InvalidArgument);
+    let (error, mut pair) = earlier.try_move_back_join_into(pair).err().unwrap();
+    assert_eq!(error, Error::InvalidArgument);
+    assert!(guard.state.is_taken_out() && earlier.state.is_taken_out());
+
+    // Fewer items than were taken out.
+    assert_eq!(*pair.0.pop().unwrap(), 6);
+    assert_eq!(counter.live(), 5);
+    let vec = guard.try_move_back_join_into(pair).unwrap();
+    let values: Vec<u8> = vec.iter().map(|tracker| **tracker).collect();
+    assert_eq!(&values[..], &[4, 5]);
+    assert_eq!(
+        (vec.as_ptr(), vec.capacity()),
+        (guard.front_ptr as *const _, full_capacity)
+    );
+
+    // Given back already.
+    let (error, stale) = guard.try_move_back_join_into(stale).err().unwrap();
+    assert_eq!(error, Error::InvalidState);
+    assert!(guard.state.is_moved_back());
+
+    let joined = earlier.try_move_back_join_into(stale).unwrap();
+    let values: Vec<u8> = joined.iter().map(|tracker| **tracker).collect();
+    assert_eq!(&values[..], &[3, 1, 2]);
+    drop((joined, vec));
+    counter.assert_all_dropped();
+}
+
+/// A guard drops its items, and frees its buffer, if its pair was never taken out.
+#[test]
+fn guard_dropped_before_taking() {
+    let counter = DropCounter::new();
+    let mut lifos = FixedDequeLifos::<_>::new_from_empty(VecDeque::with_capacity(4));
+    assert!(lifos.try_push_left(counter.track(1)).is_ok());
+    assert!(lifos.try_push_right(counter.track(2)).is_ok());
+    let guard = CrossVecPairGuard::new_from_lifos(lifos);
+    assert_eq!(counter.live(), 2);
+    drop(guard);
+    counter.assert_all_dropped();
+}
+
+#[test]
+fn try_from_lifos_checks_contiguity() {
+    let mut lifos = FixedDequeLifos::<u8>::new_from_empty(VecDeque::<u8>::with_capacity(5));
+    assert_eq!(lifos.try_push_left(1), Ok(()));
+    assert_eq!(lifos.try_push_right(2), Ok(()));
+    let guard = CrossVecPairGuard::try_from_lifos(lifos).unwrap();
+    let debug = format!("{guard:?}");
+    assert!(
+        debug.contains("orig_front_len: 1, orig_back_len: 1"),
+        "{debug}"
+    );
+    drop(guard);
+
+    let buffer = [0u32; 8];
+    assert!(is_within_one_allocation(&buffer[5..], &buffer[..2], 8));
+    assert!(is_within_one_allocation(&buffer[2..6], &[], 8));
+    // Overlapping.
+    assert!(!is_within_one_allocation(&buffer[2..], &buffer[..3], 8));
+    // Spanning more than the capacity.
+    assert!(!is_within_one_allocation(&buffer[5..], &buffer[..2], 6));
+    let other = [0u32; 2];
+    assert!(!is_within_one_allocation(&buffer[..], &other[..], 8));
+}
+
+#[test]
+fn fixed_cap_vec() {
+    let counter = DropCounter::new();
+    let mut buffer = ManuallyDrop::new(Vec::with_capacity(3));
+    let (ptr, capacity) = (buffer.as_mut_ptr(), buffer.capacity());
+    let mut fixed = FixedCapVec::new(unsafe { Vec::from_raw_parts(ptr, 0, capacity) });
+    for value in 0..capacity {
+        assert!(fixed.try_push(counter.track(value)).is_ok());
+    }
+    let rejected = fixed.try_push(counter.track(capacity)).unwrap_err();
+    assert_eq!(*rejected, capacity);
+    drop(rejected);
+    assert_eq!(
+        (fixed.len(), fixed.capacity(), fixed.as_ptr()),
+        (capacity, capacity, ptr as _)
+    );
+    assert_eq!(*fixed.pop().unwrap(), capacity - 1);
+    fixed[0] = counter.track(7);
+    assert_eq!(*fixed[0], 7);
+    fixed.truncate(1);
+    assert_eq!(counter.live(), 1);
+    // Drops the items, but it leaves the buffer.
+    drop(fixed);
+    counter.assert_all_dropped();
+    drop(unsafe { Vec::from_raw_parts(ptr, 0, capacity) });
+}
+
+/// Compiles only if `T` is [`Unpin`].
+fn assert_unpin<T: Unpin>() {}
+
+#[test]
+fn state_types_are_unpin() {
+    // No state type points into itself, so none opts out of `Unpin` (beyond what its items do).
+    assert_unpin::<CrossVecPairGuard<u8>>();
+    assert_unpin::<CrossVecPair<u8>>();
+    assert_unpin::<FixedCapVec<u8>>();
+    assert_unpin::<FixedDequeLifos<u8>>();
+}
+
+#[test]
+fn guard_survives_moves() {
+    #[inline(never)]
+    fn guard_from(counter: &DropCounter) -> CrossVecPairGuard<DropTracker<'_, u8>> {
+        let mut lifos = FixedDequeLifos::new_from_empty(VecDeque::with_capacity(6));
+        assert!(lifos.try_push_left(counter.track(1)).is_ok());
+        assert!(lifos.try_push_left(counter.track(2)).is_ok());
+        assert!(lifos.try_push_right(counter.track(3)).is_ok());
+        // Moved out of this stack frame.
+        CrossVecPairGuard::new_from_lifos(lifos)
+    }
+    #[inline(never)]
+    fn take<T>(guard: &mut CrossVecPairGuard<T>) -> CrossVecPair<T> {
+        guard.try_temp_take().unwrap()
+    }
+    let counter = DropCounter::new();
+    let guard = guard_from(&counter);
+    let (front_ptr, back_ptr) = (guard.front_ptr as *const _, guard.back_ptr as *const _);
+
+    // Onto the heap, and back.
+    let mut boxed = Box::new(guard);
+    let pair = take(&mut boxed);
+    let guard = *boxed;
+    assert!(guard.state.is_taken_out());
+    assert_eq!((pair.0.as_ptr(), pair.1.as_ptr()), (front_ptr, back_ptr));
+    assert_eq!(*pair.0[0], 3);
+    assert_eq!((*pair.1[0], *pair.1[1]), (1, 2));
+
+    // The pair is movable, too.
+    let pair = Box::new(pair);
+    assert_eq!(counter.live(), 3);
+    let mut guard = Box::new(guard);
+    let vec = guard.try_move_back_join_into(*pair).unwrap();
+    assert_eq!(vec.as_ptr(), front_ptr);
+    assert_eq!((*vec[0], *vec[1], *vec[2]), (3, 1, 2));
+    drop((vec, guard));
+    counter.assert_all_dropped();
+}
@@ -1,2 +1,97 @@
+//! Input storage: items that the sorters reorder in place, but that this crate doesn't own or
+//! manage.
+
+use crate::error::Error;
+use core::marker::PhantomData;
+use core::mem;
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+use core::slice;
+
 #[cfg(test)]
 mod input_tests;
+
+/// Items in externally managed memory (shared memory of an IPC channel, an `mmap`-ed file, a DMA
+/// buffer...), to be sorted in place without copying them. All bookkeeping (like
+/// [`crate::engine::Engine`]'s segments) stays in crate-managed storage, separate from the items.
+///
+/// `'t` is the lifetime of the "token": any value whose lifetime bounds the validity of the
+/// memory, like the handle of the mapping. The items can't outlive it.
+///
+/// ```
+/// use lazysort_no_alloc::natural::NaturalSorter;
+/// use lazysort_no_alloc::store::input::SharedItems;
+///
+/// // Stand for the memory of a mapping, and for its handle (which would unmap on drop).
+/// let mut memory = [50, 20, 40, 10, 30];
+/// let mapping = ();
+/// let items = unsafe { SharedItems::from_raw_parts(memory.as_mut_ptr(), 5, &mapping) }.unwrap();
+/// let mut sorter = NaturalSorter::<_>::new(items.into_slice());
+/// assert_eq!(sorter.next(), Some(&10));
+/// assert_eq!(sorter.next(), Some(&20));
+/// ```
+#[derive(Debug)]
+pub struct SharedItems<'t, T> {
+    ptr: NonNull<T>,
+    len: usize,
+    token: PhantomData<&'t mut [T]>,
+}
+
+impl<'t, T> SharedItems<'t, T> {
+    /// The `len` items at `ptr`, valid for as long as `token` is borrowed.
+    ///
+    /// Return [`Error::InvalidArgument`] if `ptr` is null (unless `len` is 0) or not aligned for
+    /// `T`, or if the items would span more than [`isize::MAX`] bytes.
+    ///
+    /// # Safety
+    /// For as long as `token` is borrowed:
+    /// - `ptr` has to be valid for reads and writes of `len` initialized items of `T` (within one
+    ///   mapping), and
+    /// - nothing else may access those items: in particular, no other process may write to them,
+    ///   nor read them while they're being reordered. Coordinating that (by a lock, or by a
+    ///   hand-off, see [`crate::engine::Engine::export_plan()`]) is up to the caller.
+    ///
+    /// Items of types with pointers (or with [`Drop`]) make sense only within one process.
+    pub unsafe fn from_raw_parts<L: ?Sized>(
+        ptr: *mut T,
+        len: usize,
+        _token: &'t L,
+    ) -> Result<Self, Error> {
+        let ptr = match (NonNull::new(ptr), len) {
+            (_, 0) => NonNull::dangling(),
+            (Some(ptr), _) => ptr,
+            (None, _) => return Err(Error::InvalidArgument),
+        };
+        let bytes = len.checked_mul(mem::size_of::<T>());
+        if ptr.as_ptr().align_offset(mem::align_of::<T>()) != 0
+            || bytes.map_or(true, |bytes| bytes > isize::MAX as usize)
+        {
+            return Err(Error::InvalidArgument);
+        }
+        Ok(Self {
+            ptr,
+            len,
+            token: PhantomData,
+        })
+    }
+
+    /// The items, for as long as the token is borrowed: for sorters that borrow their items.
+    pub fn into_slice(self) -> &'t mut [T] {
+        // Valid and exclusive (for `'t`) per `from_raw_parts()`.
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<'t, T> Deref for SharedItems<'t, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<'t, T> DerefMut for SharedItems<'t, T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
@@ -1 +1,46 @@
+use crate::engine::Engine;
+use crate::error::Error;
+use crate::store::input::SharedItems;
+use core::ptr;
 
+#[test]
+fn sorts_in_place() {
+    let mut memory: [u32; 100] = core::array::from_fn(|i| (i as u32 * 37 + 11) % 100);
+    let token = ();
+    let mut items =
+        unsafe { SharedItems::from_raw_parts(memory.as_mut_ptr(), 100, &token) }.unwrap();
+    assert_eq!(items.len(), 100);
+    let mut engine = Engine::<u8>::try_new(items.len()).unwrap();
+    for expected in 0..10 {
+        let live = &mut items[engine.front()..engine.back()];
+        let position = engine.next_front(live, &mut u32::cmp).unwrap();
+        assert_eq!(items[position], expected);
+    }
+    // Reordered where it lies (`items` is no longer used).
+    assert_eq!(
+        memory[..10],
+        core::array::from_fn::<u32, 10, _>(|i| i as u32)
+    );
+}
+
+#[test]
+fn rejects_invalid_parts() {
+    let token = ();
+    let empty = unsafe { SharedItems::<u64>::from_raw_parts(ptr::null_mut(), 0, &token) };
+    assert!(empty.unwrap().into_slice().is_empty());
+    assert_eq!(
+        unsafe { SharedItems::<u64>::from_raw_parts(ptr::null_mut(), 1, &token) }.map(|_| ()),
+        Err(Error::InvalidArgument)
+    );
+    let mut memory = [0u64; 2];
+    let misaligned = (memory.as_mut_ptr() as *mut u8).wrapping_add(1) as *mut u64;
+    assert_eq!(
+        unsafe { SharedItems::from_raw_parts(misaligned, 1, &token) }.map(|_| ()),
+        Err(Error::InvalidArgument)
+    );
+    assert_eq!(
+        unsafe { SharedItems::from_raw_parts(memory.as_mut_ptr(), usize::MAX / 4, &token) }
+            .map(|_| ()),
+        Err(Error::InvalidArgument)
+    );
+}
@@ -1,16 +1,302 @@
+use crate::error::Error;
+use core::cmp::Ordering;
+use core::marker::PhantomData;
+
+#[cfg(test)]
+mod lifos_tests;
+
 pub trait Lifos<T> {
     fn has_to_push_left_first() -> bool;
-    fn push_left(&mut self, value: T);
-    fn push_right(&mut self, value: T);
+
+    /// Push to the LEFT side. Panic (even in release) if there is no (pre-allocated) capacity left.
+    ///
+    /// Not available with crate feature `no-panic`. Use [`Lifos::try_push_left()`] instead.
+    #[cfg(not(feature = "no-panic"))]
+    fn push_left(&mut self, value: T) {
+        if self.try_push_left(value).is_err() {
+            release_panic!("Pushing to the LEFT side would exceed the capacity.");
+        }
+    }
+    /// Push to the RIGHT side. Panic (even in release) if there is no (pre-allocated) capacity
+    /// left.
+    ///
+    /// Not available with crate feature `no-panic`. Use [`Lifos::try_push_right()`] instead.
+    #[cfg(not(feature = "no-panic"))]
+    fn push_right(&mut self, value: T) {
+        if self.try_push_right(value).is_err() {
+            release_panic!("Pushing to the RIGHT side would exceed the capacity.");
+        }
+    }
+
+    /// Push to the LEFT side. If there is no (pre-allocated) capacity left, return the value back
+    /// in [`Err`].
+    fn try_push_left(&mut self, value: T) -> Result<(), T>;
+    /// Push to the RIGHT side. If there is no (pre-allocated) capacity left, return the value back
+    /// in [`Err`].
+    fn try_push_right(&mut self, value: T) -> Result<(), T>;
+
+    /// Push all of `items` to the LEFT side, in their order (so the last one ends up on top).
+    /// Return how many got pushed. If there is no (pre-allocated) capacity left, return the item
+    /// that didn't fit in [`Err`] (the rest of `items` is not consumed).
+    fn extend_left<ITER>(&mut self, items: ITER) -> Result<usize, T>
+    where
+        ITER: IntoIterator<Item = T>,
+        Self: Sized,
+    {
+        let mut pushed = 0;
+        for item in items {
+            self.try_push_left(item)?;
+            pushed += 1;
+        }
+        Ok(pushed)
+    }
+    /// Like [`Lifos::extend_left()`], but to the RIGHT side.
+    fn extend_right<ITER>(&mut self, items: ITER) -> Result<usize, T>
+    where
+        ITER: IntoIterator<Item = T>,
+        Self: Sized,
+    {
+        let mut pushed = 0;
+        for item in items {
+            self.try_push_right(item)?;
+            pushed += 1;
+        }
+        Ok(pushed)
+    }
+
+    /// Copy all of `items` to the LEFT side, in their order - or, if they don't fit, none: return
+    /// [`Error::CapacityExceeded`]. Implementations copy them in bulk where their layout allows.
+    fn extend_left_from_slice(&mut self, items: &[T]) -> Result<(), Error>
+    where
+        T: Copy,
+        Self: Sized,
+    {
+        if !self.can_hold(self.len() + items.len()) {
+            return Err(Error::CapacityExceeded);
+        }
+        for &item in items {
+            let pushed = self.try_push_left(item);
+            debug_assert!(pushed.is_ok());
+        }
+        Ok(())
+    }
+    /// Like [`Lifos::extend_left_from_slice()`], but to the RIGHT side.
+    fn extend_right_from_slice(&mut self, items: &[T]) -> Result<(), Error>
+    where
+        T: Copy,
+        Self: Sized,
+    {
+        if !self.can_hold(self.len() + items.len()) {
+            return Err(Error::CapacityExceeded);
+        }
+        for &item in items {
+            let pushed = self.try_push_right(item);
+            debug_assert!(pushed.is_ok());
+        }
+        Ok(())
+    }
+
     /// How many items on the right.
     fn right(&self) -> usize;
     /// How many items on the left.
     fn left(&self) -> usize;
+
+    /// How many items on both sides.
+    fn len(&self) -> usize {
+        self.left() + self.right()
+    }
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The top (last pushed) item of the LEFT side.
+    fn peek_left(&self) -> Option<&T>;
+    /// The top (last pushed) item of the RIGHT side.
+    fn peek_right(&self) -> Option<&T>;
+    fn peek_left_mut(&mut self) -> Option<&mut T>;
+    fn peek_right_mut(&mut self) -> Option<&mut T>;
+
+    /// Total (fixed) capacity, shared by both sides.
+    fn capacity(&self) -> usize;
+
+    /// Capacity needed for `n` items in total (on either side). Some implementations need more than
+    /// `n`.
+    fn required_capacity_for(n: usize) -> usize
+    where
+        Self: Sized,
+    {
+        n
+    }
+
+    /// Whether `n` items in total (on either side) are guaranteed to fit (including the ones
+    /// already pushed).
+    fn can_hold(&self, n: usize) -> bool
+    where
+        Self: Sized,
+    {
+        self.capacity() >= Self::required_capacity_for(n)
+    }
+}
+
+/// [`Lifos`] that can allocate its own storage.
+pub trait LifosWithCapacity<T>: Lifos<T> + Sized {
+    /// Empty, with capacity for (at least) `n` items in total - see
+    /// [`Lifos::required_capacity_for()`].
+    fn with_capacity(n: usize) -> Self;
+}
+
+/// Partition `items` into `lifos` around (the value of) `pivot`: the items less than `pivot` get
+/// pushed to the LEFT side, the rest to the RIGHT side. A building block for quick-partition
+/// filtering; see also [`crate::engine::partition_at_pivot()`] for slices.
+///
+/// Return how many got pushed as `(left, right)`. If `lifos` runs out of capacity, return the item
+/// that didn't fit in [`Err`] (the rest of `items` is not consumed).
+pub fn partition_into<T, L, ITER, CMP>(
+    items: ITER,
+    pivot: &T,
+    lifos: &mut L,
+    cmp: &mut CMP,
+) -> Result<(usize, usize), T>
+where
+    L: Lifos<T>,
+    ITER: IntoIterator<Item = T>,
+    CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+{
+    let (mut left, mut right) = (0, 0);
+    for item in items {
+        if cmp(&item, pivot) == Ordering::Less {
+            lifos.try_push_left(item)?;
+            left += 1;
+        } else {
+            lifos.try_push_right(item)?;
+            right += 1;
+        }
+    }
+    Ok((left, right))
+}
+
+/// The minimum of a double-ended queue that [`DequeLifos`] needs. Implemented for
+/// [`alloc::collections::VecDeque`] (with crate feature `alloc`). Implement it for other
+/// deque-like containers to use them as [`Lifos`].
+pub trait DequeLike<T> {
+    fn push_front(&mut self, value: T);
+    fn push_back(&mut self, value: T);
+    /// The items in their order from front to back, as (up to) two slices.
+    fn as_slices(&self) -> (&[T], &[T]);
+    fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]);
+    /// The (fixed) maximum number of items. Pushing beyond it is never attempted.
+    fn capacity(&self) -> usize;
+
+    fn len(&self) -> usize {
+        let (first, second) = self.as_slices();
+        first.len() + second.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// [`Lifos`] over any [`DequeLike`]: the LEFT side grows at the back of the deque, the RIGHT side
+/// at its front. So, from the front to the back, the deque holds the RIGHT items (top to bottom),
+/// and then the LEFT items (bottom to top).
+///
+/// Unlike [`lifos_vec::FixedDequeLifos`], this relies on the deque itself for pushing to the
+/// front of an empty deque.
+#[derive(Clone, Debug)]
+pub struct DequeLifos<T, D: DequeLike<T>> {
+    deque: D,
+    left: usize,
+    right: usize,
+    _items: PhantomData<T>,
+}
+
+/// This requires the deque to be (initially) EMPTY.
+impl<T, D: DequeLike<T>> From<D> for DequeLifos<T, D> {
+    fn from(deque: D) -> Self {
+        debug_assert!(deque.is_empty());
+        Self {
+            deque,
+            left: 0,
+            right: 0,
+            _items: PhantomData,
+        }
+    }
+}
+
+impl<T, D: DequeLike<T>> DequeLifos<T, D> {
+    /// The underlying deque. See [`DequeLifos`] for the order of items.
+    pub fn into_deque(self) -> D {
+        self.deque
+    }
+}
+
+impl<T, D: DequeLike<T>> Lifos<T> for DequeLifos<T, D> {
+    fn has_to_push_left_first() -> bool {
+        false
+    }
+
+    fn try_push_left(&mut self, value: T) -> Result<(), T> {
+        if self.len() >= self.capacity() {
+            return Err(value);
+        }
+        self.deque.push_back(value);
+        self.left += 1;
+        Ok(())
+    }
+
+    fn try_push_right(&mut self, value: T) -> Result<(), T> {
+        if self.len() >= self.capacity() {
+            return Err(value);
+        }
+        self.deque.push_front(value);
+        self.right += 1;
+        Ok(())
+    }
+
+    fn right(&self) -> usize {
+        self.right
+    }
+    fn left(&self) -> usize {
+        self.left
+    }
+
+    fn peek_left(&self) -> Option<&T> {
+        if self.left == 0 {
+            return None;
+        }
+        let (first, second) = self.deque.as_slices();
+        second.last().or_else(|| first.last())
+    }
+    fn peek_right(&self) -> Option<&T> {
+        if self.right == 0 {
+            return None;
+        }
+        let (first, second) = self.deque.as_slices();
+        first.first().or_else(|| second.first())
+    }
+    fn peek_left_mut(&mut self) -> Option<&mut T> {
+        if self.left == 0 {
+            return None;
+        }
+        let (first, second) = self.deque.as_mut_slices();
+        second.last_mut().or_else(|| first.last_mut())
+    }
+    fn peek_right_mut(&mut self) -> Option<&mut T> {
+        if self.right == 0 {
+            return None;
+        }
+        let (first, second) = self.deque.as_mut_slices();
+        first.first_mut().or_else(|| second.first_mut())
+    }
+
+    fn capacity(&self) -> usize {
+        self.deque.capacity()
+    }
 }
 
 // - TODO no-alloc-friendly "SliceDeque" struct
-// - TODO when Storage is backed by an array, make the array size a const generic
-// - TODO a trait and an adapter for VecDeque
 
+pub mod double_stack;
 #[cfg(feature = "alloc")]
 pub mod lifos_vec;
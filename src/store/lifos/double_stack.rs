@@ -0,0 +1,203 @@
+//! A fixed-capacity double stack: two LIFOs in one array, with no dependence on
+//! [`alloc::collections::VecDeque`] internals.
+
+use crate::error::Error;
+use crate::store::lifos::Lifos;
+use core::fmt::{Debug, Formatter, Result as FmtResult};
+use core::mem::MaybeUninit;
+use core::ptr;
+
+#[cfg(test)]
+mod double_stack_tests;
+
+/// Two LIFOs (Last-In First-Out) in one array of `N` items, growing toward each other:
+/// ```
+/// /*
+/// /-----------------------------\
+/// | LEFT                  RIGHT |
+/// | 0123 ->        <- 6543210   |
+/// | ^  ^              ^     ^   |
+/// | |  top          top     |   |
+/// | bottom               bottom |
+/// \-----------------------------/
+/// */
+/// ```
+/// That's exactly what the two-LIFO trick of [`crate::store::lifos::lifos_vec::FixedDequeLifos`]
+/// needs, but without its workarounds: either side can be pushed to first (no temporary item), the
+/// capacity is exactly `N` (even for 1 item), and the buffer never wraps around.
+pub struct DoubleStack<T, const N: usize> {
+    /// LEFT items are at `0..left` (bottom to top), RIGHT items at `N - right..N` (top to bottom).
+    /// All other slots are uninitialized.
+    items: [MaybeUninit<T>; N],
+    left: usize,
+    right: usize,
+}
+
+impl<T, const N: usize> DoubleStack<T, N> {
+    pub fn new() -> Self {
+        Self {
+            // An array of `MaybeUninit` doesn't require initialization. See
+            // <https://doc.rust-lang.org/nightly/core/mem/union.MaybeUninit.html#initializing-an-array-element-by-element>.
+            items: unsafe { MaybeUninit::uninit().assume_init() },
+            left: 0,
+            right: 0,
+        }
+    }
+
+    /// The LEFT items (bottom to top) and the RIGHT items (top to bottom). Together they are in
+    /// the same order as in the deque of [`crate::store::lifos::DequeLifos`], but with the sides
+    /// swapped.
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let (left, right) = (&self.items[..self.left], &self.items[N - self.right..]);
+        unsafe {
+            (
+                &*(left as *const [MaybeUninit<T>] as *const [T]),
+                &*(right as *const [MaybeUninit<T>] as *const [T]),
+            )
+        }
+    }
+
+    /// Like [`DoubleStack::as_slices()`].
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        let (left, rest) = self.items.split_at_mut(self.left);
+        let right = &mut rest[N - self.left - self.right..];
+        unsafe {
+            (
+                &mut *(left as *mut [MaybeUninit<T>] as *mut [T]),
+                &mut *(right as *mut [MaybeUninit<T>] as *mut [T]),
+            )
+        }
+    }
+
+    /// Remove the top item of the LEFT side.
+    pub fn pop_left(&mut self) -> Option<T> {
+        if self.left == 0 {
+            return None;
+        }
+        self.left -= 1;
+        Some(unsafe { self.items[self.left].assume_init_read() })
+    }
+
+    /// Remove the top item of the RIGHT side.
+    pub fn pop_right(&mut self) -> Option<T> {
+        if self.right == 0 {
+            return None;
+        }
+        self.right -= 1;
+        Some(unsafe { self.items[N - self.right - 1].assume_init_read() })
+    }
+
+    /// Drop all items (of both sides).
+    pub fn clear(&mut self) {
+        let (left, right) = self.as_mut_slices();
+        let (left, right) = (left as *mut [T], right as *mut [T]);
+        // Empty first, so that a panicking `Drop` of an item can't cause a double drop.
+        self.left = 0;
+        self.right = 0;
+        unsafe {
+            ptr::drop_in_place(left);
+            ptr::drop_in_place(right);
+        }
+    }
+}
+
+impl<T, const N: usize> Default for DoubleStack<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for DoubleStack<T, N> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T: Debug, const N: usize> Debug for DoubleStack<T, N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let (left, right) = self.as_slices();
+        f.debug_struct("DoubleStack")
+            .field("left", &left)
+            .field("right", &right)
+            .finish()
+    }
+}
+
+impl<T, const N: usize> Lifos<T> for DoubleStack<T, N> {
+    fn has_to_push_left_first() -> bool {
+        false
+    }
+
+    fn try_push_left(&mut self, value: T) -> Result<(), T> {
+        if self.len() >= N {
+            return Err(value);
+        }
+        self.items[self.left].write(value);
+        self.left += 1;
+        Ok(())
+    }
+
+    fn try_push_right(&mut self, value: T) -> Result<(), T> {
+        if self.len() >= N {
+            return Err(value);
+        }
+        self.items[N - self.right - 1].write(value);
+        self.right += 1;
+        Ok(())
+    }
+
+    /// One [`ptr::copy_nonoverlapping()`].
+    fn extend_left_from_slice(&mut self, items: &[T]) -> Result<(), Error>
+    where
+        T: Copy,
+    {
+        if items.len() > N - self.len() {
+            return Err(Error::CapacityExceeded);
+        }
+        let target = self.items[self.left..].as_mut_ptr() as *mut T;
+        unsafe { ptr::copy_nonoverlapping(items.as_ptr(), target, items.len()) };
+        self.left += items.len();
+        Ok(())
+    }
+
+    /// One [`ptr::copy_nonoverlapping()`], then reversed in place (the RIGHT items are stored top
+    /// to bottom).
+    fn extend_right_from_slice(&mut self, items: &[T]) -> Result<(), Error>
+    where
+        T: Copy,
+    {
+        if items.len() > N - self.len() {
+            return Err(Error::CapacityExceeded);
+        }
+        let start = N - self.right - items.len();
+        let target = self.items[start..].as_mut_ptr() as *mut T;
+        unsafe { ptr::copy_nonoverlapping(items.as_ptr(), target, items.len()) };
+        self.right += items.len();
+        self.as_mut_slices().1[..items.len()].reverse();
+        Ok(())
+    }
+
+    fn right(&self) -> usize {
+        self.right
+    }
+    fn left(&self) -> usize {
+        self.left
+    }
+
+    fn peek_left(&self) -> Option<&T> {
+        self.as_slices().0.last()
+    }
+    fn peek_right(&self) -> Option<&T> {
+        self.as_slices().1.first()
+    }
+    fn peek_left_mut(&mut self) -> Option<&mut T> {
+        self.as_mut_slices().0.last_mut()
+    }
+    fn peek_right_mut(&mut self) -> Option<&mut T> {
+        self.as_mut_slices().1.first_mut()
+    }
+
+    fn capacity(&self) -> usize {
+        N
+    }
+}
@@ -0,0 +1,73 @@
+use crate::error::Error;
+use crate::store::lifos::double_stack::DoubleStack;
+use crate::store::lifos::{partition_into, Lifos};
+use crate::testing::DropCounter;
+
+#[test]
+fn push_right_first() {
+    let mut lifos = DoubleStack::<u8, 1>::new();
+    assert_eq!(lifos.try_push_right(1), Ok(()));
+    assert_eq!(lifos.try_push_left(2), Err(2));
+    assert_eq!((lifos.peek_left(), lifos.peek_right()), (None, Some(&1)));
+    assert_eq!(lifos.pop_right(), Some(1));
+    assert!(lifos.is_empty());
+}
+
+#[test]
+fn both_sides() {
+    let mut lifos = DoubleStack::<u8, 6>::new();
+    let result = partition_into([5, 1, 9, 5, 3, 7], &5, &mut lifos, &mut u8::cmp);
+    assert_eq!(result, Ok((2, 4)));
+    assert_eq!(lifos.as_slices(), (&[1, 3][..], &[7, 5, 9, 5][..]));
+    *lifos.peek_left_mut().unwrap() = 4;
+    *lifos.peek_right_mut().unwrap() = 8;
+    assert_eq!(
+        (lifos.peek_left(), lifos.peek_right()),
+        (Some(&4), Some(&8))
+    );
+    assert_eq!(lifos.try_push_left(0), Err(0));
+    assert_eq!(lifos.pop_left(), Some(4));
+    assert_eq!(lifos.pop_right(), Some(8));
+    assert_eq!((lifos.left(), lifos.right()), (1, 3));
+    #[cfg(feature = "alloc")]
+    assert_eq!(
+        alloc::format!("{lifos:?}"),
+        "DoubleStack { left: [1], right: [5, 9, 5] }"
+    );
+}
+
+#[test]
+fn drops_remaining_items() {
+    let counter = DropCounter::new();
+    let mut lifos = DoubleStack::<_, 4>::new();
+    for i in 0..2 {
+        assert!(lifos.try_push_left(counter.track(i)).is_ok());
+        assert!(lifos.try_push_right(counter.track(i)).is_ok());
+    }
+    drop(lifos.pop_right());
+    assert_eq!(counter.dropped(), 1);
+    drop(lifos);
+    counter.assert_all_dropped();
+}
+
+#[test]
+fn extend_from_slice() {
+    let mut lifos = DoubleStack::<u8, 8>::new();
+    assert_eq!(lifos.extend_left_from_slice(&[1, 2, 3]), Ok(()));
+    assert_eq!(lifos.extend_right_from_slice(&[4, 5]), Ok(()));
+    assert_eq!(lifos.extend_right_from_slice(&[6]), Ok(()));
+    // The same as pushing one by one.
+    let mut expected = DoubleStack::<u8, 8>::new();
+    assert_eq!(expected.extend_left([1, 2, 3]), Ok(3));
+    assert_eq!(expected.extend_right([4, 5, 6]), Ok(3));
+    assert_eq!(lifos.as_slices(), expected.as_slices());
+    assert_eq!(lifos.as_slices(), (&[1, 2, 3][..], &[6, 5, 4][..]));
+
+    assert_eq!(
+        lifos.extend_left_from_slice(&[7, 8, 9]),
+        Err(Error::CapacityExceeded)
+    );
+    assert_eq!(lifos.len(), 6);
+    assert_eq!(expected.extend_right([7, 8, 9]), Err(9));
+    assert_eq!(expected.peek_right(), Some(&8));
+}
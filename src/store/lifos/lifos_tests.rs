@@ -0,0 +1,96 @@
+use crate::error::Error;
+use crate::store::lifos::{DequeLifos, DequeLike, Lifos};
+
+/// A minimal array-backed ring buffer, standing for a non-`alloc` deque.
+struct ArrayDeque<const N: usize> {
+    items: [u8; N],
+    head: usize,
+    len: usize,
+}
+
+impl<const N: usize> ArrayDeque<N> {
+    fn new() -> Self {
+        Self {
+            items: [0; N],
+            head: 0,
+            len: 0,
+        }
+    }
+}
+
+impl<const N: usize> DequeLike<u8> for ArrayDeque<N> {
+    fn push_front(&mut self, value: u8) {
+        self.head = (self.head + N - 1) % N;
+        self.items[self.head] = value;
+        self.len += 1;
+    }
+    fn push_back(&mut self, value: u8) {
+        self.items[(self.head + self.len) % N] = value;
+        self.len += 1;
+    }
+    fn as_slices(&self) -> (&[u8], &[u8]) {
+        if self.head + self.len <= N {
+            (&self.items[self.head..self.head + self.len], &[])
+        } else {
+            let wrapped = self.head + self.len - N;
+            (&self.items[self.head..], &self.items[..wrapped])
+        }
+    }
+    fn as_mut_slices(&mut self) -> (&mut [u8], &mut [u8]) {
+        if self.head + self.len <= N {
+            (&mut self.items[self.head..self.head + self.len], &mut [])
+        } else {
+            let wrapped = self.head + self.len - N;
+            let (start, end) = self.items.split_at_mut(self.head);
+            (end, &mut start[..wrapped])
+        }
+    }
+    fn capacity(&self) -> usize {
+        N
+    }
+}
+
+#[test]
+fn deque_lifos_over_custom_deque() {
+    let mut lifos = DequeLifos::from(ArrayDeque::<5>::new());
+    assert_eq!((lifos.peek_left(), lifos.peek_right()), (None, None));
+    assert_eq!(lifos.try_push_right(10), Ok(()));
+    assert_eq!((lifos.peek_left(), lifos.peek_right()), (None, Some(&10)));
+    assert_eq!(lifos.try_push_left(1), Ok(()));
+    assert_eq!(lifos.try_push_left(2), Ok(()));
+    assert_eq!(lifos.try_push_right(11), Ok(()));
+    assert_eq!(lifos.try_push_left(3), Ok(()));
+    assert_eq!(lifos.try_push_right(12), Err(12));
+    assert_eq!((lifos.left(), lifos.right()), (3, 2));
+    assert_eq!(lifos.capacity(), 5);
+    assert_eq!(
+        (lifos.peek_left(), lifos.peek_right()),
+        (Some(&3), Some(&11))
+    );
+    *lifos.peek_left_mut().unwrap() = 4;
+    *lifos.peek_right_mut().unwrap() += 1;
+    assert!(lifos.can_hold(5) && !lifos.can_hold(6));
+    let deque = lifos.into_deque();
+    let (first, second) = deque.as_slices();
+    let mut items = [0; 5];
+    items[..first.len()].copy_from_slice(first);
+    items[first.len()..].copy_from_slice(second);
+    assert_eq!(items, [12, 10, 1, 2, 4]);
+}
+
+#[test]
+fn extend_default() {
+    let mut lifos = DequeLifos::from(ArrayDeque::<5>::new());
+    assert_eq!(lifos.extend_right([10, 11]), Ok(2));
+    assert_eq!(lifos.extend_left_from_slice(&[1, 2]), Ok(()));
+    assert_eq!(
+        lifos.extend_right_from_slice(&[12, 13]),
+        Err(Error::CapacityExceeded)
+    );
+    assert_eq!(lifos.len(), 4);
+    assert_eq!(lifos.extend_left([3, 4]), Err(4));
+    assert_eq!(
+        (lifos.peek_left(), lifos.peek_right()),
+        (Some(&3), Some(&11))
+    );
+}
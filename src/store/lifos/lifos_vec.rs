@@ -1,12 +1,76 @@
 use crate::calloc::calloc_vec::{Vec, VecDeque};
 use crate::calloc::{Allocator, Global};
-use crate::store::lifos::Lifos;
-use core::mem::{self, MaybeUninit};
+use crate::error::Error;
+use crate::store::lifos::{DequeLifos, DequeLike, Lifos, LifosWithCapacity};
+use core::marker::PhantomData;
+use core::mem::{self, ManuallyDrop, MaybeUninit};
+use core::ops::{Deref, DerefMut};
 use core::ptr;
 
 #[cfg(test)]
 mod lifos_vec_tests;
 
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// How a [`FixedDequeLifos`] enforces its (fixed) capacity: [`Checked`], [`Unchecked`] or
+/// [`Fallible`]. A type parameter (rather than a crate feature), so that each dependent in a build
+/// graph chooses its own trade-off, without features unifying across them. Sealed.
+pub trait CapacityPolicy: sealed::Sealed {
+    /// Whether the capacity gets checked even in release.
+    const CHECKED: bool;
+    /// Whether the panicking API ([`Lifos::push_left()`] and [`Lifos::push_right()`]) is
+    /// available. If not, using it fails to compile.
+    const PANICKING_API: bool;
+}
+
+/// The default: the capacity is checked even in release. [`Lifos::push_left()`] and
+/// [`Lifos::push_right()`] panic if full, [`Lifos::try_push_left()`] and
+/// [`Lifos::try_push_right()`] return the value back.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Checked;
+
+/// The capacity is checked in debug builds only (by `debug_assert`). In release, pushing beyond it
+/// re-allocates (as [`VecDeque`] does): still sound, but no longer allocation-free. For callers
+/// that keep within the capacity by construction.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Unchecked;
+
+/// Like [`Checked`], but nothing panics: the panicking API fails to compile (per type, unlike
+/// crate feature `no-panic`).
+///
+/// ```compile_fail
+/// use lazysort_no_alloc::store::lifos::lifos_vec::{Fallible, FixedDequeLifos};
+/// use lazysort_no_alloc::store::lifos::Lifos;
+/// use lazysort_no_alloc::calloc::calloc_vec::VecDeque;
+///
+/// let lifos = FixedDequeLifos::new_from_empty(VecDeque::<u8>::with_capacity(2));
+/// let mut lifos = lifos.with_policy::<Fallible>();
+/// lifos.push_left(1);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Fallible;
+
+impl sealed::Sealed for Checked {}
+impl sealed::Sealed for Unchecked {}
+impl sealed::Sealed for Fallible {}
+
+impl CapacityPolicy for Checked {
+    const CHECKED: bool = true;
+    const PANICKING_API: bool = true;
+}
+
+impl CapacityPolicy for Unchecked {
+    const CHECKED: bool = false;
+    const PANICKING_API: bool = true;
+}
+
+impl CapacityPolicy for Fallible {
+    const CHECKED: bool = true;
+    const PANICKING_API: bool = false;
+}
+
 /// A contract on top of [`VecDeque`]. It (logically) keeps two LIFO (Last-In First-Out) queues,
 /// growing in the opposite directions toward each other. (Similar to how stack & heap grow toward
 /// each other in a single-threaded process/OS with no virtual memory, but with physical addressing
@@ -39,13 +103,20 @@ mod lifos_vec_tests;
 /// ```
 ///
 /// LIMITED so as NOT to expand/re-allocate. Keeping within the bounds is the responsibility of the
-/// client - otherwise [`FixedDequeLifos::push_front()`] and [`FixedDequeLifos::push_front()`] will
-/// panic (even in release)!
+/// client - otherwise [`Lifos::push_left()`] and [`Lifos::push_right()`] will panic (even in
+/// release)! [`Lifos::try_push_left()`] and [`Lifos::try_push_right()`] return the value back
+/// instead. That's per the default [`CapacityPolicy`], [`Checked`]. Choose another one by
+/// [`FixedDequeLifos::with_policy()`].
 ///
 /// Minimum [`VecDeque`] capacity is 2 (even if you expect max. 1 item).
 ///
-/// This *could* take [`VecDeque`] by mutable reference. But, it takes it owned (moved) instead -
-/// because that suits [`crate::cross::CrossVecPairGuard`].
+/// [`crate::store::lifos::double_stack::DoubleStack`] has the same semantics without these
+/// workarounds (and without `alloc`), but it can't be turned into a
+/// [`crate::store::cross::cross_vec::CrossVecPair`].
+///
+/// This takes [`VecDeque`] owned (moved) - because that suits [`crate::cross::CrossVecPairGuard`].
+/// To use a (long-lived) [`VecDeque`] by mutable reference instead, see
+/// [`FixedDequeLifos::from_mut()`].
 ///
 /// Based on source code of [`alloc::collections::VecDeque`] (for non-empty buffer, and for
 /// non-zero-sized item types):
@@ -101,16 +172,20 @@ mod lifos_vec_tests;
 /// */
 /// ```
 #[derive(Debug)]
-pub struct FixedDequeLifos<T, A: Allocator = Global> {
+pub struct FixedDequeLifos<T, A: Allocator = Global, P: CapacityPolicy = Checked> {
     vec_deque: VecDeque<T, A>,
     /// Left ("back") side length.
     left: usize,
     /// Right ("front") side length.
     right: usize,
+    /// The capacity in use: [`Lifos::capacity()`]. At most the capacity of `vec_deque`. The
+    /// difference is the slack (see [`FixedDequeLifos::slack()`]).
+    limit: usize,
 
     #[cfg(debug_assertions)]
     /// Used by checks for consistency & checks on push_front/push_back.
     original_capacity: usize,
+    policy: PhantomData<P>,
 }
 
 // TODO
@@ -144,12 +219,15 @@ impl<T, A: Allocator> From<VecDeque<T, A>> for FixedDequeLifos<T, A> {
         #[cfg(debug_assertions)]
         let original_capacity = vec_deque.capacity();
 
+        let limit = vec_deque.capacity();
         let result = Self {
             vec_deque,
             left: 0,
             right: 0,
+            limit,
             #[cfg(debug_assertions)]
             original_capacity,
+            policy: PhantomData,
         };
         // TODO have this as a function, or clearer as a macro?
         result.debug_assert_consistent();
@@ -171,6 +249,62 @@ impl<T, A: Allocator> FixedDequeLifos<T, A> {
         vec_deque.into()
     }
 
+    /// Like [`From<Vec>`], but use only `capacity` items of `vec`'s buffer, even if it has more
+    /// (which [`Vec::with_capacity()`] and the like may give). The rest is the slack: it's recorded
+    /// (see [`FixedDequeLifos::slack()`]), and it can be put to use later (see
+    /// [`FixedDequeLifos::use_slack()`]).
+    ///
+    /// Return [`Error::InvalidArgument`] if `vec` is not empty, or if `capacity` exceeds its
+    /// capacity.
+    pub fn try_from_vec_with_capacity(vec: Vec<T, A>, capacity: usize) -> Result<Self, Error> {
+        if !vec.is_empty() || capacity > vec.capacity() {
+            return Err(Error::InvalidArgument);
+        }
+        let mut lifos = Self::from(vec);
+        lifos.limit = capacity;
+        trace_event!(debug, capacity, slack = lifos.slack(), "trimmed lifos");
+        Ok(lifos)
+    }
+}
+
+impl<T, A: Allocator, P: CapacityPolicy> FixedDequeLifos<T, A, P> {
+    const PANICKING_API: () = assert!(
+        P::PANICKING_API,
+        "FixedDequeLifos with CapacityPolicy Fallible has no panicking API: use try_push_left() or try_push_right()."
+    );
+
+    /// The same items and capacity, with capacity policy `Q` from now on.
+    pub fn with_policy<Q: CapacityPolicy>(self) -> FixedDequeLifos<T, A, Q> {
+        FixedDequeLifos {
+            vec_deque: self.vec_deque,
+            left: self.left,
+            right: self.right,
+            limit: self.limit,
+            #[cfg(debug_assertions)]
+            original_capacity: self.original_capacity,
+            policy: PhantomData,
+        }
+    }
+
+    /// Number of items that the backing [`VecDeque`] has room for beyond [`Lifos::capacity()`].
+    /// Zero, unless created by [`FixedDequeLifos::try_from_vec_with_capacity()`].
+    pub fn slack(&self) -> usize {
+        self.vec_deque.capacity() - self.limit
+    }
+
+    /// Add the slack to [`Lifos::capacity()`] (for extra working space). Return the number of
+    /// items added.
+    pub fn use_slack(&mut self) -> usize {
+        let slack = self.slack();
+        self.limit += slack;
+        slack
+    }
+
+    /// The allocator of the backing [`VecDeque`].
+    pub fn allocator(&self) -> &A {
+        self.vec_deque.allocator()
+    }
+
     /// Consume this instance, and return the underlying [`VecDeque`]. Sufficient for use by
     /// [`CrossVecPairGuard`], which (instead of [`FixedDequeLifos::left`] and
     /// [`FixedDequeLifos::right`]) uses [`VecDeque::as_mut_slices()`] to retrieve both the left &
@@ -188,47 +322,186 @@ impl<T, A: Allocator> FixedDequeLifos<T, A> {
         self.vec_deque
     }
 
+    /// Move all items of `other` onto the respective sides of `self`, keeping their LIFO order:
+    /// `other`'s bottom items end up right on top of `self`'s top items (on each side). That
+    /// leaves `other` empty (and still usable).
+    ///
+    /// If the items don't fit in the (fixed) capacity of `self`, return
+    /// [`Error::CapacityExceeded`], and move nothing.
+    pub fn append<B: Allocator, Q: CapacityPolicy>(
+        &mut self,
+        other: &mut FixedDequeLifos<T, B, Q>,
+    ) -> Result<(), Error> {
+        self.debug_assert_consistent();
+        other.debug_assert_consistent();
+        // The very first push to the RIGHT needs total capacity for two. See `try_push_right(...)`.
+        let first_push_right = self.is_empty() && other.left == 0 && other.right > 0;
+        if self.len() + other.len() > self.capacity() || first_push_right && self.capacity() < 2 {
+            return Err(Error::CapacityExceeded);
+        }
+        // LEFT items first, so that (unless there are none) the RIGHT ones don't need the
+        // temporary item. In `other.vec_deque` the RIGHT items come first (top to bottom), then the
+        // LEFT items (bottom to top): both as `self` needs them.
+        let right = other.right;
+        self.vec_deque.extend(other.vec_deque.drain(right..));
+        self.left += other.left;
+
+        // What's left are the RIGHT items, at the end of the buffer: one slice.
+        let block = other.vec_deque.as_slices().0;
+        debug_assert_eq!(block.len(), right);
+        let slots = self.reserve_right(right);
+        unsafe { ptr::copy_nonoverlapping(block.as_ptr(), slots.as_mut_ptr() as *mut T, right) };
+        // Moved out (bitwise) already: don't drop them.
+        unsafe { as_uninit(&mut other.vec_deque) }.clear();
+        other.left = 0;
+        other.right = 0;
+
+        self.debug_assert_consistent();
+        other.debug_assert_consistent();
+        Ok(())
+    }
+
+    /// Make room for `n` more RIGHT items (which have to fit), and return their slots: top to
+    /// bottom, uninitialized. It moves no items: it pushes (data-less) placeholders only. The
+    /// caller has to fill all the slots before anything else uses `self`.
+    fn reserve_right(&mut self, n: usize) -> &mut [MaybeUninit<T>] {
+        debug_assert!(self.len() + n <= self.capacity());
+        let vec_deque = unsafe { as_uninit(&mut self.vec_deque) };
+        let mut placeholders = n;
+        if n > 0 && vec_deque.is_empty() {
+            // The very first RIGHT item needs a temporary LEFT one: see `try_push_right(...)`.
+            vec_deque.push_back(MaybeUninit::uninit());
+            vec_deque.push_front(MaybeUninit::uninit());
+            vec_deque.pop_back();
+            placeholders -= 1;
+        }
+        for _ in 0..placeholders {
+            vec_deque.push_front(MaybeUninit::uninit());
+        }
+        self.right += n;
+        // The RIGHT items (new ones first) come first: see `debug_assert_consistent()`.
+        &mut vec_deque.as_mut_slices().0[..n]
+    }
+
     #[inline(always)]
     fn debug_assert_consistent(&self) {
         #[cfg(debug_assertions)]
         debug_assert_eq!(self.original_capacity, self.vec_deque.capacity());
-        debug_assert_eq!(self.left + self.right, self.vec_deque.len());
+        debug_assert_eq!(self.len(), self.vec_deque.len());
         debug_assert!({
-            let (back, front) = self.vec_deque.as_slices();
-            debug_assert_eq!(back.len(), self.left);
-            debug_assert_eq!(front.len(), self.right);
+            // RIGHT items (if any) were pushed to the front, hence they wrapped around to the end
+            // of the buffer, and they come first. Otherwise the LEFT items come first.
+            let (first, second) = self.vec_deque.as_slices();
+            if self.right > 0 {
+                debug_assert_eq!(first.len(), self.right);
+                debug_assert_eq!(second.len(), self.left);
+            } else {
+                debug_assert_eq!(first.len(), self.left);
+                debug_assert!(second.is_empty());
+            }
             true
         });
     }
 
-    /// NON-debug assert: run in RELEASE, too. Otherwise client's mistakes could lead to undefined
-    /// behavior.
+    /// Checked even in RELEASE (by the callers), unless the policy is [`Unchecked`]. Otherwise
+    /// client's mistakes would make [`VecDeque`] re-allocate.
     #[inline(always)]
-    fn assert_reserve_for_one(&self) {
-        assert!(self.vec_deque.len() < self.vec_deque.capacity());
+    fn has_reserve_for_one(&self) -> bool {
+        self.fits(1)
     }
 
-    /// NON-debug assert: running in RELEASE, too. Call only on empty: specialized for use by
-    /// `push_right(...)`.
+    /// Whether `additional` more items fit in the capacity: always, if the policy is [`Unchecked`]
+    /// (but then it's checked in debug builds).
     #[inline(always)]
-    fn assert_total_capacity_for_two(&self) {
+    fn fits(&self, additional: usize) -> bool {
+        // The second condition follows from the first one (the limit being at most the capacity of
+        // `vec_deque`). But spelled out, it lets the compiler drop `VecDeque`'s growth path (which
+        // would panic on failure): see `src/no_panic_tests.rs`.
+        let fits = self.len() + additional <= self.capacity()
+            && additional <= self.vec_deque.capacity() - self.vec_deque.len();
+        debug_assert!(
+            fits || P::CHECKED,
+            "Pushing {additional} item(s) would exceed the capacity (of an Unchecked FixedDequeLifos)."
+        );
+        fits || !P::CHECKED
+    }
+
+    /// Checked even in RELEASE (by the callers), unless the policy is [`Unchecked`]. Call only on
+    /// empty: specialized for use by `try_push_right(...)`.
+    #[inline(always)]
+    fn has_total_capacity_for_two(&self) -> bool {
         debug_assert!(
             self.vec_deque.is_empty(),
             "This can be called only when vec_deque is empty. But it has {} item(s) instead!",
             self.vec_deque.len()
         );
-        assert!(self.vec_deque.capacity() >= 2);
+        let fits = self.vec_deque.capacity() >= 2;
+        debug_assert!(fits || P::CHECKED);
+        fits || !P::CHECKED
     }
 }
 
-impl<T, A: Allocator> Lifos<T> for FixedDequeLifos<T, A> {
+/// Placeholders (see `FixedDequeLifos::reserve_right(...)`) on top of the RIGHT side, that are not
+/// filled (yet). Removed once dropped.
+struct Unfilled<'a, T: 'a, A: Allocator + 'a> {
+    vec_deque: &'a mut VecDeque<T, A>,
+    right: &'a mut usize,
+    count: usize,
+}
+
+impl<T, A: Allocator> Drop for Unfilled<'_, T, A> {
+    fn drop(&mut self) {
+        let vec_deque = unsafe { as_uninit(self.vec_deque) };
+        for _ in 0..self.count {
+            vec_deque.pop_front();
+        }
+        *self.right -= self.count;
+    }
+}
+
+/// View `vec_deque` as holding [`MaybeUninit`] items: to push placeholders, or to forget the items
+/// (once moved out bitwise).
+///
+/// # Safety
+///
+/// The same layout assumption as of `try_push_right(...)`. Any placeholders pushed through the view
+/// have to be initialized before `vec_deque` gets used (or dropped) as usual again.
+unsafe fn as_uninit<T, A: Allocator>(
+    vec_deque: &mut VecDeque<T, A>,
+) -> &mut VecDeque<MaybeUninit<T>, A> {
+    &mut *(vec_deque as *mut VecDeque<T, A> as *mut VecDeque<MaybeUninit<T>, A>)
+}
+
+impl<T, A: Allocator, P: CapacityPolicy> Lifos<T> for FixedDequeLifos<T, A, P> {
     fn has_to_push_left_first() -> bool {
         true
     }
 
+    /// Fails to compile with [`Fallible`].
+    #[cfg(not(feature = "no-panic"))]
     fn push_left(&mut self, value: T) {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::PANICKING_API;
+        if self.try_push_left(value).is_err() {
+            release_panic!("Pushing to the LEFT side would exceed the capacity.");
+        }
+    }
+
+    /// Fails to compile with [`Fallible`].
+    #[cfg(not(feature = "no-panic"))]
+    fn push_right(&mut self, value: T) {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::PANICKING_API;
+        if self.try_push_right(value).is_err() {
+            release_panic!("Pushing to the RIGHT side would exceed the capacity.");
+        }
+    }
+
+    fn try_push_left(&mut self, value: T) -> Result<(), T> {
         self.debug_assert_consistent();
-        self.assert_reserve_for_one();
+        if !self.has_reserve_for_one() {
+            return Err(value);
+        }
 
         // We can always push to LEFT (VecDeque back), regardless of whether there is any RIGHT
         // (front) item or not. This will not upset the RIGHT (front) slice. (And, if there were no
@@ -238,16 +511,21 @@ impl<T, A: Allocator> Lifos<T> for FixedDequeLifos<T, A> {
         self.left += 1;
 
         self.debug_assert_consistent();
+        Ok(())
     }
 
-    fn push_right(&mut self, value: T) {
+    fn try_push_right(&mut self, value: T) -> Result<(), T> {
         self.debug_assert_consistent();
 
-        if !self.vec_deque.is_empty() {
-            self.assert_reserve_for_one();
+        if !self.is_empty() {
+            if !self.has_reserve_for_one() {
+                return Err(value);
+            }
             self.vec_deque.push_front(value);
         } else {
-            self.assert_total_capacity_for_two();
+            if !self.has_total_capacity_for_two() {
+                return Err(value);
+            }
 
             unsafe {
                 // The following failed to compile with our crate's feature
@@ -259,6 +537,13 @@ impl<T, A: Allocator> Lifos<T> for FixedDequeLifos<T, A> {
                 // TODO is this sound?
                 let mut vec_deque =
                     ptr::read(&self.vec_deque as *const _ as *const VecDeque<MaybeUninit<T>, A>);
+                // Checked already (see `has_total_capacity_for_two()`, and `self` is empty), but
+                // not on this copy. Like in `fits()`, this lets the compiler drop `VecDeque`'s
+                // growth path.
+                if P::CHECKED && (!vec_deque.is_empty() || vec_deque.capacity() < 2) {
+                    mem::forget(vec_deque);
+                    return Err(value);
+                }
 
                 vec_deque.push_back(MaybeUninit::uninit());
                 vec_deque.push_front(MaybeUninit::new(value));
@@ -284,6 +569,105 @@ impl<T, A: Allocator> Lifos<T> for FixedDequeLifos<T, A> {
         self.right += 1;
 
         self.debug_assert_consistent();
+        Ok(())
+    }
+
+    /// As many items as fit, and as `items` promises (see [`Iterator::size_hint()`]), in bulk
+    /// (through [`alloc::collections::VecDeque::extend()`]). Any more one by one.
+    fn extend_left<ITER>(&mut self, items: ITER) -> Result<usize, T>
+    where
+        ITER: IntoIterator<Item = T>,
+    {
+        self.debug_assert_consistent();
+        let mut items = items.into_iter();
+        let room = self.capacity().saturating_sub(self.len());
+        let before = self.vec_deque.len();
+        self.vec_deque.extend(items.by_ref().take(room));
+        let mut pushed = self.vec_deque.len() - before;
+        self.left += pushed;
+        self.debug_assert_consistent();
+
+        for item in items {
+            self.try_push_left(item)?;
+            pushed += 1;
+        }
+        Ok(pushed)
+    }
+
+    /// As many items as fit, and as `items` promises (see [`Iterator::size_hint()`]), written right
+    /// into slots reserved in bulk. Any more one by one.
+    fn extend_right<ITER>(&mut self, items: ITER) -> Result<usize, T>
+    where
+        ITER: IntoIterator<Item = T>,
+    {
+        self.debug_assert_consistent();
+        let mut items = items.into_iter();
+        let room = self.capacity().saturating_sub(self.len());
+        let mut reserve = items.size_hint().0.min(room);
+        if self.is_empty() && reserve > 0 && !self.has_total_capacity_for_two() {
+            reserve = 0;
+        }
+        // The slots come top to bottom, so they get filled from the last one. If `items` ends
+        // early (or panics), `unfilled` drops the placeholders left on top.
+        let slots = self.reserve_right(reserve).as_mut_ptr();
+        let mut unfilled = Unfilled {
+            vec_deque: &mut self.vec_deque,
+            right: &mut self.right,
+            count: reserve,
+        };
+        while unfilled.count > 0 {
+            let Some(item) = items.next() else {
+                break;
+            };
+            unfilled.count -= 1;
+            unsafe { slots.add(unfilled.count).write(MaybeUninit::new(item)) };
+        }
+        let mut pushed = reserve - unfilled.count;
+        drop(unfilled);
+        self.debug_assert_consistent();
+
+        for item in items {
+            self.try_push_right(item)?;
+            pushed += 1;
+        }
+        Ok(pushed)
+    }
+
+    /// In bulk ([`alloc::collections::VecDeque::extend()`] copies slices).
+    fn extend_left_from_slice(&mut self, items: &[T]) -> Result<(), Error>
+    where
+        T: Copy,
+    {
+        self.debug_assert_consistent();
+        if !self.fits(items.len()) {
+            return Err(Error::CapacityExceeded);
+        }
+        self.vec_deque.extend(items.iter());
+        self.left += items.len();
+
+        self.debug_assert_consistent();
+        Ok(())
+    }
+
+    /// One [`ptr::copy_nonoverlapping()`] into slots reserved in bulk, then reversed in place (the
+    /// RIGHT items are stored top to bottom).
+    fn extend_right_from_slice(&mut self, items: &[T]) -> Result<(), Error>
+    where
+        T: Copy,
+    {
+        self.debug_assert_consistent();
+        let first_push = self.is_empty() && !items.is_empty();
+        if !self.fits(items.len()) || first_push && !self.has_total_capacity_for_two() {
+            return Err(Error::CapacityExceeded);
+        }
+        let slots = self.reserve_right(items.len());
+        unsafe {
+            ptr::copy_nonoverlapping(items.as_ptr(), slots.as_mut_ptr() as *mut T, items.len())
+        };
+        slots.reverse();
+
+        self.debug_assert_consistent();
+        Ok(())
     }
 
     fn right(&self) -> usize {
@@ -292,4 +676,125 @@ impl<T, A: Allocator> Lifos<T> for FixedDequeLifos<T, A> {
     fn left(&self) -> usize {
         self.left
     }
+
+    fn peek_left(&self) -> Option<&T> {
+        if self.left == 0 {
+            return None;
+        }
+        self.vec_deque.back()
+    }
+    fn peek_right(&self) -> Option<&T> {
+        if self.right == 0 {
+            return None;
+        }
+        self.vec_deque.front()
+    }
+    fn peek_left_mut(&mut self) -> Option<&mut T> {
+        if self.left == 0 {
+            return None;
+        }
+        self.vec_deque.back_mut()
+    }
+    fn peek_right_mut(&mut self) -> Option<&mut T> {
+        if self.right == 0 {
+            return None;
+        }
+        self.vec_deque.front_mut()
+    }
+
+    fn capacity(&self) -> usize {
+        self.limit
+    }
+
+    /// At least 2, even for one item (or none). See [`FixedDequeLifos::from()`].
+    fn required_capacity_for(n: usize) -> usize {
+        n.max(2)
+    }
+}
+
+impl<T> FixedDequeLifos<T> {
+    /// Temporarily treat an (initially EMPTY) `vec_deque` as 2 LIFOs, without moving it. Once the
+    /// returned wrapper is dropped, `vec_deque` contains the pushed items (as per
+    /// [`FixedDequeLifos::into_vec_deque()`]).
+    ///
+    /// Return [`Error::InvalidArgument`] (and leave `vec_deque` as it is) if `vec_deque` is not
+    /// empty.
+    pub fn from_mut(vec_deque: &mut VecDeque<T>) -> Result<FixedDequeLifosMut<'_, T>, Error> {
+        if !vec_deque.is_empty() {
+            return Err(Error::InvalidArgument);
+        }
+        // No allocation: the new (empty) VecDeque lives only until the wrapper is dropped.
+        let lifos = mem::take(vec_deque).into();
+        Ok(FixedDequeLifosMut {
+            lifos: ManuallyDrop::new(lifos),
+            vec_deque,
+        })
+    }
+}
+
+/// [`FixedDequeLifos`] over a borrowed [`VecDeque`]. See [`FixedDequeLifos::from_mut()`].
+#[derive(Debug)]
+pub struct FixedDequeLifosMut<'a, T>
+where
+    T: 'a,
+{
+    /// Taken out (only) in [`Drop::drop()`].
+    lifos: ManuallyDrop<FixedDequeLifos<T>>,
+    vec_deque: &'a mut VecDeque<T>,
+}
+
+impl<T> Deref for FixedDequeLifosMut<'_, T> {
+    type Target = FixedDequeLifos<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.lifos
+    }
+}
+
+impl<T> DerefMut for FixedDequeLifosMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.lifos
+    }
+}
+
+impl<T> Drop for FixedDequeLifosMut<'_, T> {
+    fn drop(&mut self) {
+        let lifos = unsafe { ManuallyDrop::take(&mut self.lifos) };
+        *self.vec_deque = lifos.into_vec_deque();
+    }
+}
+
+impl<T> LifosWithCapacity<T> for FixedDequeLifos<T> {
+    fn with_capacity(n: usize) -> Self {
+        VecDeque::with_capacity(Self::required_capacity_for(n)).into()
+    }
+}
+
+impl<T> LifosWithCapacity<T> for DequeLifos<T, alloc::collections::VecDeque<T>> {
+    fn with_capacity(n: usize) -> Self {
+        alloc::collections::VecDeque::with_capacity(n).into()
+    }
+}
+
+/// [`DequeLike`] as is. Its capacity is the one it has now: [`crate::store::lifos::DequeLifos`]
+/// never pushes beyond it (hence it never re-allocates).
+impl<T> DequeLike<T> for alloc::collections::VecDeque<T> {
+    fn push_front(&mut self, value: T) {
+        self.push_front(value);
+    }
+    fn push_back(&mut self, value: T) {
+        self.push_back(value);
+    }
+    fn as_slices(&self) -> (&[T], &[T]) {
+        self.as_slices()
+    }
+    fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        self.as_mut_slices()
+    }
+    fn capacity(&self) -> usize {
+        self.capacity()
+    }
+    fn len(&self) -> usize {
+        self.len()
+    }
 }
@@ -1,10 +1,12 @@
 use crate::calloc::calloc_vec::VecDeque;
-use crate::store::lifos::lifos_vec::FixedDequeLifos;
-use crate::store::lifos::Lifos;
-use std::time::{SystemTime, UNIX_EPOCH};
+use crate::error::Error;
+use crate::store::lifos::lifos_vec::{Checked, Fallible, FixedDequeLifos, Unchecked};
+use crate::store::lifos::{partition_into, Lifos};
+use crate::util::rng::Rng;
 
 extern crate std;
 
+#[cfg(not(feature = "no-panic"))]
 #[test]
 fn left_right_left() {
     let mut lifos = FixedDequeLifos::<u8>::new_from_empty(VecDeque::<u8>::with_capacity(3));
@@ -12,6 +14,7 @@ fn left_right_left() {
     lifos.push_right(2);
     lifos.push_left(3);
 }
+#[cfg(not(feature = "no-panic"))]
 #[test]
 fn right_left_right() {
     let mut lifos = FixedDequeLifos::<u8>::new_from_empty(VecDeque::<u8>::with_capacity(7));
@@ -21,24 +24,8 @@ fn right_left_right() {
 }
 
 // ------------
-const MAX_RND: u32 = 1000_000_000;
-// Thanks to https://blog.orhun.dev/zero-deps-random-in-rust/
-/// Return a (semi)random number, between 0 (inclusive) up to `max` (exclusive). `max-min` must be
-/// less than, or equal to, 1 billion (10 to power of 9).
-///
-/// For high `max-min` (comparable to 1 billion) this is biased towards the lower part of the range.
-/// (Of course, `max` must be higher than `min`.)
-///
-/// NOT crypto-secure - for testing/controlled/isolated runs only.
-fn rnd_u32(min: u32, max: u32) -> u32 {
-    let range_width = max - min;
-    assert!(range_width > 0);
-    assert!(range_width <= MAX_RND);
-    match SystemTime::now().duration_since(UNIX_EPOCH) {
-        Ok(duration) => min + (duration.subsec_nanos() % range_width),
-        Err(err) => panic!("Failed to get system time nanoseconds: {}", err),
-    }
-}
+/// Fixed seed, so that failures are reproducible.
+const SEED: u64 = 0x1a2b_3c4d;
 
 const MIN_VEC_DEQUE_CAPACITY: u32 = 2;
 const MAX_VEC_DEQUE_CAPACITY: u32 = 65535;
@@ -78,7 +65,8 @@ fn single_item_vec_deque_rotate_left_does_not_circular(capacity: usize) {
 fn empty_vec_deque_puts_back_item_to_front_for_capacities() {
     empty_vec_deque_puts_back_item_to_front(MIN_VEC_DEQUE_CAPACITY as usize);
 
-    let capacity = rnd_u32(MIN_VEC_DEQUE_CAPACITY, MAX_VEC_DEQUE_CAPACITY) as usize;
+    let capacity =
+        Rng::new(SEED).range_u32(MIN_VEC_DEQUE_CAPACITY, MAX_VEC_DEQUE_CAPACITY) as usize;
     empty_vec_deque_puts_back_item_to_front(capacity);
 
     empty_vec_deque_puts_back_item_to_front(MAX_VEC_DEQUE_CAPACITY as usize);
@@ -93,8 +81,334 @@ fn empty_vec_deque_puts_back_item_to_front_for_capacities() {
 fn single_item_vec_deque_rotate_left_does_not_circular_for_capacities() {
     single_item_vec_deque_rotate_left_does_not_circular(MIN_VEC_DEQUE_CAPACITY as usize);
 
-    let capacity = rnd_u32(MIN_VEC_DEQUE_CAPACITY, MAX_VEC_DEQUE_CAPACITY) as usize;
+    let capacity =
+        Rng::new(SEED).range_u32(MIN_VEC_DEQUE_CAPACITY, MAX_VEC_DEQUE_CAPACITY) as usize;
     single_item_vec_deque_rotate_left_does_not_circular(capacity);
 
     single_item_vec_deque_rotate_left_does_not_circular(MAX_VEC_DEQUE_CAPACITY as usize);
 }
+
+#[test]
+fn try_push_beyond_capacity() {
+    let mut lifos = FixedDequeLifos::<u8>::new_from_empty(VecDeque::<u8>::with_capacity(2));
+    assert_eq!(lifos.try_push_left(1), Ok(()));
+    assert_eq!(lifos.try_push_left(2), Ok(()));
+    assert_eq!(lifos.try_push_left(3), Err(3));
+    assert_eq!(lifos.try_push_right(4), Err(4));
+    assert_eq!(lifos.left(), 2);
+    assert_eq!(lifos.right(), 0);
+}
+
+#[test]
+fn capacity_policies() {
+    let lifos = FixedDequeLifos::<u8>::new_from_empty(VecDeque::<u8>::with_capacity(3));
+    let mut lifos = lifos.with_policy::<Fallible>();
+    assert_eq!(lifos.try_push_right(1), Ok(()));
+    assert_eq!(lifos.extend_left_from_slice(&[2, 3]), Ok(()));
+    assert_eq!(lifos.try_push_left(4), Err(4));
+    assert_eq!(
+        lifos.extend_left_from_slice(&[5]),
+        Err(Error::CapacityExceeded)
+    );
+
+    // The items stay, whatever the policy.
+    let mut lifos = lifos.with_policy::<Unchecked>();
+    assert_eq!((lifos.left(), lifos.right(), lifos.capacity()), (2, 1, 3));
+    assert_eq!(lifos.peek_left_mut().map(|item| *item), Some(3));
+    let lifos = lifos.with_policy::<Checked>();
+    assert!(lifos.into_vec_deque().iter().eq(&[1, 2, 3]));
+}
+
+/// Within the capacity, [`Unchecked`] behaves as [`Checked`]. Beyond it, it panics in debug
+/// builds, and re-allocates in release.
+#[cfg(not(feature = "no-panic"))]
+#[test]
+#[cfg_attr(debug_assertions, should_panic(expected = "exceed the capacity"))]
+fn unchecked_beyond_capacity() {
+    let lifos = FixedDequeLifos::<u8>::new_from_empty(VecDeque::<u8>::with_capacity(2));
+    let mut lifos = lifos.with_policy::<Unchecked>();
+    lifos.push_right(1);
+    lifos.push_left(2);
+    assert_eq!(lifos.try_push_left(3), Ok(()));
+    assert_eq!((lifos.left(), lifos.right()), (2, 1));
+}
+
+#[test]
+fn slack() {
+    use crate::calloc::calloc_vec::Vec;
+    use crate::store::cross::cross_vec::CrossVecPairGuard;
+
+    let vec = Vec::<u8>::with_capacity(6);
+    let full_capacity = vec.capacity();
+    let mut lifos = FixedDequeLifos::try_from_vec_with_capacity(vec, 3).unwrap();
+    assert_eq!((lifos.capacity(), lifos.slack()), (3, full_capacity - 3));
+    for value in 0..3 {
+        assert_eq!(lifos.try_push_right(value), Ok(()));
+    }
+    assert_eq!(lifos.try_push_left(3), Err(3));
+    assert_eq!(lifos.use_slack(), full_capacity - 3);
+    assert_eq!((lifos.capacity(), lifos.slack()), (full_capacity, 0));
+    assert_eq!(lifos.try_push_left(3), Ok(()));
+
+    let vec = Vec::<u8>::with_capacity(4);
+    let full_capacity = vec.capacity();
+    let lifos = FixedDequeLifos::try_from_vec_with_capacity(vec, 2).unwrap();
+    let guard = CrossVecPairGuard::new_from_lifos(lifos);
+    assert_eq!(guard.slack(), full_capacity - 2);
+    drop(guard);
+
+    let vec = Vec::<u8>::with_capacity(4);
+    let too_much = vec.capacity() + 1;
+    assert_eq!(
+        FixedDequeLifos::try_from_vec_with_capacity(vec, too_much).unwrap_err(),
+        Error::InvalidArgument
+    );
+}
+
+#[test]
+fn partition_into_lifos() {
+    let mut lifos = FixedDequeLifos::<u8>::new_from_empty(VecDeque::<u8>::with_capacity(8));
+    let result = partition_into([5, 1, 9, 5, 3, 7], &5, &mut lifos, &mut u8::cmp);
+    assert_eq!(result, Ok((2, 4)));
+    assert_eq!((lifos.left(), lifos.right()), (2, 4));
+    let overflow = partition_into([0, 2, 4], &1, &mut lifos, &mut u8::cmp);
+    assert_eq!(overflow, Err(4));
+}
+
+#[test]
+fn append() {
+    let mut lifos = FixedDequeLifos::<u8>::new_from_empty(VecDeque::<u8>::with_capacity(8));
+    assert_eq!(lifos.try_push_left(1), Ok(()));
+    assert_eq!(lifos.try_push_right(10), Ok(()));
+    let mut other = FixedDequeLifos::<u8>::new_from_empty(VecDeque::<u8>::with_capacity(4));
+    assert_eq!(other.try_push_left(2), Ok(()));
+    assert_eq!(other.try_push_left(3), Ok(()));
+    assert_eq!(other.try_push_right(11), Ok(()));
+    assert_eq!(other.try_push_right(12), Ok(()));
+    assert_eq!(lifos.append(&mut other), Ok(()));
+    assert_eq!((lifos.left(), lifos.right()), (3, 3));
+    assert_eq!((other.left(), other.right()), (0, 0));
+    // RIGHT side (top to bottom), then LEFT side (bottom to top).
+    let items: std::vec::Vec<u8> = lifos.into_vec_deque().iter().copied().collect();
+    assert_eq!(items, [12, 11, 10, 1, 2, 3]);
+    // `other` is still usable.
+    assert_eq!(other.try_push_right(5), Ok(()));
+}
+
+/// The items are moved (not copied, nor dropped), and the layout stays as a [`CrossVecPairGuard`]
+/// expects it.
+#[test]
+fn append_moves_in_bulk() {
+    use crate::store::cross::cross_vec::CrossVecPairGuard;
+    use crate::testing::DropCounter;
+    use alloc::{vec, vec::Vec};
+
+    let counter = DropCounter::new();
+    for (self_left, self_right) in [(0u8, 0u8), (0, 2), (2, 0), (1, 1)] {
+        let mut lifos = FixedDequeLifos::<_>::new_from_empty(VecDeque::with_capacity(10));
+        for value in 0..self_left {
+            assert!(lifos.try_push_left(counter.track(value)).is_ok());
+        }
+        for value in 0..self_right {
+            assert!(lifos.try_push_right(counter.track(10 + value)).is_ok());
+        }
+        let mut other = FixedDequeLifos::<_>::new_from_empty(VecDeque::with_capacity(6));
+        for value in 0..3 {
+            assert!(other.try_push_left(counter.track(20 + value)).is_ok());
+            assert!(other.try_push_right(counter.track(30 + value)).is_ok());
+        }
+        assert_eq!(lifos.append(&mut other), Ok(()));
+        let (self_left_len, self_right_len) = (usize::from(self_left), usize::from(self_right));
+        assert_eq!(
+            (lifos.left(), lifos.right()),
+            (self_left_len + 3, self_right_len + 3)
+        );
+        assert_eq!(counter.live(), 6 + self_left_len + self_right_len);
+        assert_eq!(**lifos.peek_right().unwrap(), 32);
+        assert_eq!(**lifos.peek_left().unwrap(), 22);
+
+        let mut guard = CrossVecPairGuard::try_from_lifos(lifos).unwrap();
+        let pair = guard.try_temp_take().unwrap();
+        let right: Vec<u8> = pair.0.iter().map(|tracker| **tracker).collect();
+        let left: Vec<u8> = pair.1.iter().map(|tracker| **tracker).collect();
+        let mut expected_right = vec![32, 31, 30];
+        expected_right.extend((0..self_right).rev().map(|value| 10 + value));
+        let mut expected_left: Vec<u8> = (0..self_left).collect();
+        expected_left.extend([20, 21, 22]);
+        assert_eq!((right, left), (expected_right, expected_left));
+        assert!(guard.try_move_back_join_into(pair).is_ok());
+        drop(other);
+        counter.assert_all_dropped();
+    }
+}
+
+#[test]
+fn append_beyond_capacity() {
+    let mut lifos = FixedDequeLifos::<u8>::new_from_empty(VecDeque::<u8>::with_capacity(3));
+    assert_eq!(lifos.try_push_left(1), Ok(()));
+    let mut other = FixedDequeLifos::<u8>::new_from_empty(VecDeque::<u8>::with_capacity(3));
+    for value in 2..5 {
+        assert_eq!(other.try_push_right(value), Ok(()));
+    }
+    assert_eq!(lifos.append(&mut other), Err(Error::CapacityExceeded));
+    assert_eq!((lifos.left(), lifos.right()), (1, 0));
+    assert_eq!((other.left(), other.right()), (0, 3));
+
+    let mut empty = FixedDequeLifos::<u8>::new_from_empty(VecDeque::<u8>::with_capacity(3));
+    assert_eq!(empty.append(&mut other), Ok(()));
+    assert_eq!((empty.left(), empty.right()), (0, 3));
+}
+
+#[test]
+fn deque_lifos_over_vec_deque() {
+    use crate::store::lifos::DequeLifos;
+    let mut lifos = DequeLifos::from(alloc::collections::VecDeque::<u8>::with_capacity(3));
+    assert_eq!(lifos.try_push_right(1), Ok(()));
+    assert_eq!(lifos.try_push_left(2), Ok(()));
+    let capacity = lifos.into_deque().capacity();
+    let mut lifos = DequeLifos::from(alloc::collections::VecDeque::<u8>::with_capacity(3));
+    for value in 0..capacity as u8 {
+        assert_eq!(lifos.try_push_left(value), Ok(()));
+    }
+    assert_eq!(lifos.try_push_right(99), Err(99));
+}
+
+#[test]
+fn capacity_contract() {
+    use crate::store::lifos::{DequeLifos, LifosWithCapacity};
+    assert_eq!(FixedDequeLifos::<u8>::required_capacity_for(1), 2);
+    assert_eq!(FixedDequeLifos::<u8>::required_capacity_for(10), 10);
+    let lifos = FixedDequeLifos::<u8>::with_capacity(1);
+    assert!(lifos.capacity() >= 2);
+    assert!(lifos.can_hold(1) && lifos.can_hold(lifos.capacity()));
+    assert!(!lifos.can_hold(lifos.capacity() + 1));
+
+    let mut lifos = DequeLifos::<u8, alloc::collections::VecDeque<u8>>::with_capacity(4);
+    assert!(lifos.can_hold(4));
+    for value in 0..lifos.capacity() as u8 {
+        assert_eq!(lifos.try_push_right(value), Ok(()));
+    }
+    assert_eq!(lifos.try_push_left(99), Err(99));
+}
+
+#[test]
+fn peek() {
+    let mut lifos = FixedDequeLifos::<u8>::new_from_empty(VecDeque::<u8>::with_capacity(4));
+    assert!(lifos.is_empty());
+    assert_eq!((lifos.peek_left(), lifos.peek_right()), (None, None));
+    assert_eq!(lifos.try_push_right(1), Ok(()));
+    assert_eq!((lifos.peek_left(), lifos.peek_right()), (None, Some(&1)));
+    assert_eq!(lifos.try_push_left(2), Ok(()));
+    assert_eq!(lifos.try_push_left(3), Ok(()));
+    assert_eq!(
+        (lifos.peek_left(), lifos.peek_right()),
+        (Some(&3), Some(&1))
+    );
+    *lifos.peek_right_mut().unwrap() = 5;
+    *lifos.peek_left_mut().unwrap() = 6;
+    assert_eq!(
+        (lifos.peek_left(), lifos.peek_right()),
+        (Some(&6), Some(&5))
+    );
+
+    let mut left_only = FixedDequeLifos::<u8>::new_from_empty(VecDeque::<u8>::with_capacity(2));
+    assert_eq!(left_only.try_push_left(7), Ok(()));
+    assert_eq!(
+        (left_only.peek_left(), left_only.peek_right()),
+        (Some(&7), None)
+    );
+}
+
+#[test]
+fn from_mut() {
+    let mut vec_deque = VecDeque::<u8>::with_capacity(4);
+    let capacity = vec_deque.capacity();
+    {
+        let mut lifos = FixedDequeLifos::from_mut(&mut vec_deque).unwrap();
+        assert_eq!(lifos.try_push_left(1), Ok(()));
+        assert_eq!(lifos.try_push_right(2), Ok(()));
+        assert_eq!(lifos.try_push_left(3), Ok(()));
+        assert_eq!((lifos.left(), lifos.right()), (2, 1));
+    }
+    // RIGHT side (top to bottom), then LEFT side (bottom to top).
+    let items: std::vec::Vec<u8> = vec_deque.iter().copied().collect();
+    assert_eq!(items, [2, 1, 3]);
+    assert_eq!(vec_deque.capacity(), capacity);
+
+    // Not empty: left as it is.
+    assert_eq!(
+        FixedDequeLifos::from_mut(&mut vec_deque).err(),
+        Some(Error::InvalidArgument)
+    );
+    let items: std::vec::Vec<u8> = vec_deque.iter().copied().collect();
+    assert_eq!(
+        (&items[..], vec_deque.capacity()),
+        (&[2, 1, 3][..], capacity)
+    );
+
+    vec_deque.clear();
+    let mut lifos = FixedDequeLifos::from_mut(&mut vec_deque).unwrap();
+    assert_eq!(lifos.try_push_right(5), Ok(()));
+    drop(lifos);
+    assert_eq!(vec_deque.front(), Some(&5));
+}
+
+#[test]
+fn extend_from_slice() {
+    let mut lifos = FixedDequeLifos::<u8>::new_from_empty(VecDeque::with_capacity(6));
+    assert_eq!(lifos.extend_right_from_slice(&[4, 5]), Ok(()));
+    assert_eq!(lifos.extend_left_from_slice(&[1, 2, 3]), Ok(()));
+    assert_eq!(
+        (lifos.peek_left(), lifos.peek_right()),
+        (Some(&3), Some(&5))
+    );
+    assert_eq!(
+        lifos.extend_left_from_slice(&[6, 7]),
+        Err(Error::CapacityExceeded)
+    );
+    assert_eq!(lifos.extend_left_from_slice(&[6]), Ok(()));
+    let vec_deque = lifos.into_vec_deque();
+    let (right, left) = vec_deque.as_slices();
+    assert_eq!((right, left), (&[5, 4][..], &[1, 2, 3, 6][..]));
+}
+
+/// Promises more items than it yields.
+struct Overpromising(core::ops::Range<u8>);
+
+impl Iterator for Overpromising {
+    type Item = u8;
+    fn next(&mut self) -> Option<u8> {
+        self.0.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len() + 2, None)
+    }
+}
+
+#[test]
+fn extend() {
+    let mut lifos = FixedDequeLifos::<u8>::new_from_empty(VecDeque::with_capacity(8));
+    assert_eq!(lifos.extend_right([4, 5]), Ok(2));
+    assert_eq!(lifos.extend_left([1, 2]), Ok(2));
+    assert_eq!(lifos.extend_right(Overpromising(6..7)), Ok(1));
+    // No size hint: one by one.
+    assert_eq!(lifos.extend_left((3..6).filter(|&value| value == 3)), Ok(1));
+    assert_eq!((lifos.left(), lifos.right()), (3, 3));
+    assert_eq!(lifos.extend_right(7..10), Err(9));
+    assert_eq!(lifos.extend_left(10..11), Err(10));
+    let vec_deque = lifos.into_vec_deque();
+    let (right, left) = vec_deque.as_slices();
+    assert_eq!((right, left), (&[8, 7, 6, 5, 4][..], &[1, 2, 3][..]));
+
+    let mut lifos = FixedDequeLifos::<u8>::new_from_empty(VecDeque::with_capacity(4));
+    assert_eq!(lifos.extend_right(Overpromising(0..1)), Ok(1));
+    assert_eq!(lifos.extend_right_from_slice(&[1, 2]), Ok(()));
+    assert_eq!(
+        lifos.extend_right_from_slice(&[3, 4]),
+        Err(Error::CapacityExceeded)
+    );
+    assert_eq!(lifos.extend_left(Overpromising(3..4)), Ok(1));
+    let vec_deque = lifos.into_vec_deque();
+    let (right, left) = vec_deque.as_slices();
+    assert_eq!((right, left), (&[2, 1, 0][..], &[3][..]));
+}
@@ -0,0 +1,223 @@
+//! Lazy sorting specialized for strings (and byte strings).
+
+use crate::engine;
+use core::cmp::Ordering;
+use core::marker::PhantomData;
+use core::slice;
+
+#[cfg(test)]
+mod strings_tests;
+
+/// Maximum number of pending segments. Beyond that, a segment is sorted fully (by generic
+/// comparisons of its remaining suffixes).
+const STACK_DEPTH: usize = 64;
+
+/// Segments this short get insertion-sorted (comparing the suffixes after their common prefix).
+const SMALL_SORT_LEN: usize = 8;
+
+/// A pending segment `start..end`, all of whose items share their first `depth` bytes.
+#[derive(Clone, Copy, Debug, Default)]
+struct Segment {
+    start: usize,
+    end: usize,
+    depth: usize,
+    sorted: bool,
+}
+
+/// Lazily sorts strings ([`str`], `[u8]`, or anything that is [`AsRef<[u8]>`]) by their bytes
+/// (which, for [`str`], is the same as their [`Ord`]), yielding them in ascending order.
+///
+/// It partitions by one byte at a time (three-way radix quicksort, also known as multi-key
+/// quicksort): the bytes of a common prefix are compared only once per partitioning level, rather
+/// than again in every comparison of two strings. That pays off for long shared prefixes (URLs,
+/// paths, keys with a common namespace...).
+///
+/// No allocation, no recursion.
+pub struct StrSorter<'a, S: AsRef<[u8]>> {
+    /// Start of the items. The yielded ones (at `..front`, in order) are accessed only shared, the
+    /// others (at `front..items_len`) only through `&mut self`. Positions (in [`Segment`]s) are
+    /// relative to the start.
+    items: *mut S,
+    items_len: usize,
+    front: usize,
+    /// Pending segments, covering `front..` (left to right from the top of the stack down).
+    stack: [Segment; STACK_DEPTH],
+    len: usize,
+    lifetime: PhantomData<&'a mut [S]>,
+}
+
+// Like `&'a mut [S]`, which it stands for.
+unsafe impl<'a, S: AsRef<[u8]> + Send> Send for StrSorter<'a, S> {}
+unsafe impl<'a, S: AsRef<[u8]> + Sync> Sync for StrSorter<'a, S> {}
+
+impl<'a, S: AsRef<[u8]>> StrSorter<'a, S> {
+    pub fn new(items: &'a mut [S]) -> Self {
+        let mut sorter = Self {
+            items: items.as_mut_ptr(),
+            items_len: items.len(),
+            front: 0,
+            stack: [Segment::default(); STACK_DEPTH],
+            len: 0,
+            lifetime: PhantomData,
+        };
+        if sorter.items_len > 0 {
+            sorter.push(0, sorter.items_len, 0, false);
+        }
+        sorter
+    }
+
+    /// Number of items not yielded yet.
+    pub fn len(&self) -> usize {
+        self.items_len - self.front
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The items yielded so far, in ascending order: a view of the start of the original items.
+    pub fn sorted_prefix(&self) -> &'a [S] {
+        unsafe { slice::from_raw_parts(self.items, self.front) }
+    }
+
+    /// The items not yielded yet: the (pending) segment that the next items come from, and the
+    /// rest. Each item of the first part is less than or equal to every item of the second part.
+    /// Neither is necessarily sorted.
+    pub fn unsorted_parts(&self) -> (&[S], &[S]) {
+        let end = match self.len {
+            0 => self.front,
+            len => self.stack[len - 1].end,
+        };
+        unsafe {
+            (
+                slice::from_raw_parts(self.items.add(self.front), end - self.front),
+                slice::from_raw_parts(self.items.add(end), self.items_len - end),
+            )
+        }
+    }
+
+    /// Items at `start..end` (not yielded yet).
+    fn segment_mut(&mut self, start: usize, end: usize) -> &mut [S] {
+        debug_assert!(self.front <= start && start <= end && end <= self.items_len);
+        unsafe { slice::from_raw_parts_mut(self.items.add(start), end - start) }
+    }
+
+    fn push(&mut self, start: usize, end: usize, depth: usize, sorted: bool) {
+        if start < end {
+            self.stack[self.len] = Segment {
+                start,
+                end,
+                depth,
+                sorted,
+            };
+            self.len += 1;
+        }
+    }
+
+    /// Partition (only as needed) so that the smallest item is first.
+    fn resolve_front(&mut self) {
+        loop {
+            let top = self.stack[self.len - 1];
+            if top.sorted || top.end - top.start == 1 {
+                return;
+            }
+            let stack_full = self.len + 2 > STACK_DEPTH;
+            let segment = self.segment_mut(top.start, top.end);
+            if segment.len() <= SMALL_SORT_LEN || stack_full {
+                let mut cmp = |a: &S, b: &S| suffix(a, top.depth).cmp(suffix(b, top.depth));
+                if segment.len() <= SMALL_SORT_LEN {
+                    engine::insertion_sort(segment, &mut cmp);
+                } else {
+                    trace_event!(
+                        debug,
+                        len = segment.len(),
+                        "segment limit reached: sorting fully"
+                    );
+                    engine::sort(segment, &mut cmp);
+                }
+                self.stack[self.len - 1].sorted = true;
+                return;
+            }
+            let (pivot, lt, gt) = partition(segment, top.depth);
+            trace_event!(
+                trace,
+                top.start,
+                top.end,
+                top.depth,
+                lt,
+                gt,
+                "partition by byte"
+            );
+            self.len -= 1;
+            let (lt, gt) = (top.start + lt, top.start + gt);
+            self.push(gt, top.end, top.depth, false);
+            // All equal: they share one more byte, or they all ended (then they are equal).
+            self.push(lt, gt, top.depth + 1, pivot.is_none());
+            self.push(top.start, lt, top.depth, false);
+        }
+    }
+}
+
+impl<'a, S: AsRef<[u8]>> Iterator for StrSorter<'a, S> {
+    type Item = &'a S;
+
+    fn next(&mut self) -> Option<&'a S> {
+        if self.front == self.items_len {
+            return None;
+        }
+        self.resolve_front();
+        let top = &mut self.stack[self.len - 1];
+        top.start += 1;
+        if top.start == top.end {
+            self.len -= 1;
+        }
+        self.front += 1;
+        // From now on accessed only shared (see `sorted_prefix()`).
+        Some(unsafe { &*self.items.add(self.front - 1) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len(), Some(self.len()))
+    }
+}
+
+impl<'a, S: AsRef<[u8]>> ExactSizeIterator for StrSorter<'a, S> {}
+
+fn suffix<S: AsRef<[u8]>>(item: &S, depth: usize) -> &[u8] {
+    item.as_ref().get(depth..).unwrap_or(&[])
+}
+
+/// The byte at `depth`, or [`None`] past the end (which sorts first).
+fn byte<S: AsRef<[u8]>>(item: &S, depth: usize) -> Option<u8> {
+    item.as_ref().get(depth).copied()
+}
+
+/// Three-way partition of `items` by their bytes at `depth`, around the median of the first, the
+/// middle and the last of those bytes. Return `(pivot, lt, gt)`, with the items equal to the pivot
+/// at `lt..gt`.
+fn partition<S: AsRef<[u8]>>(items: &mut [S], depth: usize) -> (Option<u8>, usize, usize) {
+    let len = items.len();
+    let mut samples = [
+        byte(&items[0], depth),
+        byte(&items[len / 2], depth),
+        byte(&items[len - 1], depth),
+    ];
+    samples.sort_unstable();
+    let pivot = samples[1];
+    let (mut lt, mut i, mut gt) = (0, 0, len);
+    while i < gt {
+        match byte(&items[i], depth).cmp(&pivot) {
+            Ordering::Less => {
+                items.swap(lt, i);
+                lt += 1;
+                i += 1;
+            }
+            Ordering::Equal => i += 1,
+            Ordering::Greater => {
+                gt -= 1;
+                items.swap(i, gt);
+            }
+        }
+    }
+    (pivot, lt, gt)
+}
@@ -0,0 +1,84 @@
+use crate::strings::StrSorter;
+
+fn words() -> [&'static str; 40] {
+    core::array::from_fn(|i| {
+        const WORDS: [&str; 10] = [
+            "https://example.com/a",
+            "https://example.com/ab",
+            "https://example.com/",
+            "https://example.org/b",
+            "",
+            "https://example.com/a",
+            "http",
+            "zebra",
+            "https://example.com/b",
+            "é",
+        ];
+        WORDS[(i * 7) % 10]
+    })
+}
+
+#[test]
+fn yields_in_ord_order() {
+    let mut expected = words();
+    expected.sort_unstable();
+    let mut words = words();
+    let sorter = StrSorter::new(&mut words);
+    assert_eq!(sorter.len(), 40);
+    assert!(sorter.copied().eq(expected));
+}
+
+#[test]
+fn byte_strings_and_empty() {
+    let mut bytes: [&[u8]; 5] = [b"\xff", b"a\x00", b"a", b"", b"\x00"];
+    let sorted: [&[u8]; 5] = [b"", b"\x00", b"a", b"a\x00", b"\xff"];
+    assert!(StrSorter::new(&mut bytes).copied().eq(sorted));
+    let mut none: [&str; 0] = [];
+    assert_eq!(StrSorter::new(&mut none).next(), None);
+}
+
+#[test]
+fn many_distinct_and_deep() {
+    // Long shared prefixes and many segments (beyond the stack depth).
+    let mut items: [[u8; 40]; 500] = core::array::from_fn(|i| {
+        let mut item = [b'x'; 40];
+        let value = (i * 7919) % 500;
+        item[37] = b'0' + (value / 100) as u8;
+        item[38] = b'0' + (value / 10 % 10) as u8;
+        item[39] = b'0' + (value % 10) as u8;
+        item
+    });
+    let mut previous = None;
+    let mut count = 0;
+    for item in StrSorter::new(&mut items) {
+        assert!(previous < Some(*item));
+        previous = Some(*item);
+        count += 1;
+    }
+    assert_eq!(count, 500);
+}
+
+#[test]
+fn sorted_prefix_and_unsorted_parts() {
+    let mut items = words();
+    let mut sorted_items = words();
+    sorted_items.sort_unstable();
+    let mut sorter = StrSorter::new(&mut items);
+    assert!(sorter.sorted_prefix().is_empty());
+    assert_eq!(sorter.unsorted_parts().0.len(), 40);
+
+    let first: [&&str; 10] = core::array::from_fn(|_| sorter.next().unwrap());
+    let prefix = sorter.sorted_prefix();
+    assert_eq!(prefix, &sorted_items[..10]);
+    assert!(first.iter().zip(prefix).all(|(a, b)| core::ptr::eq(*a, b)));
+
+    let (next, rest) = sorter.unsorted_parts();
+    assert_eq!(next.len() + rest.len(), 30);
+    assert!(!next.is_empty());
+    let max_next = next.iter().max().unwrap();
+    assert!(rest.iter().all(|item| item >= max_next));
+
+    sorter.by_ref().for_each(drop);
+    assert_eq!(sorter.sorted_prefix(), &sorted_items[..]);
+    assert_eq!(sorter.unsorted_parts(), (&[][..], &[][..]));
+}
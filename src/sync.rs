@@ -0,0 +1,151 @@
+//! Sharing primitives that build on every target, including those without atomic compare-and-swap
+//! (like `thumbv6m-none-eabi`).
+//!
+//! With atomics (`cfg(target_has_atomic = ...)`) these are thread-safe ([`Send`] + [`Sync`] where
+//! the contents are). Without them [`Code`] and [`Counter`] guard a [`core::cell::Cell`] by a
+//! [`critical-section`](https://docs.rs/critical-section) (so that interrupt handlers can share
+//! them, too, as `static`s): the application has to provide an implementation of it, as usual on
+//! such targets. [`Flag`] and [`Shared`] fall back to single-threaded [`core::cell::Cell`] and
+//! [`alloc::rc::Rc`] (neither [`Sync`]). Use these (rather than [`core::sync::atomic`] or `Arc`
+//! directly) in any (cleanup tracking, parallel...) feature, so that no feature combination breaks
+//! those targets. The crate's own `static`s (`ffi`'s last error, `CrossVecPairGuard`
+//! generations) are of these.
+
+#[cfg(target_has_atomic = "8")]
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+#[cfg(target_has_atomic = "ptr")]
+use core::sync::atomic::AtomicUsize;
+
+#[cfg(not(all(target_has_atomic = "8", target_has_atomic = "ptr")))]
+use core::cell::Cell;
+
+#[cfg(not(all(target_has_atomic = "8", target_has_atomic = "ptr")))]
+use critical_section::Mutex;
+
+#[cfg(test)]
+mod sync_tests;
+
+/// Reference-counted shared ownership: `Arc` with atomics, `Rc` without.
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+pub type Shared<T> = alloc::sync::Arc<T>;
+
+/// Reference-counted shared ownership: `Arc` with atomics, `Rc` without.
+#[cfg(all(feature = "alloc", not(target_has_atomic = "ptr")))]
+pub type Shared<T> = alloc::rc::Rc<T>;
+
+/// A flag that gets set (once, or repeatedly) and checked: for example, that a cleanup has
+/// happened. Only loads and stores, no compare-and-swap.
+#[derive(Debug, Default)]
+pub struct Flag {
+    #[cfg(target_has_atomic = "8")]
+    set: AtomicBool,
+    #[cfg(not(target_has_atomic = "8"))]
+    set: Cell<bool>,
+}
+
+impl Flag {
+    pub const fn new() -> Self {
+        Self {
+            #[cfg(target_has_atomic = "8")]
+            set: AtomicBool::new(false),
+            #[cfg(not(target_has_atomic = "8"))]
+            set: Cell::new(false),
+        }
+    }
+
+    pub fn set(&self) {
+        #[cfg(target_has_atomic = "8")]
+        self.set.store(true, Ordering::Release);
+        #[cfg(not(target_has_atomic = "8"))]
+        self.set.set(true);
+    }
+
+    pub fn is_set(&self) -> bool {
+        #[cfg(target_has_atomic = "8")]
+        return self.set.load(Ordering::Acquire);
+        #[cfg(not(target_has_atomic = "8"))]
+        return self.set.get();
+    }
+}
+
+/// A small code (like an error code) that gets stored and loaded. Only loads and stores, no
+/// compare-and-swap. [`Sync`] on every target (see the module docs).
+#[derive(Debug)]
+pub struct Code {
+    #[cfg(target_has_atomic = "8")]
+    code: AtomicU8,
+    #[cfg(not(target_has_atomic = "8"))]
+    code: Mutex<Cell<u8>>,
+}
+
+impl Default for Code {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl Code {
+    pub const fn new(code: u8) -> Self {
+        Self {
+            #[cfg(target_has_atomic = "8")]
+            code: AtomicU8::new(code),
+            #[cfg(not(target_has_atomic = "8"))]
+            code: Mutex::new(Cell::new(code)),
+        }
+    }
+
+    pub fn store(&self, code: u8) {
+        #[cfg(target_has_atomic = "8")]
+        self.code.store(code, Ordering::Release);
+        #[cfg(not(target_has_atomic = "8"))]
+        critical_section::with(|cs| self.code.borrow(cs).set(code));
+    }
+
+    pub fn load(&self) -> u8 {
+        #[cfg(target_has_atomic = "8")]
+        return self.code.load(Ordering::Acquire);
+        #[cfg(not(target_has_atomic = "8"))]
+        return critical_section::with(|cs| self.code.borrow(cs).get());
+    }
+}
+
+/// A counter that hands out distinct values (until it wraps around): for example, generations of
+/// guards. Atomic increments (not compare-and-swap) with atomics. [`Sync`] on every target (see the
+/// module docs).
+#[derive(Debug)]
+pub struct Counter {
+    #[cfg(target_has_atomic = "ptr")]
+    count: AtomicUsize,
+    #[cfg(not(target_has_atomic = "ptr"))]
+    count: Mutex<Cell<usize>>,
+}
+
+impl Default for Counter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Counter {
+    pub const fn new() -> Self {
+        Self {
+            #[cfg(target_has_atomic = "ptr")]
+            count: AtomicUsize::new(0),
+            #[cfg(not(target_has_atomic = "ptr"))]
+            count: Mutex::new(Cell::new(0)),
+        }
+    }
+
+    /// A value different from the ones returned before (wrapping around after [`usize::MAX`]
+    /// calls).
+    pub fn next(&self) -> usize {
+        #[cfg(target_has_atomic = "ptr")]
+        return self.count.fetch_add(1, Ordering::Relaxed);
+        #[cfg(not(target_has_atomic = "ptr"))]
+        return critical_section::with(|cs| {
+            let count = self.count.borrow(cs);
+            count.replace(count.get().wrapping_add(1))
+        });
+    }
+}
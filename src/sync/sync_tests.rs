@@ -0,0 +1,38 @@
+use crate::sync::{Code, Counter, Flag};
+
+#[test]
+fn flag() {
+    let flag = Flag::new();
+    assert!(!flag.is_set());
+    flag.set();
+    flag.set();
+    assert!(flag.is_set());
+    assert!(!Flag::default().is_set());
+}
+
+#[test]
+fn code() {
+    static CODE: Code = Code::new(3);
+    assert_eq!(CODE.load(), 3);
+    CODE.store(200);
+    assert_eq!((CODE.load(), Code::default().load()), (200, 0));
+}
+
+#[test]
+fn counter() {
+    static COUNTER: Counter = Counter::new();
+    let (first, second) = (COUNTER.next(), COUNTER.next());
+    assert_ne!(first, second);
+    assert_eq!(Counter::default().next(), 0);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn shared_flag() {
+    use crate::sync::Shared;
+    let flag = Shared::new(Flag::new());
+    let other = Shared::clone(&flag);
+    other.set();
+    assert!(flag.is_set());
+    assert_eq!(Shared::strong_count(&flag), 2);
+}
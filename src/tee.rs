@@ -0,0 +1,258 @@
+//! Fan-out of one (lazily sorted) sequence to several independent consumers.
+//!
+//! ```
+//! use lazysort_no_alloc::lazy_sort::LazySort;
+//! use lazysort_no_alloc::tee::Tee;
+//!
+//! # #[cfg(not(feature = "no-panic"))] {
+//! let sorted: LazySort<u32> = LazySort::new(vec![5, 3, 9, 1, 7]);
+//! let mut fast = Tee::new(sorted);
+//! let mut slow = fast.clone();
+//! assert_eq!(fast.by_ref().take(3).collect::<Vec<_>>(), [1, 3, 5]);
+//! // Only the gap between the two consumers is buffered.
+//! assert_eq!(fast.buffered(), 3);
+//! assert_eq!(slow.next(), Some(1));
+//! assert_eq!(slow.buffered(), 2);
+//! assert_eq!(fast.collect::<Vec<_>>(), [7, 9]);
+//! assert_eq!(slow.collect::<Vec<_>>(), [3, 5, 7, 9]);
+//! # }
+//! ```
+//!
+//! Needs crate feature `alloc`, for the state shared through [`Rc`] and for the buffer. The buffer
+//! is a [`VecDeque`] of its own, rather than a [`crate::scratch::ScratchProvider`] region: its
+//! length follows the gap between the consumers, which is not known upfront (while a region's
+//! length is fixed once acquired).
+
+use crate::error::Error;
+use alloc::collections::VecDeque;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::{Cell, RefCell};
+use core::fmt;
+#[cfg(not(feature = "no-panic"))]
+use core::iter::FusedIterator;
+
+#[cfg(test)]
+mod tee_tests;
+
+/// The source, and the items pulled from it that not all consumers have taken yet.
+struct Queue<I: Iterator> {
+    /// `None` once exhausted.
+    source: Option<I>,
+    buffer: VecDeque<I::Item>,
+}
+
+/// State shared by all consumers of one [`Tee`].
+struct Shared<I: Iterator> {
+    /// Borrowed only within [`Tee::try_next()`], `size_hint()` and `drop()`. Those run user code
+    /// (the source, `Clone` or `Drop` of the items), which may re-enter them.
+    queue: RefCell<Queue<I>>,
+    /// Absolute position (in the whole sequence) of `queue.buffer[0]`.
+    offset: Cell<usize>,
+    /// How many items were pulled from the source (the absolute position after the last one).
+    pulled: Cell<usize>,
+    /// Absolute position of the next item for each consumer. `None` for dropped consumers (their
+    /// slots get reused). Taken out only while no user code runs, so it's always there when needed.
+    positions: Cell<Vec<Option<usize>>>,
+}
+
+impl<I: Iterator> Shared<I> {
+    fn with_positions<R>(&self, f: impl FnOnce(&mut Vec<Option<usize>>) -> R) -> R {
+        let mut positions = self.positions.take();
+        let result = f(&mut positions);
+        self.positions.set(positions);
+        result
+    }
+
+    fn position(&self, consumer: usize) -> usize {
+        self.with_positions(|positions| positions[consumer].unwrap_or_else(|| unreachable!()))
+    }
+
+    /// Drop the buffered items that every (remaining) consumer has taken already.
+    fn trim(&self, queue: &mut Queue<I>) {
+        let slowest = self.with_positions(|positions| positions.iter().flatten().min().copied());
+        let slowest = slowest.unwrap_or(self.pulled.get());
+        let taken = slowest - self.offset.get();
+        self.offset.set(slowest);
+        queue.buffer.drain(..taken);
+    }
+
+    fn add(&self, position: usize) -> usize {
+        self.with_positions(
+            |positions| match positions.iter().position(Option::is_none) {
+                Some(consumer) => {
+                    positions[consumer] = Some(position);
+                    consumer
+                }
+                None => {
+                    positions.push(Some(position));
+                    positions.len() - 1
+                }
+            },
+        )
+    }
+}
+
+/// One consumer of a shared sequence. Create the first one with [`Tee::new()`], and more with
+/// [`Clone::clone()`] - each clone continues from where the cloned consumer is.
+///
+/// The source is pulled only as far as the fastest consumer got. Items stay buffered only until the
+/// slowest consumer takes them, so the memory needed is for the gap between the slowest and the
+/// fastest consumer (not for the whole sequence). Hence it suits a lazily sorting source (like
+/// [`crate::lazy_sort::LazySort`]): nothing gets sorted beyond what the fastest consumer asks for.
+///
+/// Consumers share the state through [`Rc`], so they all stay on one thread. Each item is cloned
+/// for each consumer but the last one to take it (which gets it moved).
+pub struct Tee<I: Iterator> {
+    shared: Rc<Shared<I>>,
+    consumer: usize,
+}
+
+impl<I: Iterator> Tee<I>
+where
+    I::Item: Clone,
+{
+    /// The first consumer of `source`.
+    pub fn new(source: I) -> Self {
+        Self {
+            shared: Rc::new(Shared {
+                queue: RefCell::new(Queue {
+                    source: Some(source),
+                    buffer: VecDeque::new(),
+                }),
+                offset: Cell::new(0),
+                pulled: Cell::new(0),
+                positions: Cell::new(alloc::vec![Some(0)]),
+            }),
+            consumer: 0,
+        }
+    }
+
+    /// How many items (pulled from the source) are kept for the slower consumers: the gap between
+    /// the slowest and the fastest consumer.
+    pub fn buffered(&self) -> usize {
+        self.shared.pulled.get() - self.shared.offset.get()
+    }
+
+    /// How many items this consumer is behind the fastest one.
+    pub fn behind(&self) -> usize {
+        self.shared.pulled.get() - self.shared.position(self.consumer)
+    }
+
+    /// Like [`Iterator::next()`], but return [`Error::InvalidState`] (and change nothing) if called
+    /// from within another call of a consumer of the same sequence: from the source, or from
+    /// `Clone` or `Drop` of an item.
+    pub fn try_next(&mut self) -> Result<Option<I::Item>, Error> {
+        let shared = &*self.shared;
+        let Ok(mut queue) = shared.queue.try_borrow_mut() else {
+            return Err(Error::InvalidState);
+        };
+        // Consumers dropped while this was borrowed could not trim.
+        shared.trim(&mut queue);
+        let position = shared.position(self.consumer);
+        let index = position - shared.offset.get();
+        if index == queue.buffer.len() {
+            let Some(source) = queue.source.as_mut() else {
+                return Ok(None);
+            };
+            match source.next() {
+                Some(item) => {
+                    queue.buffer.push_back(item);
+                    shared.pulled.set(shared.pulled.get() + 1);
+                }
+                None => {
+                    queue.source = None;
+                    return Ok(None);
+                }
+            }
+        }
+        let others_need = shared.with_positions(|positions| {
+            positions[self.consumer] = Some(position + 1);
+            positions.contains(&Some(position))
+        });
+        if index == 0 && !others_need {
+            shared.offset.set(position + 1);
+            Ok(queue.buffer.pop_front())
+        } else {
+            Ok(Some(queue.buffer[index].clone()))
+        }
+    }
+}
+
+impl<I: Iterator> Clone for Tee<I>
+where
+    I::Item: Clone,
+{
+    /// Another consumer, at the same position as this one.
+    fn clone(&self) -> Self {
+        let position = self.shared.position(self.consumer);
+        Self {
+            shared: Rc::clone(&self.shared),
+            consumer: self.shared.add(position),
+        }
+    }
+}
+
+/// Not available with crate feature `no-panic`. Use [`Tee::try_next()`] instead.
+#[cfg(not(feature = "no-panic"))]
+impl<I: Iterator> Iterator for Tee<I>
+where
+    I::Item: Clone,
+{
+    type Item = I::Item;
+
+    /// Panic if called from within another call of a consumer of the same sequence (see
+    /// [`Tee::try_next()`]).
+    fn next(&mut self) -> Option<I::Item> {
+        match self.try_next() {
+            Ok(item) => item,
+            Err(_) => release_panic!("Tee consumer called from its source, or from an item."),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let ahead = self.behind();
+        let (lower, upper) = match self.shared.queue.try_borrow() {
+            Ok(queue) => queue
+                .source
+                .as_ref()
+                .map_or((0, Some(0)), Iterator::size_hint),
+            // Called from within the source.
+            Err(_) => (0, None),
+        };
+        (
+            lower.saturating_add(ahead),
+            upper.and_then(|upper| upper.checked_add(ahead)),
+        )
+    }
+}
+
+#[cfg(not(feature = "no-panic"))]
+impl<I: Iterator> FusedIterator for Tee<I> where I::Item: Clone {}
+
+impl<I: Iterator> Drop for Tee<I> {
+    fn drop(&mut self) {
+        let shared = &*self.shared;
+        shared.with_positions(|positions| positions[self.consumer] = None);
+        // If dropped from within `try_next()` (of another consumer), that one trims next time.
+        if let Ok(mut queue) = shared.queue.try_borrow_mut() {
+            shared.trim(&mut queue);
+        }
+    }
+}
+
+impl<I: Iterator> fmt::Debug for Tee<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let position = self
+            .shared
+            .with_positions(|positions| positions[self.consumer]);
+        f.debug_struct("Tee")
+            .field("position", &position)
+            .field("offset", &self.shared.offset.get())
+            .field(
+                "buffered",
+                &(self.shared.pulled.get() - self.shared.offset.get()),
+            )
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,158 @@
+use crate::error::Error;
+#[cfg(not(feature = "no-panic"))]
+use crate::lazy_sort::LazySort;
+use crate::tee::Tee;
+#[cfg(not(feature = "no-panic"))]
+use crate::testing::scrambled;
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+#[cfg(not(feature = "no-panic"))]
+use core::cell::Cell;
+use core::cell::RefCell;
+
+#[cfg(not(feature = "no-panic"))]
+fn items() -> Vec<u32> {
+    (0..200).map(|i| scrambled(i, 0, 200)).collect()
+}
+
+#[cfg(not(feature = "no-panic"))]
+#[test]
+fn consumers_see_the_same_sequence() {
+    let mut first = Tee::new(LazySort::<u32>::new(items()));
+    let mut second = first.clone();
+    let mut third = first.clone();
+    let mut seen = [Vec::new(), Vec::new(), Vec::new()];
+    for step in 0..400 {
+        seen[0].extend(first.next());
+        if step % 2 == 0 {
+            seen[1].extend(second.next());
+        }
+        if step % 3 == 0 {
+            seen[2].extend(third.next());
+        }
+    }
+    seen[1].extend(second);
+    seen[2].extend(third);
+    let expected: Vec<u32> = (0..200).collect();
+    for seen in &seen {
+        assert_eq!(*seen, expected);
+    }
+}
+
+#[cfg(not(feature = "no-panic"))]
+#[test]
+fn buffers_only_the_gap() {
+    let pulled = Cell::new(0);
+    let source = (0..100u32).inspect(|_| pulled.set(pulled.get() + 1));
+    let mut fast = Tee::new(source);
+    let mut slow = fast.clone();
+    assert_eq!(fast.nth(9), Some(9));
+    assert_eq!((pulled.get(), fast.buffered()), (10, 10));
+    assert_eq!((fast.behind(), slow.behind()), (0, 10));
+    assert_eq!(slow.nth(3), Some(3));
+    assert_eq!((pulled.get(), slow.buffered()), (10, 6));
+    assert_eq!(slow.size_hint(), (96, Some(96)));
+    assert_eq!(fast.size_hint(), (90, Some(90)));
+
+    // Overtaking: now `slow` is the fast one.
+    assert_eq!(slow.nth(9), Some(13));
+    assert_eq!((pulled.get(), fast.buffered(), fast.behind()), (14, 4, 4));
+}
+
+#[cfg(not(feature = "no-panic"))]
+#[test]
+fn dropping_the_slowest_releases_its_gap() {
+    let mut fast = Tee::new(0..100u32);
+    let slow = fast.clone();
+    let mut skipped = fast.by_ref().skip(19).take(1);
+    assert_eq!(skipped.next(), Some(19));
+    assert_eq!(fast.buffered(), 20);
+    drop(slow);
+    assert_eq!(fast.buffered(), 0);
+
+    // A clone continues from where the cloned consumer is.
+    let mut clone = fast.clone();
+    assert_eq!((fast.next(), clone.next()), (Some(20), Some(20)));
+    assert_eq!(fast.buffered(), 0);
+}
+
+#[cfg(not(feature = "no-panic"))]
+#[test]
+fn items_are_dropped_once() {
+    let marker = Rc::new(());
+    let source = (0..50).map(|_| Rc::clone(&marker));
+    let mut first = Tee::new(source);
+    let mut second = first.clone();
+    let held: Vec<_> = first.by_ref().take(30).collect();
+    assert_eq!(Rc::strong_count(&marker), 1 + 30 + 30);
+    drop(held);
+    assert_eq!(second.nth(9).map(|_| ()), Some(()));
+    assert_eq!(Rc::strong_count(&marker), 1 + 20);
+    drop(first);
+    drop(second);
+    assert_eq!(Rc::strong_count(&marker), 1);
+}
+
+#[cfg(not(feature = "no-panic"))]
+#[test]
+fn fused() {
+    let mut first = Tee::new([1u8, 2].into_iter());
+    let mut second = first.clone();
+    assert_eq!(first.by_ref().count(), 2);
+    assert_eq!((first.next(), first.next()), (None, None));
+    assert_eq!(second.by_ref().count(), 2);
+    assert_eq!((second.next(), second.buffered()), (None, 0));
+}
+
+type Source = Box<dyn Iterator<Item = u8>>;
+
+/// A consumer called from within the source (of the same sequence) gets no item, nor does it
+/// miss any.
+#[test]
+fn called_from_the_source() {
+    let inner: Rc<RefCell<Option<Tee<Source>>>> = Rc::default();
+    let results = Rc::new(RefCell::new(Vec::new()));
+    let source: Source = {
+        let (inner, results) = (Rc::clone(&inner), Rc::clone(&results));
+        Box::new((0..3).inspect(move |_| {
+            if let Some(inner) = inner.borrow_mut().as_mut() {
+                results.borrow_mut().push(inner.try_next());
+            }
+        }))
+    };
+    let mut outer = Tee::new(source);
+    *inner.borrow_mut() = Some(outer.clone());
+    assert_eq!(outer.try_next(), Ok(Some(0)));
+    assert_eq!(*results.borrow(), [Err(Error::InvalidState)]);
+
+    let mut inner = inner.borrow_mut().take().unwrap();
+    assert_eq!((inner.behind(), inner.buffered()), (1, 1));
+    assert_eq!(inner.try_next(), Ok(Some(0)));
+    assert_eq!(inner.try_next(), Ok(Some(1)));
+    assert_eq!(outer.try_next(), Ok(Some(1)));
+    assert_eq!(results.borrow().len(), 1);
+}
+
+/// A consumer dropped from within the source releases its gap on the next call.
+#[test]
+fn dropped_from_the_source() {
+    let slow: Rc<RefCell<Option<Tee<Source>>>> = Rc::default();
+    let source: Source = {
+        let slow = Rc::clone(&slow);
+        Box::new((0..10).inspect(move |&item| {
+            if item == 3 {
+                drop(slow.borrow_mut().take());
+            }
+        }))
+    };
+    let mut fast = Tee::new(source);
+    *slow.borrow_mut() = Some(fast.clone());
+    for expected in 0..4 {
+        assert_eq!(fast.try_next(), Ok(Some(expected)));
+    }
+    assert!(slow.borrow().is_none());
+    assert_eq!(fast.buffered(), 4);
+    assert_eq!(fast.try_next(), Ok(Some(4)));
+    assert_eq!(fast.buffered(), 0);
+}
@@ -0,0 +1,170 @@
+//! Test support: items that count how often they get constructed and dropped. For verifying that
+//! storage backends (like [`crate::store::lifos::Lifos`] implementations) and sorters neither leak
+//! nor double-drop.
+//!
+//! Available with crate feature `test-support` (and in this crate's own tests).
+//!
+//! ```
+//! use lazysort_no_alloc::store::lifos::double_stack::DoubleStack;
+//! use lazysort_no_alloc::store::lifos::Lifos;
+//! use lazysort_no_alloc::testing::DropCounter;
+//!
+//! let counter = DropCounter::new();
+//! let mut lifos = DoubleStack::<_, 4>::new();
+//! assert!(lifos.try_push_left(counter.track(1)).is_ok());
+//! assert!(lifos.try_push_right(counter.track(2)).is_ok());
+//! assert_eq!(counter.live(), 2);
+//! drop(lifos);
+//! counter.assert_all_dropped();
+//! ```
+//!
+//! Moves are bitwise copies in Rust, so they can't be counted. But a move that duplicates an item
+//! (and then drops both copies) shows up as more drops than constructions - which panics right
+//! away.
+//!
+//! Also [`scrambled()`]: deterministic test data.
+
+use core::cell::Cell;
+use core::cmp::Ordering;
+use core::fmt::{Debug, Formatter, Result as FmtResult};
+use core::mem::ManuallyDrop;
+use core::ops::{Deref, DerefMut};
+
+pub mod storage_conformance;
+
+#[cfg(test)]
+mod testing_tests;
+
+/// Counts constructions and drops of its [`DropTracker`]s. Single-threaded.
+#[derive(Debug, Default)]
+pub struct DropCounter {
+    constructed: Cell<usize>,
+    dropped: Cell<usize>,
+}
+
+impl DropCounter {
+    pub const fn new() -> Self {
+        Self {
+            constructed: Cell::new(0),
+            dropped: Cell::new(0),
+        }
+    }
+
+    /// Wrap `value`, counting it as constructed.
+    pub fn track<T>(&self, value: T) -> DropTracker<'_, T> {
+        self.constructed.set(self.constructed.get() + 1);
+        DropTracker {
+            value: ManuallyDrop::new(value),
+            counter: self,
+        }
+    }
+
+    /// Number of [`DropTracker`]s constructed (including clones).
+    pub fn constructed(&self) -> usize {
+        self.constructed.get()
+    }
+
+    /// Number of [`DropTracker`]s dropped (including [`DropTracker::into_inner()`]).
+    pub fn dropped(&self) -> usize {
+        self.dropped.get()
+    }
+
+    /// Number of [`DropTracker`]s not dropped yet.
+    pub fn live(&self) -> usize {
+        self.constructed() - self.dropped()
+    }
+
+    /// Panic if any [`DropTracker`] hasn't been dropped (yet), or if it leaked.
+    pub fn assert_all_dropped(&self) {
+        assert_eq!(
+            self.live(),
+            0,
+            "{} of {} tracked item(s) not dropped",
+            self.live(),
+            self.constructed()
+        );
+    }
+
+    fn count_drop(&self) {
+        let dropped = self.dropped.get() + 1;
+        assert!(
+            dropped <= self.constructed(),
+            "double drop: more drops than constructions ({})",
+            self.constructed()
+        );
+        self.dropped.set(dropped);
+    }
+}
+
+/// A value counted by a [`DropCounter`]. Compares (and dereferences) like the value itself.
+pub struct DropTracker<'c, T> {
+    value: ManuallyDrop<T>,
+    counter: &'c DropCounter,
+}
+
+impl<'c, T> DropTracker<'c, T> {
+    /// Unwrap the value, counting the tracker as dropped.
+    pub fn into_inner(self) -> T {
+        let mut this = ManuallyDrop::new(self);
+        this.counter.count_drop();
+        unsafe { ManuallyDrop::take(&mut this.value) }
+    }
+}
+
+impl<T> Drop for DropTracker<'_, T> {
+    fn drop(&mut self) {
+        self.counter.count_drop();
+        unsafe { ManuallyDrop::drop(&mut self.value) };
+    }
+}
+
+impl<T: Clone> Clone for DropTracker<'_, T> {
+    fn clone(&self) -> Self {
+        self.counter.track(T::clone(&self.value))
+    }
+}
+
+impl<T> Deref for DropTracker<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for DropTracker<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T: Debug> Debug for DropTracker<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        T::fmt(&self.value, f)
+    }
+}
+
+impl<T: PartialEq> PartialEq for DropTracker<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        *self.value == *other.value
+    }
+}
+impl<T: Eq> Eq for DropTracker<'_, T> {}
+
+impl<T: PartialOrd> PartialOrd for DropTracker<'_, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.value.partial_cmp(&other.value)
+    }
+}
+impl<T: Ord> Ord for DropTracker<'_, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.value.cmp(&other.value)
+    }
+}
+
+/// Item `i` of deterministic, (somewhat) shuffled data: `(i * 7919 + offset) % modulo`. As 7919 is
+/// a prime, items `0..modulo` are a permutation of `0..modulo` (unless `modulo` is a multiple of
+/// 7919). A smaller `modulo` (than the number of items) gives duplicates.
+pub const fn scrambled(i: usize, offset: u32, modulo: u32) -> u32 {
+    (i as u32).wrapping_mul(7919).wrapping_add(offset) % modulo
+}
@@ -0,0 +1,214 @@
+//! Generic conformance checks for [`Lifos`] implementations (storage backends). Every check takes a
+//! fresh, empty backend (with capacity for at least [`MIN_CAPACITY`] items) that holds
+//! [`DropTracker`]s, and it verifies that no item leaks or gets dropped twice.
+//!
+//! Authors of a new backend run all of them with one [`crate::lifos_conformance!`] invocation in
+//! their tests:
+//! ```
+//! use lazysort_no_alloc::store::lifos::double_stack::DoubleStack;
+//!
+//! lazysort_no_alloc::lifos_conformance!(double_stack, DoubleStack::<_, 8>::new());
+//! # fn main() {}
+//! ```
+//!
+//! [`Lifos`] has no popping, and no conversions, so the checks cover pushing (and peeking), the
+//! accounting of both sides, capacity edges and dropping.
+
+use crate::store::lifos::Lifos;
+use crate::testing::{DropCounter, DropTracker};
+
+#[cfg(test)]
+mod storage_conformance_tests;
+
+/// The least capacity that the backends under test need.
+pub const MIN_CAPACITY: usize = 4;
+
+/// Run each named check (a function in this module) as a `#[test]` on a backend created by the
+/// given expression, in a module of the given name. The expression gets evaluated once per check,
+/// inside that module (which imports everything from its parent), and its item type gets inferred.
+#[macro_export]
+macro_rules! lifos_conformance {
+    ($module:ident, $new:expr) => {
+        $crate::lifos_conformance!(
+            $module,
+            $new,
+            [empty, push_order, extend, peek_mut, capacity_edges, drop_on_drop]
+        );
+    };
+    ($module:ident, $new:expr, [$($check:ident),+ $(,)?]) => {
+        #[cfg(test)]
+        mod $module {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $(
+                #[test]
+                fn $check() {
+                    let counter = $crate::testing::DropCounter::new();
+                    $crate::testing::storage_conformance::$check(&counter, $new);
+                    counter.assert_all_dropped();
+                }
+            )+
+        }
+    };
+}
+
+fn assert_min_capacity<T, L: Lifos<T>>(lifos: &L) {
+    assert!(
+        lifos.is_empty() && lifos.capacity() >= MIN_CAPACITY,
+        "the backend under test has to be empty, with capacity for at least {} items",
+        MIN_CAPACITY
+    );
+}
+
+fn top(item: Option<&DropTracker<'_, usize>>) -> Option<usize> {
+    item.map(|item| **item)
+}
+
+/// Push alternately (starting on the LEFT, in case of [`Lifos::has_to_push_left_first()`]) until
+/// a push fails. Return how many got pushed.
+fn fill<'c, L: Lifos<DropTracker<'c, usize>>>(counter: &'c DropCounter, lifos: &mut L) -> usize {
+    let mut pushed = 0;
+    loop {
+        let item = counter.track(pushed);
+        let result = if pushed % 2 == 0 {
+            lifos.try_push_left(item)
+        } else {
+            lifos.try_push_right(item)
+        };
+        if result.is_err() {
+            return pushed;
+        }
+        pushed += 1;
+    }
+}
+
+/// A new backend is empty on both sides.
+pub fn empty<'c, L: Lifos<DropTracker<'c, usize>>>(_: &'c DropCounter, mut lifos: L) {
+    assert_min_capacity(&lifos);
+    assert_eq!((lifos.left(), lifos.right(), lifos.len()), (0, 0, 0));
+    assert!(lifos.peek_left().is_none() && lifos.peek_right().is_none());
+    assert!(lifos.peek_left_mut().is_none() && lifos.peek_right_mut().is_none());
+}
+
+/// Each side is a LIFO of its own: its top is the item last pushed to it, whatever got pushed to
+/// the other side in between.
+pub fn push_order<'c, L: Lifos<DropTracker<'c, usize>>>(counter: &'c DropCounter, mut lifos: L) {
+    assert_min_capacity(&lifos);
+    let sides = [true, false, false, true];
+    let (mut left, mut right) = (None, None);
+    for (value, &to_left) in sides.iter().enumerate() {
+        if to_left {
+            assert!(lifos.try_push_left(counter.track(value)).is_ok());
+            left = Some(value);
+        } else {
+            assert!(lifos.try_push_right(counter.track(value)).is_ok());
+            right = Some(value);
+        }
+        assert_eq!(
+            (top(lifos.peek_left()), top(lifos.peek_right())),
+            (left, right)
+        );
+        let pushed_left = sides[..=value].iter().filter(|&&to_left| to_left).count();
+        assert_eq!(
+            (lifos.left(), lifos.right()),
+            (pushed_left, value + 1 - pushed_left)
+        );
+        assert_eq!(lifos.len(), value + 1);
+        assert!(!lifos.is_empty());
+    }
+    assert_eq!(counter.live(), sides.len());
+}
+
+/// [`Lifos::extend_left()`] and [`Lifos::extend_right()`] push in order, and they stop at the
+/// first item that doesn't fit.
+pub fn extend<'c, L: Lifos<DropTracker<'c, usize>>>(counter: &'c DropCounter, mut lifos: L) {
+    assert_min_capacity(&lifos);
+    assert_eq!(
+        lifos.extend_left((0..2).map(|value| counter.track(value))),
+        Ok(2)
+    );
+    assert_eq!(
+        lifos.extend_right((2..4).map(|value| counter.track(value))),
+        Ok(2)
+    );
+    assert_eq!(
+        (top(lifos.peek_left()), top(lifos.peek_right())),
+        (Some(1), Some(3))
+    );
+    assert_eq!((lifos.left(), lifos.right()), (2, 2));
+
+    let mut rest = (4..).map(|value| counter.track(value));
+    let rejected = lifos.extend_left(rest.by_ref().take(lifos.capacity()));
+    let rejected = rejected.expect_err("pushed beyond the capacity");
+    assert!(lifos.len() <= lifos.capacity());
+    // The pushed ones are `4..*rejected`.
+    assert_eq!(lifos.left(), 2 + *rejected - 4);
+    let left_top = if *rejected > 4 { *rejected - 1 } else { 1 };
+    assert_eq!(top(lifos.peek_left()), Some(left_top));
+    assert_eq!(counter.live(), lifos.len() + 1);
+}
+
+/// Changes through [`Lifos::peek_left_mut()`] and [`Lifos::peek_right_mut()`] stick, and they
+/// don't affect the other side.
+pub fn peek_mut<'c, L: Lifos<DropTracker<'c, usize>>>(counter: &'c DropCounter, mut lifos: L) {
+    assert_min_capacity(&lifos);
+    assert!(lifos.try_push_left(counter.track(1)).is_ok());
+    assert!(lifos.try_push_right(counter.track(2)).is_ok());
+    **lifos.peek_left_mut().unwrap() = 10;
+    assert_eq!(
+        (top(lifos.peek_left()), top(lifos.peek_right())),
+        (Some(10), Some(2))
+    );
+    **lifos.peek_right_mut().unwrap() = 20;
+    assert_eq!(
+        (top(lifos.peek_left()), top(lifos.peek_right())),
+        (Some(10), Some(20))
+    );
+}
+
+/// Everything that [`Lifos::can_hold()`] promises fits, but never more than
+/// [`Lifos::capacity()`]. Once full, pushes to either side fail, they give the item back (not
+/// dropped), and they leave the contents unchanged.
+pub fn capacity_edges<'c, L: Lifos<DropTracker<'c, usize>>>(
+    counter: &'c DropCounter,
+    mut lifos: L,
+) {
+    assert_min_capacity(&lifos);
+    let capacity = lifos.capacity();
+    let promised = (0..=capacity).filter(|&n| lifos.can_hold(n)).max();
+    assert!(!lifos.can_hold(capacity + 1));
+
+    let pushed = fill(counter, &mut lifos);
+    assert!(promised.map_or(true, |promised| pushed >= promised));
+    assert!(pushed <= capacity && lifos.len() == pushed);
+    assert_eq!(counter.live(), pushed);
+
+    let tops = (top(lifos.peek_left()), top(lifos.peek_right()));
+    for to_left in [true, false] {
+        let item = counter.track(usize::MAX);
+        let rejected = if to_left {
+            lifos.try_push_left(item)
+        } else {
+            lifos.try_push_right(item)
+        };
+        let rejected = rejected.expect_err("pushed beyond what was rejected before");
+        assert_eq!((*rejected, counter.live()), (usize::MAX, pushed + 1));
+        drop(rejected);
+        assert_eq!((top(lifos.peek_left()), top(lifos.peek_right())), tops);
+        assert_eq!(lifos.len(), pushed);
+    }
+}
+
+/// Dropping a partly filled backend drops each of its items exactly once. (For a full one, see
+/// [`capacity_edges()`].)
+pub fn drop_on_drop<'c, L: Lifos<DropTracker<'c, usize>>>(counter: &'c DropCounter, mut lifos: L) {
+    assert_min_capacity(&lifos);
+    for value in 0..MIN_CAPACITY / 2 {
+        assert!(lifos.try_push_left(counter.track(value)).is_ok());
+    }
+    assert!(lifos.try_push_right(counter.track(MIN_CAPACITY)).is_ok());
+    assert_eq!(counter.live(), MIN_CAPACITY / 2 + 1);
+    drop(lifos);
+    counter.assert_all_dropped();
+}
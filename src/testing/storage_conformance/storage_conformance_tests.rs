@@ -0,0 +1,22 @@
+use crate::store::lifos::double_stack::DoubleStack;
+#[cfg(feature = "alloc")]
+use crate::store::lifos::{lifos_vec::FixedDequeLifos, DequeLifos, LifosWithCapacity};
+
+crate::lifos_conformance!(double_stack, DoubleStack::<_, 8>::new());
+crate::lifos_conformance!(double_stack_min, DoubleStack::<_, 4>::new());
+
+#[cfg(feature = "alloc")]
+crate::lifos_conformance!(fixed_deque_lifos, FixedDequeLifos::with_capacity(8));
+
+#[cfg(feature = "alloc")]
+crate::lifos_conformance!(
+    deque_lifos,
+    DequeLifos::from(alloc::collections::VecDeque::with_capacity(5))
+);
+
+// Only some of the checks.
+crate::lifos_conformance!(
+    selected,
+    DoubleStack::<_, 6>::new(),
+    [push_order, drop_on_drop]
+);
@@ -0,0 +1,42 @@
+use crate::testing::{scrambled, DropCounter};
+
+#[test]
+fn counts() {
+    let counter = DropCounter::new();
+    let first = counter.track(1u8);
+    let second = first.clone();
+    assert_eq!((counter.constructed(), counter.live()), (2, 2));
+    assert!(first == second && *first == 1);
+    assert_eq!(second.into_inner(), 1);
+    assert_eq!(counter.live(), 1);
+    drop(first);
+    counter.assert_all_dropped();
+}
+
+#[test]
+#[should_panic(expected = "not dropped")]
+fn leak() {
+    let counter = DropCounter::new();
+    core::mem::forget(counter.track(()));
+    counter.assert_all_dropped();
+}
+
+#[test]
+#[should_panic(expected = "double drop")]
+fn double_drop() {
+    let counter = DropCounter::new();
+    let tracker = counter.track(());
+    let duplicate = unsafe { core::ptr::read(&tracker) };
+    drop(tracker);
+    drop(duplicate);
+}
+
+#[test]
+fn scrambled_is_a_permutation() {
+    let mut seen = [false; 97];
+    for i in 0..97 {
+        seen[scrambled(i, 13, 97) as usize] = true;
+    }
+    assert!(seen.iter().all(|&seen| seen));
+    assert_eq!((scrambled(1, 0, 1000), scrambled(1, 13, 1000)), (919, 932));
+}
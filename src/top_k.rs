@@ -0,0 +1,211 @@
+//! The greatest `k` items of a stream, in bounded memory. Or the smallest (or greatest) `k` items
+//! of a [`Vec`], without sorting the rest: see [`k_smallest()`] and [`k_largest()`].
+
+use crate::engine::{Engine, Pivot};
+use crate::quantile::weighted_select;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::iter::FusedIterator;
+
+#[cfg(test)]
+mod top_k_tests;
+
+/// Keeps the `k` greatest items pushed so far, in `O(k)` memory, regardless of the stream length.
+///
+/// The items are buffered in a [`Vec`] allocated once (with capacity `2 * k`) and never
+/// re-allocated. Whenever it fills up, it's partitioned around the `k`-th greatest item, and the
+/// smaller half is dropped. That's `O(1)` amortized comparisons per push. After that, pushed items
+/// not greater than the `k`-th greatest item are dropped straight away.
+///
+/// The result is sorted only when consumed, and only as far as it's consumed: see
+/// [`StreamingTopK::into_sorted_iter()`].
+#[derive(Clone, Debug)]
+pub struct StreamingTopK<T: Ord> {
+    k: usize,
+    items: Vec<T>,
+    /// Whether `items[0]` is the `k`-th greatest item so far (since the last partitioning).
+    has_threshold: bool,
+}
+
+impl<T: Ord> StreamingTopK<T> {
+    pub fn new(k: usize) -> Self {
+        trace_event!(
+            debug,
+            capacity = k.saturating_mul(2),
+            "allocating top-k buffer"
+        );
+        Self {
+            k,
+            items: Vec::with_capacity(k.saturating_mul(2)),
+            has_threshold: false,
+        }
+    }
+
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Number of items kept: at most `k`.
+    pub fn len(&self) -> usize {
+        self.items.len().min(self.k)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty() || self.k == 0
+    }
+
+    pub fn push(&mut self, value: T) {
+        if self.k == 0 || self.has_threshold && value <= self.items[0] {
+            return;
+        }
+        if self.items.len() == self.items.capacity() {
+            self.truncate();
+        }
+        self.items.push(value);
+    }
+
+    /// Keep (only) the `k` greatest items, with the `k`-th greatest at the start.
+    fn truncate(&mut self) {
+        let len = self.items.len();
+        if len <= self.k {
+            return;
+        }
+        let mut engine = Engine::<usize>::try_new(len).unwrap_or_else(|_| unreachable!());
+        engine.set_pivot(Pivot::TowardTarget);
+        engine.resolve_at(&mut self.items, len - self.k, &mut T::cmp);
+        self.items.drain(..len - self.k);
+        self.has_threshold = true;
+    }
+
+    /// The `k` greatest items, greatest first. They get sorted lazily, as they are consumed.
+    pub fn into_sorted_iter(mut self) -> IntoSortedIter<T> {
+        self.truncate();
+        let engine = Engine::try_new(self.items.len()).unwrap_or_else(|_| unreachable!());
+        IntoSortedIter {
+            items: self.items,
+            engine,
+        }
+    }
+
+    /// The `k` greatest items, in no particular order.
+    pub fn into_vec(mut self) -> Vec<T> {
+        self.truncate();
+        self.items
+    }
+}
+
+/// The `k` smallest `items`, smallest first (all of them, if fewer). Partitions only until the `k`
+/// smallest are apart from the rest, and sorts only those: O(n + k log k) expected comparisons. The
+/// result reuses the buffer the items came in: no allocation.
+///
+/// ```
+/// use lazysort_no_alloc::top_k::k_smallest;
+///
+/// let latencies = vec![40, 12, 95, 31, 18, 27, 60];
+/// assert_eq!(k_smallest(latencies, 3), [12, 18, 27]);
+/// ```
+pub fn k_smallest<T: Ord>(items: Vec<T>, k: usize) -> Vec<T> {
+    first_k(items, k, &mut T::cmp)
+}
+
+/// Like [`k_smallest()`], but the `k` greatest `items`, greatest first.
+///
+/// ```
+/// use lazysort_no_alloc::top_k::k_largest;
+///
+/// let scores = vec![40, 12, 95, 31, 18, 27, 60];
+/// assert_eq!(k_largest(scores, 2), [95, 60]);
+/// ```
+pub fn k_largest<T: Ord>(items: Vec<T>, k: usize) -> Vec<T> {
+    first_k(items, k, &mut |a: &T, b: &T| b.cmp(a))
+}
+
+/// The first `k` of `items` in the order of `cmp`, sorted. Drop the rest.
+fn first_k<T, CMP>(mut items: Vec<T>, k: usize, cmp: &mut CMP) -> Vec<T>
+where
+    CMP: FnMut(&T, &T) -> Ordering,
+{
+    let mut engine = Engine::<usize>::try_new(items.len()).unwrap_or_else(|_| unreachable!());
+    engine.set_pivot(Pivot::TowardTarget);
+    let kept = engine.next_chunk_front(&mut items, k, cmp);
+    trace_event!(debug, len = items.len(), kept = kept.len(), "first k");
+    items.truncate(kept.end);
+    items
+}
+
+/// The greatest items, greatest first, until their cumulative weight reaches `max_weight`
+/// (including the item that reaches it). For example: the top transactions that make up 90% of the
+/// volume. All items, if their total weight is less. None, if `max_weight` is NaN or not positive.
+///
+/// It selects that prefix (by weighted quickselect) and drops the rest first: O(n) expected. Then
+/// it sorts (lazily, as consumed) only the prefix.
+///
+/// ```
+/// use lazysort_no_alloc::top_k::top_until_weight;
+///
+/// let volumes = vec![5, 40, 10, 30, 15];
+/// let top = top_until_weight(volumes, 60.0, |&volume| f64::from(volume));
+/// assert!(top.eq([40, 30]));
+/// ```
+pub fn top_until_weight<T, W>(
+    mut items: Vec<T>,
+    max_weight: f64,
+    mut weight: W,
+) -> IntoSortedIter<T>
+where
+    T: Ord,
+    W: FnMut(&T) -> f64,
+{
+    if items.is_empty() || max_weight.is_nan() || max_weight <= 0.0 {
+        items.clear();
+    } else {
+        let greater_first = &mut |a: &T, b: &T| b.cmp(a);
+        let (last, before) = weighted_select(&mut items, max_weight, &mut weight, greater_first);
+        // The target may be reached only within the run of items equal to the last one.
+        let mut cumulative = before + weight(&items[last]);
+        let mut end = last + 1;
+        while end < items.len()
+            && cumulative < max_weight
+            && greater_first(&items[end], &items[last]) == Ordering::Equal
+        {
+            cumulative += weight(&items[end]);
+            end += 1;
+        }
+        trace_event!(debug, len = items.len(), kept = end, "top until weight");
+        items.truncate(end);
+    }
+    let engine = Engine::try_new(items.len()).unwrap_or_else(|_| unreachable!());
+    IntoSortedIter { items, engine }
+}
+
+/// Iterator returned by [`StreamingTopK::into_sorted_iter()`] and [`top_until_weight()`].
+#[derive(Clone, Debug)]
+pub struct IntoSortedIter<T: Ord> {
+    items: Vec<T>,
+    engine: Engine,
+}
+
+impl<T: Ord> Iterator for IntoSortedIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let position = self.engine.next_back(&mut self.items, &mut T::cmp)?;
+        debug_assert_eq!(position, self.items.len() - 1);
+        self.items.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.items.len(), Some(self.items.len()))
+    }
+}
+
+impl<T: Ord> ExactSizeIterator for IntoSortedIter<T> {}
+
+impl<T: Ord> FusedIterator for IntoSortedIter<T> {}
+
+impl<T: Ord> IntoSortedIter<T> {
+    /// The items not yielded yet, in no particular order.
+    pub fn into_inner(self) -> Vec<T> {
+        self.items
+    }
+}
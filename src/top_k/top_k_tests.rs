@@ -0,0 +1,89 @@
+use crate::top_k::StreamingTopK;
+use alloc::vec;
+use alloc::vec::Vec;
+
+fn stream() -> impl Iterator<Item = u32> {
+    (0..10_000u32).map(|i| (i * 7919 + 3) % 5000)
+}
+
+#[test]
+fn greatest_first() {
+    let mut top = StreamingTopK::new(10);
+    stream().for_each(|value| top.push(value));
+    assert_eq!(top.len(), 10);
+    let expected: Vec<u32> = (4995..5000).rev().flat_map(|v| [v, v]).collect();
+    assert_eq!(top.into_sorted_iter().collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn bounded_capacity() {
+    let mut top = StreamingTopK::new(7);
+    let capacity = top.items.capacity();
+    stream().for_each(|value| top.push(value));
+    assert_eq!(top.items.capacity(), capacity);
+    let mut kept = top.into_vec();
+    kept.sort_unstable();
+    assert_eq!(kept, [4996, 4997, 4997, 4998, 4998, 4999, 4999]);
+}
+
+#[test]
+fn fewer_than_k() {
+    let mut top = StreamingTopK::new(5);
+    [3, 1, 2].into_iter().for_each(|value| top.push(value));
+    assert_eq!(top.len(), 3);
+    let iter = top.into_sorted_iter();
+    assert_eq!(iter.len(), 3);
+    assert_eq!(iter.collect::<Vec<_>>(), [3, 2, 1]);
+
+    let mut none = StreamingTopK::new(0);
+    none.push(1);
+    assert!(none.is_empty());
+    assert_eq!(none.into_sorted_iter().next(), None);
+}
+
+#[test]
+fn abandoned_sorted_iter() {
+    let mut top = StreamingTopK::new(5);
+    (0..20u32).for_each(|value| top.push(value));
+    let mut sorted = top.into_sorted_iter();
+    assert_eq!(sorted.next(), Some(19));
+    let mut rest = sorted.into_inner();
+    rest.sort_unstable();
+    assert_eq!(rest, [15, 16, 17, 18]);
+}
+
+#[test]
+fn until_weight() {
+    use crate::top_k::top_until_weight;
+    // Value `v` has weight `v`.
+    let weight = |&value: &u32| f64::from(value);
+    let top = top_until_weight(stream().collect(), 4999.0 * 2.0 + 1.0, weight);
+    assert!(top.eq([4999, 4999, 4998]));
+    let top = top_until_weight(stream().collect(), 4999.0 * 2.0, weight);
+    assert!(top.eq([4999, 4999]));
+    // A run of equal items reaches it.
+    let top = top_until_weight(vec![3, 1, 3, 3, 2], 7.0, weight);
+    assert!(top.eq([3, 3, 3]));
+
+    assert_eq!(top_until_weight(vec![3, 1], 100.0, weight).len(), 2);
+    assert_eq!(top_until_weight(vec![3, 1], 0.0, weight).len(), 0);
+    assert_eq!(top_until_weight(vec![3, 1], f64::NAN, weight).len(), 0);
+    assert_eq!(top_until_weight(Vec::new(), 1.0, weight).len(), 0);
+}
+
+#[test]
+fn k_smallest_and_largest() {
+    use crate::top_k::{k_largest, k_smallest};
+
+    let items: Vec<u32> = stream().collect();
+    let buffer = items.as_ptr();
+    let smallest = k_smallest(items, 5);
+    // The same buffer.
+    assert_eq!(smallest.as_ptr(), buffer);
+    assert_eq!(smallest, [0, 0, 1, 1, 2]);
+    assert_eq!(k_largest(stream().collect(), 3), [4999, 4999, 4998]);
+
+    assert_eq!(k_smallest(vec![3, 1, 2], 10), [1, 2, 3]);
+    assert_eq!(k_largest(vec![3, 1, 2], 0), []);
+    assert_eq!(k_smallest(Vec::<u8>::new(), 2), []);
+}
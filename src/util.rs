@@ -0,0 +1,35 @@
+//! Small self-contained helpers (no dependencies, `no_std`).
+
+use core::cmp::Ordering;
+
+pub mod rng;
+
+/// `cmp(&items[a], &items[b])`, but [`Ordering::Equal`] if `a` or `b` is out of range. For
+/// positions in range by an invariant that the compiler can't see: it spares a bounds check panic
+/// (see crate feature `no-panic`).
+#[inline(always)]
+pub(crate) fn cmp_at<T, CMP>(items: &[T], a: usize, b: usize, cmp: &mut CMP) -> Ordering
+where
+    CMP: FnMut(&T, &T) -> Ordering + ?Sized,
+{
+    match (items.get(a), items.get(b)) {
+        (Some(a), Some(b)) => cmp(a, b),
+        _ => Ordering::Equal,
+    }
+}
+
+/// `items.swap(a, b)`, but nothing if `a` or `b` is out of range. See [`cmp_at()`].
+#[inline(always)]
+pub(crate) fn swap_at<T>(items: &mut [T], a: usize, b: usize) {
+    if a < items.len() && b < items.len() {
+        items.swap(a, b);
+    }
+}
+
+/// The panic of `release_panic!` with crate feature `tiny-panic`: no message, no formatting.
+#[cfg(feature = "tiny-panic")]
+#[cold]
+#[inline(never)]
+pub(crate) fn tiny_panic() -> ! {
+    panic!()
+}
@@ -0,0 +1,57 @@
+//! A tiny pseudo-random number generator: xorshift64* (Marsaglia, Vigna).
+//!
+//! NOT crypto-secure. Meant for pivot randomization (see [`crate::engine::Pivot::Random`]) and for
+//! tests, which stay reproducible, since the seed is explicit.
+
+#[cfg(test)]
+mod rng_tests;
+
+/// Used instead of a zero seed (which xorshift would never leave).
+const DEFAULT_SEED: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// xorshift64* generator. Copy it to replay the same sequence.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self::new(DEFAULT_SEED)
+    }
+}
+
+impl Rng {
+    /// The same `seed` gives the same sequence. Any seed works (including zero).
+    pub const fn new(seed: u64) -> Self {
+        Self {
+            state: if seed == 0 { DEFAULT_SEED } else { seed },
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.state = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        // The high bits are the better ones.
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// A number in `0..bound` (nearly unbiased, by multiplying rather than by modulo). Zero if
+    /// `bound` is zero.
+    pub fn below(&mut self, bound: usize) -> usize {
+        ((self.next_u64() as u128 * bound as u128) >> 64) as usize
+    }
+
+    /// A number in `min..max`. `min` if the range is empty.
+    pub fn range_u32(&mut self, min: u32, max: u32) -> u32 {
+        let width = max.saturating_sub(min);
+        min + ((self.next_u32() as u64 * width as u64) >> 32) as u32
+    }
+}
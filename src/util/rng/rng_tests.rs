@@ -0,0 +1,32 @@
+use crate::util::rng::Rng;
+
+#[test]
+fn same_seed_same_sequence() {
+    let (mut a, mut b) = (Rng::new(42), Rng::new(42));
+    for _ in 0..100 {
+        assert_eq!(a.next_u64(), b.next_u64());
+    }
+    assert_ne!(Rng::new(1).next_u64(), Rng::new(2).next_u64());
+}
+
+#[test]
+fn zero_seed_is_not_stuck() {
+    let mut rng = Rng::new(0);
+    assert_eq!(rng, Rng::default());
+    assert_ne!(rng.next_u64(), 0);
+    assert_ne!(rng.next_u64(), rng.next_u64());
+}
+
+#[test]
+fn bounds() {
+    let mut rng = Rng::new(7);
+    let mut seen = [false; 10];
+    for _ in 0..1000 {
+        seen[rng.below(10)] = true;
+        let value = rng.range_u32(5, 8);
+        assert!((5..8).contains(&value));
+    }
+    assert!(seen.iter().all(|&seen| seen));
+    assert_eq!(rng.below(0), 0);
+    assert_eq!(rng.range_u32(3, 3), 3);
+}
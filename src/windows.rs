@@ -0,0 +1,112 @@
+//! Sliding windows over the sorted order, resolved lazily: for sliding-window statistics over
+//! ranked data (like "the largest gap among 5 consecutive ranks"), without sorting everything.
+
+use crate::engine::Engine;
+use crate::error::Error;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::slice;
+
+#[cfg(test)]
+mod windows_tests;
+
+/// Overlapping windows (each of `width` items) over the sorted order of `items`: the 1st to the
+/// `width`-th smallest, then the 2nd to the `width + 1`-th... Panic if `width` is 0.
+///
+/// Not available with crate feature `no-panic`. Use [`SortedWindows::try_new()`] instead.
+///
+/// ```
+/// use lazysort_no_alloc::windows::sorted_windows;
+///
+/// let mut latencies = [40, 12, 95, 31, 18, 27, 60];
+/// let mut windows = sorted_windows(&mut latencies, 3);
+/// assert_eq!(windows.next(), Some(&[12, 18, 27][..]));
+/// // The spread of each window of 3 consecutive ranks.
+/// let spreads: Vec<u32> = windows.map(|window| window[2] - window[0]).collect();
+/// assert_eq!(spreads, [13, 13, 29, 55]);
+/// ```
+#[cfg(not(feature = "no-panic"))]
+pub fn sorted_windows<T: Ord>(items: &mut [T], width: usize) -> SortedWindows<'_, T> {
+    match SortedWindows::try_new(items, width) {
+        Ok(windows) => windows,
+        Err(_) => release_panic!("The width of the windows has to be positive."),
+    }
+}
+
+/// See [`sorted_windows()`].
+///
+/// Sorts `items` in place, and only as far as the windows yielded so far reach: each window is the
+/// (sorted) region of `items` just before the items not resolved yet, so no window gets copied, and
+/// no other memory is needed. Each [`Iterator::next()`] resolves one more item (except for the
+/// first one, which resolves `width` items at once, partitioning only once at rank `width`).
+#[derive(Debug)]
+pub struct SortedWindows<'a, T: Ord> {
+    /// Start of the items. The engine never moves the items it has consumed (the ones before its
+    /// front), so the yielded windows (made of such items only) stay valid.
+    items: *mut T,
+    width: usize,
+    engine: Engine,
+    /// Whether the first window has been yielded.
+    started: bool,
+    lifetime: PhantomData<&'a mut [T]>,
+}
+
+impl<'a, T: Ord> SortedWindows<'a, T> {
+    /// Like [`sorted_windows()`], but return [`Error::InvalidArgument`] if `width` is 0.
+    pub fn try_new(items: &'a mut [T], width: usize) -> Result<Self, Error> {
+        if width == 0 {
+            return Err(Error::InvalidArgument);
+        }
+        Ok(Self {
+            engine: Engine::try_new(items.len())?,
+            items: items.as_mut_ptr(),
+            width,
+            started: false,
+            lifetime: PhantomData,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The items not resolved yet.
+    ///
+    /// # Safety
+    /// No reference to those items may be alive.
+    unsafe fn live(&mut self) -> &'a mut [T] {
+        slice::from_raw_parts_mut(self.items.add(self.engine.front()), self.engine.len())
+    }
+}
+
+impl<'a, T: Ord> Iterator for SortedWindows<'a, T> {
+    type Item = &'a [T];
+
+    fn next(&mut self) -> Option<&'a [T]> {
+        // The live items are disjoint from the (consumed) items of the windows yielded so far.
+        let live = unsafe { self.live() };
+        if self.started {
+            self.engine.next_front(live, &mut T::cmp)?;
+        } else if self.engine.len() < self.width {
+            return None;
+        } else {
+            self.engine.next_chunk_front(live, self.width, &mut T::cmp);
+            self.started = true;
+        }
+        // Consumed: never moved again.
+        let start = self.engine.front() - self.width;
+        Some(unsafe { slice::from_raw_parts(self.items.add(start), self.width) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = match self.started {
+            true => self.engine.len(),
+            false => (self.engine.len() + 1).saturating_sub(self.width),
+        };
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a, T: Ord> ExactSizeIterator for SortedWindows<'a, T> {}
+
+impl<'a, T: Ord> FusedIterator for SortedWindows<'a, T> {}
@@ -0,0 +1,54 @@
+use crate::error::Error;
+use crate::testing::scrambled;
+use crate::windows::SortedWindows;
+
+const LEN: usize = 300;
+
+fn items() -> [u32; LEN] {
+    core::array::from_fn(|i| scrambled(i, 0, 97))
+}
+
+#[test]
+fn windows_over_sorted_order() {
+    let mut expected = items();
+    expected.sort_unstable();
+    for width in [1, 2, 5, LEN - 1, LEN] {
+        let mut items = items();
+        let windows = SortedWindows::try_new(&mut items, width).unwrap();
+        assert_eq!(windows.len(), LEN - width + 1);
+        assert!(windows.eq(expected.windows(width)));
+    }
+}
+
+/// Windows yielded earlier stay valid (and unchanged) while later ones get resolved.
+#[test]
+fn earlier_windows_stay_valid() {
+    let mut expected = items();
+    expected.sort_unstable();
+    let mut items = items();
+    let mut windows = SortedWindows::try_new(&mut items, 4).unwrap();
+    let first = windows.next().unwrap();
+    let tenth = windows.nth(8).unwrap();
+    assert_eq!(windows.len(), LEN - 4 + 1 - 10);
+    let rest = windows.count();
+    assert_eq!(
+        (first, tenth, rest),
+        (&expected[..4], &expected[9..13], LEN - 13)
+    );
+}
+
+#[test]
+fn too_few_items() {
+    let mut items = [3, 1, 2];
+    let mut windows = SortedWindows::try_new(&mut items, 4).unwrap();
+    assert_eq!((windows.len(), windows.next()), (0, None));
+    assert_eq!(windows.next(), None);
+    let mut items = [3, 1, 2];
+    assert!(SortedWindows::try_new(&mut items, 3)
+        .unwrap()
+        .eq([&[1, 2, 3][..]]));
+    assert_eq!(
+        SortedWindows::try_new(&mut items, 0).err(),
+        Some(Error::InvalidArgument)
+    );
+}